@@ -1,18 +1,55 @@
 //! 统一执行入口：请求 -> 计算 -> 输出
 
-use crate::arbitrage::{calculate_arbitrage, calculate_multi_arbitrage};
+use std::path::PathBuf;
+
+use crate::arbitrage::{
+    calculate_arbitrage, calculate_multi_arbitrage, effective_odds_for, placed_stakes,
+    plan_arbitrage_stakes,
+};
+use crate::back_lay::calculate_back_lay;
+use crate::calibration::{apply_shrink, calibration_report};
 use crate::display::{
-    print_result, print_result_arbitrage, print_result_arbitrage_json, print_result_json,
+    effective_fraction, print_json_error, print_recorded_bets, print_result,
+    print_result_arbitrage, print_result_arbitrage_json, print_result_back_lay,
+    print_result_back_lay_json, print_result_calibration, print_result_calibration_json,
+    print_result_dutching, print_result_dutching_json, print_result_hedge, print_result_hedge_json,
+    print_result_json, print_result_ledger, print_result_ledger_json, print_result_merton,
+    print_result_merton_json, print_result_middle, print_result_middle_json,
     print_result_multi_arbitrage, print_result_multi_arbitrage_json, print_result_nash,
-    print_result_nash_json, print_result_polymarket, print_result_polymarket_json,
-    print_result_portfolio, print_result_portfolio_correlated,
-    print_result_portfolio_correlated_json, print_result_portfolio_json, print_result_stock,
-    print_result_stock_json,
+    print_result_nash_json, print_result_option, print_result_option_json, print_result_polymarket,
+    print_result_polymarket_json, print_result_portfolio, print_result_portfolio_correlated,
+    print_result_portfolio_correlated_json, print_result_portfolio_json, print_result_scan,
+    print_result_scan_json, print_result_stock, print_result_stock_json,
+    print_result_stock_scale_out, print_result_stock_scale_out_json, print_result_stock_trailing,
+    print_result_stock_trailing_json, print_result_trade_history, print_result_trade_history_json,
+    print_result_value_scan, print_result_value_scan_json,
 };
+use crate::dutching::calculate_dutching;
+use crate::futures::{kelly_futures, size_futures};
+use crate::heat::calculate_portfolio_heat;
+use crate::hedge::calculate_hedge;
 use crate::kelly::{build_stock_info, kelly_criterion, kelly_polymarket, kelly_stock};
+use crate::ledger::record_ledger_bets;
+use crate::leverage::leveraged_stock_kelly;
+use crate::merton::calculate_merton;
+use crate::middle::calculate_middle;
 use crate::nash::calculate_nash_2x2;
+use crate::options::price_option_strategy;
 use crate::portfolio::{calculate_portfolio_kelly, calculate_portfolio_kelly_correlated};
-use crate::types::{PortfolioLeg, PortfolioScenario};
+use crate::scale_out::calculate_scale_out;
+use crate::scanner::scan_arbitrage;
+use crate::sizing::apply_position_sizer;
+use crate::trade_history::analyze_trade_history;
+use crate::trailing::simulate_trailing_stop;
+use crate::types::{
+    ArbitrageOptions, ArbitragePlan, BackLayKind, CapitalSource, CurrencySettings, DutchingTarget,
+    HedgeBet, KellyResult, LedgerEntry, LedgerRecord, LedgerSummary, LeverageSettings,
+    MertonConstraints, MiddleBet, ModelProbability, MoneyContext, OddsQuote, OptionMarket,
+    OptionStrategy, PathSimulation, PortfolioLeg, PortfolioScenario, Prediction, PriceView,
+    RecordSize, ScaleOutTarget, SizedBet, SizerResult, StockCosts, StockSizingContext,
+    StockSizingOptions, TradeDirection, TrailingStop, VolatilityLevels,
+};
+use crate::value_scan::scan_value_bets;
 
 #[derive(Clone, Copy)]
 pub enum OutputFormat {
@@ -31,102 +68,890 @@ pub enum ModeRequest {
         odds: f64,
         win_rate: f64,
         capital: Option<f64>,
+        /// `--shrink` 收缩系数
+        shrink: Option<f64>,
     },
     Polymarket {
         market_price: f64,
         your_probability: f64,
         capital: Option<f64>,
+        /// `--shrink` 收缩系数
+        shrink: Option<f64>,
     },
     Stock {
+        direction: TradeDirection,
         entry_price: f64,
         target_price: f64,
         stop_loss: f64,
         win_rate: f64,
+        costs: StockCosts,
+        volatility: Option<VolatilityLevels>,
+        sizing: Box<StockSizingOptions>,
+        capital: Option<f64>,
+    },
+    StockScaleOut {
+        direction: TradeDirection,
+        entry_price: f64,
+        stop_loss: f64,
+        targets: Vec<ScaleOutTarget>,
+        breakeven_after_first: bool,
+        costs: StockCosts,
+        capital: Option<f64>,
+    },
+    StockTrailing {
+        direction: TradeDirection,
+        entry_price: f64,
+        trailing: TrailingStop,
+        simulation: PathSimulation,
+        costs: StockCosts,
+        capital: Option<f64>,
+    },
+    TradeHistory {
+        source: String,
+        trades: Vec<f64>,
+        r_multiple: bool,
+        bootstrap_samples: usize,
+        confidence: f64,
+        seed: u64,
+        capital: Option<f64>,
+    },
+    Option {
+        strategy: OptionStrategy,
+        market: OptionMarket,
+        view: PriceView,
+        multiplier: u64,
+        capital: Option<f64>,
+    },
+    Merton {
+        means: Vec<f64>,
+        covariance: Vec<Vec<f64>>,
+        risk_free: f64,
+        constraints: MertonConstraints,
         capital: Option<f64>,
     },
     Arbitrage {
         odds1: f64,
         odds2: f64,
+        options: ArbitrageOptions,
         capital: Option<f64>,
     },
     MultiArbitrage {
         odds: Vec<f64>,
+        options: ArbitrageOptions,
+        capital: Option<f64>,
+    },
+    BackLay {
+        back_odds: f64,
+        lay_odds: f64,
+        back_stake: f64,
+        commission: f64,
+        kind: BackLayKind,
+    },
+    ValueScan {
+        quotes: Vec<OddsQuote>,
+        probabilities: Vec<ModelProbability>,
+        polymarket: bool,
+        min_edge: f64,
+        capital: Option<f64>,
+    },
+    Scan {
+        quotes: Vec<OddsQuote>,
+        max_margin: f64,
+        capital: Option<f64>,
+    },
+    Dutching {
+        odds: Vec<f64>,
+        target: Option<DutchingTarget>,
+        win_prob: Option<f64>,
+        capital: Option<f64>,
+    },
+    Hedge {
+        bet: HedgeBet,
+        ratios: Vec<f64>,
+        polymarket: bool,
+        cash_out: Option<f64>,
+        capital: Option<f64>,
+    },
+    Middle {
+        bet: MiddleBet,
         capital: Option<f64>,
     },
     Nash {
         row_payoffs: [[f64; 2]; 2],
         col_payoffs: [[f64; 2]; 2],
     },
+    Calibration {
+        /// 数据来源描述（预测文件或账本路径）
+        source: String,
+        predictions: Vec<Prediction>,
+        /// 账本中无法用于校准的下注数（未记录胜率、未结算或非输赢结算）
+        skipped: Option<usize>,
+    },
+    Ledger {
+        path: PathBuf,
+        recorded: Option<LedgerEntry>,
+        summary: LedgerSummary,
+    },
     Portfolio {
         legs: Vec<PortfolioLeg>,
+        leverage: Option<LeverageSettings>,
         capital: Option<f64>,
     },
     PortfolioCorrelated {
         leg_count: usize,
         scenarios: Vec<PortfolioScenario>,
+        leverage: Option<LeverageSettings>,
         capital: Option<f64>,
     },
 }
 
-pub fn execute_mode(mode: ModeRequest, output: OutputFormat) {
+pub fn emit_error(output: OutputFormat, message: &str) {
+    if output.is_json() {
+        print_json_error(message);
+    } else {
+        println!("✗ {}", message);
+    }
+}
+
+impl ModeRequest {
+    /// `--shrink` 收缩系数（只有标准与 Polymarket 模式有市场概率可供收缩）
+    pub fn shrink_mut(&mut self) -> Option<&mut Option<f64>> {
+        match self {
+            ModeRequest::Standard { shrink, .. } | ModeRequest::Polymarket { shrink, .. } => {
+                Some(shrink)
+            }
+            _ => None,
+        }
+    }
+
+    /// 检查 `--record` 的记账方式是否适用于本请求
+    ///
+    /// 凯利仓位（full/half/quarter）用于标准、Polymarket、价值投注扫描、中间盘与带子集胜率的分散投注；
+    /// 分配金额（stake）用于套利、多标的套利、套利扫描与带金额目标的分散投注。
+    pub fn check_record_size(&self, size: RecordSize) -> Result<(), String> {
+        let require = |ok: bool, message: &str| {
+            if ok { Ok(()) } else { Err(message.to_string()) }
+        };
+        match (self, size) {
+            (
+                ModeRequest::Standard { capital, .. }
+                | ModeRequest::Polymarket { capital, .. }
+                | ModeRequest::ValueScan { capital, .. }
+                | ModeRequest::Middle { capital, .. },
+                RecordSize::Kelly(_),
+            ) => require(capital.is_some(), "--record 需要本金或 --ledger"),
+            (
+                ModeRequest::Dutching {
+                    capital, win_prob, ..
+                },
+                RecordSize::Kelly(_),
+            ) => require(
+                capital.is_some() && win_prob.is_some(),
+                "分散投注按凯利仓位记账需要子集胜率与本金",
+            ),
+            (ModeRequest::Dutching { target, .. }, RecordSize::Stake) => require(
+                target.is_some(),
+                "分散投注 --record stake 需要 --stake 或 --target-profit 金额目标",
+            ),
+            (
+                ModeRequest::Arbitrage { capital, .. }
+                | ModeRequest::MultiArbitrage { capital, .. }
+                | ModeRequest::Scan { capital, .. },
+                RecordSize::Stake,
+            ) => require(capital.is_some(), "--record stake 需要输入总投注金额"),
+            (
+                ModeRequest::Standard { .. }
+                | ModeRequest::Polymarket { .. }
+                | ModeRequest::ValueScan { .. }
+                | ModeRequest::Middle { .. },
+                RecordSize::Stake,
+            ) => Err("该模式按凯利仓位记账，请使用 --record full|half|quarter".to_string()),
+            (
+                ModeRequest::Arbitrage { .. }
+                | ModeRequest::MultiArbitrage { .. }
+                | ModeRequest::Scan { .. },
+                RecordSize::Kelly(_),
+            ) => Err(
+                "套利按输入的总投注金额分配，不是凯利仓位，请使用 --record stake 并输入总投注金额"
+                    .to_string(),
+            ),
+            _ => Err(
+                "--record 不适用于该模式：账本只记录按小数赔率下注的投注，股票、期权、期货、默顿与组合仓位不是单笔投注，对冲（-H、-b）针对已有投注或交易所反向投注"
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// 请求中的本金（纳什均衡、校准与账本没有本金）
+    pub fn capital_mut(&mut self) -> Option<&mut Option<f64>> {
+        match self {
+            ModeRequest::Standard { capital, .. }
+            | ModeRequest::Polymarket { capital, .. }
+            | ModeRequest::Stock { capital, .. }
+            | ModeRequest::StockScaleOut { capital, .. }
+            | ModeRequest::StockTrailing { capital, .. }
+            | ModeRequest::TradeHistory { capital, .. }
+            | ModeRequest::Option { capital, .. }
+            | ModeRequest::Merton { capital, .. }
+            | ModeRequest::Arbitrage { capital, .. }
+            | ModeRequest::MultiArbitrage { capital, .. }
+            | ModeRequest::ValueScan { capital, .. }
+            | ModeRequest::Scan { capital, .. }
+            | ModeRequest::Dutching { capital, .. }
+            | ModeRequest::Hedge { capital, .. }
+            | ModeRequest::Middle { capital, .. }
+            | ModeRequest::Portfolio { capital, .. }
+            | ModeRequest::PortfolioCorrelated { capital, .. } => Some(capital),
+            // 对冲下注的背注金额本身即以下注货币输入
+            ModeRequest::BackLay { .. }
+            | ModeRequest::Nash { .. }
+            | ModeRequest::Calibration { .. }
+            | ModeRequest::Ledger { .. } => None,
+        }
+    }
+}
+
+/// `--record` 时把模式算出的下注记入账本（金额由下注货币换算回本金货币）
+fn record_bets(
+    record: Option<&LedgerRecord>,
+    mode: &str,
+    money: &MoneyContext,
+    bets: Vec<SizedBet>,
+) -> Result<Option<Vec<LedgerEntry>>, String> {
+    let Some(record) = record else {
+        return Ok(None);
+    };
+    let bets: Vec<SizedBet> = bets
+        .into_iter()
+        .map(|bet| SizedBet {
+            stake: money
+                .currency
+                .as_ref()
+                .map_or(bet.stake, |settings| settings.to_capital(bet.stake)),
+            ..bet
+        })
+        .collect();
+    record_ledger_bets(record, mode, &bets).map(Some)
+}
+
+/// 凯利仓位模式的待记账下注：全凯利金额 = 本金 × 建议仓位
+fn kelly_bet(odds: f64, probability: f64, result: &KellyResult, capital: Option<f64>) -> SizedBet {
+    SizedBet {
+        odds,
+        stake: capital.unwrap_or_default()
+            * effective_fraction(result.expected_value, result.optimal_fraction),
+        probability: Some(probability),
+        market: String::new(),
+        label: String::new(),
+    }
+}
+
+/// 多腿投注（套利、分散投注、中间盘）的待记账下注，备注为腿的序号
+fn leg_bets(odds: &[f64], stakes: &[f64], prefix: &str) -> Vec<SizedBet> {
+    odds.iter()
+        .zip(stakes)
+        .enumerate()
+        .map(|(i, (&odds, &stake))| SizedBet {
+            odds,
+            stake,
+            probability: None,
+            market: String::new(),
+            label: format!("{}{}", prefix, i + 1),
+        })
+        .collect()
+}
+
+/// 套利各腿的投注金额：取整后金额优先，其次限额内金额，否则按比例分配本金（扣除手续费）
+fn arbitrage_stakes(
+    plan: &ArbitragePlan,
+    ratios: &[f64],
+    options: &ArbitrageOptions,
+    capital: Option<f64>,
+) -> Vec<f64> {
+    match (&plan.rounded, &plan.limited) {
+        (Some(rounded), _) => rounded.stakes.clone(),
+        (None, Some(limited)) => limited.stakes.clone(),
+        (None, None) => {
+            let outlays: Vec<f64> = ratios
+                .iter()
+                .map(|r| capital.unwrap_or_default() * r)
+                .collect();
+            placed_stakes(&outlays, options)
+        }
+    }
+}
+
+fn print_recorded(record: Option<&LedgerRecord>, recorded: Option<&[LedgerEntry]>) {
+    if let (Some(record), Some(entries)) = (record, recorded) {
+        print_recorded_bets(&record.path, entries);
+    }
+}
+
+/// 执行请求；`record` 为 `--record` 设置，只有能记账的模式使用（见 `check_record_size`）
+pub fn execute_mode(
+    mut mode: ModeRequest,
+    output: OutputFormat,
+    currency: Option<&CurrencySettings>,
+    capital_source: CapitalSource,
+    record: Option<&LedgerRecord>,
+) {
+    let money = MoneyContext {
+        currency: currency.cloned(),
+        entered_capital: mode.capital_mut().and_then(|capital| *capital),
+        capital_source,
+    };
+    // 本金按汇率换算为下注货币，之后所有金额均以下注货币计算
+    if let (Some(settings), Some(capital)) = (currency, mode.capital_mut()) {
+        *capital = capital.map(|cap| settings.to_stake(cap));
+    }
     match mode {
         ModeRequest::Standard {
             odds,
             win_rate,
             capital,
+            shrink,
         } => {
-            let result = kelly_criterion(odds, win_rate);
+            // `--shrink` 时先把胜率向赔率隐含概率收缩
+            let (probability, shrink) = apply_shrink(win_rate, 1.0 / odds, shrink);
+            let result = kelly_criterion(odds, probability);
+            let bet = kelly_bet(odds, probability, &result, capital);
+            let recorded = match record_bets(record, "standard", &money, vec![bet]) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
             if output.is_json() {
-                print_result_json(odds, win_rate, &result, capital);
+                print_result_json(
+                    odds,
+                    probability,
+                    shrink,
+                    &result,
+                    capital,
+                    &money,
+                    recorded.as_deref(),
+                );
             } else {
-                print_result(odds, win_rate, &result, capital);
+                print_result(odds, probability, shrink, &result, capital, &money);
+                print_recorded(record, recorded.as_deref());
             }
         }
         ModeRequest::Polymarket {
             market_price,
             your_probability,
             capital,
+            shrink,
         } => {
-            let result = kelly_polymarket(market_price, your_probability);
+            let (probability, shrink) = apply_shrink(your_probability, market_price, shrink);
+            let result = kelly_polymarket(market_price, probability);
+            let bet = kelly_bet(1.0 / market_price, probability, &result, capital);
+            let recorded = match record_bets(record, "polymarket", &money, vec![bet]) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
             if output.is_json() {
-                print_result_polymarket_json(market_price, your_probability, &result, capital);
+                print_result_polymarket_json(
+                    market_price,
+                    probability,
+                    shrink,
+                    &result,
+                    capital,
+                    &money,
+                    recorded.as_deref(),
+                );
             } else {
-                print_result_polymarket(market_price, your_probability, &result, capital);
+                print_result_polymarket(
+                    market_price,
+                    probability,
+                    shrink,
+                    &result,
+                    capital,
+                    &money,
+                );
+                print_recorded(record, recorded.as_deref());
             }
         }
         ModeRequest::Stock {
+            direction,
             entry_price,
             target_price,
             stop_loss,
             win_rate,
+            costs,
+            volatility,
+            sizing,
+            capital,
+        } => {
+            let info = build_stock_info(direction, entry_price, target_price, stop_loss, &costs);
+            let sizers: Vec<SizerResult> = match capital {
+                Some(cap) => sizing
+                    .sizers
+                    .iter()
+                    .map(|sizer| apply_position_sizer(sizer, &info, &costs, cap))
+                    .collect(),
+                None => Vec::new(),
+            };
+            // 期货按每手净盈亏（整跳、含手续费）计算凯利，而非价格涨跌幅
+            let result = match &sizing.futures {
+                Some(contract) => kelly_futures(&info, contract, win_rate),
+                None => kelly_stock(
+                    direction,
+                    entry_price,
+                    target_price,
+                    stop_loss,
+                    win_rate,
+                    &costs,
+                ),
+            };
+            let context = StockSizingContext {
+                volatility,
+                sizers,
+                heat: match (sizing.heat, capital) {
+                    (Some(settings), Some(cap)) => Some(calculate_portfolio_heat(&settings, cap)),
+                    _ => None,
+                },
+                leverage: sizing
+                    .leverage
+                    .map(|settings| leveraged_stock_kelly(&info, win_rate, &settings)),
+                futures: match (sizing.futures, capital) {
+                    (Some(contract), Some(cap)) => {
+                        let risk_fraction =
+                            effective_fraction(result.expected_value, result.optimal_fraction);
+                        Some(size_futures(&info, &contract, cap * risk_fraction, cap))
+                    }
+                    _ => None,
+                },
+            };
+            if output.is_json() {
+                print_result_stock_json(
+                    &info, &costs, win_rate, &result, &context, capital, &money,
+                );
+            } else {
+                print_result_stock(&info, &costs, win_rate, &result, &context, capital, &money);
+            }
+        }
+        ModeRequest::StockScaleOut {
+            direction,
+            entry_price,
+            stop_loss,
+            targets,
+            breakeven_after_first,
+            costs,
+            capital,
+        } => {
+            let result = calculate_scale_out(
+                direction,
+                entry_price,
+                stop_loss,
+                &targets,
+                breakeven_after_first,
+                &costs,
+            );
+            if output.is_json() {
+                print_result_stock_scale_out_json(
+                    &targets,
+                    breakeven_after_first,
+                    &costs,
+                    &result,
+                    capital,
+                    &money,
+                );
+            } else {
+                print_result_stock_scale_out(
+                    &targets,
+                    breakeven_after_first,
+                    &costs,
+                    &result,
+                    capital,
+                    &money,
+                );
+            }
+        }
+        ModeRequest::StockTrailing {
+            direction,
+            entry_price,
+            trailing,
+            simulation,
+            costs,
+            capital,
+        } => {
+            let result =
+                simulate_trailing_stop(direction, entry_price, trailing, &simulation, &costs);
+            if output.is_json() {
+                print_result_stock_trailing_json(
+                    trailing,
+                    &simulation,
+                    &costs,
+                    &result,
+                    capital,
+                    &money,
+                );
+            } else {
+                print_result_stock_trailing(
+                    trailing,
+                    &simulation,
+                    &costs,
+                    &result,
+                    capital,
+                    &money,
+                );
+            }
+        }
+        ModeRequest::TradeHistory {
+            source,
+            trades,
+            r_multiple,
+            bootstrap_samples,
+            confidence,
+            seed,
             capital,
         } => {
-            let info = build_stock_info(entry_price, target_price, stop_loss);
-            let result = kelly_stock(entry_price, target_price, stop_loss, win_rate);
+            let result =
+                analyze_trade_history(&trades, r_multiple, bootstrap_samples, confidence, seed);
             if output.is_json() {
-                print_result_stock_json(&info, win_rate, &result, capital);
+                print_result_trade_history_json(&source, r_multiple, &result, capital, &money);
             } else {
-                print_result_stock(&info, win_rate, &result, capital);
+                print_result_trade_history(&source, r_multiple, &result, capital, &money);
+            }
+        }
+        ModeRequest::Merton {
+            means,
+            covariance,
+            risk_free,
+            constraints,
+            capital,
+        } => {
+            let result = calculate_merton(&means, &covariance, risk_free, &constraints);
+            if output.is_json() {
+                print_result_merton_json(
+                    &means,
+                    &covariance,
+                    risk_free,
+                    &constraints,
+                    &result,
+                    capital,
+                    &money,
+                );
+            } else {
+                print_result_merton(
+                    &means,
+                    &covariance,
+                    risk_free,
+                    &constraints,
+                    &result,
+                    capital,
+                    &money,
+                );
+            }
+        }
+        ModeRequest::Option {
+            strategy,
+            market,
+            view,
+            multiplier,
+            capital,
+        } => {
+            let result = price_option_strategy(&strategy, &market, &view);
+            if output.is_json() {
+                print_result_option_json(
+                    &strategy, &market, &view, multiplier, &result, capital, &money,
+                );
+            } else {
+                print_result_option(
+                    &strategy, &market, &view, multiplier, &result, capital, &money,
+                );
             }
         }
         ModeRequest::Arbitrage {
             odds1,
             odds2,
+            options,
+            capital,
+        } => {
+            let effective = effective_odds_for(&[odds1, odds2], &options);
+            let result = calculate_arbitrage(effective[0], effective[1]);
+            let plan = if result.has_arbitrage {
+                plan_arbitrage_stakes(
+                    &[odds1, odds2],
+                    &[result.stake1_ratio, result.stake2_ratio],
+                    result.arbitrage_profit,
+                    &options,
+                    capital,
+                )
+            } else {
+                ArbitragePlan::default()
+            };
+            let bets = if result.has_arbitrage {
+                let ratios = [result.stake1_ratio, result.stake2_ratio];
+                let stakes = arbitrage_stakes(&plan, &ratios, &options, capital);
+                leg_bets(&[odds1, odds2], &stakes, "方案")
+            } else {
+                Vec::new()
+            };
+            let recorded = match record_bets(record, "arbitrage", &money, bets) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
+            if output.is_json() {
+                print_result_arbitrage_json(
+                    [odds1, odds2],
+                    &options,
+                    &result,
+                    &plan,
+                    capital,
+                    &money,
+                    recorded.as_deref(),
+                );
+            } else {
+                print_result_arbitrage(odds1, odds2, &options, &result, &plan, capital, &money);
+                print_recorded(record, recorded.as_deref());
+            }
+        }
+        ModeRequest::MultiArbitrage {
+            odds,
+            options,
             capital,
         } => {
-            let result = calculate_arbitrage(odds1, odds2);
+            let result = calculate_multi_arbitrage(&effective_odds_for(&odds, &options));
+            let plan = if result.has_arbitrage {
+                plan_arbitrage_stakes(
+                    &odds,
+                    &result.stake_ratios,
+                    result.arbitrage_profit,
+                    &options,
+                    capital,
+                )
+            } else {
+                ArbitragePlan::default()
+            };
+            let bets = if result.has_arbitrage {
+                let stakes = arbitrage_stakes(&plan, &result.stake_ratios, &options, capital);
+                leg_bets(&odds, &stakes, "方案")
+            } else {
+                Vec::new()
+            };
+            let recorded = match record_bets(record, "multi_arbitrage", &money, bets) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
             if output.is_json() {
-                print_result_arbitrage_json(odds1, odds2, &result, capital);
+                print_result_multi_arbitrage_json(
+                    &odds,
+                    &options,
+                    &result,
+                    &plan,
+                    capital,
+                    &money,
+                    recorded.as_deref(),
+                );
             } else {
-                print_result_arbitrage(odds1, odds2, &result, capital);
+                print_result_multi_arbitrage(&odds, &options, &result, &plan, capital, &money);
+                print_recorded(record, recorded.as_deref());
             }
         }
-        ModeRequest::MultiArbitrage { odds, capital } => {
-            let result = calculate_multi_arbitrage(&odds);
+        ModeRequest::BackLay {
+            back_odds,
+            lay_odds,
+            back_stake,
+            commission,
+            kind,
+        } => {
+            let result = calculate_back_lay(back_odds, lay_odds, back_stake, commission, kind);
+            if output.is_json() {
+                print_result_back_lay_json(
+                    back_odds, lay_odds, back_stake, commission, kind, &result, &money,
+                );
+            } else {
+                print_result_back_lay(
+                    back_odds, lay_odds, back_stake, commission, kind, &result, &money,
+                );
+            }
+        }
+        ModeRequest::Calibration {
+            source,
+            predictions,
+            skipped,
+        } => {
+            let report = calibration_report(&predictions);
             if output.is_json() {
-                print_result_multi_arbitrage_json(&odds, &result, capital);
+                print_result_calibration_json(&source, skipped, &report, &money);
             } else {
-                print_result_multi_arbitrage(&odds, &result, capital);
+                print_result_calibration(&source, skipped, &report);
+            }
+        }
+        ModeRequest::Ledger {
+            path,
+            recorded,
+            summary,
+        } => {
+            if output.is_json() {
+                print_result_ledger_json(&path, recorded.as_ref(), &summary, &money);
+            } else {
+                print_result_ledger(&path, recorded.as_ref(), &summary);
+            }
+        }
+        ModeRequest::ValueScan {
+            quotes,
+            probabilities,
+            polymarket,
+            min_edge,
+            capital,
+        } => {
+            let scan = scan_value_bets(&quotes, &probabilities, polymarket, min_edge);
+            let bets: Vec<SizedBet> = scan
+                .bets
+                .iter()
+                .map(|bet| SizedBet {
+                    market: bet.market.clone(),
+                    label: format!("{} / {} ({})", bet.event, bet.outcome, bet.bookmaker),
+                    ..kelly_bet(bet.odds, bet.probability, &bet.kelly, capital)
+                })
+                .collect();
+            let recorded = match record_bets(record, "value_scan", &money, bets) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
+            if output.is_json() {
+                print_result_value_scan_json(&scan, capital, &money, recorded.as_deref());
+            } else {
+                print_result_value_scan(&scan, capital, &money);
+                print_recorded(record, recorded.as_deref());
+            }
+        }
+        ModeRequest::Scan {
+            quotes,
+            max_margin,
+            capital,
+        } => {
+            let scan = scan_arbitrage(&quotes, max_margin);
+            // 只记录收益率最高的套利盘口，各腿按投注比例分配输入的总投注
+            let bets: Vec<SizedBet> = scan
+                .markets
+                .iter()
+                .find(|market| market.result.has_arbitrage)
+                .map(|market| {
+                    market
+                        .legs
+                        .iter()
+                        .map(|leg| SizedBet {
+                            odds: leg.odds,
+                            stake: capital.unwrap_or_default() * leg.stake_ratio,
+                            probability: None,
+                            market: market.market.clone(),
+                            label: format!(
+                                "{} / {} ({})",
+                                market.event, leg.outcome, leg.bookmaker
+                            ),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let recorded = match record_bets(record, "scan", &money, bets) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
+            if output.is_json() {
+                print_result_scan_json(&scan, capital, &money, recorded.as_deref());
+            } else {
+                print_result_scan(&scan, capital, &money);
+                print_recorded(record, recorded.as_deref());
+            }
+        }
+        ModeRequest::Dutching {
+            odds,
+            target,
+            win_prob,
+            capital,
+        } => {
+            let result = calculate_dutching(&odds, target, win_prob);
+            // `--record stake` 记录金额目标给出的各选项金额，凯利倍数时记录全凯利各选项金额
+            let stakes: Vec<f64> = match (record.map(|r| r.size), &result.kelly) {
+                (Some(RecordSize::Kelly(_)), Some(kelly)) => {
+                    let fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+                    result
+                        .stake_ratios
+                        .iter()
+                        .map(|w| capital.unwrap_or_default() * fraction * w)
+                        .collect()
+                }
+                (Some(RecordSize::Stake), _) => result.stakes.clone(),
+                _ => Vec::new(),
+            };
+            let bets = leg_bets(&odds, &stakes, "选项");
+            let recorded = match record_bets(record, "dutching", &money, bets) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
+            if output.is_json() {
+                print_result_dutching_json(
+                    &odds,
+                    win_prob,
+                    &result,
+                    capital,
+                    &money,
+                    recorded.as_deref(),
+                );
+            } else {
+                print_result_dutching(&odds, win_prob, &result, capital, &money);
+                print_recorded(record, recorded.as_deref());
+            }
+        }
+        ModeRequest::Hedge {
+            bet,
+            ratios,
+            polymarket,
+            cash_out,
+            capital,
+        } => {
+            let result = calculate_hedge(&bet, &ratios, capital);
+            if output.is_json() {
+                print_result_hedge_json(&bet, polymarket, cash_out, &result, capital, &money);
+            } else {
+                print_result_hedge(&bet, polymarket, cash_out, &result, capital, &money);
+            }
+        }
+        ModeRequest::Middle { bet, capital } => {
+            let result = calculate_middle(&bet);
+            // 全凯利总投注按两边比例分配
+            let fraction =
+                effective_fraction(result.kelly.expected_value, result.kelly.optimal_fraction);
+            let stakes: Vec<f64> = result
+                .stake_ratios
+                .iter()
+                .map(|r| capital.unwrap_or_default() * fraction * r)
+                .collect();
+            let bets = leg_bets(&[bet.odds1, bet.odds2], &stakes, "方案");
+            let recorded = match record_bets(record, "middle", &money, bets) {
+                Ok(recorded) => recorded,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
+            if output.is_json() {
+                print_result_middle_json(&bet, &result, capital, &money, recorded.as_deref());
+            } else {
+                print_result_middle(&bet, &result, capital, &money);
+                print_recorded(record, recorded.as_deref());
             }
         }
         ModeRequest::Nash {
@@ -140,24 +965,44 @@ pub fn execute_mode(mode: ModeRequest, output: OutputFormat) {
                 print_result_nash(row_payoffs, col_payoffs, &result);
             }
         }
-        ModeRequest::Portfolio { legs, capital } => {
-            let result = calculate_portfolio_kelly(&legs);
+        ModeRequest::Portfolio {
+            legs,
+            leverage,
+            capital,
+        } => {
+            let result = calculate_portfolio_kelly(&legs, leverage.as_ref());
             if output.is_json() {
-                print_result_portfolio_json(&legs, &result, capital);
+                print_result_portfolio_json(&legs, leverage.as_ref(), &result, capital, &money);
             } else {
-                print_result_portfolio(&legs, &result, capital);
+                print_result_portfolio(&legs, leverage.as_ref(), &result, capital, &money);
             }
         }
         ModeRequest::PortfolioCorrelated {
             leg_count,
             scenarios,
+            leverage,
             capital,
         } => {
-            let result = calculate_portfolio_kelly_correlated(leg_count, &scenarios);
+            let result =
+                calculate_portfolio_kelly_correlated(leg_count, &scenarios, leverage.as_ref());
             if output.is_json() {
-                print_result_portfolio_correlated_json(leg_count, &scenarios, &result, capital);
+                print_result_portfolio_correlated_json(
+                    leg_count,
+                    &scenarios,
+                    leverage.as_ref(),
+                    &result,
+                    capital,
+                    &money,
+                );
             } else {
-                print_result_portfolio_correlated(leg_count, &scenarios, &result, capital);
+                print_result_portfolio_correlated(
+                    leg_count,
+                    &scenarios,
+                    leverage.as_ref(),
+                    &result,
+                    capital,
+                    &money,
+                );
             }
         }
     }
@@ -1,6 +1,12 @@
 //! 套利机会计算
 
-use crate::types::{ArbitrageResult, MultiArbitrageResult};
+use crate::types::{
+    ArbitrageOptions, ArbitragePlan, ArbitrageResult, LimitedArbitrage, MultiArbitrageResult,
+    RoundedArbitrage,
+};
+
+/// 取整搜索的最大组合数，超过时缩小每个标的的候选范围
+const MAX_ROUNDING_COMBINATIONS: usize = 1_000_000;
 
 /// 计算套利机会（两个标的）
 /// 输入两边的赔率，返回套利方案
@@ -81,9 +87,234 @@ pub fn calculate_multi_arbitrage(odds: &[f64]) -> MultiArbitrageResult {
     }
 }
 
+/// 扣除佣金后每单位投注的获胜回报：1 + (赔率 - 1) × (1 - 佣金率)
+pub fn net_odds(odds: f64, commission: f64) -> f64 {
+    1.0 + (odds - 1.0) * (1.0 - commission)
+}
+
+/// 有效赔率：每单位资金支出（投注 + 手续费）的获胜回报
+pub fn effective_odds(odds: f64, commission: f64, fee: f64) -> f64 {
+    net_odds(odds, commission) / (1.0 + fee)
+}
+
+/// 按下注选项中的佣金与手续费计算各标的有效赔率
+pub fn effective_odds_for(odds: &[f64], options: &ArbitrageOptions) -> Vec<f64> {
+    odds.iter()
+        .enumerate()
+        .map(|(i, &o)| {
+            let (commission, fee) = options.leg_costs(i);
+            effective_odds(o, commission, fee)
+        })
+        .collect()
+}
+
+/// 资金支出（含手续费）换算为各标的实际投注额
+pub fn placed_stakes(outlays: &[f64], options: &ArbitrageOptions) -> Vec<f64> {
+    outlays
+        .iter()
+        .enumerate()
+        .map(|(i, outlay)| outlay / (1.0 + options.leg_costs(i).1))
+        .collect()
+}
+
+/// 在单边限额下按理想比例缩放套利规模
+///
+/// 各标的支出 = 总支出 × 比例，实际投注 = 支出 / (1 + 手续费率)，
+/// 总支出上限为 min(限额_i × (1 + 手续费率_i) / 比例_i)，取到最小值的标的即为限制标的；
+/// 指定本金时总支出不超过本金。
+pub fn limit_arbitrage_stakes(
+    stake_ratios: &[f64],
+    arbitrage_profit: f64,
+    max_stakes: &[f64],
+    options: &ArbitrageOptions,
+    capital: Option<f64>,
+) -> LimitedArbitrage {
+    let (limiting_leg, max_total) = stake_ratios
+        .iter()
+        .zip(max_stakes)
+        .enumerate()
+        .map(|(i, (ratio, max))| max * (1.0 + options.leg_costs(i).1) / ratio)
+        .enumerate()
+        .fold((0, f64::INFINITY), |best, (i, total)| {
+            if total < best.1 { (i, total) } else { best }
+        });
+    let total_stake = capital.map_or(max_total, |cap| cap.min(max_total));
+    LimitedArbitrage {
+        max_stakes: max_stakes.to_vec(),
+        max_total,
+        limiting_leg,
+        binding: capital.is_none_or(|cap| max_total < cap),
+        total_stake,
+        stakes: placed_stakes(
+            &stake_ratios
+                .iter()
+                .map(|r| total_stake * r)
+                .collect::<Vec<f64>>(),
+            options,
+        ),
+        guaranteed_profit: total_stake * arbitrage_profit,
+    }
+}
+
+/// 按下注选项调整理想方案：先应用单边限额，再按投注单位取整
+///
+/// `stake_ratios` 与 `arbitrage_profit` 需按有效赔率计算（见 `effective_odds_for`）。
+pub fn plan_arbitrage_stakes(
+    odds: &[f64],
+    stake_ratios: &[f64],
+    arbitrage_profit: f64,
+    options: &ArbitrageOptions,
+    capital: Option<f64>,
+) -> ArbitragePlan {
+    let limited = options
+        .max_stakes
+        .as_ref()
+        .map(|max| limit_arbitrage_stakes(stake_ratios, arbitrage_profit, max, options, capital));
+    let budget = limited.as_ref().map(|l| l.total_stake).or(capital);
+    let rounded = match (&options.stake_steps, budget) {
+        (Some(steps), Some(budget)) => {
+            let outlays: Vec<f64> = stake_ratios.iter().map(|r| budget * r).collect();
+            let ideal = placed_stakes(&outlays, options);
+            Some(round_arbitrage_stakes(odds, &ideal, budget, steps, options))
+        }
+        _ => None,
+    };
+    ArbitragePlan { limited, rounded }
+}
+
+/// 投注的总资金支出（投注 + 手续费）
+fn total_outlay(stakes: &[f64], options: &ArbitrageOptions) -> f64 {
+    stakes
+        .iter()
+        .enumerate()
+        .map(|(i, s)| s * (1.0 + options.leg_costs(i).1))
+        .sum()
+}
+
+/// 给定投注金额时各结果的净收益：该标的扣佣金后的回报 - 总支出
+fn outcome_profits(odds: &[f64], stakes: &[f64], options: &ArbitrageOptions) -> Vec<f64> {
+    let total = total_outlay(stakes, options);
+    odds.iter()
+        .zip(stakes)
+        .enumerate()
+        .map(|(i, (&o, s))| net_odds(o, options.leg_costs(i).0) * s - total)
+        .collect()
+}
+
+/// 单个标的的取整候选：理想金额上下各取整一次，再各向外扩展一个单位，不超过单边限额
+fn rounding_candidates(ideal: f64, step: f64, radius: i64, max_stake: f64) -> Vec<f64> {
+    let units = ideal / step;
+    let floor = (units + 1e-9).floor() as i64;
+    let ceil = (units - 1e-9).ceil() as i64;
+    ((floor - radius).max(0)..=ceil + radius)
+        .map(|k| k as f64 * step)
+        .filter(|&stake| stake <= max_stake * (1.0 + 1e-12))
+        .collect()
+}
+
+/// 在理想投注附近搜索按投注单位取整的组合
+///
+/// 总支出（含手续费）不超过本金、各标的不超过单边限额，最大化最差结果的净收益；
+/// 净收益相同时取总支出较小者。
+pub fn round_arbitrage_stakes(
+    odds: &[f64],
+    ideal_stakes: &[f64],
+    capital: f64,
+    steps: &[f64],
+    options: &ArbitrageOptions,
+) -> RoundedArbitrage {
+    let max_stake = |i: usize| options.max_stakes.as_ref().map_or(f64::INFINITY, |m| m[i]);
+    let mut radius = 1;
+    let mut candidates: Vec<Vec<f64>> = Vec::new();
+    while radius >= 0 {
+        candidates = ideal_stakes
+            .iter()
+            .zip(steps)
+            .enumerate()
+            .map(|(i, (&ideal, &step))| rounding_candidates(ideal, step, radius, max_stake(i)))
+            .collect();
+        let combinations = candidates
+            .iter()
+            .try_fold(1usize, |acc, c| acc.checked_mul(c.len()))
+            .unwrap_or(usize::MAX);
+        if combinations <= MAX_ROUNDING_COMBINATIONS {
+            break;
+        }
+        radius -= 1;
+    }
+    if radius < 0 {
+        // 标的过多时退化为就近取整
+        candidates = ideal_stakes
+            .iter()
+            .zip(steps)
+            .enumerate()
+            .map(|(i, (&ideal, &step))| {
+                vec![((ideal / step).round() * step).min((max_stake(i) / step).floor() * step)]
+            })
+            .collect();
+    }
+
+    let budget = capital * (1.0 + 1e-12);
+    let mut best: Option<(f64, f64, Vec<f64>)> = None;
+    let mut indices = vec![0usize; candidates.len()];
+    loop {
+        let stakes: Vec<f64> = indices
+            .iter()
+            .zip(&candidates)
+            .map(|(&i, c)| c[i])
+            .collect();
+        let total = total_outlay(&stakes, options);
+        if total > 0.0 && total <= budget {
+            let min_profit = outcome_profits(odds, &stakes, options)
+                .into_iter()
+                .fold(f64::INFINITY, f64::min);
+            let better = match &best {
+                None => true,
+                Some((best_min, best_total, _)) => {
+                    min_profit > best_min + 1e-9
+                        || ((min_profit - best_min).abs() <= 1e-9 && total < *best_total)
+                }
+            };
+            if better {
+                best = Some((min_profit, total, stakes));
+            }
+        }
+
+        // 混合进制递增
+        let mut position = 0;
+        while position < indices.len() {
+            indices[position] += 1;
+            if indices[position] < candidates[position].len() {
+                break;
+            }
+            indices[position] = 0;
+            position += 1;
+        }
+        if position == indices.len() {
+            break;
+        }
+    }
+
+    let stakes = best.map_or_else(|| vec![0.0; odds.len()], |(_, _, stakes)| stakes);
+    let profits = outcome_profits(odds, &stakes, options);
+    let min_profit = profits.iter().copied().fold(f64::INFINITY, f64::min);
+    RoundedArbitrage {
+        steps: steps.to_vec(),
+        total_stake: total_outlay(&stakes, options),
+        stakes,
+        min_profit,
+        survives: min_profit > 0.0,
+        profits,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{calculate_arbitrage, calculate_multi_arbitrage};
+    use super::{
+        calculate_arbitrage, calculate_multi_arbitrage, effective_odds, effective_odds_for,
+        limit_arbitrage_stakes, plan_arbitrage_stakes, round_arbitrage_stakes,
+    };
+    use crate::types::ArbitrageOptions;
 
     const EPS: f64 = 1e-10;
 
@@ -134,4 +365,145 @@ mod tests {
         assert!(result.juice_rate > 0.0);
         assert!(result.stake_ratios.iter().all(|&r| r == 0.0));
     }
+
+    #[test]
+    fn rounded_stakes_keep_guaranteed_profit() {
+        let result = calculate_arbitrage(2.1, 2.0);
+        let ideal = [1000.0 * result.stake1_ratio, 1000.0 * result.stake2_ratio];
+        let rounded = round_arbitrage_stakes(
+            &[2.1, 2.0],
+            &ideal,
+            1000.0,
+            &[5.0, 5.0],
+            &ArbitrageOptions::default(),
+        );
+        assert!(rounded.total_stake <= 1000.0);
+        for stake in &rounded.stakes {
+            assert_almost_eq(stake % 5.0, 0.0);
+        }
+        assert!(rounded.survives);
+        // 理想方案每个结果收益 ≈ 24.39，取整后最差结果不会更好
+        assert!(rounded.min_profit <= 1000.0 * result.arbitrage_profit + EPS);
+        assert_almost_eq(
+            rounded.min_profit,
+            rounded
+                .profits
+                .iter()
+                .copied()
+                .fold(f64::INFINITY, f64::min),
+        );
+    }
+
+    #[test]
+    fn coarse_rounding_can_destroy_thin_arbitrage() {
+        let odds = [2.02, 2.0];
+        let result = calculate_arbitrage(odds[0], odds[1]);
+        assert!(result.has_arbitrage);
+        let ideal = [100.0 * result.stake1_ratio, 100.0 * result.stake2_ratio];
+        let rounded = round_arbitrage_stakes(
+            &odds,
+            &ideal,
+            100.0,
+            &[50.0, 50.0],
+            &ArbitrageOptions::default(),
+        );
+        assert!(!rounded.survives);
+
+        let fine = round_arbitrage_stakes(
+            &odds,
+            &ideal,
+            100.0,
+            &[0.5, 0.5],
+            &ArbitrageOptions::default(),
+        );
+        assert!(fine.survives);
+    }
+
+    #[test]
+    fn binding_leg_limits_total_stake() {
+        let odds = [2.5, 3.6, 4.2];
+        let result = calculate_multi_arbitrage(&odds);
+        // 标的1 限额 200：总投注上限 200 / 0.4367 ≈ 457.9
+        let limited = limit_arbitrage_stakes(
+            &result.stake_ratios,
+            result.arbitrage_profit,
+            &[200.0, 1000.0, 1000.0],
+            &ArbitrageOptions::default(),
+            Some(1000.0),
+        );
+        assert_eq!(limited.limiting_leg, 0);
+        assert!(limited.binding);
+        assert_almost_eq(limited.stakes[0], 200.0);
+        assert_almost_eq(limited.total_stake, 200.0 / result.stake_ratios[0]);
+        assert_almost_eq(
+            limited.guaranteed_profit,
+            limited.total_stake * result.arbitrage_profit,
+        );
+
+        let loose = limit_arbitrage_stakes(
+            &result.stake_ratios,
+            result.arbitrage_profit,
+            &[5000.0; 3],
+            &ArbitrageOptions::default(),
+            Some(1000.0),
+        );
+        assert!(!loose.binding);
+        assert_almost_eq(loose.total_stake, 1000.0);
+    }
+
+    #[test]
+    fn rounding_respects_max_stakes() {
+        let odds = [2.1, 2.0];
+        let result = calculate_arbitrage(odds[0], odds[1]);
+        let options = ArbitrageOptions {
+            stake_steps: Some(vec![10.0, 10.0]),
+            max_stakes: Some(vec![250.0, 1000.0]),
+            ..ArbitrageOptions::default()
+        };
+        let ratios = [result.stake1_ratio, result.stake2_ratio];
+        let plan = plan_arbitrage_stakes(&odds, &ratios, result.arbitrage_profit, &options, None);
+        let limited = plan.limited.unwrap();
+        assert_almost_eq(limited.stakes[0], 250.0);
+        let rounded = plan.rounded.unwrap();
+        assert!(rounded.stakes[0] <= 250.0);
+        assert!(rounded.total_stake <= limited.total_stake + EPS);
+        assert!(rounded.survives);
+    }
+
+    #[test]
+    fn commission_can_remove_apparent_arbitrage() {
+        // 2.02 / 2.02 表面套利 1%，交易所一侧收 5% 佣金后消失
+        assert!(calculate_arbitrage(2.02, 2.02).has_arbitrage);
+        let net = effective_odds(2.02, 0.05, 0.0);
+        assert_almost_eq(net, 1.0 + 1.02 * 0.95);
+        assert!(!calculate_arbitrage(2.02, net).has_arbitrage);
+
+        // 2% 佣金下仍有套利，按有效赔率分配后各结果收益相等
+        let options = ArbitrageOptions {
+            commissions: Some(vec![0.0, 0.02]),
+            fees: Some(vec![0.01, 0.0]),
+            stake_steps: Some(vec![0.01, 0.01]),
+            ..ArbitrageOptions::default()
+        };
+        let odds = [2.1, 2.1];
+        let effective = effective_odds_for(&odds, &options);
+        let result = calculate_arbitrage(effective[0], effective[1]);
+        assert!(result.has_arbitrage);
+        let ratios = [result.stake1_ratio, result.stake2_ratio];
+        let rounded = plan_arbitrage_stakes(
+            &odds,
+            &ratios,
+            result.arbitrage_profit,
+            &options,
+            Some(1000.0),
+        )
+        .rounded
+        .unwrap();
+        assert!(rounded.total_stake <= 1000.0);
+        // 取整单位很小时接近理想收益
+        assert!((rounded.min_profit - 1000.0 * result.arbitrage_profit).abs() < 0.05);
+        // 方案1投注含 1% 手续费：支出 = 投注 × 1.01
+        let outlay = rounded.stakes[0] * 1.01 + rounded.stakes[1];
+        assert_almost_eq(rounded.total_stake, outlay);
+    }
 }
@@ -0,0 +1,108 @@
+//! 对冲下注（背注 + 交易所对冲）：计算使两种结果收益相等的对冲金额
+
+use crate::types::{BackLayKind, BackLayResult};
+
+/// 计算对冲金额与各结果收益
+///
+/// 背注赢时收益 = 背注赢额 - 对冲金额 × (对冲赔率 - 1)，
+/// 背注输时收益 = 对冲金额 × (1 - 佣金率) - 背注损失 + 退款价值。
+/// 令两者相等解出对冲金额。
+pub fn calculate_back_lay(
+    back_odds: f64,
+    lay_odds: f64,
+    back_stake: f64,
+    commission: f64,
+    kind: BackLayKind,
+) -> BackLayResult {
+    // 背注赢时的净赢额与背注输时损失的本金
+    let (back_win, back_loss) = match kind {
+        BackLayKind::Qualifying | BackLayKind::RiskFree { .. } => {
+            (back_stake * (back_odds - 1.0), back_stake)
+        }
+        // 不退本金的免费投注：赢时只得净赢额，输时无损失
+        BackLayKind::FreeBetSnr => (back_stake * (back_odds - 1.0), 0.0),
+        // 退本金的免费投注：赢时得全部回报，输时无损失
+        BackLayKind::FreeBetSr => (back_stake * back_odds, 0.0),
+    };
+    let refund = match kind {
+        BackLayKind::RiskFree { retention } => back_stake * retention,
+        _ => 0.0,
+    };
+
+    let lay_stake = ((back_win + back_loss - refund) / (lay_odds - commission)).max(0.0);
+    let liability = lay_stake * (lay_odds - 1.0);
+    let back_wins_profit = back_win - liability;
+    let lay_wins_profit = lay_stake * (1.0 - commission) - back_loss + refund;
+    let profit = back_wins_profit.min(lay_wins_profit);
+
+    BackLayResult {
+        lay_stake,
+        liability,
+        back_wins_profit,
+        lay_wins_profit,
+        profit,
+        rating: back_odds / lay_odds,
+        conversion: match kind {
+            BackLayKind::FreeBetSnr | BackLayKind::FreeBetSr => Some(profit / back_stake),
+            _ => None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::calculate_back_lay;
+    use crate::types::BackLayKind;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn qualifying_bet_equalizes_outcomes() {
+        // 背注 100 @ 3.0，对冲 @ 3.1，佣金 2%：对冲金额 300 / 3.08
+        let result = calculate_back_lay(3.0, 3.1, 100.0, 0.02, BackLayKind::Qualifying);
+        assert!(approx(result.lay_stake, 300.0 / 3.08));
+        assert!(approx(result.liability, result.lay_stake * 2.1));
+        assert!(approx(result.back_wins_profit, result.lay_wins_profit));
+        assert!(result.profit < 0.0);
+    }
+
+    #[test]
+    fn equal_odds_without_commission_break_even() {
+        let result = calculate_back_lay(2.5, 2.5, 50.0, 0.0, BackLayKind::Qualifying);
+        assert!(approx(result.lay_stake, 50.0));
+        assert!(approx(result.profit, 0.0));
+    }
+
+    #[test]
+    fn free_bets_lock_in_profit() {
+        // 不退本金：对冲金额 = (B - 1) × S / (L - c)
+        let snr = calculate_back_lay(6.0, 6.4, 20.0, 0.02, BackLayKind::FreeBetSnr);
+        assert!(approx(snr.lay_stake, 5.0 * 20.0 / 6.38));
+        assert!(approx(snr.back_wins_profit, snr.lay_wins_profit));
+        assert!(snr.profit > 0.0);
+        assert!(approx(snr.conversion.unwrap(), snr.profit / 20.0));
+
+        let sr = calculate_back_lay(6.0, 6.4, 20.0, 0.02, BackLayKind::FreeBetSr);
+        assert!(sr.profit > snr.profit);
+    }
+
+    #[test]
+    fn risk_free_refund_reduces_lay_stake() {
+        let plain = calculate_back_lay(4.0, 4.2, 50.0, 0.02, BackLayKind::Qualifying);
+        let risk_free = calculate_back_lay(
+            4.0,
+            4.2,
+            50.0,
+            0.02,
+            BackLayKind::RiskFree { retention: 0.7 },
+        );
+        assert!(risk_free.lay_stake < plain.lay_stake);
+        assert!(approx(
+            risk_free.back_wins_profit,
+            risk_free.lay_wins_profit
+        ));
+        assert!(risk_free.profit > 0.0);
+    }
+}
@@ -0,0 +1,281 @@
+//! 概率校准：对已结算预测计算 Brier 分数、对数损失与可靠性分组，并拟合向市场概率收缩的系数
+
+use std::fs;
+
+use crate::scanner::parse_records;
+use crate::types::{
+    BetSettlement, CalibrationReport, LedgerBet, Prediction, ReliabilityBucket, ShrinkAdjustment,
+};
+use crate::validation::{parse_market_price, parse_odds, parse_percent};
+
+/// 预测文件字段：结果（1/0、win/loss）、下注赔率（或 Polymarket 价格%）、你的概率%
+const PREDICTION_FIELDS: [&str; 3] = ["outcome", "odds", "probability"];
+const RELIABILITY_BUCKETS: usize = 10;
+const FIT_ITERATIONS: usize = 200;
+
+/// 向市场概率收缩：p' = m + k·(p - m)，k = 1 不变，k = 0 完全采用市场概率
+pub fn shrink_toward(probability: f64, market: f64, factor: f64) -> f64 {
+    market + factor * (probability - market)
+}
+
+/// 按 `--shrink` 系数调整输入概率，返回调整后的概率与调整信息（未指定系数时原样返回）
+pub fn apply_shrink(
+    probability: f64,
+    market: f64,
+    factor: Option<f64>,
+) -> (f64, Option<ShrinkAdjustment>) {
+    match factor {
+        Some(factor) => (
+            shrink_toward(probability, market, factor),
+            Some(ShrinkAdjustment {
+                factor,
+                raw_probability: probability,
+            }),
+        ),
+        None => (probability, None),
+    }
+}
+
+fn parse_outcome(input: &str) -> Result<bool, String> {
+    match input.to_ascii_lowercase().as_str() {
+        "1" | "win" | "w" | "true" | "赢" => Ok(true),
+        "0" | "loss" | "lose" | "l" | "false" | "输" => Ok(false),
+        _ => Err(format!("结果 '{input}' 无效，应为 1/0 或 win/loss")),
+    }
+}
+
+/// 解析预测文件（CSV 或 JSON）
+///
+/// `polymarket` 时赔率列为买入价格（%），市场概率即价格；否则市场概率为 1 / 赔率。
+pub fn parse_predictions(content: &str, polymarket: bool) -> Result<Vec<Prediction>, String> {
+    parse_records(content, &PREDICTION_FIELDS)?
+        .into_iter()
+        .map(|(place, values)| {
+            let with_place = |e: String| format!("{place}: {e}");
+            let probability = parse_percent(&values[2], "概率").map_err(with_place)?;
+            if probability <= 0.0 || probability >= 1.0 {
+                return Err(with_place("概率必须在 (0, 100) 之间".to_string()));
+            }
+            Ok(Prediction {
+                outcome: parse_outcome(&values[0]).map_err(with_place)?,
+                market_prob: if polymarket {
+                    parse_market_price(&values[1]).map_err(with_place)?
+                } else {
+                    1.0 / parse_odds(&values[1], "赔率").map_err(with_place)?
+                },
+                probability,
+            })
+        })
+        .collect()
+}
+
+pub fn load_predictions(path: &str, polymarket: bool) -> Result<Vec<Prediction>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("无法读取预测文件 '{path}': {e}"))?;
+    parse_predictions(&content, polymarket)
+}
+
+/// 从账本取出记录了下注时胜率且结算为赢/输的下注，返回预测与跳过的下注数
+pub fn ledger_predictions(bets: &[LedgerBet]) -> (Vec<Prediction>, usize) {
+    let predictions: Vec<Prediction> = bets
+        .iter()
+        .filter_map(|bet| {
+            let probability = bet.probability?;
+            let outcome = match bet.settlement.as_ref()?.0 {
+                BetSettlement::Win => true,
+                BetSettlement::Loss => false,
+                BetSettlement::Void | BetSettlement::Partial => return None,
+            };
+            Some(Prediction {
+                probability,
+                market_prob: 1.0 / bet.odds,
+                outcome,
+            })
+        })
+        .collect();
+    let skipped = bets.len() - predictions.len();
+    (predictions, skipped)
+}
+
+fn brier(probabilities: &[f64], predictions: &[Prediction]) -> f64 {
+    let total: f64 = probabilities
+        .iter()
+        .zip(predictions)
+        .map(|(p, pred)| (p - f64::from(u8::from(pred.outcome))).powi(2))
+        .sum();
+    total / predictions.len() as f64
+}
+
+fn log_likelihood(probabilities: &[f64], predictions: &[Prediction]) -> f64 {
+    probabilities
+        .iter()
+        .zip(predictions)
+        .map(|(p, pred)| if pred.outcome { p.ln() } else { (1.0 - p).ln() })
+        .sum()
+}
+
+fn log_loss(probabilities: &[f64], predictions: &[Prediction]) -> f64 {
+    -log_likelihood(probabilities, predictions) / predictions.len() as f64
+}
+
+/// 拟合收缩系数 k ∈ [0, 1]，最大化收缩后概率的对数似然
+///
+/// 对数似然是 k 的凹函数，三分法求最大值；k = 1 表示无需收缩。
+pub fn fit_shrink_factor(predictions: &[Prediction]) -> f64 {
+    let likelihood = |k: f64| {
+        let shrunk: Vec<f64> = predictions
+            .iter()
+            .map(|p| shrink_toward(p.probability, p.market_prob, k))
+            .collect();
+        log_likelihood(&shrunk, predictions)
+    };
+    let mut lo = 0.0;
+    let mut hi = 1.0;
+    for _ in 0..FIT_ITERATIONS {
+        let m1 = lo + (hi - lo) / 3.0;
+        let m2 = hi - (hi - lo) / 3.0;
+        if likelihood(m1) < likelihood(m2) {
+            lo = m1;
+        } else {
+            hi = m2;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// 可靠性分组：按预测概率每 10% 一组，只保留有数据的区间
+fn reliability_buckets(predictions: &[Prediction]) -> Vec<ReliabilityBucket> {
+    let width = 1.0 / RELIABILITY_BUCKETS as f64;
+    (0..RELIABILITY_BUCKETS)
+        .filter_map(|i| {
+            let members: Vec<&Prediction> = predictions
+                .iter()
+                .filter(|p| {
+                    ((p.probability * RELIABILITY_BUCKETS as f64) as usize)
+                        .min(RELIABILITY_BUCKETS - 1)
+                        == i
+                })
+                .collect();
+            if members.is_empty() {
+                return None;
+            }
+            let count = members.len();
+            Some(ReliabilityBucket {
+                lower: i as f64 * width,
+                upper: (i + 1) as f64 * width,
+                count,
+                mean_predicted: members.iter().map(|p| p.probability).sum::<f64>() / count as f64,
+                observed: members.iter().filter(|p| p.outcome).count() as f64 / count as f64,
+            })
+        })
+        .collect()
+}
+
+/// 生成校准报告；`predictions` 不能为空
+pub fn calibration_report(predictions: &[Prediction]) -> CalibrationReport {
+    let n = predictions.len() as f64;
+    let yours: Vec<f64> = predictions.iter().map(|p| p.probability).collect();
+    let market: Vec<f64> = predictions.iter().map(|p| p.market_prob).collect();
+    let shrink_factor = fit_shrink_factor(predictions);
+    let shrunk: Vec<f64> = predictions
+        .iter()
+        .map(|p| shrink_toward(p.probability, p.market_prob, shrink_factor))
+        .collect();
+    let wins = predictions.iter().filter(|p| p.outcome).count();
+
+    CalibrationReport {
+        count: predictions.len(),
+        wins,
+        mean_predicted: yours.iter().sum::<f64>() / n,
+        observed_rate: wins as f64 / n,
+        brier: brier(&yours, predictions),
+        log_loss: log_loss(&yours, predictions),
+        market_brier: brier(&market, predictions),
+        market_log_loss: log_loss(&market, predictions),
+        buckets: reliability_buckets(predictions),
+        shrink_factor,
+        shrunk_brier: brier(&shrunk, predictions),
+        shrunk_log_loss: log_loss(&shrunk, predictions),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_shrink, calibration_report, fit_shrink_factor, parse_predictions, shrink_toward,
+    };
+    use crate::types::Prediction;
+
+    fn prediction(probability: f64, market_prob: f64, outcome: bool) -> Prediction {
+        Prediction {
+            probability,
+            market_prob,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn parses_prediction_files() {
+        let csv = "outcome,odds,probability\nwin,2.5,48\n0,1.8,60\n";
+        let predictions = parse_predictions(csv, false).unwrap();
+        assert_eq!(predictions.len(), 2);
+        assert!(predictions[0].outcome);
+        assert!((predictions[0].market_prob - 0.4).abs() < 1e-12);
+        assert!((predictions[1].probability - 0.6).abs() < 1e-12);
+
+        let json = r#"[{"outcome":"loss","odds":35,"probability":45}]"#;
+        let predictions = parse_predictions(json, true).unwrap();
+        assert!((predictions[0].market_prob - 0.35).abs() < 1e-12);
+        assert!(!predictions[0].outcome);
+
+        assert!(parse_predictions("draw,2.0,50\n", false).is_err());
+        assert!(parse_predictions("1,2.0,100\n", false).is_err());
+    }
+
+    #[test]
+    fn scores_match_definitions() {
+        let predictions = [
+            prediction(0.8, 0.5, true),
+            prediction(0.6, 0.5, false),
+            prediction(0.3, 0.25, false),
+            prediction(0.9, 0.8, true),
+        ];
+        let report = calibration_report(&predictions);
+        // Brier = (0.04 + 0.36 + 0.09 + 0.01) / 4
+        assert!((report.brier - 0.125).abs() < 1e-12);
+        let log_loss = -(0.8f64.ln() + 0.4f64.ln() + 0.7f64.ln() + 0.9f64.ln()) / 4.0;
+        assert!((report.log_loss - log_loss).abs() < 1e-12);
+        assert!((report.market_brier - (0.25 + 0.25 + 0.0625 + 0.04) / 4.0).abs() < 1e-12);
+        assert_eq!(report.wins, 2);
+        assert!((report.mean_predicted - 0.65).abs() < 1e-12);
+
+        let buckets: Vec<(usize, usize)> = report
+            .buckets
+            .iter()
+            .map(|b| ((b.lower * 10.0).round() as usize, b.count))
+            .collect();
+        assert_eq!(buckets, vec![(3, 1), (6, 1), (8, 1), (9, 1)]);
+        // 预测好于市场时不需要收缩
+        assert!(report.shrink_factor > 0.999);
+    }
+
+    #[test]
+    fn overconfidence_is_shrunk_toward_market() {
+        // 市场 50%，你总说 70%，实际命中 55%：最优 k 使 0.5 + 0.2k = 0.55
+        let mut predictions = Vec::new();
+        for i in 0..100 {
+            predictions.push(prediction(0.7, 0.5, i < 55));
+        }
+        let k = fit_shrink_factor(&predictions);
+        assert!((k - 0.25).abs() < 1e-6, "k = {k}");
+        assert!((shrink_toward(0.7, 0.5, k) - 0.55).abs() < 1e-6);
+        let (shrunk, adjustment) = apply_shrink(0.7, 0.5, Some(0.25));
+        assert!((shrunk - 0.55).abs() < 1e-12);
+        assert_eq!(adjustment.unwrap().raw_probability, 0.7);
+        assert_eq!(apply_shrink(0.7, 0.5, None), (0.7, None));
+
+        let report = calibration_report(&predictions);
+        assert!(report.shrunk_brier < report.brier);
+        assert!(report.shrunk_log_loss < report.log_loss);
+    }
+}
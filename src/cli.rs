@@ -1,10 +1,47 @@
 //! CLI 命令行模式
 
-use crate::app::{ModeRequest, OutputFormat, execute_mode};
-use crate::display::{print_json_error, print_usage};
+use std::path::{Path, PathBuf};
+
+use crate::app::{ModeRequest, OutputFormat, emit_error, execute_mode};
+use crate::calibration::{ledger_predictions, load_predictions};
+use crate::currency::{load_fx_rate, parse_currency_code};
+use crate::display::print_usage;
+use crate::dutching::dutching_implied_prob;
+use crate::heat::load_open_positions;
+use crate::ledger::{
+    LEDGER_MODES, append_ledger_entry, build_ledger_entry, data_dir, ledger_path, load_ledger,
+    parse_ledger_date, summarize_ledger, today,
+};
+use crate::merton::validate_covariance;
+use crate::middle::validate_middle_lines;
 use crate::portfolio_input::{build_standard_leg, parse_portfolio_leg_descriptor};
-use crate::types::PortfolioScenario;
-use crate::validation::{parse_f64, parse_market_price, parse_odds, parse_percent, parse_positive};
+use crate::scale_out::{parse_scale_out_plan, validate_scale_out_plan};
+use crate::scanner::load_odds_snapshot;
+use crate::sizing::parse_trade_results;
+use crate::trade_history::load_trade_history;
+use crate::types::{
+    ArbitrageOptions, BackLayKind, BetSettlement, CapitalSource, CurrencySettings, DutchingTarget,
+    FuturesContract, HeatSettings, HedgeBet, LedgerCommand, LedgerRecord, LedgerStake,
+    LeverageSettings, MertonConstraints, MiddleBet, OptionMarket, OptionStrategy, PathSimulation,
+    PortfolioScenario, PositionSizer, PriceView, RecordSize, StockCosts, StockSizingOptions,
+    TradeDirection, TrailingStop, VolatilitySource,
+};
+use crate::validation::{
+    expand_per_leg, parse_f64, parse_market_price, parse_non_negative, parse_non_negative_integer,
+    parse_odds, parse_option_kind, parse_per_leg_percents, parse_per_leg_values, parse_percent,
+    parse_positive, parse_positive_integer, validate_stock_prices,
+};
+use crate::value_scan::load_model_probabilities;
+use crate::volatility::derive_volatility_levels;
+
+const DEFAULT_BOOTSTRAP_SAMPLES: usize = 1000;
+const DEFAULT_SEED: u64 = 42;
+const DEFAULT_CONFIDENCE: f64 = 0.9;
+const DEFAULT_TRAIL_MULT: f64 = 3.0;
+const DEFAULT_MAX_HOLDING_DAYS: usize = 252;
+const DEFAULT_SIMULATION_PATHS: usize = 10000;
+const MAX_SIMULATION_PATHS: usize = 1_000_000;
+const DEFAULT_OPTION_MULTIPLIER: u64 = 100;
 
 fn is_help_flag(flag: &str) -> bool {
     matches!(flag, "-h" | "-help" | "--help")
@@ -35,14 +72,602 @@ fn probability_sum_tolerance(scenario_count: usize) -> f64 {
     (scenario_count as f64) * 0.00005 + 1e-9
 }
 
-fn emit_error(output: OutputFormat, message: &str) {
-    if output.is_json() {
-        print_json_error(message);
-    } else {
-        println!("✗ {}", message);
+/// 取出 `--name <值>` 形式的可选参数，并从参数列表中移除
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let Some(pos) = args.iter().position(|a| a == name) else {
+        return Ok(None);
+    };
+    if pos + 1 >= args.len() {
+        return Err(format!("{name} 缺少参数值"));
+    }
+    let value = args.remove(pos + 1);
+    args.remove(pos);
+    Ok(Some(value))
+}
+
+/// 取出布尔开关参数，并从参数列表中移除
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let before = args.len();
+    args.retain(|a| a != name);
+    args.len() != before
+}
+
+/// 解析股票交易成本选项（融券费率仅适用于做空）
+fn take_stock_costs(
+    args: &mut Vec<String>,
+    direction: TradeDirection,
+) -> Result<StockCosts, String> {
+    let mut costs = StockCosts::default();
+    if let Some(v) = take_option(args, "--lot")? {
+        costs.lot_size = parse_positive_integer(&v, "每手股数")?;
+    }
+    if let Some(v) = take_option(args, "--commission")? {
+        costs.commission_per_share = parse_non_negative(&v, "每股佣金")?;
+    }
+    if let Some(v) = take_option(args, "--commission-pct")? {
+        costs.commission_rate = parse_percent(&v, "佣金费率")?;
+    }
+    if let Some(v) = take_option(args, "--min-commission")? {
+        costs.min_commission = parse_non_negative(&v, "最低佣金")?;
+    }
+    if let Some(v) = take_option(args, "--stamp-duty")? {
+        costs.stamp_duty_rate = parse_percent(&v, "印花税率")?;
+    }
+    if let Some(v) = take_option(args, "--borrow-rate")? {
+        if direction != TradeDirection::Short {
+            return Err("--borrow-rate 仅适用于做空（--short）".to_string());
+        }
+        costs.borrow_rate = parse_percent(&v, "融券年费率")?;
+    }
+    if let Some(v) = take_option(args, "--days")? {
+        costs.holding_days = parse_non_negative(&v, "持有天数")?;
+    }
+    Ok(costs)
+}
+
+/// 波动率止损选项
+struct VolatilityOptions {
+    source: VolatilitySource,
+    stop_mult: f64,
+    target_mult: f64,
+    daily_drift: Option<f64>,
+    estimate_probability: bool,
+}
+
+/// 解析波动率止损选项（`--atr` 或 `--vol`，未提供时返回 None）
+fn take_volatility_options(
+    args: &mut Vec<String>,
+    holding_days: f64,
+) -> Result<Option<VolatilityOptions>, String> {
+    let estimate_probability = take_flag(args, "--estimate-prob");
+    let atr = take_option(args, "--atr")?;
+    let vol = take_option(args, "--vol")?;
+    let stop_mult = take_option(args, "--stop-mult")?;
+    let target_mult = take_option(args, "--target-mult")?;
+    let drift = take_option(args, "--drift")?;
+
+    let source = match (atr, vol) {
+        (None, None) => {
+            if stop_mult.is_some() || target_mult.is_some() || drift.is_some() {
+                return Err("波动率倍数需要配合 --atr 或 --vol 使用".to_string());
+            }
+            if estimate_probability {
+                return Err("--estimate-prob 需要配合 --atr 或 --vol 使用".to_string());
+            }
+            return Ok(None);
+        }
+        (Some(_), Some(_)) => return Err("--atr 与 --vol 只能二选一".to_string()),
+        (Some(v), None) => VolatilitySource::Atr(parse_positive(&v, "ATR")?),
+        (None, Some(v)) => VolatilitySource::Sigma {
+            daily_vol: parse_positive(&v, "日波动率")? / 100.0,
+            days: if holding_days > 0.0 {
+                holding_days
+            } else {
+                1.0
+            },
+        },
+    };
+
+    let (Some(stop_mult), Some(target_mult)) = (stop_mult, target_mult) else {
+        return Err("波动率止损需要同时提供 --stop-mult 与 --target-mult".to_string());
+    };
+
+    Ok(Some(VolatilityOptions {
+        source,
+        stop_mult: parse_positive(&stop_mult, "止损倍数")?,
+        target_mult: parse_positive(&target_mult, "止盈倍数")?,
+        daily_drift: match drift {
+            Some(v) => Some(parse_f64(&v, "每日漂移")? / 100.0),
+            None => None,
+        },
+        estimate_probability,
+    }))
+}
+
+/// 解析凯利之外的仓位算法选项（用于对比）
+fn take_position_sizers(args: &mut Vec<String>) -> Result<Vec<PositionSizer>, String> {
+    let mut sizers = Vec::new();
+    if let Some(v) = take_option(args, "--risk-pct")? {
+        sizers.push(PositionSizer::FixedFractional {
+            risk_fraction: parse_percent(&v, "每笔风险比例")?,
+        });
+    }
+    if let Some(v) = take_option(args, "--fixed-ratio")? {
+        let (delta, profit) = match v.split_once(':') {
+            Some((delta, profit)) => (delta, Some(profit)),
+            None => (v.as_str(), None),
+        };
+        sizers.push(PositionSizer::FixedRatio {
+            delta: parse_positive(delta, "固定比率增量 Δ")?,
+            accumulated_profit: match profit {
+                Some(p) => parse_f64(p, "累计利润")?,
+                None => 0.0,
+            },
+        });
+    }
+    let target_vol = take_option(args, "--target-vol")?;
+    let asset_vol = take_option(args, "--asset-vol")?;
+    match (target_vol, asset_vol) {
+        (None, None) => {}
+        (Some(target), Some(asset)) => sizers.push(PositionSizer::VolatilityTarget {
+            target_vol: parse_positive(&target, "目标年化波动率")? / 100.0,
+            asset_vol: parse_positive(&asset, "标的年化波动率")? / 100.0,
+        }),
+        _ => return Err("波动率目标需要同时提供 --target-vol 与 --asset-vol".to_string()),
+    }
+    if let Some(v) = take_option(args, "--trades")? {
+        sizers.push(PositionSizer::OptimalF {
+            trades: parse_trade_results(&v)?,
+        });
+    }
+    Ok(sizers)
+}
+
+/// 解析移动止损模拟选项（`--trail-pct` 或 `--trail-atr`，均未提供时返回 None）
+fn take_trailing_options(
+    args: &mut Vec<String>,
+) -> Result<Option<(TrailingStop, PathSimulation)>, String> {
+    let trail_pct = take_option(args, "--trail-pct")?;
+    let trail_atr = take_option(args, "--trail-atr")?;
+    let trail_mult = take_option(args, "--trail-mult")?;
+    let gbm = take_option(args, "--gbm")?;
+    let max_days = take_option(args, "--max-days")?;
+    let paths = take_option(args, "--paths")?;
+    let seed = take_option(args, "--seed")?;
+
+    let trailing = match (trail_pct, trail_atr) {
+        (None, None) => {
+            if trail_mult.is_some()
+                || gbm.is_some()
+                || max_days.is_some()
+                || paths.is_some()
+                || seed.is_some()
+            {
+                return Err("移动止损模拟需要配合 --trail-pct 或 --trail-atr 使用".to_string());
+            }
+            return Ok(None);
+        }
+        (Some(_), Some(_)) => return Err("--trail-pct 与 --trail-atr 只能二选一".to_string()),
+        (Some(v), None) => {
+            let pct = parse_percent(&v, "移动止损回撤")?;
+            if pct <= 0.0 || pct >= 1.0 {
+                return Err("移动止损回撤必须在 (0, 100) 之间".to_string());
+            }
+            TrailingStop::Percent(pct)
+        }
+        (None, Some(v)) => TrailingStop::Atr {
+            atr: parse_positive(&v, "ATR")?,
+            mult: match trail_mult {
+                Some(m) => parse_positive(&m, "移动止损倍数")?,
+                None => DEFAULT_TRAIL_MULT,
+            },
+        },
+    };
+
+    let Some(gbm) = gbm else {
+        return Err("移动止损模拟需要 --gbm <年化漂移%>:<年化波动率%>".to_string());
+    };
+    let Some((drift, vol)) = gbm.split_once(':') else {
+        return Err("--gbm 格式应为 <年化漂移%>:<年化波动率%>".to_string());
+    };
+    let paths = match paths {
+        Some(v) => parse_positive_integer(&v, "模拟路径数量")? as usize,
+        None => DEFAULT_SIMULATION_PATHS,
+    };
+    if paths > MAX_SIMULATION_PATHS {
+        return Err(format!("模拟路径数量不能超过 {MAX_SIMULATION_PATHS}"));
+    }
+
+    Ok(Some((
+        trailing,
+        PathSimulation {
+            annual_drift: parse_f64(drift, "年化漂移")? / 100.0,
+            annual_vol: parse_positive(vol, "年化波动率")? / 100.0,
+            max_days: match max_days {
+                Some(v) => parse_positive_integer(&v, "最长持有天数")? as usize,
+                None => DEFAULT_MAX_HOLDING_DAYS,
+            },
+            paths,
+            seed: match seed {
+                Some(v) => parse_non_negative_integer(&v, "随机种子")?,
+                None => DEFAULT_SEED,
+            },
+        },
+    )))
+}
+
+/// 解析组合热度选项（`--positions` 持仓文件与 `--max-heat` 上限，均未提供时返回 None）
+fn take_heat_settings(args: &mut Vec<String>) -> Result<Option<HeatSettings>, String> {
+    let source = take_option(args, "--positions")?;
+    let max_heat = match take_option(args, "--max-heat")? {
+        Some(v) => Some(parse_percent(&v, "热度上限")?),
+        None => None,
+    };
+    if source.is_none() && max_heat.is_none() {
+        return Ok(None);
+    }
+    let positions = match &source {
+        Some(path) => load_open_positions(path)?,
+        None => Vec::new(),
+    };
+    Ok(Some(HeatSettings {
+        source,
+        positions,
+        max_heat,
+    }))
+}
+
+/// 解析杠杆融资选项（`--max-leverage` 最大杠杆，`--financing-rate` 融资年利率，
+/// `--margin-call` 维持保证金比例；未提供最大杠杆时返回 None）
+fn take_leverage_settings(
+    args: &mut Vec<String>,
+    holding_days: f64,
+) -> Result<Option<LeverageSettings>, String> {
+    let max_leverage = take_option(args, "--max-leverage")?;
+    let financing_rate = take_option(args, "--financing-rate")?;
+    let margin_call = take_option(args, "--margin-call")?;
+
+    let Some(max_leverage) = max_leverage else {
+        if financing_rate.is_some() || margin_call.is_some() {
+            return Err(
+                "--financing-rate 与 --margin-call 需要配合 --max-leverage 使用".to_string(),
+            );
+        }
+        return Ok(None);
+    };
+    let financing_rate = match financing_rate {
+        Some(v) => parse_percent(&v, "融资年利率")?,
+        None => 0.0,
+    };
+    if financing_rate > 0.0 && holding_days <= 0.0 {
+        return Err("计算融资成本需要 --days <持有天数>".to_string());
+    }
+    let maintenance_margin = match margin_call {
+        Some(v) => parse_percent(&v, "维持保证金比例")?,
+        None => 0.0,
+    };
+    if maintenance_margin >= 1.0 {
+        return Err("维持保证金比例必须小于 100".to_string());
+    }
+
+    Ok(Some(LeverageSettings {
+        max_leverage: parse_positive(&max_leverage, "最大杠杆")?,
+        financing_rate,
+        holding_days,
+        maintenance_margin,
+    }))
+}
+
+/// 解析期货合约选项（`--multiplier` 合约乘数，`--tick` 最小变动价位[:每跳价值]，
+/// `--margin` 每手保证金，`--contract-fee` 每手单边手续费；均未提供时返回 None）
+fn take_futures_contract(args: &mut Vec<String>) -> Result<Option<FuturesContract>, String> {
+    let multiplier = take_option(args, "--multiplier")?;
+    let tick = take_option(args, "--tick")?;
+    let margin = take_option(args, "--margin")?;
+    let fee = take_option(args, "--contract-fee")?;
+    if multiplier.is_none() && tick.is_none() && margin.is_none() && fee.is_none() {
+        return Ok(None);
+    }
+
+    let multiplier = match multiplier {
+        Some(v) => Some(parse_positive(&v, "合约乘数")?),
+        None => None,
+    };
+    let (tick_size, multiplier) = match tick {
+        Some(v) => {
+            let (size, value) = match v.split_once(':') {
+                Some((size, value)) => (size, Some(parse_positive(value, "每跳价值")?)),
+                None => (v.as_str(), None),
+            };
+            let size = parse_positive(size, "最小变动价位")?;
+            let multiplier = match (multiplier, value) {
+                (Some(m), Some(value)) => {
+                    if (size * m - value).abs() > 1e-9 * value.max(1.0) {
+                        return Err(format!(
+                            "每跳价值 {value} 与最小变动价位 × 合约乘数 ({}) 不一致",
+                            size * m
+                        ));
+                    }
+                    m
+                }
+                (Some(m), None) => m,
+                (None, Some(value)) => value / size,
+                (None, None) => {
+                    return Err("--tick 未给出每跳价值时需要 --multiplier <合约乘数>".to_string());
+                }
+            };
+            (Some(size), multiplier)
+        }
+        None => match multiplier {
+            Some(m) => (None, m),
+            None => {
+                return Err(
+                    "期货合约需要 --multiplier <合约乘数> 或 --tick <最小变动价位>:<每跳价值>"
+                        .to_string(),
+                );
+            }
+        },
+    };
+    let Some(margin) = margin else {
+        return Err("期货合约需要 --margin <每手保证金>".to_string());
+    };
+
+    Ok(Some(FuturesContract {
+        multiplier,
+        tick_size,
+        initial_margin: parse_positive(&margin, "每手保证金")?,
+        fee_per_contract: match fee {
+            Some(v) => parse_non_negative(&v, "每手手续费")?,
+            None => 0.0,
+        },
+    }))
+}
+
+/// 解析组合模式的杠杆融资选项（持有天数由 `--days` 指定）
+fn take_portfolio_leverage(args: &mut Vec<String>) -> Result<Option<LeverageSettings>, String> {
+    let holding_days = match take_option(args, "--days")? {
+        Some(v) => Some(parse_non_negative(&v, "持有天数")?),
+        None => None,
+    };
+    let leverage = take_leverage_settings(args, holding_days.unwrap_or(0.0))?;
+    if leverage.is_none() && holding_days.is_some() {
+        return Err("组合模式的 --days 需要配合 --max-leverage 使用".to_string());
+    }
+    Ok(leverage)
+}
+
+/// 期权价格观点的原始选项
+struct PriceViewOptions {
+    vol: Option<String>,
+    drift: Option<String>,
+    scenarios: Option<String>,
+}
+
+/// 取出期权价格观点选项（`--view-vol` [`--view-drift`] 或 `--view`）
+fn take_price_view_options(args: &mut Vec<String>) -> Result<PriceViewOptions, String> {
+    Ok(PriceViewOptions {
+        vol: take_option(args, "--view-vol")?,
+        drift: take_option(args, "--view-drift")?,
+        scenarios: take_option(args, "--view")?,
+    })
+}
+
+/// 解析到期价格观点：主观波动率（对数正态）或目标价格情景
+///
+/// 未指定漂移时按无风险利率（风险中性漂移）处理。
+fn parse_price_view(opts: PriceViewOptions, rate: f64) -> Result<PriceView, String> {
+    let PriceViewOptions {
+        vol: view_vol,
+        drift: view_drift,
+        scenarios,
+    } = opts;
+
+    match (view_vol, scenarios) {
+        (Some(_), Some(_)) => Err("--view-vol 与 --view 只能二选一".to_string()),
+        (None, None) => Err(
+            "期权模式需要价格观点: --view-vol <主观波动率%> 或 --view <价格:概率%,...>".to_string(),
+        ),
+        (Some(vol), None) => Ok(PriceView::Lognormal {
+            drift: match view_drift {
+                Some(v) => parse_f64(&v, "主观漂移")? / 100.0,
+                None => rate,
+            },
+            vol: parse_positive(&vol, "主观波动率")? / 100.0,
+        }),
+        (None, Some(raw)) => {
+            if view_drift.is_some() {
+                return Err("--view-drift 只能与 --view-vol 一起使用".to_string());
+            }
+            let mut points = Vec::new();
+            for (i, item) in raw.split(',').enumerate() {
+                let Some((price, prob)) = item.trim().split_once(':') else {
+                    return Err(format!("情景{}格式错误，应为 价格:概率%", i + 1));
+                };
+                points.push((
+                    parse_non_negative(price, &format!("情景{}价格", i + 1))?,
+                    parse_percent(prob, &format!("情景{}概率", i + 1))?,
+                ));
+            }
+            let prob_sum: f64 = points.iter().map(|(_, p)| p).sum();
+            let tolerance = probability_sum_tolerance(points.len());
+            if (prob_sum - 1.0).abs() > tolerance {
+                return Err(format!(
+                    "所有情景概率之和必须约等于 100%，当前为 {:.4}%",
+                    prob_sum * 100.0
+                ));
+            }
+            Ok(PriceView::Scenarios(points))
+        }
+    }
+}
+
+/// 解析货币选项（`--currency` 下注货币，`--capital-currency` 本金货币，
+/// `--fx` 汇率或 `--fx-file` 汇率文件；均未提供时返回 None）
+fn take_currency_settings(args: &mut Vec<String>) -> Result<Option<CurrencySettings>, String> {
+    let stake = take_option(args, "--currency")?;
+    let capital = take_option(args, "--capital-currency")?;
+    let fx = take_option(args, "--fx")?;
+    let fx_file = take_option(args, "--fx-file")?;
+
+    let Some(stake) = stake else {
+        if capital.is_some() || fx.is_some() || fx_file.is_some() {
+            return Err(
+                "--capital-currency、--fx 与 --fx-file 需要配合 --currency <下注货币> 使用"
+                    .to_string(),
+            );
+        }
+        return Ok(None);
+    };
+    let stake = parse_currency_code(&stake)?;
+    let capital = match capital {
+        Some(v) => parse_currency_code(&v)?,
+        None => stake.clone(),
+    };
+    let rate = match (fx, fx_file) {
+        (Some(_), Some(_)) => return Err("--fx 与 --fx-file 不能同时使用".to_string()),
+        (Some(v), None) => parse_positive(&v, "汇率")?,
+        (None, Some(path)) => load_fx_rate(&path, &stake, &capital)?,
+        (None, None) if stake == capital => 1.0,
+        (None, None) => {
+            return Err(format!(
+                "{stake} 与 {capital} 之间需要 --fx <汇率> 或 --fx-file <汇率文件>"
+            ));
+        }
+    };
+
+    Ok(Some(CurrencySettings {
+        stake,
+        capital,
+        rate,
+    }))
+}
+
+/// 全局选项：货币设置、账本文件与 `--ledger` 的默认本金
+struct GlobalOptions {
+    currency: Option<CurrencySettings>,
+    ledger_path: PathBuf,
+    /// 账本可用余额（本金货币），未输入本金时作为本金
+    ledger_capital: Option<f64>,
+    /// `--record`：把计算出的仓位记入账本
+    record: Option<LedgerRecord>,
+    /// `--shrink` 收缩系数
+    shrink: Option<f64>,
+}
+
+/// 解析账本选项：`--data-dir <目录>` 指定数据目录，`--ledger` 以账本可用余额作为默认本金
+fn take_ledger_settings(args: &mut Vec<String>) -> Result<(PathBuf, Option<f64>), String> {
+    let dir = take_option(args, "--data-dir")?.map(PathBuf::from);
+    let path = ledger_path(&data_dir(dir));
+    if !take_flag(args, "--ledger") {
+        return Ok((path, None));
+    }
+    let balance = summarize_ledger(&load_ledger(&path)?).balance;
+    if balance <= 0.0 {
+        return Err("账本可用余额为零，请先用 bo -L deposit <金额> 入金".to_string());
+    }
+    Ok((path, Some(balance)))
+}
+
+/// 解析 `--record <full|half|quarter|stake>`：按全/半/1/4凯利仓位或按输入的总投注分配金额记入账本，
+/// `--market`、`--label` 为记录的盘口类型与备注（扫描模式默认取报价中的盘口与结果）
+fn take_record_settings(
+    args: &mut Vec<String>,
+    ledger_path: &Path,
+) -> Result<Option<LedgerRecord>, String> {
+    let Some(size) = take_option(args, "--record")? else {
+        return Ok(None);
+    };
+    let size = match size.as_str() {
+        "full" => RecordSize::Kelly(1.0),
+        "half" => RecordSize::Kelly(0.5),
+        "quarter" => RecordSize::Kelly(0.25),
+        "stake" => RecordSize::Stake,
+        _ => return Err("--record 必须为 full、half、quarter 或 stake".to_string()),
+    };
+    Ok(Some(LedgerRecord {
+        path: ledger_path.to_path_buf(),
+        date: today(),
+        size,
+        market: take_option(args, "--market")?.unwrap_or_default(),
+        label: take_option(args, "--label")?.unwrap_or_default(),
+    }))
+}
+
+/// 未输入本金时填入账本可用余额，返回本金来源
+fn fill_ledger_capital(request: &mut ModeRequest, ledger_capital: Option<f64>) -> CapitalSource {
+    match (ledger_capital, request.capital_mut()) {
+        (Some(balance), Some(capital)) if capital.is_none() => {
+            *capital = Some(balance);
+            CapitalSource::Ledger
+        }
+        _ => CapitalSource::Input,
+    }
+}
+
+/// 执行请求：`--ledger` 时未输入的本金取账本可用余额
+fn run_mode(mut request: ModeRequest, output: OutputFormat, globals: &GlobalOptions) {
+    let source = fill_ledger_capital(&mut request, globals.ledger_capital);
+    if let Err(e) = fill_shrink(&mut request, globals.shrink) {
+        emit_error(output, &e);
+        return;
+    }
+    if let Err(e) = check_record(&request, globals.record.as_ref(), source) {
+        emit_error(output, &e);
+        return;
+    }
+    execute_mode(
+        request,
+        output,
+        globals.currency.as_ref(),
+        source,
+        globals.record.as_ref(),
+    );
+}
+
+/// 填入 `--shrink` 收缩系数；其他模式没有市场概率可供收缩，指定时报错
+fn fill_shrink(request: &mut ModeRequest, factor: Option<f64>) -> Result<(), String> {
+    match (factor, request.shrink_mut()) {
+        (None, _) => Ok(()),
+        (Some(factor), Some(shrink)) => {
+            *shrink = Some(factor);
+            Ok(())
+        }
+        (Some(_), None) => Err("--shrink 只适用于标准模式与 Polymarket 模式".to_string()),
+    }
+}
+
+/// `--record` 的记账方式需适用于该模式；`stake` 记录的是输入的总投注，不能取账本余额
+fn check_record(
+    request: &ModeRequest,
+    record: Option<&LedgerRecord>,
+    source: CapitalSource,
+) -> Result<(), String> {
+    let Some(record) = record else {
+        return Ok(());
+    };
+    if record.size == RecordSize::Stake && source == CapitalSource::Ledger {
+        return Err(
+            "--record stake 需要在命令行输入总投注金额，不能以账本可用余额全额下注".to_string(),
+        );
+    }
+    request.check_record_size(record.size)
+}
+
+/// 解析收缩系数（0 到 1 之间）
+fn parse_shrink_factor(input: &str) -> Result<f64, String> {
+    match parse_f64(input, "收缩系数")? {
+        k if (0.0..=1.0).contains(&k) => Ok(k),
+        _ => Err("收缩系数必须在 0 到 1 之间".to_string()),
     }
 }
 
+/// 解析 `--shrink <系数>`：下注前把输入概率向市场隐含概率收缩
+fn take_shrink_setting(args: &mut Vec<String>) -> Result<Option<f64>, String> {
+    take_option(args, "--shrink")?
+        .map(|value| parse_shrink_factor(&value))
+        .transpose()
+}
+
 /// 处理命令行参数
 pub fn handle_args(args: Vec<String>) {
     let output = if args.iter().any(|a| a == "--json") {
@@ -51,7 +676,42 @@ pub fn handle_args(args: Vec<String>) {
         OutputFormat::Text
     };
 
-    let args: Vec<String> = args.into_iter().filter(|a| a != "--json").collect();
+    let mut args: Vec<String> = args.into_iter().filter(|a| a != "--json").collect();
+    let currency = match take_currency_settings(&mut args) {
+        Ok(settings) => settings,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let (ledger_path, ledger_capital) = match take_ledger_settings(&mut args) {
+        Ok(settings) => settings,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let record = match take_record_settings(&mut args, &ledger_path) {
+        Ok(record) => record,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let shrink = match take_shrink_setting(&mut args) {
+        Ok(shrink) => shrink,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let globals = GlobalOptions {
+        currency,
+        ledger_path,
+        ledger_capital,
+        record,
+        shrink,
+    };
 
     if args.len() == 2 && is_help_flag(&args[1]) {
         print_usage();
@@ -71,30 +731,63 @@ pub fn handle_args(args: Vec<String>) {
     let is_stock = args.iter().any(|a| a == "-s");
     let is_arbitrage = args.iter().any(|a| a == "-a");
     let is_multi_arbitrage = args.iter().any(|a| a == "-A");
+    let is_back_lay = args.iter().any(|a| a == "-b");
+    let is_middle = args.iter().any(|a| a == "-M");
+    let is_hedge = args.iter().any(|a| a == "-H");
+    let is_dutching = args.iter().any(|a| a == "-d");
+    let is_scan = args.iter().any(|a| a == "-S");
+    let is_value_scan = args.iter().any(|a| a == "-V");
     let is_nash = args.iter().any(|a| a == "-n");
     let is_portfolio_correlated = args.iter().any(|a| a == "-K");
     let is_portfolio = args.iter().any(|a| a == "-k");
-
-    if is_portfolio_correlated {
-        handle_portfolio_correlated(args, output);
+    let is_trade_history = args.iter().any(|a| a == "-T");
+    let is_option = args.iter().any(|a| a == "-o");
+    let is_merton = args.iter().any(|a| a == "-m");
+    let is_ledger = args.iter().any(|a| a == "-L");
+    let is_calibration = args.iter().any(|a| a == "-C");
+
+    if is_ledger {
+        handle_ledger(args, output, &globals);
+    } else if is_calibration {
+        handle_calibration(args, output, &globals);
+    } else if is_trade_history {
+        handle_trade_history(args, output, &globals);
+    } else if is_option {
+        handle_option(args, output, &globals);
+    } else if is_merton {
+        handle_merton(args, output, &globals);
+    } else if is_portfolio_correlated {
+        handle_portfolio_correlated(args, output, &globals);
     } else if is_portfolio {
-        handle_portfolio(args, output);
+        handle_portfolio(args, output, &globals);
     } else if is_nash {
-        handle_nash(args, output);
+        handle_nash(args, output, &globals);
+    } else if is_value_scan {
+        handle_value_scan(args, output, &globals);
+    } else if is_scan {
+        handle_scan(args, output, &globals);
+    } else if is_dutching {
+        handle_dutching(args, output, &globals);
+    } else if is_hedge {
+        handle_hedge(args, output, &globals);
+    } else if is_middle {
+        handle_middle(args, output, &globals);
+    } else if is_back_lay {
+        handle_back_lay(args, output, &globals);
     } else if is_multi_arbitrage {
-        handle_multi_arbitrage(args, output);
+        handle_multi_arbitrage(args, output, &globals);
     } else if is_arbitrage {
-        handle_arbitrage(args, output);
+        handle_arbitrage(args, output, &globals);
     } else if is_stock {
-        handle_stock(args, output);
+        handle_stock(args, output, &globals);
     } else if is_polymarket {
-        handle_polymarket(args, output);
+        handle_polymarket(args, output, &globals);
     } else {
-        handle_standard(args, output);
+        handle_standard(args, output, &globals);
     }
 }
 
-fn handle_standard(args: Vec<String>, output: OutputFormat) {
+fn handle_standard(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
     match args.len() {
         2 => {
             if is_help_flag(&args[1]) {
@@ -123,13 +816,15 @@ fn handle_standard(args: Vec<String>, output: OutputFormat) {
                     return;
                 }
             };
-            execute_mode(
+            run_mode(
                 ModeRequest::Standard {
                     odds,
                     win_rate,
                     capital: None,
+                    shrink: None,
                 },
                 output,
+                globals,
             );
         }
         4 => {
@@ -154,13 +849,15 @@ fn handle_standard(args: Vec<String>, output: OutputFormat) {
                     return;
                 }
             };
-            execute_mode(
+            run_mode(
                 ModeRequest::Standard {
                     odds,
                     win_rate,
                     capital: Some(capital),
+                    shrink: None,
                 },
                 output,
+                globals,
             );
         }
         _ => {
@@ -172,7 +869,7 @@ fn handle_standard(args: Vec<String>, output: OutputFormat) {
     }
 }
 
-fn handle_polymarket(args: Vec<String>, output: OutputFormat) {
+fn handle_polymarket(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
     let pm_args: Vec<&String> = args.iter().filter(|&a| a != "-p").collect();
 
     match pm_args.len() {
@@ -194,13 +891,15 @@ fn handle_polymarket(args: Vec<String>, output: OutputFormat) {
                     return;
                 }
             };
-            execute_mode(
+            run_mode(
                 ModeRequest::Polymarket {
                     market_price,
                     your_probability: your_prob,
                     capital: None,
+                    shrink: None,
                 },
                 output,
+                globals,
             );
         }
         4 => {
@@ -225,13 +924,15 @@ fn handle_polymarket(args: Vec<String>, output: OutputFormat) {
                     return;
                 }
             };
-            execute_mode(
+            run_mode(
                 ModeRequest::Polymarket {
                     market_price,
                     your_probability: your_prob,
                     capital: Some(capital),
+                    shrink: None,
                 },
                 output,
+                globals,
             );
         }
         _ => {
@@ -245,14 +946,165 @@ fn handle_polymarket(args: Vec<String>, output: OutputFormat) {
     }
 }
 
-fn handle_stock(args: Vec<String>, output: OutputFormat) {
+fn handle_stock(mut args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    let direction = if take_flag(&mut args, "--short") {
+        TradeDirection::Short
+    } else {
+        TradeDirection::Long
+    };
+    let costs = match take_stock_costs(&mut args, direction) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let volatility = match take_volatility_options(&mut args, costs.holding_days) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let trailing = match take_trailing_options(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let sizers = match take_position_sizers(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let heat = match take_heat_settings(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let leverage = match take_leverage_settings(&mut args, costs.holding_days) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let futures = match take_futures_contract(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    if futures.is_some() {
+        if !sizers.is_empty() || heat.is_some() || leverage.is_some() {
+            emit_error(output, "期货合约暂不支持仓位算法对比、组合热度或杠杆融资");
+            return;
+        }
+        if costs.has_fees() || costs.lot_size != 1 {
+            emit_error(
+                output,
+                "期货合约按手计费，请使用 --contract-fee 代替股票成本选项",
+            );
+            return;
+        }
+    }
+    let sizing = Box::new(StockSizingOptions {
+        sizers,
+        heat,
+        leverage,
+        futures,
+    });
+    let breakeven_after_first = take_flag(&mut args, "--breakeven");
+    let plan = match take_option(&mut args, "--plan") {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
     let s_args: Vec<&String> = args.iter().filter(|&a| a != "-s").collect();
 
+    if let Some((trailing, simulation)) = trailing {
+        if volatility.is_some()
+            || plan.is_some()
+            || !sizing.sizers.is_empty()
+            || sizing.heat.is_some()
+            || sizing.leverage.is_some()
+            || sizing.futures.is_some()
+        {
+            emit_error(
+                output,
+                "移动止损模拟不能与波动率止损、--plan、仓位算法对比、组合热度、杠杆融资或期货合约同时使用",
+            );
+            return;
+        }
+        handle_stock_trailing(
+            &s_args, trailing, simulation, direction, costs, output, globals,
+        );
+        return;
+    }
+
+    if let Some(opts) = volatility {
+        if plan.is_some() {
+            emit_error(output, "--plan 不能与波动率止损同时使用");
+            return;
+        }
+        handle_stock_volatility(&s_args, opts, direction, costs, sizing, output, globals);
+        return;
+    }
+
+    if let Some(plan) = plan {
+        if !sizing.sizers.is_empty() {
+            emit_error(output, "分批止盈计划暂不支持仓位算法对比");
+            return;
+        }
+        if sizing.heat.is_some() {
+            emit_error(output, "分批止盈计划暂不支持组合热度限制");
+            return;
+        }
+        if sizing.leverage.is_some() {
+            emit_error(output, "分批止盈计划暂不支持杠杆融资");
+            return;
+        }
+        if sizing.futures.is_some() {
+            emit_error(output, "分批止盈计划暂不支持期货合约");
+            return;
+        }
+        handle_stock_scale_out(
+            &s_args,
+            &plan,
+            direction,
+            breakeven_after_first,
+            costs,
+            output,
+            globals,
+        );
+        return;
+    }
+
     match s_args.len() {
         1 => {
             emit_error(output, "股票模式参数不足");
         }
         5 => {
+            if !sizing.sizers.is_empty() {
+                emit_error(output, "仓位算法对比需要提供本金");
+                return;
+            }
+            if sizing.heat.is_some() {
+                emit_error(output, "组合热度需要提供本金");
+                return;
+            }
+            if sizing.futures.is_some() {
+                emit_error(output, "期货合约需要提供本金");
+                return;
+            }
             let entry = match parse_positive(s_args[1], "当前价") {
                 Ok(v) => v,
                 Err(e) => {
@@ -282,21 +1134,23 @@ fn handle_stock(args: Vec<String>, output: OutputFormat) {
                 }
             };
 
-            if target <= entry || stop >= entry {
-                emit_error(
-                    output,
-                    "参数错误: 止盈价必须大于当前价，止损价必须小于当前价",
-                );
+            if let Err(e) = validate_stock_prices(direction, entry, target, stop) {
+                emit_error(output, &e);
             } else {
-                execute_mode(
+                run_mode(
                     ModeRequest::Stock {
+                        direction,
                         entry_price: entry,
                         target_price: target,
                         stop_loss: stop,
                         win_rate,
+                        costs,
+                        volatility: None,
+                        sizing,
                         capital: None,
                     },
                     output,
+                    globals,
                 );
             }
         }
@@ -337,21 +1191,23 @@ fn handle_stock(args: Vec<String>, output: OutputFormat) {
                 }
             };
 
-            if target <= entry || stop >= entry {
-                emit_error(
-                    output,
-                    "参数错误: 止盈价必须大于当前价，止损价必须小于当前价",
-                );
+            if let Err(e) = validate_stock_prices(direction, entry, target, stop) {
+                emit_error(output, &e);
             } else {
-                execute_mode(
+                run_mode(
                     ModeRequest::Stock {
+                        direction,
                         entry_price: entry,
                         target_price: target,
                         stop_loss: stop,
                         win_rate,
+                        costs,
+                        volatility: None,
+                        sizing,
                         capital: Some(capital),
                     },
                     output,
+                    globals,
                 );
             }
         }
@@ -359,27 +1215,321 @@ fn handle_stock(args: Vec<String>, output: OutputFormat) {
             emit_error(output, "股票模式参数错误");
             if !output.is_json() {
                 println!();
-                println!("用法: bo -s <当前价> <止盈价> <止损价> <胜率> [本金]");
+                println!(
+                    "用法: bo -s <当前价> <止盈价> <止损价> <胜率> [本金] [--short] [成本选项]"
+                );
+                println!(
+                    "成本选项: --lot <每手股数> --commission <每股佣金> --commission-pct <佣金费率%> --min-commission <最低佣金> --stamp-duty <印花税率%> --borrow-rate <融券年费率%> --days <持有天数>"
+                );
                 println!("示例: bo -s 100 120 90 60    # 当前价100，止盈120，止损90，胜率60%");
             }
         }
     }
 }
 
-fn handle_arbitrage(args: Vec<String>, output: OutputFormat) {
-    let a_args: Vec<&String> = args.iter().filter(|&a| a != "-a").collect();
+/// 波动率止损：`-s <当前价> [胜率] [本金] --atr <ATR> | --vol <日波动率%> ...`
+fn handle_stock_volatility(
+    s_args: &[&String],
+    opts: VolatilityOptions,
+    direction: TradeDirection,
+    costs: StockCosts,
+    sizing: Box<StockSizingOptions>,
+    output: OutputFormat,
+    globals: &GlobalOptions,
+) {
+    // 估计概率时不需要输入胜率
+    let estimate = opts.estimate_probability || opts.daily_drift.is_some();
+    let base_len = if estimate { 2 } else { 3 };
+    if s_args.len() != base_len && s_args.len() != base_len + 1 {
+        emit_error(output, "波动率止损模式参数错误");
+        if !output.is_json() {
+            println!();
+            println!(
+                "用法: bo -s <当前价> <胜率> [本金] --atr <ATR> --stop-mult <k> --target-mult <m>"
+            );
+            println!(
+                "      bo -s <当前价> [本金] --vol <日波动率%> --days <天数> --stop-mult <k> --target-mult <m> --estimate-prob [--drift <每日漂移%>]"
+            );
+            println!("示例: bo -s 100 55 10000 --atr 2.5 --stop-mult 2 --target-mult 3");
+        }
+        return;
+    }
 
-    match a_args.len() {
-        1 => {
-            emit_error(output, "套利模式参数不足");
+    let entry = match parse_positive(s_args[1], "当前价") {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
         }
-        3 => {
-            let odds1 = match parse_odds(a_args[1], "赔率1") {
-                Ok(v) => v,
-                Err(e) => {
-                    emit_error(output, &e);
-                    return;
-                }
+    };
+    let levels = match derive_volatility_levels(
+        direction,
+        entry,
+        opts.source,
+        opts.stop_mult,
+        opts.target_mult,
+        opts.daily_drift,
+        estimate,
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let win_rate = match levels.hit_probability {
+        Some(p) => p,
+        None => match parse_percent(s_args[2], "胜率") {
+            Ok(v) => v,
+            Err(e) => {
+                emit_error(output, &e);
+                return;
+            }
+        },
+    };
+    let capital = if s_args.len() == base_len + 1 {
+        match parse_positive(s_args[base_len], "本金") {
+            Ok(v) => Some(v),
+            Err(e) => {
+                emit_error(output, &e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+    if capital.is_none() && !sizing.sizers.is_empty() {
+        emit_error(output, "仓位算法对比需要提供本金");
+        return;
+    }
+    if capital.is_none() && sizing.heat.is_some() {
+        emit_error(output, "组合热度需要提供本金");
+        return;
+    }
+    if capital.is_none() && sizing.futures.is_some() {
+        emit_error(output, "期货合约需要提供本金");
+        return;
+    }
+
+    run_mode(
+        ModeRequest::Stock {
+            direction,
+            entry_price: entry,
+            target_price: levels.target_price,
+            stop_loss: levels.stop_loss,
+            win_rate,
+            costs,
+            volatility: Some(levels),
+            sizing,
+            capital,
+        },
+        output,
+        globals,
+    );
+}
+
+/// 移动止损模拟：`-s <当前价> [本金] --trail-pct <回撤%> | --trail-atr <ATR> --gbm <漂移%>:<波动率%>`
+fn handle_stock_trailing(
+    s_args: &[&String],
+    trailing: TrailingStop,
+    simulation: PathSimulation,
+    direction: TradeDirection,
+    costs: StockCosts,
+    output: OutputFormat,
+    globals: &GlobalOptions,
+) {
+    if s_args.len() != 2 && s_args.len() != 3 {
+        emit_error(output, "移动止损模拟参数错误");
+        if !output.is_json() {
+            println!();
+            println!(
+                "用法: bo -s <当前价> [本金] (--trail-pct <回撤%> | --trail-atr <ATR> [--trail-mult <k>]) --gbm <年化漂移%>:<年化波动率%> [--max-days <天数>] [--paths <路径数>] [--seed <种子>]"
+            );
+            println!("示例: bo -s 100 10000 --trail-pct 8 --gbm 10:30 --max-days 60");
+        }
+        return;
+    }
+
+    let entry = match parse_positive(s_args[1], "当前价") {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    if entry - direction.sign() * trailing.distance(entry) <= 0.0 {
+        emit_error(output, "移动止损距离过大，初始止损价必须为正数");
+        return;
+    }
+    let capital = if s_args.len() == 3 {
+        match parse_positive(s_args[2], "本金") {
+            Ok(v) => Some(v),
+            Err(e) => {
+                emit_error(output, &e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    run_mode(
+        ModeRequest::StockTrailing {
+            direction,
+            entry_price: entry,
+            trailing,
+            simulation,
+            costs,
+            capital,
+        },
+        output,
+        globals,
+    );
+}
+
+/// 分批止盈：`-s <当前价> <止损价> [本金] --plan <价格:减仓%:到达概率%,...>`
+fn handle_stock_scale_out(
+    s_args: &[&String],
+    plan: &str,
+    direction: TradeDirection,
+    breakeven_after_first: bool,
+    costs: StockCosts,
+    output: OutputFormat,
+    globals: &GlobalOptions,
+) {
+    if s_args.len() != 3 && s_args.len() != 4 {
+        emit_error(output, "分批止盈模式参数错误");
+        if !output.is_json() {
+            println!();
+            println!(
+                "用法: bo -s <当前价> <止损价> [本金] --plan <价格:减仓%:到达概率%,...> [--breakeven]"
+            );
+            println!("示例: bo -s 100 90 10000 --plan 110:50:70,120:30:45,130:20:25 --breakeven");
+        }
+        return;
+    }
+
+    let entry = match parse_positive(s_args[1], "当前价") {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let stop = match parse_positive(s_args[2], "止损价") {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let capital = if s_args.len() == 4 {
+        match parse_positive(s_args[3], "本金") {
+            Ok(v) => Some(v),
+            Err(e) => {
+                emit_error(output, &e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+    let targets = match parse_scale_out_plan(plan) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    if let Err(e) = validate_scale_out_plan(direction, entry, stop, &targets) {
+        emit_error(output, &e);
+        return;
+    }
+
+    run_mode(
+        ModeRequest::StockScaleOut {
+            direction,
+            entry_price: entry,
+            stop_loss: stop,
+            targets,
+            breakeven_after_first,
+            costs,
+            capital,
+        },
+        output,
+        globals,
+    );
+}
+
+/// 取出套利下注选项：`--round <单位>`、`--max-stake <限额>`、
+/// `--win-commission <佣金%>`、`--stake-fee <手续费%>`
+/// （单个值用于所有标的，或逗号分隔逐个指定）
+fn take_arbitrage_options(args: &mut Vec<String>) -> Result<ArbitrageOptions, String> {
+    let stake_steps = take_option(args, "--round")?
+        .map(|v| parse_per_leg_values(&v, "投注单位"))
+        .transpose()?;
+    let max_stakes = take_option(args, "--max-stake")?
+        .map(|v| parse_per_leg_values(&v, "单边限额"))
+        .transpose()?;
+    let commissions = take_option(args, "--win-commission")?
+        .map(|v| parse_per_leg_percents(&v, "佣金率"))
+        .transpose()?;
+    let fees = take_option(args, "--stake-fee")?
+        .map(|v| parse_per_leg_percents(&v, "手续费率"))
+        .transpose()?;
+    Ok(ArbitrageOptions {
+        stake_steps,
+        max_stakes,
+        commissions,
+        fees,
+    })
+}
+
+/// 按标的数量展开逐标的选项，并检查本金
+fn resolve_arbitrage_options(
+    mut options: ArbitrageOptions,
+    legs: usize,
+    capital: Option<f64>,
+) -> Result<ArbitrageOptions, String> {
+    if let Some(steps) = options.stake_steps.as_mut() {
+        if capital.is_none() && options.max_stakes.is_none() {
+            return Err("--round 需要指定本金或 --max-stake".to_string());
+        }
+        expand_per_leg(steps, legs, "--round")?;
+    }
+    if let Some(max_stakes) = options.max_stakes.as_mut() {
+        expand_per_leg(max_stakes, legs, "--max-stake")?;
+    }
+    if let Some(commissions) = options.commissions.as_mut() {
+        expand_per_leg(commissions, legs, "--win-commission")?;
+    }
+    if let Some(fees) = options.fees.as_mut() {
+        expand_per_leg(fees, legs, "--stake-fee")?;
+    }
+    Ok(options)
+}
+
+fn handle_arbitrage(mut args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    let options = match take_arbitrage_options(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let a_args: Vec<&String> = args.iter().filter(|&a| a != "-a").collect();
+
+    match a_args.len() {
+        1 => {
+            emit_error(output, "套利模式参数不足");
+        }
+        3 => {
+            let odds1 = match parse_odds(a_args[1], "赔率1") {
+                Ok(v) => v,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
             };
             let odds2 = match parse_odds(a_args[2], "赔率2") {
                 Ok(v) => v,
@@ -388,13 +1538,22 @@ fn handle_arbitrage(args: Vec<String>, output: OutputFormat) {
                     return;
                 }
             };
-            execute_mode(
+            let options = match resolve_arbitrage_options(options, 2, None) {
+                Ok(v) => v,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
+            run_mode(
                 ModeRequest::Arbitrage {
                     odds1,
                     odds2,
+                    options,
                     capital: None,
                 },
                 output,
+                globals,
             );
         }
         4 => {
@@ -419,34 +1578,54 @@ fn handle_arbitrage(args: Vec<String>, output: OutputFormat) {
                     return;
                 }
             };
-            execute_mode(
+            let options = match resolve_arbitrage_options(options, 2, Some(capital)) {
+                Ok(v) => v,
+                Err(e) => {
+                    emit_error(output, &e);
+                    return;
+                }
+            };
+            run_mode(
                 ModeRequest::Arbitrage {
                     odds1,
                     odds2,
+                    options,
                     capital: Some(capital),
                 },
                 output,
+                globals,
             );
         }
         _ => {
             emit_error(output, "套利模式参数错误");
             if !output.is_json() {
                 println!();
-                println!("用法: bo -a <赔率1> <赔率2> [本金]");
+                println!(
+                    "用法: bo -a <赔率1> <赔率2> [本金] [--round <投注单位>[,...]] [--max-stake <限额>[,...]]"
+                );
                 println!("示例: bo -a 1.9 2.1    # 方案1赔率1.9，方案2赔率2.1");
             }
         }
     }
 }
 
-fn handle_multi_arbitrage(args: Vec<String>, output: OutputFormat) {
+fn handle_multi_arbitrage(mut args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    let options = match take_arbitrage_options(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
     let ma_args: Vec<&String> = args.iter().filter(|&a| a != "-A").collect();
 
     if ma_args.len() < 2 {
         emit_error(output, "多标的套利模式参数不足");
         if !output.is_json() {
             println!();
-            println!("用法: bo -A <标的数量> <赔率1> ... <赔率N> [本金]");
+            println!(
+                "用法: bo -A <标的数量> <赔率1> ... <赔率N> [本金] [--round <投注单位>[,...]]"
+            );
             println!("示例: bo -A 3 2.0 3.5 4.0    # 3个标的，赔率分别为2.0, 3.5, 4.0");
         }
         return;
@@ -478,7 +1657,9 @@ fn handle_multi_arbitrage(args: Vec<String>, output: OutputFormat) {
         );
         if !output.is_json() {
             println!();
-            println!("用法: bo -A <标的数量> <赔率1> ... <赔率N> [本金]");
+            println!(
+                "用法: bo -A <标的数量> <赔率1> ... <赔率N> [本金] [--round <投注单位>[,...]]"
+            );
             println!("示例: bo -A 3 2.0 3.5 4.0    # 3个标的，赔率分别为2.0, 3.5, 4.0");
         }
         return;
@@ -513,10 +1694,25 @@ fn handle_multi_arbitrage(args: Vec<String>, output: OutputFormat) {
         None
     };
 
-    execute_mode(ModeRequest::MultiArbitrage { odds, capital }, output);
+    let options = match resolve_arbitrage_options(options, count, capital) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    run_mode(
+        ModeRequest::MultiArbitrage {
+            odds,
+            options,
+            capital,
+        },
+        output,
+        globals,
+    );
 }
 
-fn handle_nash(args: Vec<String>, output: OutputFormat) {
+fn handle_nash(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
     let n_args: Vec<&String> = args.iter().filter(|&a| a != "-n").collect();
 
     match n_args.len() {
@@ -538,12 +1734,13 @@ fn handle_nash(args: Vec<String>, output: OutputFormat) {
                 values[i] = value;
             }
 
-            execute_mode(
+            run_mode(
                 ModeRequest::Nash {
                     row_payoffs: [[values[0], values[1]], [values[2], values[3]]],
                     col_payoffs: [[values[4], values[5]], [values[6], values[7]]],
                 },
                 output,
+                globals,
             );
         }
         _ => {
@@ -557,7 +1754,18 @@ fn handle_nash(args: Vec<String>, output: OutputFormat) {
     }
 }
 
-fn handle_portfolio_correlated(args: Vec<String>, output: OutputFormat) {
+fn handle_portfolio_correlated(
+    mut args: Vec<String>,
+    output: OutputFormat,
+    globals: &GlobalOptions,
+) {
+    let leverage = match take_portfolio_leverage(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
     let c_args: Vec<&String> = args.iter().filter(|&a| a != "-K").collect();
 
     if c_args.len() < 3 {
@@ -673,17 +1881,26 @@ fn handle_portfolio_correlated(args: Vec<String>, output: OutputFormat) {
         None
     };
 
-    execute_mode(
+    run_mode(
         ModeRequest::PortfolioCorrelated {
             leg_count,
             scenarios,
+            leverage,
             capital,
         },
         output,
+        globals,
     );
 }
 
-fn handle_portfolio(args: Vec<String>, output: OutputFormat) {
+fn handle_portfolio(mut args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    let leverage = match take_portfolio_leverage(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
     let p_args: Vec<&String> = args.iter().filter(|&a| a != "-k").collect();
 
     if p_args.len() < 2 {
@@ -740,7 +1957,15 @@ fn handle_portfolio(args: Vec<String>, output: OutputFormat) {
             return;
         }
 
-        execute_mode(ModeRequest::Portfolio { legs, capital }, output);
+        run_mode(
+            ModeRequest::Portfolio {
+                legs,
+                leverage,
+                capital,
+            },
+            output,
+            globals,
+        );
         return;
     }
 
@@ -813,7 +2038,814 @@ fn handle_portfolio(args: Vec<String>, output: OutputFormat) {
         None
     };
 
-    execute_mode(ModeRequest::Portfolio { legs, capital }, output);
+    run_mode(
+        ModeRequest::Portfolio {
+            legs,
+            leverage,
+            capital,
+        },
+        output,
+        globals,
+    );
+}
+
+/// 交易历史模式选项
+struct TradeHistoryOptions {
+    r_multiple: bool,
+    column: Option<String>,
+    bootstrap_samples: usize,
+    seed: u64,
+    confidence: f64,
+}
+
+/// 解析交易历史模式选项
+fn take_trade_history_options(args: &mut Vec<String>) -> Result<TradeHistoryOptions, String> {
+    let r_multiple = take_flag(args, "--r-multiple");
+    let column = take_option(args, "--column")?;
+    let bootstrap_samples = match take_option(args, "--bootstrap")? {
+        Some(v) => parse_non_negative_integer(&v, "bootstrap 次数")? as usize,
+        None => DEFAULT_BOOTSTRAP_SAMPLES,
+    };
+    let seed = match take_option(args, "--seed")? {
+        Some(v) => parse_non_negative_integer(&v, "随机种子")?,
+        None => DEFAULT_SEED,
+    };
+    let confidence = match take_option(args, "--confidence")? {
+        Some(v) => parse_percent(&v, "置信度")?,
+        None => DEFAULT_CONFIDENCE,
+    };
+    if confidence <= 0.0 || confidence >= 1.0 {
+        return Err("置信度必须在 (0, 100) 之间".to_string());
+    }
+
+    Ok(TradeHistoryOptions {
+        r_multiple,
+        column,
+        bootstrap_samples,
+        seed,
+        confidence,
+    })
+}
+
+fn handle_trade_history(mut args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    let opts = match take_trade_history_options(&mut args) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+
+    let t_args: Vec<&String> = args.iter().filter(|&a| a != "-T").collect();
+    if t_args.len() != 2 && t_args.len() != 3 {
+        emit_error(output, "交易历史模式参数错误");
+        if !output.is_json() {
+            println!();
+            println!(
+                "用法: bo -T <交易历史文件> [本金] [--r-multiple] [--column <列名|列号>] [--bootstrap <次数>] [--seed <种子>] [--confidence <置信度%>]"
+            );
+            println!("示例: bo -T trades.csv 100000 --r-multiple");
+        }
+        return;
+    }
+
+    let trades = match load_trade_history(t_args[1], opts.column.as_deref()) {
+        Ok(v) => v,
+        Err(e) => {
+            emit_error(output, &e);
+            return;
+        }
+    };
+    let capital = if t_args.len() == 3 {
+        match parse_positive(t_args[2], "本金") {
+            Ok(v) => Some(v),
+            Err(e) => {
+                emit_error(output, &e);
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    run_mode(
+        ModeRequest::TradeHistory {
+            source: t_args[1].clone(),
+            trades,
+            r_multiple: opts.r_multiple,
+            bootstrap_samples: opts.bootstrap_samples,
+            confidence: opts.confidence,
+            seed: opts.seed,
+            capital,
+        },
+        output,
+        globals,
+    );
+}
+
+fn print_option_usage() {
+    println!();
+    println!(
+        "用法: bo -o <call|put> <现价> <行权价> <隐含波动率%> <无风险利率%> <到期天数> [本金] [--spread <卖出行权价>] [--multiplier <合约乘数>] (--view-vol <主观波动率%> [--view-drift <年化漂移%>] | --view <价格:概率%,...>)"
+    );
+    println!("示例: bo -o call 100 100 25 3 30 10000 --spread 110 --view-vol 35");
+}
+
+/// 解析期权模式参数：`-o <call|put> <现价> <行权价> <隐含波动率%> <无风险利率%> <到期天数> [本金]`
+fn parse_option_request(mut args: Vec<String>) -> Result<ModeRequest, String> {
+    let short_strike = match take_option(&mut args, "--spread")? {
+        Some(v) => Some(parse_positive(&v, "卖出行权价")?),
+        None => None,
+    };
+    let multiplier = match take_option(&mut args, "--multiplier")? {
+        Some(v) => parse_positive_integer(&v, "合约乘数")?,
+        None => DEFAULT_OPTION_MULTIPLIER,
+    };
+    let view_opts = take_price_view_options(&mut args)?;
+
+    let o_args: Vec<&String> = args.iter().filter(|&a| a != "-o").collect();
+    if o_args.len() != 7 && o_args.len() != 8 {
+        return Err("期权模式参数错误".to_string());
+    }
+
+    let strategy = OptionStrategy {
+        kind: parse_option_kind(o_args[1])?,
+        long_strike: parse_positive(o_args[3], "行权价")?,
+        short_strike,
+    };
+    if short_strike == Some(strategy.long_strike) {
+        return Err("卖出行权价不能与买入行权价相同".to_string());
+    }
+    let market = OptionMarket {
+        spot: parse_positive(o_args[2], "现价")?,
+        implied_vol: parse_positive(o_args[4], "隐含波动率")? / 100.0,
+        rate: parse_f64(o_args[5], "无风险利率")? / 100.0,
+        days: parse_positive(o_args[6], "到期天数")?,
+    };
+    let capital = match o_args.get(7) {
+        Some(v) => Some(parse_positive(v, "本金")?),
+        None => None,
+    };
+    let view = parse_price_view(view_opts, market.rate)?;
+
+    Ok(ModeRequest::Option {
+        strategy,
+        market,
+        view,
+        multiplier,
+        capital,
+    })
+}
+
+fn handle_option(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_option_request(args) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_option_usage();
+            }
+        }
+    }
+}
+
+fn print_merton_usage() {
+    println!();
+    println!(
+        "用法: bo -m <预期年化收益%> <年化波动率%> <无风险利率%> [本金] [--long-only] [--max-leverage <总敞口上限>]"
+    );
+    println!(
+        "      bo -m <标的数量> <无风险利率%> <μ1%> ... <μN%> <协方差11> ... <协方差NN> [本金] [--long-only] [--max-leverage <总敞口上限>]"
+    );
+    println!("      协方差按 %² 输入（波动率 20% 对应方差 400），按行展开 N×N 矩阵");
+    println!("示例: bo -m 8 20 3 100000");
+}
+
+/// 解析连续收益凯利参数：单标的 `-m <μ%> <σ%> <r%> [本金]`，
+/// 多标的 `-m <N> <r%> <μ1%> ... <μN%> <协方差 N×N（%²）> [本金]`
+fn parse_merton_request(mut args: Vec<String>) -> Result<ModeRequest, String> {
+    let long_only = take_flag(&mut args, "--long-only");
+    let max_leverage = match take_option(&mut args, "--max-leverage")? {
+        Some(v) => Some(parse_positive(&v, "总敞口上限")?),
+        None => None,
+    };
+    let m_args: Vec<&String> = args.iter().filter(|&a| a != "-m").collect();
+    let values = &m_args[1..];
+
+    let (means, covariance, risk_free, capital) = if values.len() == 3 || values.len() == 4 {
+        let vol = parse_positive(values[1], "年化波动率")? / 100.0;
+        (
+            vec![parse_f64(values[0], "预期年化收益")? / 100.0],
+            vec![vec![vol * vol]],
+            parse_f64(values[2], "无风险利率")? / 100.0,
+            values.get(3),
+        )
+    } else {
+        let Some(count) = values.first() else {
+            return Err("连续收益凯利参数不足".to_string());
+        };
+        let n = count
+            .parse::<usize>()
+            .map_err(|_| "标的数量必须是整数".to_string())?;
+        if !(2..=12).contains(&n) {
+            return Err("标的数量必须在 2-12 之间".to_string());
+        }
+        let expected = 2 + n + n * n;
+        if values.len() != expected && values.len() != expected + 1 {
+            return Err(format!(
+                "{n} 个标的需要 {expected} 个参数（标的数量、无风险利率、{n} 个期望收益、{} 个协方差），可选本金",
+                n * n
+            ));
+        }
+        let means = values[2..2 + n]
+            .iter()
+            .enumerate()
+            .map(|(i, v)| parse_f64(v, &format!("标的{}期望收益", i + 1)).map(|m| m / 100.0))
+            .collect::<Result<Vec<f64>, String>>()?;
+        let mut covariance = vec![vec![0.0; n]; n];
+        for (k, v) in values[2 + n..expected].iter().enumerate() {
+            let (i, j) = (k / n, k % n);
+            covariance[i][j] = parse_f64(v, &format!("协方差({},{})", i + 1, j + 1))? / 10000.0;
+        }
+        (
+            means,
+            covariance,
+            parse_f64(values[1], "无风险利率")? / 100.0,
+            values.get(expected),
+        )
+    };
+    validate_covariance(&covariance)?;
+    let capital = match capital {
+        Some(v) => Some(parse_positive(v, "本金")?),
+        None => None,
+    };
+
+    Ok(ModeRequest::Merton {
+        means,
+        covariance,
+        risk_free,
+        constraints: MertonConstraints {
+            long_only,
+            max_leverage,
+        },
+        capital,
+    })
+}
+
+fn handle_merton(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_merton_request(args) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_merton_usage();
+            }
+        }
+    }
+}
+
+fn print_back_lay_usage() {
+    println!();
+    println!(
+        "用法: bo -b <背注赔率> <对冲赔率> <背注金额> [交易所佣金%] [--free-bet snr|sr | --risk-free <退款保留率%>]"
+    );
+    println!("示例: bo -b 3.0 3.1 100 2              # 资格投注");
+    println!("      bo -b 6.0 6.4 20 2 --free-bet snr  # 不退本金的免费投注");
+}
+
+/// 解析对冲下注参数：`-b <背注赔率> <对冲赔率> <背注金额> [佣金%]`
+fn parse_back_lay_request(mut args: Vec<String>) -> Result<ModeRequest, String> {
+    let free_bet = take_option(&mut args, "--free-bet")?;
+    let risk_free = take_option(&mut args, "--risk-free")?;
+    let kind = match (free_bet, risk_free) {
+        (Some(_), Some(_)) => return Err("--free-bet 与 --risk-free 不能同时使用".to_string()),
+        (Some(v), None) => match v.to_lowercase().as_str() {
+            "snr" => BackLayKind::FreeBetSnr,
+            "sr" => BackLayKind::FreeBetSr,
+            _ => return Err("--free-bet 必须为 snr（不退本金）或 sr（退本金）".to_string()),
+        },
+        (None, Some(v)) => BackLayKind::RiskFree {
+            retention: parse_percent(&v, "退款保留率")?,
+        },
+        (None, None) => BackLayKind::Qualifying,
+    };
+
+    let b_args: Vec<&String> = args.iter().filter(|&a| a != "-b").collect();
+    let values = &b_args[1..];
+    if values.len() != 3 && values.len() != 4 {
+        return Err("对冲下注需要背注赔率、对冲赔率、背注金额，可选交易所佣金".to_string());
+    }
+    Ok(ModeRequest::BackLay {
+        back_odds: parse_odds(values[0], "背注赔率")?,
+        lay_odds: parse_odds(values[1], "对冲赔率")?,
+        back_stake: parse_positive(values[2], "背注金额")?,
+        commission: match values.get(3) {
+            Some(v) => parse_percent(v, "交易所佣金")?,
+            None => 0.0,
+        },
+        kind,
+    })
+}
+
+fn handle_back_lay(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_back_lay_request(args) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_back_lay_usage();
+            }
+        }
+    }
+}
+
+fn print_ledger_usage() {
+    println!();
+    println!("用法: bo -L deposit <金额>                        # 入金");
+    println!("      bo -L withdraw <金额>                       # 出金");
+    println!(
+        "      bo -L add <赔率> <金额|比例%> [备注...] [--mode <模式>] [--market <盘口类型>] [--prob <胜率%>]"
+    );
+    println!("      bo -L settle <编号> win|loss|void|partial [返还金额]");
+    println!("      bo -L close <编号> <收盘赔率> [同盘口其他结果收盘赔率...]  # 记录收盘价");
+    println!("      bo -L show");
+    println!(
+        "      写入类命令可选 --date YYYY-MM-DD；add 与 close 加 --polymarket 时赔率按价格%输入；账本位于 --data-dir、$BO_DATA_DIR 或 ~/.bo"
+    );
+    println!("示例: bo -L add 2.5 4.2% 曼联胜 --mode standard --market 1x2 --prob 44");
+    println!("      bo -L close 1 2.30 3.40 3.30              # 三项盘口收盘，去水计算 CLV");
+}
+
+/// 解析账本金额：以 `%` 结尾时为占可用余额的比例
+fn parse_ledger_stake(input: &str) -> Result<LedgerStake, String> {
+    match input.strip_suffix('%') {
+        Some(percent) => match parse_percent(percent, "下注比例")? {
+            0.0 => Err("下注比例必须为正数".to_string()),
+            fraction => Ok(LedgerStake::Fraction(fraction)),
+        },
+        None => Ok(LedgerStake::Amount(parse_positive(input, "下注金额")?)),
+    }
+}
+
+/// 账本选项及使用它们的子命令
+const LEDGER_OPTIONS: [(&str, &[&str]); 5] = [
+    ("--date", &["deposit", "withdraw", "add", "settle", "close"]),
+    ("--mode", &["add"]),
+    ("--market", &["add"]),
+    ("--prob", &["add"]),
+    ("--polymarket", &["add", "close"]),
+];
+
+/// 解析账本参数：`-L <命令> ...`，返回命令与记账日期
+fn parse_ledger_request(mut args: Vec<String>) -> Result<(LedgerCommand, String), String> {
+    let given: Vec<&str> = LEDGER_OPTIONS
+        .iter()
+        .filter(|(name, _)| args.iter().any(|a| a == name))
+        .map(|(name, _)| *name)
+        .collect();
+    let date = match take_option(&mut args, "--date")? {
+        Some(v) => parse_ledger_date(&v)?,
+        None => today(),
+    };
+    let mode = take_option(&mut args, "--mode")?.unwrap_or_default();
+    if !mode.is_empty() && !LEDGER_MODES.contains(&mode.as_str()) {
+        return Err(format!(
+            "未知模式: {}（可选: {}）",
+            mode,
+            LEDGER_MODES.join(", ")
+        ));
+    }
+    let market = take_option(&mut args, "--market")?.unwrap_or_default();
+    let probability = match take_option(&mut args, "--prob")? {
+        Some(v) => match parse_percent(&v, "胜率")? {
+            p if p > 0.0 && p < 1.0 => Some(p),
+            _ => return Err("胜率必须在 (0, 100) 之间".to_string()),
+        },
+        None => None,
+    };
+    let polymarket = take_flag(&mut args, "--polymarket");
+    // Polymarket 价格换算为等效小数赔率
+    let price_odds = |input: &str, field: &str| {
+        if polymarket {
+            parse_market_price(input).map(|price| 1.0 / price)
+        } else {
+            parse_odds(input, field)
+        }
+    };
+    let l_args: Vec<&String> = args.iter().filter(|&a| a != "-L").collect();
+    let values: Vec<&str> = l_args[1..].iter().map(|a| a.as_str()).collect();
+    let subcommand = values.first().copied().unwrap_or("show");
+    if let Some((name, _)) = LEDGER_OPTIONS
+        .iter()
+        .find(|(name, used_by)| given.contains(name) && !used_by.contains(&subcommand))
+    {
+        return Err(format!("{} 不适用于 -L {}", name, subcommand));
+    }
+    let command = match values.as_slice() {
+        [] | ["show"] => LedgerCommand::Show,
+        ["deposit", amount] => LedgerCommand::Deposit(parse_positive(amount, "入金金额")?),
+        ["withdraw", amount] => LedgerCommand::Withdraw(parse_positive(amount, "出金金额")?),
+        ["add", odds, stake, label @ ..] => LedgerCommand::Add {
+            odds: price_odds(odds, "赔率")?,
+            stake: parse_ledger_stake(stake)?,
+            mode,
+            market,
+            probability,
+            label: label.join(" "),
+        },
+        ["close", id, odds, others @ ..] => LedgerCommand::Close {
+            id: parse_positive_integer(id, "编号")?,
+            odds: price_odds(odds, "收盘赔率")?,
+            others: others
+                .iter()
+                .map(|o| price_odds(o, "收盘赔率"))
+                .collect::<Result<Vec<f64>, String>>()?,
+        },
+        ["settle", id, result, rest @ ..] if rest.len() <= 1 => {
+            let settlement = BetSettlement::from_code(result)?;
+            let returned = rest
+                .first()
+                .map(|v| parse_non_negative(v, "返还金额"))
+                .transpose()?;
+            match (settlement, returned) {
+                (BetSettlement::Partial, None) => {
+                    return Err("部分结算需要返还金额（含本金）".to_string());
+                }
+                (BetSettlement::Partial, _) | (_, None) => {}
+                (_, Some(_)) => return Err("只有部分结算（partial）需要返还金额".to_string()),
+            }
+            LedgerCommand::Settle {
+                id: parse_positive_integer(id, "编号")?,
+                settlement,
+                returned,
+            }
+        }
+        _ => {
+            return Err(
+                "账本命令应为 deposit、withdraw、add、settle、close 或 show，参数数量需匹配"
+                    .to_string(),
+            );
+        }
+    };
+    Ok((command, date))
+}
+
+/// 读取账本、追加新记录后输出汇总
+fn run_ledger(args: Vec<String>, path: PathBuf) -> Result<ModeRequest, String> {
+    let (command, date) = parse_ledger_request(args)?;
+    let mut entries = load_ledger(&path)?;
+    let recorded = build_ledger_entry(&entries, &command, &date)?;
+    if let Some(entry) = &recorded {
+        append_ledger_entry(&path, entry)?;
+        entries.push(entry.clone());
+    }
+    Ok(ModeRequest::Ledger {
+        path,
+        recorded,
+        summary: summarize_ledger(&entries),
+    })
+}
+
+fn handle_ledger(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match run_ledger(args, globals.ledger_path.clone()) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_ledger_usage();
+            }
+        }
+    }
+}
+
+fn print_calibration_usage() {
+    println!();
+    println!("用法: bo -C [预测文件] [--polymarket]");
+    println!(
+        "      预测文件为 CSV（结果,赔率,概率%）或同字段的 JSON 对象数组，结果为 1/0 或 win/loss"
+    );
+    println!("      省略文件时使用账本中记录了 --prob 且已结算为 win/loss 的下注");
+    println!("示例: bo -C predictions.csv");
+    println!("      bo 2.5 48 1000 --shrink 0.6             # 下注前按拟合系数收缩胜率");
+}
+
+/// 解析概率校准参数：`-C [预测文件]`，省略时读取账本
+fn parse_calibration_request(mut args: Vec<String>, ledger: &Path) -> Result<ModeRequest, String> {
+    let polymarket = take_flag(&mut args, "--polymarket");
+    let c_args: Vec<&String> = args.iter().filter(|&a| a != "-C").collect();
+    let (source, predictions, skipped) = match &c_args[1..] {
+        [] => {
+            let summary = summarize_ledger(&load_ledger(ledger)?);
+            let (predictions, skipped) = ledger_predictions(&summary.bets);
+            (ledger.display().to_string(), predictions, Some(skipped))
+        }
+        [file] => (file.to_string(), load_predictions(file, polymarket)?, None),
+        _ => return Err("概率校准最多接受一个预测文件".to_string()),
+    };
+    if predictions.is_empty() {
+        return Err(
+            "没有可用于校准的已结算预测（账本下注需 --prob 且结算为 win/loss）".to_string(),
+        );
+    }
+    Ok(ModeRequest::Calibration {
+        source,
+        predictions,
+        skipped,
+    })
+}
+
+fn handle_calibration(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_calibration_request(args, &globals.ledger_path) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_calibration_usage();
+            }
+        }
+    }
+}
+
+fn print_value_scan_usage() {
+    println!();
+    println!(
+        "用法: bo -V <模型概率文件> <价格快照文件> [本金] [--polymarket] [--min-edge <百分点>]"
+    );
+    println!("      模型概率为 CSV（事件,盘口,结果,概率%）或同字段的 JSON 对象数组");
+    println!("示例: bo -V model.csv odds.csv 10000 --min-edge 2");
+}
+
+/// 解析价值投注扫描参数：`-V <模型概率文件> <价格快照文件> [本金]`
+fn parse_value_scan_request(mut args: Vec<String>) -> Result<ModeRequest, String> {
+    let polymarket = take_flag(&mut args, "--polymarket");
+    let min_edge = match take_option(&mut args, "--min-edge")? {
+        Some(v) => parse_percent(&v, "最小优势")?,
+        None => 0.0,
+    };
+    let v_args: Vec<&String> = args.iter().filter(|&a| a != "-V").collect();
+    let values = &v_args[1..];
+    if values.len() != 2 && values.len() != 3 {
+        return Err("价值投注扫描需要模型概率文件与价格快照文件，可选本金".to_string());
+    }
+    Ok(ModeRequest::ValueScan {
+        probabilities: load_model_probabilities(values[0])?,
+        quotes: load_odds_snapshot(values[1], polymarket)?,
+        polymarket,
+        min_edge,
+        capital: values
+            .get(2)
+            .map(|v| parse_positive(v, "本金"))
+            .transpose()?,
+    })
+}
+
+fn handle_value_scan(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_value_scan_request(args) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_value_scan_usage();
+            }
+        }
+    }
+}
+
+fn print_scan_usage() {
+    println!();
+    println!("用法: bo -S <赔率快照文件> [本金] [--max-margin <抽水%>]");
+    println!("      快照为 CSV（事件,盘口,结果,庄家,赔率）或同字段的 JSON 对象数组");
+    println!("示例: bo -S odds.csv 1000 --max-margin 1.5");
+}
+
+/// 默认列出的低抽水盘口抽水率上限
+const DEFAULT_SCAN_MAX_MARGIN: f64 = 0.02;
+
+/// 解析套利扫描参数：`-S <赔率快照文件> [本金]`
+fn parse_scan_request(mut args: Vec<String>) -> Result<ModeRequest, String> {
+    let max_margin = match take_option(&mut args, "--max-margin")? {
+        Some(v) => parse_percent(&v, "抽水率上限")?,
+        None => DEFAULT_SCAN_MAX_MARGIN,
+    };
+    let s_args: Vec<&String> = args.iter().filter(|&a| a != "-S").collect();
+    let values = &s_args[1..];
+    if values.is_empty() || values.len() > 2 {
+        return Err("套利扫描需要赔率快照文件，可选本金".to_string());
+    }
+    Ok(ModeRequest::Scan {
+        quotes: load_odds_snapshot(values[0], false)?,
+        max_margin,
+        capital: values
+            .get(1)
+            .map(|v| parse_positive(v, "本金"))
+            .transpose()?,
+    })
+}
+
+fn handle_scan(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_scan_request(args) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_scan_usage();
+            }
+        }
+    }
+}
+
+fn print_dutching_usage() {
+    println!();
+    println!(
+        "用法: bo -d <选项数量> <赔率1> ... <赔率N> [本金] [--stake <总投注> | --target-profit <目标收益>] [--win-prob <子集胜率%>]"
+    );
+    println!("示例: bo -d 3 4.0 6.0 12.0 --stake 100          # 3 个选项共投 100");
+    println!("      bo -d 3 4.0 6.0 12.0 10000 --win-prob 60  # 按子集胜率 60% 凯利定总投注");
+}
+
+/// 解析分散投注参数：`-d <选项数量> <赔率1> ... <赔率N> [本金]`
+fn parse_dutching_request(mut args: Vec<String>) -> Result<ModeRequest, String> {
+    let stake = take_option(&mut args, "--stake")?;
+    let profit = take_option(&mut args, "--target-profit")?;
+    let target = match (stake, profit) {
+        (Some(_), Some(_)) => return Err("--stake 与 --target-profit 不能同时使用".to_string()),
+        (Some(v), None) => Some(DutchingTarget::TotalStake(parse_positive(&v, "总投注")?)),
+        (None, Some(v)) => Some(DutchingTarget::TargetProfit(parse_positive(
+            &v,
+            "目标收益",
+        )?)),
+        (None, None) => None,
+    };
+    let win_prob = take_option(&mut args, "--win-prob")?
+        .map(|v| parse_percent(&v, "子集胜率"))
+        .transpose()?;
+
+    let d_args: Vec<&String> = args.iter().filter(|&a| a != "-d").collect();
+    if d_args.len() < 2 {
+        return Err("分散投注模式参数不足".to_string());
+    }
+    let count: usize = match d_args[1].parse() {
+        Ok(n) if n >= 2 => n,
+        Ok(_) => return Err("选项数量必须至少为 2".to_string()),
+        Err(_) => return Err("选项数量必须是数字".to_string()),
+    };
+    let values = &d_args[2..];
+    if values.len() != count && values.len() != count + 1 {
+        return Err(format!(
+            "参数数量不匹配，期望 {} 个赔率值，实际得到 {}",
+            count,
+            values.len()
+        ));
+    }
+    let odds = values[..count]
+        .iter()
+        .enumerate()
+        .map(|(i, v)| parse_odds(v, &format!("赔率{}", i + 1)))
+        .collect::<Result<Vec<f64>, String>>()?;
+    let implied = dutching_implied_prob(&odds);
+    if implied >= 1.0 {
+        return Err(format!(
+            "选定选项的合计隐含概率为 {:.2}%，不低于 100%，任一选项胜出也无法盈利",
+            implied * 100.0
+        ));
+    }
+    Ok(ModeRequest::Dutching {
+        odds,
+        target,
+        win_prob,
+        capital: values
+            .get(count)
+            .map(|v| parse_positive(v, "本金"))
+            .transpose()?,
+    })
+}
+
+fn handle_dutching(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_dutching_request(args) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_dutching_usage();
+            }
+        }
+    }
+}
+
+fn print_hedge_usage() {
+    println!();
+    println!(
+        "用法: bo -H <原投注金额> <原赔率> <当前反向赔率> [更新胜率%] [可用本金] [--partial <对冲比例%>[,...]] [--cash-out <兑现报价>]"
+    );
+    println!("      bo -H <原投注金额> <买入价格%> <当前价格%> ... --polymarket");
+    println!("示例: bo -H 100 3.0 1.8 50 1000          # 100 @ 3.0，反向赔率跌至 1.8");
+    println!("      bo -H 100 30 55 60 1000 --polymarket  # 30% 买入，现价 55%");
+}
+
+/// 默认列出的部分对冲比例（占完全对冲金额）
+const DEFAULT_HEDGE_RATIOS: [f64; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+/// 解析对冲参数：`-H <原投注金额> <原赔率> <当前反向赔率> [更新胜率%] [可用本金]`
+fn parse_hedge_request(mut args: Vec<String>) -> Result<ModeRequest, String> {
+    let polymarket = take_flag(&mut args, "--polymarket");
+    let ratios = match take_option(&mut args, "--partial")? {
+        Some(v) => parse_per_leg_percents(&v, "对冲比例")?,
+        None => DEFAULT_HEDGE_RATIOS.to_vec(),
+    };
+    let cash_out = take_option(&mut args, "--cash-out")?
+        .map(|v| parse_non_negative(&v, "兑现报价"))
+        .transpose()?;
+
+    let h_args: Vec<&String> = args.iter().filter(|&a| a != "-H").collect();
+    let values = &h_args[1..];
+    if !(3..=5).contains(&values.len()) {
+        return Err("对冲需要原投注金额、原赔率、当前反向赔率，可选更新胜率与可用本金".to_string());
+    }
+    let (odds, hedge_odds) = if polymarket {
+        // 持有结果的买入价与当前价：反向买入价格为 1 - 当前价
+        let entry = parse_market_price(values[1])?;
+        let current = parse_market_price(values[2])?;
+        (1.0 / entry, 1.0 / (1.0 - current))
+    } else {
+        (
+            parse_odds(values[1], "原赔率")?,
+            parse_odds(values[2], "当前反向赔率")?,
+        )
+    };
+    Ok(ModeRequest::Hedge {
+        bet: HedgeBet {
+            stake: parse_positive(values[0], "原投注金额")?,
+            odds,
+            hedge_odds,
+            win_prob: values
+                .get(3)
+                .map(|v| parse_percent(v, "更新胜率"))
+                .transpose()?,
+        },
+        ratios,
+        polymarket,
+        cash_out,
+        capital: values
+            .get(4)
+            .map(|v| parse_positive(v, "可用本金"))
+            .transpose()?,
+    })
+}
+
+fn handle_hedge(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_hedge_request(args) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_hedge_usage();
+            }
+        }
+    }
+}
+
+fn print_middle_usage() {
+    println!();
+    println!("用法: bo -M <线1> <赔率1> <线2> <赔率2> <中间区概率%> [本金]");
+    println!("      方案1在结果 > 线1 时赢，方案2在结果 < 线2 时赢，线1 < 线2，均为半分线（x.5）");
+    println!("示例: bo -M 45.5 1.95 47.5 1.95 5 10000   # 大 45.5 + 小 47.5");
+}
+
+/// 解析中间盘参数：`-M <线1> <赔率1> <线2> <赔率2> <中间区概率%> [本金]`
+fn parse_middle_request(args: Vec<String>) -> Result<ModeRequest, String> {
+    let m_args: Vec<&String> = args.iter().filter(|&a| a != "-M").collect();
+    let values = &m_args[1..];
+    if values.len() != 5 && values.len() != 6 {
+        return Err("中间盘需要线1、赔率1、线2、赔率2、中间区概率，可选本金".to_string());
+    }
+    let line1 = parse_f64(values[0], "线1")?;
+    let line2 = parse_f64(values[2], "线2")?;
+    validate_middle_lines(line1, line2)?;
+    Ok(ModeRequest::Middle {
+        bet: MiddleBet {
+            line1,
+            odds1: parse_odds(values[1], "赔率1")?,
+            line2,
+            odds2: parse_odds(values[3], "赔率2")?,
+            middle_prob: parse_percent(values[4], "中间区概率")?,
+        },
+        capital: values
+            .get(5)
+            .map(|v| parse_positive(v, "本金"))
+            .transpose()?,
+    })
+}
+
+fn handle_middle(args: Vec<String>, output: OutputFormat, globals: &GlobalOptions) {
+    match parse_middle_request(args) {
+        Ok(request) => run_mode(request, output, globals),
+        Err(e) => {
+            emit_error(output, &e);
+            if !output.is_json() {
+                print_middle_usage();
+            }
+        }
+    }
 }
 
 /// 检查是否为交互式模式调用
@@ -834,7 +2866,20 @@ pub fn is_interactive_call(args: &[String]) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{parse_return_percent, probability_sum_tolerance};
+    use std::path::Path;
+
+    use super::{
+        check_record, fill_ledger_capital, fill_shrink, parse_calibration_request,
+        parse_ledger_request, parse_middle_request, parse_return_percent, parse_shrink_factor,
+        probability_sum_tolerance, take_currency_settings, take_option, take_record_settings,
+        take_stock_costs, take_trade_history_options,
+    };
+    use crate::app::ModeRequest;
+    use crate::ledger::build_ledger_entry;
+    use crate::types::{
+        BetSettlement, CapitalSource, LedgerCommand, LedgerEntry, LedgerRecord, LedgerStake,
+        RecordSize, TradeDirection,
+    };
 
     #[test]
     fn return_percent_rejects_less_than_negative_hundred() {
@@ -851,4 +2896,336 @@ mod tests {
         let sum: f64 = 0.3333 + 0.3333 + 0.3333;
         assert!((sum - 1.0).abs() <= probability_sum_tolerance(3));
     }
+
+    #[test]
+    fn take_option_removes_flag_and_value() {
+        let mut args: Vec<String> = ["bo", "-s", "--lot", "100", "10"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            take_option(&mut args, "--lot").unwrap().as_deref(),
+            Some("100")
+        );
+        assert_eq!(args, vec!["bo", "-s", "10"]);
+        assert!(take_option(&mut args, "--lot").unwrap().is_none());
+    }
+
+    #[test]
+    fn take_option_requires_value() {
+        let mut args: Vec<String> = vec!["bo".to_string(), "--lot".to_string()];
+        assert!(take_option(&mut args, "--lot").is_err());
+    }
+
+    #[test]
+    fn stock_costs_parse_percent_options() {
+        let mut args: Vec<String> = ["-s", "--commission-pct", "0.03", "--stamp-duty", "0.1"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let costs = take_stock_costs(&mut args, TradeDirection::Long).unwrap();
+        assert!((costs.commission_rate - 0.0003).abs() < 1e-12);
+        assert!((costs.stamp_duty_rate - 0.001).abs() < 1e-12);
+        assert_eq!(costs.lot_size, 1);
+        assert_eq!(args, vec!["-s"]);
+    }
+
+    #[test]
+    fn borrow_rate_requires_short() {
+        let args: Vec<String> = ["-s", "--borrow-rate", "8", "--days", "20"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(take_stock_costs(&mut args.clone(), TradeDirection::Long).is_err());
+        let costs = take_stock_costs(&mut args.clone(), TradeDirection::Short).unwrap();
+        assert!((costs.borrow_rate - 0.08).abs() < 1e-12);
+        assert!((costs.holding_days - 20.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn trade_history_options_use_defaults() {
+        let mut args: Vec<String> = ["bo", "-T", "t.csv", "--r-multiple", "--confidence", "95"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let opts = take_trade_history_options(&mut args).unwrap();
+        assert!(opts.r_multiple);
+        assert_eq!(opts.bootstrap_samples, 1000);
+        assert_eq!(opts.seed, 42);
+        assert!((opts.confidence - 0.95).abs() < 1e-12);
+        assert_eq!(args, vec!["bo", "-T", "t.csv"]);
+
+        let mut args: Vec<String> = vec!["--confidence".to_string(), "100".to_string()];
+        assert!(take_trade_history_options(&mut args).is_err());
+    }
+
+    #[test]
+    fn currency_settings_require_rate_between_currencies() {
+        let mut args: Vec<String> = ["bo", "-a", "1.9", "2.2", "--currency", "eur"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let settings = take_currency_settings(&mut args).unwrap().unwrap();
+        assert_eq!(settings.capital, "EUR");
+        assert_eq!(settings.rate, 1.0);
+        assert_eq!(args, vec!["bo", "-a", "1.9", "2.2"]);
+
+        let mut args: Vec<String> = ["--currency", "EUR", "--capital-currency", "USD"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(take_currency_settings(&mut args).is_err());
+
+        let mut args: Vec<String> = [
+            "--currency",
+            "usd",
+            "--capital-currency",
+            "EUR",
+            "--fx",
+            "0.9",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let settings = take_currency_settings(&mut args).unwrap().unwrap();
+        assert_eq!(settings.stake, "USD");
+        assert_eq!(settings.capital, "EUR");
+        assert!((settings.to_capital(100.0) - 90.0).abs() < 1e-12);
+
+        let mut args: Vec<String> = vec!["--fx".to_string(), "1.1".to_string()];
+        assert!(take_currency_settings(&mut args).is_err());
+    }
+
+    #[test]
+    fn middle_requires_ordered_distinct_lines() {
+        let args = |values: &[&str]| -> Vec<String> {
+            ["bo", "-M"]
+                .iter()
+                .chain(values)
+                .map(|s| s.to_string())
+                .collect()
+        };
+        assert!(parse_middle_request(args(&["45.5", "1.95", "47.5", "1.95", "5"])).is_ok());
+        assert!(parse_middle_request(args(&["3.5", "1.91", "7.5", "1.91", "8", "1000"])).is_ok());
+        assert!(parse_middle_request(args(&["45", "1.95", "47", "1.95", "5"])).is_err());
+        assert!(parse_middle_request(args(&["45.5", "1.95", "45.5", "1.95", "5"])).is_err());
+        assert!(parse_middle_request(args(&["47.5", "1.95", "45.5", "1.95", "5"])).is_err());
+    }
+
+    #[test]
+    fn calibration_options_are_validated() {
+        assert_eq!(parse_shrink_factor("0.6"), Ok(0.6));
+        assert_eq!(parse_shrink_factor("1"), Ok(1.0));
+        assert!(parse_shrink_factor("1.2").is_err());
+        assert!(parse_shrink_factor("-0.1").is_err());
+        assert!(parse_shrink_factor("abc").is_err());
+
+        let args: Vec<String> = ["bo", "-C", "a.csv", "b.csv"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(parse_calibration_request(args, Path::new("ledger.csv")).is_err());
+    }
+
+    #[test]
+    fn ledger_balance_only_fills_missing_capital() {
+        let standard = |capital| ModeRequest::Standard {
+            odds: 2.0,
+            win_rate: 0.6,
+            capital,
+            shrink: None,
+        };
+        let mut request = standard(None);
+        assert_eq!(
+            fill_ledger_capital(&mut request, Some(500.0)),
+            CapitalSource::Ledger
+        );
+        assert_eq!(request.capital_mut(), Some(&mut Some(500.0)));
+
+        let mut request = standard(Some(1000.0));
+        assert_eq!(
+            fill_ledger_capital(&mut request, Some(500.0)),
+            CapitalSource::Input
+        );
+        assert_eq!(request.capital_mut(), Some(&mut Some(1000.0)));
+
+        let mut request = standard(None);
+        assert_eq!(
+            fill_ledger_capital(&mut request, None),
+            CapitalSource::Input
+        );
+        assert_eq!(request.capital_mut(), Some(&mut None));
+    }
+
+    #[test]
+    fn ledger_commands_parse_stakes_and_settlements() {
+        let args = |values: &[&str]| -> Vec<String> {
+            ["bo", "-L"]
+                .iter()
+                .chain(values)
+                .map(|s| s.to_string())
+                .collect()
+        };
+        let (command, date) = parse_ledger_request(args(&[
+            "add",
+            "2.5",
+            "4%",
+            "曼联",
+            "胜",
+            "--date",
+            "2024-03-01",
+            "--prob",
+            "48",
+        ]))
+        .unwrap();
+        assert_eq!(date, "2024-03-01");
+        assert_eq!(
+            command,
+            LedgerCommand::Add {
+                odds: 2.5,
+                stake: LedgerStake::Fraction(0.04),
+                mode: String::new(),
+                market: String::new(),
+                probability: Some(0.48),
+                label: "曼联 胜".to_string(),
+            }
+        );
+        let (command, _) = parse_ledger_request(args(&["settle", "3", "partial", "75"])).unwrap();
+        assert_eq!(
+            command,
+            LedgerCommand::Settle {
+                id: 3,
+                settlement: BetSettlement::Partial,
+                returned: Some(75.0),
+            }
+        );
+        assert_eq!(
+            parse_ledger_request(args(&[])).unwrap().0,
+            LedgerCommand::Show
+        );
+        let (command, _) =
+            parse_ledger_request(args(&["close", "2", "40", "62", "--polymarket"])).unwrap();
+        assert_eq!(
+            command,
+            LedgerCommand::Close {
+                id: 2,
+                odds: 2.5,
+                others: vec![1.0 / 0.62],
+            }
+        );
+        assert!(parse_ledger_request(args(&["close", "2", "1.0"])).is_err());
+        assert!(parse_ledger_request(args(&["add", "2.5", "10", "--prob", "100"])).is_err());
+        assert!(parse_ledger_request(args(&["settle", "3", "partial"])).is_err());
+        assert!(parse_ledger_request(args(&["settle", "3", "win", "10"])).is_err());
+        assert!(parse_ledger_request(args(&["add", "2.5", "0%"])).is_err());
+        assert!(parse_ledger_request(args(&["deposit", "100", "--date", "2024-3-1"])).is_err());
+        assert!(parse_ledger_request(args(&["add", "2.5", "10", "--mode", "kelly"])).is_err());
+        assert!(parse_ledger_request(args(&["deposit", "100", "--prob", "40"])).is_err());
+        assert!(parse_ledger_request(args(&["settle", "3", "win", "--polymarket"])).is_err());
+        assert!(parse_ledger_request(args(&["show", "--market", "1x2"])).is_err());
+        assert!(parse_ledger_request(args(&["withdraw", "50", "--date", "2024-03-02"])).is_ok());
+        assert!(parse_ledger_request(args(&["add", "2.5", "10", "--mode", "value_scan"])).is_ok());
+    }
+
+    #[test]
+    fn shrink_only_applies_to_standard_and_polymarket() {
+        let mut request = ModeRequest::Polymarket {
+            market_price: 0.4,
+            your_probability: 0.55,
+            capital: None,
+            shrink: None,
+        };
+        assert!(fill_shrink(&mut request, Some(0.6)).is_ok());
+        assert_eq!(request.shrink_mut(), Some(&mut Some(0.6)));
+
+        let mut nash = ModeRequest::Nash {
+            row_payoffs: [[1.0, 0.0], [0.0, 1.0]],
+            col_payoffs: [[0.0, 1.0], [1.0, 0.0]],
+        };
+        assert!(fill_shrink(&mut nash, None).is_ok());
+        assert!(fill_shrink(&mut nash, Some(0.6)).is_err());
+    }
+
+    #[test]
+    fn polymarket_closing_prices_are_devigged() {
+        let entries = vec![
+            LedgerEntry::Deposit {
+                date: "2024-03-01".to_string(),
+                amount: 1000.0,
+            },
+            LedgerEntry::Bet {
+                id: 1,
+                date: "2024-03-01".to_string(),
+                odds: 2.5,
+                stake: 100.0,
+                mode: "polymarket".to_string(),
+                market: String::new(),
+                probability: None,
+                label: String::new(),
+            },
+        ];
+        let args: Vec<String> = ["bo", "-L", "close", "1", "42", "60", "--polymarket"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let (command, date) = parse_ledger_request(args).unwrap();
+        match build_ledger_entry(&entries, &command, &date).unwrap() {
+            Some(LedgerEntry::Close { id: 1, line }) => {
+                // 价格 42% / 60% 之和 102%，去水后 42 / 102
+                assert!(line.devigged);
+                assert!((line.odds - 1.0 / 0.42).abs() < 1e-12);
+                assert!((line.probability - 0.42 / 1.02).abs() < 1e-12);
+            }
+            other => panic!("unexpected entry {other:?}"),
+        }
+    }
+
+    #[test]
+    fn record_size_must_match_mode_and_stake_source() {
+        let mut args: Vec<String> = ["bo", "2", "60", "--record", "half", "--label", "模型"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let record = take_record_settings(&mut args, Path::new("ledger.csv"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(args, ["bo", "2", "60"]);
+        assert_eq!(record.size, RecordSize::Kelly(0.5));
+        assert_eq!(record.label, "模型");
+        let mut args = vec!["--record".to_string(), "double".to_string()];
+        assert!(take_record_settings(&mut args, Path::new("ledger.csv")).is_err());
+        let stake = LedgerRecord {
+            size: RecordSize::Stake,
+            ..record.clone()
+        };
+
+        let standard = |capital| ModeRequest::Standard {
+            odds: 2.0,
+            win_rate: 0.6,
+            capital,
+            shrink: None,
+        };
+        let input = CapitalSource::Input;
+        assert!(check_record(&standard(Some(1000.0)), None, input).is_ok());
+        assert!(check_record(&standard(Some(1000.0)), Some(&record), input).is_ok());
+        assert!(check_record(&standard(None), Some(&record), input).is_err());
+        assert!(check_record(&standard(Some(1000.0)), Some(&stake), input).is_err());
+
+        // 套利分配不是凯利仓位，且总投注必须在命令行输入
+        let scan = |capital| ModeRequest::Scan {
+            quotes: Vec::new(),
+            max_margin: 0.02,
+            capital,
+        };
+        assert!(check_record(&scan(Some(100.0)), Some(&stake), input).is_ok());
+        assert!(check_record(&scan(Some(100.0)), Some(&record), input).is_err());
+        assert!(check_record(&scan(None), Some(&stake), input).is_err());
+        assert!(check_record(&scan(Some(1000.0)), Some(&stake), CapitalSource::Ledger).is_err());
+
+        let nash = ModeRequest::Nash {
+            row_payoffs: [[1.0, 0.0], [0.0, 1.0]],
+            col_payoffs: [[0.0, 1.0], [1.0, 0.0]],
+        };
+        assert!(check_record(&nash, Some(&record), input).is_err());
+    }
 }
@@ -0,0 +1,148 @@
+//! 多币种：本金货币与下注货币的汇率换算与金额显示
+
+use std::fs;
+
+use crate::types::{CurrencySettings, MoneyContext};
+
+/// 规范化货币代码（3 位字母，大写）
+pub fn parse_currency_code(input: &str) -> Result<String, String> {
+    let code = input.trim().to_ascii_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code)
+    } else {
+        Err(format!(
+            "货币代码 '{input}' 无效，应为 3 位字母（如 USD、EUR、HKD）"
+        ))
+    }
+}
+
+/// 解析汇率文件内容：每行 `基准/计价,汇率`（如 `EUR/USD,1.085` 表示 1 EUR = 1.085 USD）
+///
+/// 分隔符支持逗号、空白与 `=`，空行与 `#` 开头的注释行忽略。
+pub fn parse_fx_rates(content: &str) -> Result<Vec<(String, String, f64)>, String> {
+    let mut rates = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_no = i + 1;
+        let fields: Vec<&str> = line
+            .split(|c: char| c == ',' || c == '=' || c.is_whitespace())
+            .filter(|f| !f.is_empty())
+            .collect();
+        let format_error = || format!("汇率文件第 {line_no} 行格式错误，应为 基准/计价,汇率");
+        if fields.len() != 2 {
+            return Err(format_error());
+        }
+        let Some((base, quote)) = fields[0].split_once('/') else {
+            return Err(format_error());
+        };
+        let with_line = |e: String| format!("汇率文件第 {line_no} 行: {e}");
+        let rate = fields[1]
+            .parse::<f64>()
+            .ok()
+            .filter(|r| r.is_finite() && *r > 0.0)
+            .ok_or_else(|| with_line(format!("汇率 '{}' 必须是正数", fields[1])))?;
+        rates.push((
+            parse_currency_code(base).map_err(with_line)?,
+            parse_currency_code(quote).map_err(with_line)?,
+            rate,
+        ));
+    }
+    Ok(rates)
+}
+
+/// 在汇率表中查找 1 单位 `from` 折合的 `to` 数量：支持直接、反向报价与经一种中间货币交叉
+pub fn lookup_fx_rate(rates: &[(String, String, f64)], from: &str, to: &str) -> Option<f64> {
+    if from == to {
+        return Some(1.0);
+    }
+    let direct = |a: &str, b: &str| {
+        rates.iter().find_map(|(base, quote, rate)| {
+            if base == a && quote == b {
+                Some(*rate)
+            } else if base == b && quote == a {
+                Some(1.0 / rate)
+            } else {
+                None
+            }
+        })
+    };
+    direct(from, to).or_else(|| {
+        rates
+            .iter()
+            .flat_map(|(base, quote, _)| [base, quote])
+            .filter(|via| via.as_str() != from && via.as_str() != to)
+            .find_map(|via| Some(direct(from, via)? * direct(via, to)?))
+    })
+}
+
+/// 从汇率文件读取汇率
+pub fn load_fx_rate(path: &str, from: &str, to: &str) -> Result<f64, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("无法读取汇率文件 '{path}': {e}"))?;
+    let rates = parse_fx_rates(&content)?;
+    lookup_fx_rate(&rates, from, to).ok_or_else(|| format!("汇率文件中找不到 {from}/{to} 的汇率"))
+}
+
+/// 按货币设置格式化金额：下注货币金额后附本金货币折算
+pub fn format_money_with(settings: Option<&CurrencySettings>, amount: f64) -> String {
+    // 舍入后为零的浮点误差不显示为 -0.00
+    let amount = if amount.abs() < 0.005 { 0.0 } else { amount };
+    match settings {
+        None => format!("{:.2}", amount),
+        Some(s) if s.stake == s.capital => format!("{:.2} {}", amount, s.stake),
+        Some(s) => format!(
+            "{:.2} {} ({:.2} {})",
+            amount,
+            s.stake,
+            s.to_capital(amount),
+            s.capital
+        ),
+    }
+}
+
+/// 按金额上下文的货币设置格式化金额
+pub fn format_money(money: &MoneyContext, amount: f64) -> String {
+    format_money_with(money.currency.as_ref(), amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_money_with, lookup_fx_rate, parse_currency_code, parse_fx_rates};
+    use crate::types::CurrencySettings;
+
+    #[test]
+    fn parses_rates_file_and_resolves_pairs() {
+        let content = "# 汇率\nEUR/USD,1.08\nusd/hkd 7.8\nGBP/USD=1.25\n";
+        let rates = parse_fx_rates(content).unwrap();
+        assert_eq!(rates.len(), 3);
+        assert_eq!(lookup_fx_rate(&rates, "EUR", "USD"), Some(1.08));
+        assert!((lookup_fx_rate(&rates, "HKD", "USD").unwrap() - 1.0 / 7.8).abs() < 1e-12);
+        // 经 USD 交叉：1 EUR = 1.08 × 7.8 HKD
+        assert!((lookup_fx_rate(&rates, "EUR", "HKD").unwrap() - 8.424).abs() < 1e-12);
+        assert_eq!(lookup_fx_rate(&rates, "JPY", "USD"), None);
+        assert_eq!(lookup_fx_rate(&rates, "JPY", "JPY"), Some(1.0));
+
+        assert!(parse_fx_rates("EURUSD,1.08").is_err());
+        assert!(parse_fx_rates("EUR/USD,-1").is_err());
+        assert!(parse_currency_code("US").is_err());
+    }
+
+    #[test]
+    fn money_shows_both_currencies() {
+        let settings = CurrencySettings {
+            stake: "EUR".to_string(),
+            capital: "USD".to_string(),
+            rate: 1.08,
+        };
+        assert_eq!(format_money_with(None, 1234.5), "1234.50");
+        assert_eq!(format_money_with(None, -1e-12), "0.00");
+        assert_eq!(
+            format_money_with(Some(&settings), 100.0),
+            "100.00 EUR (108.00 USD)"
+        );
+        assert!((settings.to_stake(1080.0) - 1000.0).abs() < 1e-9);
+    }
+}
@@ -1,23 +1,58 @@
 //! 显示输出相关功能
 
+use std::path::Path;
+
+use crate::arbitrage::{effective_odds_for, placed_stakes};
+use crate::currency::{format_money, format_money_with};
+use crate::heat::cap_risk_budget;
+use crate::kelly::size_stock_position;
+use crate::ledger::{bet_clv, placement_ev};
+use crate::leverage::{financing_cost, liquidation_price};
+use crate::sizing::position_for_notional;
+use crate::trailing::r_percentile;
 use crate::types::{
-    ArbitrageResult, KellyResult, MultiArbitrageResult, NashResult, PortfolioKellyResult,
-    PortfolioLeg, PortfolioScenario, StockInfo,
+    ArbitrageOptions, ArbitragePlan, ArbitrageResult, ArbitrageScan, BackLayKind, BackLayResult,
+    BetSettlement, CalibrationReport, CapitalSource, ClosingLine, ClvSummary, DutchingResult,
+    FuturesSizing, HedgeBet, HedgeOutcome, HedgeResult, KellyResult, LedgerBet, LedgerEntry,
+    LedgerSummary, LeverageSettings, LeveragedStockResult, LimitedArbitrage, MertonConstraints,
+    MertonResult, MiddleBet, MiddleResult, MoneyContext, MultiArbitrageResult, NashResult,
+    OptionMarket, OptionQuote, OptionResult, OptionStrategy, PathSimulation, PortfolioHeat,
+    PortfolioKellyResult, PortfolioLeg, PortfolioScenario, PositionSizer, PriceView,
+    RoundedArbitrage, ScaleOutResult, ScaleOutTarget, ShrinkAdjustment, SizerResult, StockCosts,
+    StockInfo, StockPositionSize, StockSizingContext, TradeDirection, TradeHistoryResult,
+    TrailingStop, TrailingStopResult, ValueScan, VolatilityLevels, VolatilitySource,
 };
 
 // EV 以百分比显示到小数点后两位，这里使用对应阈值避免出现“显示 0.00% 但判定正/负期望”。
 const EV_EPSILON: f64 = 0.00005;
 
+/// 本金金额，取自账本可用余额时注明来源
+fn format_capital(money: &MoneyContext, capital: f64) -> String {
+    match money.capital_source {
+        CapitalSource::Ledger => format!("{} (账本可用余额)", format_money(money, capital)),
+        CapitalSource::Input => format_money(money, capital),
+    }
+}
+
 /// 格式化百分比
 pub fn format_pct(value: f64) -> String {
     format!("{:.2}%", value * 100.0)
 }
 
+/// `--shrink` 调整前的概率与收缩系数
+fn shrink_note(shrink: &ShrinkAdjustment) -> String {
+    format!(
+        "校准前: {} (收缩系数 {:.2}，向市场概率收缩)",
+        format_pct(shrink.raw_probability),
+        shrink.factor
+    )
+}
+
 fn safe_fraction(value: f64) -> f64 {
     if value.is_finite() { value } else { 0.0 }
 }
 
-fn effective_fraction(expected_value: f64, fraction: f64) -> f64 {
+pub fn effective_fraction(expected_value: f64, fraction: f64) -> f64 {
     if expected_value.abs() <= EV_EPSILON {
         0.0
     } else {
@@ -81,11 +116,39 @@ fn json_optional_number(value: Option<f64>) -> String {
     }
 }
 
+/// 货币设置：下注货币、本金货币与汇率（本金货币 / 下注货币）；JSON 中的金额均为下注货币
+fn json_currency(money: &MoneyContext) -> String {
+    match &money.currency {
+        Some(settings) => format!(
+            r#"{{"stake":"{}","capital":"{}","fx_rate":{}}}"#,
+            settings.stake,
+            settings.capital,
+            json_number(settings.rate)
+        ),
+        None => "null".to_string(),
+    }
+}
+
+/// 本金来源：输入或账本可用余额；未提供本金时为 null
+fn json_capital_source(money: &MoneyContext) -> String {
+    match money.entered_capital {
+        Some(_) => format!(r#""{}""#, money.capital_source.code()),
+        None => "null".to_string(),
+    }
+}
+
 fn json_array(values: &[f64]) -> String {
     let parts: Vec<String> = values.iter().map(|&v| json_number(v)).collect();
     format!("[{}]", parts.join(","))
 }
 
+fn json_optional_array(values: Option<&[f64]>) -> String {
+    match values {
+        Some(v) => json_array(v),
+        None => "null".to_string(),
+    }
+}
+
 fn json_matrix_2x2(matrix: [[f64; 2]; 2]) -> String {
     format!(
         "[[{},{}],[{},{}]]",
@@ -104,214 +167,970 @@ fn format_returns_pct(values: &[f64]) -> String {
         .join(", ")
 }
 
-pub fn print_json_error(message: &str) {
-    println!(r#"{{"ok":false,"error":"{}"}}"#, json_escape(message));
+fn stock_position_fraction(info: &StockInfo, risk_fraction: f64) -> f64 {
+    let net_stop_pct = info.net_risk / info.entry_price;
+    if net_stop_pct > 0.0 {
+        risk_fraction / net_stop_pct
+    } else {
+        0.0
+    }
 }
 
-/// 打印分隔线
-pub fn separator() {
-    println!("{}", "─".repeat(50));
+fn stock_kelly_positions(
+    info: &StockInfo,
+    costs: &StockCosts,
+    full_risk: f64,
+) -> [(&'static str, StockPositionSize); 3] {
+    [
+        ("全凯利", size_stock_position(info, costs, full_risk)),
+        ("半凯利", size_stock_position(info, costs, full_risk * 0.5)),
+        (
+            "1/4凯利",
+            size_stock_position(info, costs, full_risk * 0.25),
+        ),
+    ]
 }
 
-/// 打印标准凯利标题
-pub fn print_title() {
-    separator();
-    println!("                    仓位管理计算器");
-    separator();
-    println!();
+/// 各凯利变体的整手仓位及按组合热度限制后的仓位
+fn stock_heat_capped_positions(
+    info: &StockInfo,
+    costs: &StockCosts,
+    full_risk: f64,
+    heat: &PortfolioHeat,
+) -> [(&'static str, StockPositionSize, StockPositionSize); 3] {
+    stock_kelly_positions(info, costs, full_risk).map(|(label, kelly)| {
+        let multiplier = match label {
+            "半凯利" => 0.5,
+            "1/4凯利" => 0.25,
+            _ => 1.0,
+        };
+        let capped =
+            size_stock_position(info, costs, cap_risk_budget(heat, full_risk * multiplier));
+        (label, kelly, capped)
+    })
 }
 
-/// 打印 Polymarket 标题
-pub fn print_title_polymarket() {
-    separator();
-    println!("              Polymarket 仓位管理计算器");
-    println!("             Position Sizing for Polymarket");
-    separator();
-    println!();
+fn print_stock_risk_suggestion(risk_fraction: f64, position_fraction: f64) {
+    if position_fraction <= 0.0 {
+        println!("    ├─ 风险建议: 0% (不交易)");
+        println!("    └─ 建仓仓位: 0% (不交易)");
+    } else {
+        println!("    ├─ 风险建议: {}", format_pct(risk_fraction));
+        if position_fraction > 1.0 {
+            println!(
+                "    └─ 建仓仓位: {} (需杠杆 {:.2}x)",
+                format_pct(position_fraction),
+                position_fraction
+            );
+        } else {
+            println!("    └─ 建仓仓位: {}", format_pct(position_fraction));
+        }
+    }
 }
 
-/// 打印股票标题
-pub fn print_title_stock() {
-    separator();
-    println!("                    股票交易仓位计算器");
-    separator();
-    println!();
+/// 打印各凯利变体的风险金与整手仓位，返回计算出的仓位
+fn print_stock_risk_positions(
+    info: &StockInfo,
+    costs: &StockCosts,
+    full_risk: f64,
+    money: &MoneyContext,
+) -> [(&'static str, StockPositionSize); 3] {
+    let positions = stock_kelly_positions(info, costs, full_risk);
+    let scales = [1.0, 0.5, 0.25];
+    for (i, ((label, position), scale)) in positions.iter().zip(scales).enumerate() {
+        let branch = if i + 1 == positions.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        println!(
+            "    {} {}: 风险金 {} / {} 股 / 占用资金 {} / 止损亏损 {}",
+            branch,
+            label,
+            format_money(money, full_risk * scale),
+            position.shares,
+            format_money(money, position.capital_used),
+            format_money(money, position.risk_at_stop)
+        );
+    }
+    positions
 }
 
-/// 打印套利标题
-pub fn print_title_arbitrage() {
-    separator();
-    println!("                      套利/抽水计算器");
-    separator();
-    println!();
+fn json_stock_position(position: &StockPositionSize) -> String {
+    format!(
+        r#"{{"shares":{},"capital_used":{},"risk_at_stop":{},"profit_at_target":{}}}"#,
+        position.shares,
+        json_number(position.capital_used),
+        json_number(position.risk_at_stop),
+        json_number(position.profit_at_target)
+    )
 }
 
-/// 打印组合凯利标题
-pub fn print_title_portfolio() {
-    separator();
-    println!("                      组合仓位计算器");
-    separator();
-    println!();
+/// 按减仓比例把总股数拆分为各批次（整手取整，最后一批取剩余）
+fn split_scale_out_shares(total: u64, lot: u64, targets: &[ScaleOutTarget]) -> Vec<u64> {
+    let lot = lot.max(1);
+    let mut remaining = total;
+    let mut split = Vec::with_capacity(targets.len());
+    for (i, target) in targets.iter().enumerate() {
+        let shares = if i + 1 == targets.len() {
+            remaining
+        } else {
+            let raw = (total as f64 * target.portion).floor() as u64;
+            (raw / lot * lot).min(remaining)
+        };
+        remaining -= shares;
+        split.push(shares);
+    }
+    split
 }
 
-/// 打印纳什均衡标题
-pub fn print_title_nash() {
-    separator();
-    println!("                      纳什均衡计算器");
-    println!("                    2x2 Normal Form Game");
-    separator();
-    println!();
+fn scale_out_outcome_label(targets_hit: usize) -> String {
+    if targets_hit == 0 {
+        "直接止损".to_string()
+    } else {
+        format!("到达 T{}", targets_hit)
+    }
 }
 
-/// 打印标准凯利结果
-pub fn print_result(odds: f64, win_rate: f64, result: &KellyResult, capital: Option<f64>) {
-    let fraction = effective_fraction(result.expected_value, result.optimal_fraction);
+fn volatility_source_label(source: VolatilitySource) -> String {
+    match source {
+        VolatilitySource::Atr(atr) => format!("ATR {:.4}", atr),
+        VolatilitySource::Sigma { daily_vol, days } => {
+            format!("日波动率 {} × √{:.0}天", format_pct(daily_vol), days)
+        }
+    }
+}
 
-    println!();
-    separator();
-    println!("                        计算结果");
-    separator();
-    println!();
-    println!("  输入参数:");
-    println!("    ├─ 赔率: {:.2}", odds);
-    println!("    ├─ 净赔率 (b): {:.2}", odds - 1.0);
-    println!("    └─ 胜率 (p): {}", format_pct(win_rate));
-    println!();
-    println!("  分析:");
+fn print_volatility_levels(levels: &VolatilityLevels) {
+    println!("  波动率止损:");
     println!(
-        "    ├─ 期望收益 (EV): {:.2}%",
-        result.expected_value * 100.0
+        "    ├─ 波动率单位: {} = {:.4}",
+        volatility_source_label(levels.source),
+        levels.unit
     );
-
-    print_ev_status(
-        result.positive_ev,
-        result.expected_value,
-        "✓ 正期望值 (值得下注)",
-        "✗ 负期望值 (不建议下注)",
-        "○ 中性期望值 (长期不赚不亏，建议不下注)",
+    println!(
+        "    ├─ 止损距离: {:.2} × 单位 = {:.4}",
+        levels.stop_mult,
+        levels.stop_mult * levels.unit
+    );
+    println!(
+        "    ├─ 止盈距离: {:.2} × 单位 = {:.4}",
+        levels.target_mult,
+        levels.target_mult * levels.unit
     );
+    match (levels.hit_probability, levels.daily_drift) {
+        (Some(p), Some(mu)) => println!(
+            "    └─ 先触及止盈概率: {} (漂移布朗运动，每日漂移 {})",
+            format_pct(p),
+            format_pct(mu)
+        ),
+        (Some(p), None) => println!("    └─ 先触及止盈概率: {} (无漂移布朗运动)", format_pct(p)),
+        (None, _) => println!("    └─ 胜率来源: 手动输入"),
+    }
+    println!();
+}
 
-    if fraction <= 0.0 {
-        println!("    └─ 仓位建议: 0% (不下注)");
-    } else if fraction > 1.0 {
-        println!("    └─ 仓位建议: 100%+ (全仓甚至加杠杆，高风险！)");
-    } else {
-        println!("    └─ 仓位建议: {}", format_pct(fraction));
+fn json_volatility_levels(levels: Option<&VolatilityLevels>) -> String {
+    let Some(levels) = levels else {
+        return "null".to_string();
+    };
+    let source = match levels.source {
+        VolatilitySource::Atr(atr) => format!(r#"{{"type":"atr","atr":{}}}"#, json_number(atr)),
+        VolatilitySource::Sigma { daily_vol, days } => format!(
+            r#"{{"type":"sigma","daily_vol":{},"days":{}}}"#,
+            json_number(daily_vol),
+            json_number(days)
+        ),
+    };
+    format!(
+        r#"{{"source":{},"unit":{},"stop_mult":{},"target_mult":{},"stop_loss":{},"target_price":{},"daily_drift":{},"hit_probability":{}}}"#,
+        source,
+        json_number(levels.unit),
+        json_number(levels.stop_mult),
+        json_number(levels.target_mult),
+        json_number(levels.stop_loss),
+        json_number(levels.target_price),
+        json_optional_number(levels.daily_drift),
+        json_optional_number(levels.hit_probability)
+    )
+}
+
+fn print_portfolio_heat(
+    info: &StockInfo,
+    costs: &StockCosts,
+    heat: &PortfolioHeat,
+    full_risk: f64,
+    capital: f64,
+    money: &MoneyContext,
+) {
+    match &heat.settings.source {
+        Some(path) => println!("  组合热度 (持仓文件 {}):", path),
+        None => println!("  组合热度:"),
+    }
+    for (position, risk) in heat.settings.positions.iter().zip(&heat.position_risks) {
+        println!(
+            "    ├─ {} {} {} 股: 入场 {:.2} / 止损 {:.2} / 止损风险 {}",
+            position.symbol,
+            position.direction.label(),
+            position.shares,
+            position.entry_price,
+            position.stop_loss,
+            format_money(money, *risk)
+        );
+    }
+    match (heat.settings.max_heat, heat.remaining_risk) {
+        (Some(limit), Some(remaining)) => {
+            println!(
+                "    ├─ 当前总热度: {} ({})",
+                format_money(money, heat.total_risk),
+                format_pct(heat.heat)
+            );
+            println!(
+                "    ├─ 热度上限: {} ({})",
+                format_pct(limit),
+                format_money(money, capital * limit)
+            );
+            println!("    └─ 剩余风险额度: {}", format_money(money, remaining));
+        }
+        _ => println!(
+            "    └─ 当前总热度: {} ({})",
+            format_money(money, heat.total_risk),
+            format_pct(heat.heat)
+        ),
     }
     println!();
 
-    if let Some(cap) = capital {
-        println!("  基于本金 {:.2} 的投注金额:", cap);
-        if fraction > 0.0 {
-            println!("    ├─ 全凯利: {:.2}", cap * fraction);
-            println!("    ├─ 半凯利: {:.2}", cap * fraction * 0.5);
-            println!("    └─ 1/4凯利: {:.2}", cap * fraction * 0.25);
+    if full_risk <= 0.0 {
+        return;
+    }
+    if heat.remaining_risk.is_some() {
+        println!("  热度限制后的仓位:");
+    } else {
+        println!("  加入新交易后的热度:");
+    }
+    let positions = stock_heat_capped_positions(info, costs, full_risk, heat);
+    for (i, (label, kelly, capped)) in positions.iter().enumerate() {
+        let branch = if i + 1 == positions.len() {
+            "└─"
         } else {
-            println!("    └─ 建议: 不下注");
+            "├─"
+        };
+        let after = format_pct((heat.total_risk + capped.risk_at_stop) / capital);
+        if heat.remaining_risk.is_some() {
+            println!(
+                "    {} {}: 凯利 {} 股 → 限制后 {} 股 / 止损亏损 {} / 加仓后热度 {}",
+                branch,
+                label,
+                kelly.shares,
+                capped.shares,
+                format_money(money, capped.risk_at_stop),
+                after
+            );
+        } else {
+            println!(
+                "    {} {}: {} 股 / 止损亏损 {} / 加仓后热度 {}",
+                branch,
+                label,
+                kelly.shares,
+                format_money(money, kelly.risk_at_stop),
+                after
+            );
         }
-        println!();
     }
+    println!();
+}
 
-    separator();
+fn json_portfolio_heat(
+    info: &StockInfo,
+    costs: &StockCosts,
+    heat: Option<&PortfolioHeat>,
+    full_risk: f64,
+) -> String {
+    let Some(heat) = heat else {
+        return "null".to_string();
+    };
+    let positions: Vec<String> = heat
+        .settings
+        .positions
+        .iter()
+        .zip(&heat.position_risks)
+        .map(|(position, risk)| {
+            format!(
+                r#"{{"symbol":"{}","direction":"{}","entry_price":{},"stop_loss":{},"shares":{},"risk":{}}}"#,
+                json_escape(&position.symbol),
+                position.direction.as_str(),
+                json_number(position.entry_price),
+                json_number(position.stop_loss),
+                position.shares,
+                json_number(*risk)
+            )
+        })
+        .collect();
+    let [full, half, quarter] = stock_heat_capped_positions(info, costs, full_risk, heat);
+    format!(
+        r#"{{"source":{},"positions":[{}],"total_risk":{},"heat":{},"max_heat":{},"remaining_risk":{},"capped_shares":{{"full":{},"half":{},"quarter":{}}}}}"#,
+        match &heat.settings.source {
+            Some(path) => format!(r#""{}""#, json_escape(path)),
+            None => "null".to_string(),
+        },
+        positions.join(","),
+        json_number(heat.total_risk),
+        json_number(heat.heat),
+        json_optional_number(heat.settings.max_heat),
+        json_optional_number(heat.remaining_risk),
+        json_stock_position(&full.2),
+        json_stock_position(&half.2),
+        json_stock_position(&quarter.2)
+    )
 }
 
-/// 打印 Polymarket 结果
-pub fn print_result_polymarket(
-    market_price: f64,
-    your_probability: f64,
-    result: &KellyResult,
-    capital: Option<f64>,
-) {
-    let fraction = effective_fraction(result.expected_value, result.optimal_fraction);
+fn leverage_settings_label(settings: &LeverageSettings) -> String {
+    let mut label = format!("杠杆上限 {:.2}x", settings.max_leverage);
+    if settings.financing_rate > 0.0 {
+        label.push_str(&format!(
+            " / 融资年利率 {} / 持有 {:.0} 天",
+            format_pct(settings.financing_rate),
+            settings.holding_days
+        ));
+    }
+    label
+}
 
-    println!();
-    separator();
-    println!("                    Polymarket 计算结果");
-    separator();
-    println!();
-    println!("  输入参数:");
+fn json_leverage_settings(settings: &LeverageSettings) -> String {
+    format!(
+        r#"{{"max_leverage":{},"financing_rate":{},"holding_days":{},"maintenance_margin":{}}}"#,
+        json_number(settings.max_leverage),
+        json_number(settings.financing_rate),
+        json_number(settings.holding_days),
+        json_number(settings.maintenance_margin)
+    )
+}
+
+/// 各凯利变体按杠杆名义仓位取整手后的仓位与强平价
+fn leveraged_stock_positions(
+    info: &StockInfo,
+    costs: &StockCosts,
+    leverage: &LeveragedStockResult,
+    capital: f64,
+) -> [(&'static str, StockPositionSize, Option<f64>); 3] {
+    [("全凯利", 1.0), ("半凯利", 0.5), ("1/4凯利", 0.25)].map(|(label, scale)| {
+        let position =
+            position_for_notional(info, costs, capital * leverage.position_fraction * scale);
+        let liquidation = liquidation_price(
+            info.direction,
+            info.entry_price,
+            position.capital_used / capital,
+            leverage.settings.maintenance_margin,
+        );
+        (label, position, liquidation)
+    })
+}
+
+/// 打印期货合约手数
+fn print_futures_sizing(sizing: &FuturesSizing, cap: f64, money: &MoneyContext) {
+    let contract = &sizing.contract;
+    println!("  期货合约:");
+    match (contract.tick_size, contract.tick_value()) {
+        (Some(tick), Some(tick_value)) => println!(
+            "    ├─ 合约乘数: {} / 最小变动价位: {} / 每跳价值: {:.2}",
+            contract.multiplier, tick, tick_value
+        ),
+        _ => println!("    ├─ 合约乘数: {}", contract.multiplier),
+    }
     println!(
-        "    ├─ 市场价格: {:.4}% (市场隐含概率)",
-        market_price * 100.0
+        "    ├─ 每手保证金: {:.2} / 本金可开: {} 手",
+        contract.initial_margin, sizing.max_contracts_by_margin
     );
+    if contract.fee_per_contract > 0.0 {
+        println!("    ├─ 每手单边手续费: {:.2}", contract.fee_per_contract);
+    }
+    if let (Some(stop), Some(target)) = (sizing.ticks_to_stop, sizing.ticks_to_target) {
+        println!("    ├─ 止损跳数: {} / 止盈跳数: {}", stop, target);
+    }
     println!(
-        "    ├─ 你的概率: {} (你估计的真实概率)",
-        format_pct(your_probability)
+        "    ├─ 每手止损风险: {} (占本金 {})",
+        format_money(money, sizing.risk_per_contract),
+        format_pct(sizing.risk_per_contract / cap)
     );
-    println!("    └─ 隐含赔率: {:.6}", 1.0 / market_price);
-    println!();
-    println!("  分析:");
     println!(
-        "    ├─ 期望收益 (EV): {:.2}%",
-        result.expected_value * 100.0
-    );
-
-    print_ev_status(
-        result.positive_ev,
-        result.expected_value,
-        "✓ 正期望值 (值得下注)",
-        "✗ 负期望值 (不建议下注)",
-        "○ 中性期望值 (长期不赚不亏，建议不下注)",
+        "    └─ 每手止盈净收益: {}",
+        format_money(money, sizing.profit_per_contract)
     );
-
-    if fraction <= 0.0 {
-        println!("    └─ 仓位建议: 0% (不下注)");
-    } else if fraction > 1.0 {
-        println!("    └─ 仓位建议: 100%+ (全仓甚至加杠杆，高风险！)");
-    } else {
-        println!("    └─ 仓位建议: {}", format_pct(fraction));
-    }
     println!();
 
-    if let Some(cap) = capital {
-        println!("  基于本金 {:.2} 的投注金额:", cap);
-        if fraction > 0.0 {
-            println!("    ├─ 全凯利: {:.2}", cap * fraction);
-            println!("    ├─ 半凯利: {:.2}", cap * fraction * 0.5);
-            println!("    └─ 1/4凯利: {:.2}", cap * fraction * 0.25);
+    println!("  按合约取整:");
+    let labels = ["全凯利", "半凯利", "1/4凯利"];
+    for (i, (label, position)) in labels.iter().zip(&sizing.positions).enumerate() {
+        let branch = if i + 1 == labels.len() {
+            "└─"
         } else {
-            println!("    └─ 建议: 不下注");
-        }
-        println!();
+            "├─"
+        };
+        println!(
+            "    {} {}: {} 手 / 保证金 {} ({}) / 止损亏损 {} / 止盈净收益 {}{}",
+            branch,
+            label,
+            position.contracts,
+            format_money(money, position.margin_used),
+            format_pct(position.margin_used / cap),
+            format_money(money, position.risk_at_stop),
+            format_money(money, position.profit_at_target),
+            if position.margin_limited {
+                " (受保证金限制)"
+            } else {
+                ""
+            }
+        );
     }
+    if sizing.positions[0].contracts == 0 && sizing.max_contracts_by_margin > 0 {
+        println!("  提示: 风险金不足以承担 1 手的止损风险");
+    }
+}
 
-    separator();
+fn json_futures_sizing(sizing: Option<&FuturesSizing>) -> String {
+    let Some(sizing) = sizing else {
+        return "null".to_string();
+    };
+    let contract = &sizing.contract;
+    let [full, half, quarter] = sizing.positions.map(|position| {
+        format!(
+            r#"{{"contracts":{},"margin_used":{},"risk_at_stop":{},"profit_at_target":{},"margin_limited":{}}}"#,
+            position.contracts,
+            json_number(position.margin_used),
+            json_number(position.risk_at_stop),
+            json_number(position.profit_at_target),
+            position.margin_limited
+        )
+    });
+    format!(
+        r#"{{"multiplier":{},"tick_size":{},"tick_value":{},"initial_margin":{},"fee_per_contract":{},"ticks_to_stop":{},"ticks_to_target":{},"risk_per_contract":{},"profit_per_contract":{},"net_ratio":{},"max_contracts_by_margin":{},"contracts":{{"full":{},"half":{},"quarter":{}}}}}"#,
+        json_number(contract.multiplier),
+        json_optional_number(contract.tick_size),
+        json_optional_number(contract.tick_value()),
+        json_number(contract.initial_margin),
+        json_number(contract.fee_per_contract),
+        sizing
+            .ticks_to_stop
+            .map_or("null".to_string(), |t| t.to_string()),
+        sizing
+            .ticks_to_target
+            .map_or("null".to_string(), |t| t.to_string()),
+        json_number(sizing.risk_per_contract),
+        json_number(sizing.profit_per_contract),
+        json_number(sizing.profit_per_contract / sizing.risk_per_contract),
+        sizing.max_contracts_by_margin,
+        full,
+        half,
+        quarter
+    )
 }
 
-/// 打印股票结果
-pub fn print_result_stock(
+/// 打印股票杠杆融资分析
+fn print_stock_leverage(
     info: &StockInfo,
-    win_rate: f64,
-    result: &KellyResult,
+    costs: &StockCosts,
+    leverage: &LeveragedStockResult,
     capital: Option<f64>,
+    money: &MoneyContext,
 ) {
-    let risk_fraction = effective_fraction(result.expected_value, result.optimal_fraction);
-    let stop_loss_pct = info.risk / info.entry_price;
-    let position_fraction = if stop_loss_pct > 0.0 {
-        risk_fraction / stop_loss_pct
-    } else {
-        0.0
-    };
+    let settings = &leverage.settings;
+    println!("  杠杆与融资:");
+    println!("    ├─ 设置: {}", leverage_settings_label(settings));
+    println!(
+        "    ├─ 扣除融资成本的最优仓位: {}",
+        format_pct(leverage.unconstrained_fraction)
+    );
+    if leverage.unconstrained_fraction > settings.max_leverage {
+        println!(
+            "    ├─ 杠杆凯利仓位: {} (受杠杆上限约束)",
+            format_pct(leverage.position_fraction)
+        );
+    } else {
+        println!(
+            "    ├─ 杠杆凯利仓位: {}",
+            format_pct(leverage.position_fraction)
+        );
+    }
+    if info.direction == TradeDirection::Long {
+        println!(
+            "    ├─ 借入资金: {} / 融资成本: {} (占本金)",
+            format_pct(leverage.borrowed_fraction),
+            format_pct(leverage.financing_cost)
+        );
+    }
+    println!(
+        "    ├─ 期望对数增长: {:.4}%",
+        leverage.expected_log_growth * 100.0
+    );
+    match leverage.liquidation_price {
+        Some(price) => {
+            println!(
+                "    ├─ 强平价 (维持保证金 {}): {:.2}",
+                format_pct(settings.maintenance_margin),
+                price
+            );
+            if leverage.stop_before_liquidation {
+                println!(
+                    "    └─ 止损检查: ✓ 止损价 {:.2} 先于强平触发",
+                    info.stop_loss
+                );
+            } else {
+                println!(
+                    "    └─ 止损检查: ✗ 强平先于止损价 {:.2} 触发，请降低杠杆",
+                    info.stop_loss
+                );
+            }
+        }
+        None => println!("    └─ 强平价: - (未借入资金)"),
+    }
+    println!();
+
+    let Some(cap) = capital else {
+        return;
+    };
+    println!(
+        "  基于本金 {} 的杠杆仓位 (每手 {} 股):",
+        format_capital(money, cap),
+        costs.lot_size
+    );
+    if leverage.position_fraction <= 0.0 {
+        println!("    └─ 建议: 不交易");
+        println!();
+        return;
+    }
+    let positions = leveraged_stock_positions(info, costs, leverage, cap);
+    for (i, (label, position, liquidation)) in positions.iter().enumerate() {
+        let branch = if i + 1 == positions.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        let used = position.capital_used / cap;
+        let mut line = format!(
+            "    {} {}: {} 股 / 名义 {} ({:.2}x) / 止损亏损 {}",
+            branch,
+            label,
+            position.shares,
+            format_money(money, position.capital_used),
+            used,
+            format_money(money, position.risk_at_stop)
+        );
+        if info.direction == TradeDirection::Long && used > 1.0 {
+            line.push_str(&format!(
+                " / 借入 {} / 融资成本 {}",
+                format_money(money, position.capital_used - cap),
+                format_money(money, cap * financing_cost(info.direction, settings, used))
+            ));
+        }
+        if let Some(price) = liquidation {
+            line.push_str(&format!(" / 强平价 {:.2}", price));
+        }
+        println!("{}", line);
+    }
+    println!();
+}
+
+fn json_stock_leverage(
+    info: &StockInfo,
+    costs: &StockCosts,
+    leverage: Option<&LeveragedStockResult>,
+    capital: Option<f64>,
+) -> String {
+    let Some(leverage) = leverage else {
+        return "null".to_string();
+    };
+    let shares = match capital {
+        Some(cap) if leverage.position_fraction > 0.0 => {
+            let [full, half, quarter] = leveraged_stock_positions(info, costs, leverage, cap).map(
+                |(_, position, liquidation)| {
+                    format!(
+                        r#"{{"position":{},"leverage":{},"liquidation_price":{}}}"#,
+                        json_stock_position(&position),
+                        json_number(position.capital_used / cap),
+                        json_optional_number(liquidation)
+                    )
+                },
+            );
+            format!(
+                r#"{{"full":{},"half":{},"quarter":{}}}"#,
+                full, half, quarter
+            )
+        }
+        _ => "null".to_string(),
+    };
+    format!(
+        r#"{{"settings":{},"unconstrained_fraction":{},"position_fraction":{},"borrowed_fraction":{},"financing_cost":{},"expected_log_growth":{},"liquidation_price":{},"stop_before_liquidation":{},"shares":{}}}"#,
+        json_leverage_settings(&leverage.settings),
+        json_number(leverage.unconstrained_fraction),
+        json_number(leverage.position_fraction),
+        json_number(leverage.borrowed_fraction),
+        json_number(leverage.financing_cost),
+        json_number(leverage.expected_log_growth),
+        json_optional_number(leverage.liquidation_price),
+        leverage.stop_before_liquidation,
+        shares
+    )
+}
+
+/// 打印组合杠杆融资分析
+fn print_portfolio_leverage(settings: &LeverageSettings, result: &PortfolioKellyResult) {
+    println!("  杠杆与融资:");
+    println!("    ├─ 设置: {}", leverage_settings_label(settings));
+    println!(
+        "    ├─ 借入资金: {} / 融资成本: {} (占本金)",
+        format_pct(result.borrowed),
+        format_pct(result.financing_cost)
+    );
+    println!(
+        "    └─ 追加保证金概率 (维持保证金 {}): {}",
+        format_pct(settings.maintenance_margin),
+        format_pct(result.margin_call_prob.unwrap_or(0.0))
+    );
+    println!();
+}
+
+fn json_portfolio_leverage(
+    settings: Option<&LeverageSettings>,
+    result: &PortfolioKellyResult,
+) -> String {
+    match settings {
+        Some(settings) => format!(
+            r#"{{"settings":{},"borrowed":{},"financing_cost":{},"margin_call_prob":{}}}"#,
+            json_leverage_settings(settings),
+            json_number(result.borrowed),
+            json_number(result.financing_cost),
+            json_optional_number(result.margin_call_prob)
+        ),
+        None => "null".to_string(),
+    }
+}
+
+/// 打印组合凯利资金分配（全/半/1/4凯利金额与剩余现金或借入资金）
+fn print_portfolio_allocation_amounts(
+    result: &PortfolioKellyResult,
+    cap: f64,
+    money: &MoneyContext,
+) {
+    println!("  基于本金 {} 的分配金额:", format_capital(money, cap));
+    let full_used: f64 = result.allocations.iter().map(|a| cap * a).sum();
+    for (i, alloc) in result.allocations.iter().enumerate() {
+        println!(
+            "    ├─ 标的{}: 全凯利 {} / 半凯利 {} / 1/4凯利 {}",
+            i + 1,
+            format_money(money, cap * alloc),
+            format_money(money, cap * alloc * 0.5),
+            format_money(money, cap * alloc * 0.25)
+        );
+    }
+    if result.total_allocation > 1.0 {
+        println!(
+            "    ├─ 全凯利借入资金: {}",
+            format_money(money, cap * result.borrowed)
+        );
+    } else {
+        println!(
+            "    ├─ 全凯利剩余现金: {}",
+            format_money(money, cap * (1.0 - result.total_allocation).max(0.0))
+        );
+    }
+    println!(
+        "    └─ 全凯利总投入: {} (占比 {})",
+        format_money(money, full_used),
+        format_pct(result.total_allocation)
+    );
+    println!();
+}
+
+fn position_sizer_label(result: &SizerResult) -> String {
+    match &result.sizer {
+        PositionSizer::FixedFractional { risk_fraction } => {
+            format!("固定比例风险 {}", format_pct(*risk_fraction))
+        }
+        PositionSizer::FixedRatio {
+            delta,
+            accumulated_profit,
+        } => format!(
+            "固定比率 (Δ={:.2}, 累计利润 {:.2}, {} 单位)",
+            delta,
+            accumulated_profit,
+            result.units.unwrap_or(0)
+        ),
+        PositionSizer::VolatilityTarget {
+            target_vol,
+            asset_vol,
+        } => format!(
+            "波动率目标 ({} / {})",
+            format_pct(*target_vol),
+            format_pct(*asset_vol)
+        ),
+        PositionSizer::OptimalF { trades } => match &result.optimal_f {
+            Some(f) => format!(
+                "最优 f (f={}, 最大亏损 {:.2}R, {} 笔)",
+                format_pct(f.fraction),
+                f.worst_loss,
+                trades.len()
+            ),
+            None => "最优 f".to_string(),
+        },
+    }
+}
+
+fn print_sizer_comparison(
+    kelly: &StockPositionSize,
+    sizers: &[SizerResult],
+    capital: f64,
+    money: &MoneyContext,
+) {
+    println!(
+        "  仓位算法对比 (基于本金 {}):",
+        format_capital(money, capital)
+    );
+    let rows: Vec<(String, &StockPositionSize)> = std::iter::once(("全凯利".to_string(), kelly))
+        .chain(
+            sizers
+                .iter()
+                .map(|result| (position_sizer_label(result), &result.position)),
+        )
+        .collect();
+    for (i, (label, position)) in rows.iter().enumerate() {
+        let branch = if i + 1 == rows.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        println!(
+            "    {} {}: {} 股 / 占用资金 {} / 止损亏损 {} ({}) / 止盈净收益 {}",
+            branch,
+            label,
+            position.shares,
+            format_money(money, position.capital_used),
+            format_money(money, position.risk_at_stop),
+            format_pct(position.risk_at_stop / capital),
+            format_money(money, position.profit_at_target)
+        );
+    }
+    println!();
+}
+
+fn json_sizer_results(sizers: &[SizerResult], capital: Option<f64>) -> String {
+    let items: Vec<String> = sizers
+        .iter()
+        .map(|result| {
+            let params = match &result.sizer {
+                PositionSizer::FixedFractional { risk_fraction } => {
+                    format!(r#"{{"risk_fraction":{}}}"#, json_number(*risk_fraction))
+                }
+                PositionSizer::FixedRatio {
+                    delta,
+                    accumulated_profit,
+                } => format!(
+                    r#"{{"delta":{},"accumulated_profit":{}}}"#,
+                    json_number(*delta),
+                    json_number(*accumulated_profit)
+                ),
+                PositionSizer::VolatilityTarget {
+                    target_vol,
+                    asset_vol,
+                } => format!(
+                    r#"{{"target_vol":{},"asset_vol":{}}}"#,
+                    json_number(*target_vol),
+                    json_number(*asset_vol)
+                ),
+                PositionSizer::OptimalF { trades } => {
+                    format!(r#"{{"trades":{}}}"#, json_array(trades))
+                }
+            };
+            let optimal_f = match &result.optimal_f {
+                Some(f) => format!(
+                    r#"{{"fraction":{},"worst_loss":{},"twr":{},"geometric_mean":{}}}"#,
+                    json_number(f.fraction),
+                    json_number(f.worst_loss),
+                    json_number(f.twr),
+                    json_number(f.geometric_mean)
+                ),
+                None => "null".to_string(),
+            };
+            format!(
+                r#"{{"method":"{}","params":{},"units":{},"optimal_f":{},"budget":{},"risk_pct_of_capital":{},"position":{}}}"#,
+                result.sizer.as_str(),
+                params,
+                result
+                    .units
+                    .map_or_else(|| "null".to_string(), |u| u.to_string()),
+                optimal_f,
+                json_number(result.budget),
+                json_optional_number(capital.map(|cap| result.position.risk_at_stop / cap)),
+                json_stock_position(&result.position)
+            )
+        })
+        .collect();
+    format!("[{}]", items.join(","))
+}
+
+pub fn print_json_error(message: &str) {
+    println!(r#"{{"ok":false,"error":"{}"}}"#, json_escape(message));
+}
+
+/// 打印分隔线
+pub fn separator() {
+    println!("{}", "─".repeat(50));
+}
 
+/// 打印标准凯利标题
+pub fn print_title() {
+    separator();
+    println!("                    仓位管理计算器");
+    separator();
     println!();
+}
+
+/// 打印 Polymarket 标题
+pub fn print_title_polymarket() {
     separator();
-    println!("                        股票交易计算结果");
+    println!("              Polymarket 仓位管理计算器");
+    println!("             Position Sizing for Polymarket");
+    separator();
+    println!();
+}
+
+/// 打印股票标题
+pub fn print_title_stock() {
+    separator();
+    println!("                    股票交易仓位计算器");
+    separator();
+    println!();
+}
+
+/// 打印套利标题
+pub fn print_title_arbitrage() {
+    separator();
+    println!("                      套利/抽水计算器");
+    separator();
+    println!();
+}
+
+/// 打印组合凯利标题
+pub fn print_title_portfolio() {
+    separator();
+    println!("                      组合仓位计算器");
+    separator();
+    println!();
+}
+
+/// 打印纳什均衡标题
+pub fn print_title_nash() {
+    separator();
+    println!("                      纳什均衡计算器");
+    println!("                    2x2 Normal Form Game");
+    separator();
+    println!();
+}
+
+/// 打印标准凯利结果
+pub fn print_result(
+    odds: f64,
+    win_rate: f64,
+    shrink: Option<ShrinkAdjustment>,
+    result: &KellyResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let fraction = effective_fraction(result.expected_value, result.optimal_fraction);
+
+    println!();
+    separator();
+    println!("                        计算结果");
     separator();
     println!();
     println!("  输入参数:");
-    println!("    ├─ 当前价: {:.2}", info.entry_price);
-    println!("    ├─ 止盈价: {:.2}", info.target_price);
-    println!("    ├─ 止损价: {:.2}", info.stop_loss);
-    println!("    └─ 胜率 (p): {}", format_pct(win_rate));
+    println!("    ├─ 赔率: {:.2}", odds);
+    println!("    ├─ 净赔率 (b): {:.2}", odds - 1.0);
+    match shrink {
+        Some(shrink) => {
+            println!("    ├─ 胜率 (p): {} (校准后)", format_pct(win_rate));
+            println!("    └─ {}", shrink_note(&shrink));
+        }
+        None => println!("    └─ 胜率 (p): {}", format_pct(win_rate)),
+    }
     println!();
-    println!("  风险分析:");
-    println!("    ├─ 预期收益: {:.2}", info.profit);
-    println!("    ├─ 风险: {:.2}", info.risk);
-    println!("    ├─ 止损幅度: {}", format_pct(stop_loss_pct));
-    println!("    └─ 盈亏比: {:.2}", info.ratio);
+    println!("  分析:");
+    println!(
+        "    ├─ 期望收益 (EV): {:.2}%",
+        result.expected_value * 100.0
+    );
+
+    print_ev_status(
+        result.positive_ev,
+        result.expected_value,
+        "✓ 正期望值 (值得下注)",
+        "✗ 负期望值 (不建议下注)",
+        "○ 中性期望值 (长期不赚不亏，建议不下注)",
+    );
+
+    if fraction <= 0.0 {
+        println!("    └─ 仓位建议: 0% (不下注)");
+    } else if fraction > 1.0 {
+        println!("    └─ 仓位建议: 100%+ (全仓甚至加杠杆，高风险！)");
+    } else {
+        println!("    └─ 仓位建议: {}", format_pct(fraction));
+    }
+    println!();
+
+    if let Some(cap) = capital {
+        println!("  基于本金 {} 的投注金额:", format_capital(money, cap));
+        if fraction > 0.0 {
+            println!("    ├─ 全凯利: {}", format_money(money, cap * fraction));
+            println!(
+                "    ├─ 半凯利: {}",
+                format_money(money, cap * fraction * 0.5)
+            );
+            println!(
+                "    └─ 1/4凯利: {}",
+                format_money(money, cap * fraction * 0.25)
+            );
+        } else {
+            println!("    └─ 建议: 不下注");
+        }
+        println!();
+    }
+
+    separator();
+}
+
+/// 打印 Polymarket 结果
+pub fn print_result_polymarket(
+    market_price: f64,
+    your_probability: f64,
+    shrink: Option<ShrinkAdjustment>,
+    result: &KellyResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let fraction = effective_fraction(result.expected_value, result.optimal_fraction);
+
+    println!();
+    separator();
+    println!("                    Polymarket 计算结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    println!(
+        "    ├─ 市场价格: {:.4}% (市场隐含概率)",
+        market_price * 100.0
+    );
+    match shrink {
+        Some(shrink) => {
+            println!("    ├─ 你的概率: {} (校准后)", format_pct(your_probability));
+            println!("    ├─ {}", shrink_note(&shrink));
+        }
+        None => println!(
+            "    ├─ 你的概率: {} (你估计的真实概率)",
+            format_pct(your_probability)
+        ),
+    }
+    println!("    └─ 隐含赔率: {:.6}", 1.0 / market_price);
     println!();
     println!("  分析:");
-    println!("    ├─ 净赔率 (b): {:.2}", info.ratio);
     println!(
         "    ├─ 期望收益 (EV): {:.2}%",
         result.expected_value * 100.0
@@ -320,101 +1139,2007 @@ pub fn print_result_stock(
     print_ev_status(
         result.positive_ev,
         result.expected_value,
-        "✓ 正期望值 (值得交易)",
-        "✗ 负期望值 (不建议交易)",
-        "○ 中性期望值 (长期不赚不亏，建议不交易)",
+        "✓ 正期望值 (值得下注)",
+        "✗ 负期望值 (不建议下注)",
+        "○ 中性期望值 (长期不赚不亏，建议不下注)",
     );
 
-    if position_fraction <= 0.0 {
-        println!("    ├─ 风险建议: 0% (不交易)");
-        println!("    └─ 建仓仓位: 0% (不交易)");
+    if fraction <= 0.0 {
+        println!("    └─ 仓位建议: 0% (不下注)");
+    } else if fraction > 1.0 {
+        println!("    └─ 仓位建议: 100%+ (全仓甚至加杠杆，高风险！)");
+    } else {
+        println!("    └─ 仓位建议: {}", format_pct(fraction));
+    }
+    println!();
+
+    if let Some(cap) = capital {
+        println!("  基于本金 {} 的投注金额:", format_capital(money, cap));
+        if fraction > 0.0 {
+            println!("    ├─ 全凯利: {}", format_money(money, cap * fraction));
+            println!(
+                "    ├─ 半凯利: {}",
+                format_money(money, cap * fraction * 0.5)
+            );
+            println!(
+                "    └─ 1/4凯利: {}",
+                format_money(money, cap * fraction * 0.25)
+            );
+        } else {
+            println!("    └─ 建议: 不下注");
+        }
+        println!();
+    }
+
+    separator();
+}
+
+/// 打印股票结果
+pub fn print_result_stock(
+    info: &StockInfo,
+    costs: &StockCosts,
+    win_rate: f64,
+    result: &KellyResult,
+    context: &StockSizingContext,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let volatility = context.volatility.as_ref();
+    let sizers = &context.sizers;
+    let heat = context.heat.as_ref();
+    let risk_fraction = effective_fraction(result.expected_value, result.optimal_fraction);
+    let stop_loss_pct = info.risk / info.entry_price;
+    let position_fraction = stock_position_fraction(info, risk_fraction);
+
+    println!();
+    separator();
+    println!("                        股票交易计算结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    if info.direction == TradeDirection::Short {
+        println!("    ├─ 方向: {}", info.direction.label());
+    }
+    println!("    ├─ 当前价: {:.2}", info.entry_price);
+    println!("    ├─ 止盈价: {:.2}", info.target_price);
+    println!("    ├─ 止损价: {:.2}", info.stop_loss);
+    if info.direction == TradeDirection::Short
+        && costs.borrow_rate > 0.0
+        && costs.holding_days > 0.0
+    {
+        println!(
+            "    ├─ 融券费用: 年化 {} / 持有 {:.0} 天",
+            format_pct(costs.borrow_rate),
+            costs.holding_days
+        );
+    }
+    println!("    └─ 胜率 (p): {}", format_pct(win_rate));
+    println!();
+    if let Some(levels) = volatility {
+        print_volatility_levels(levels);
+    }
+    println!("  风险分析:");
+    println!("    ├─ 预期收益: {:.2}", info.profit);
+    println!("    ├─ 风险: {:.2}", info.risk);
+    println!("    ├─ 止损幅度: {}", format_pct(stop_loss_pct));
+    if costs.has_fees() {
+        println!("    ├─ 盈亏比: {:.2}", info.ratio);
+        println!("    ├─ 每股净收益 (扣费): {:.4}", info.net_profit);
+        println!("    ├─ 每股净风险 (含费): {:.4}", info.net_risk);
+        println!("    └─ 净盈亏比: {:.2}", info.net_ratio);
+    } else {
+        println!("    └─ 盈亏比: {:.2}", info.ratio);
+    }
+    println!();
+    println!("  分析:");
+    match &context.futures {
+        Some(futures) => println!(
+            "    ├─ 净赔率 (b，每手含费): {:.2}",
+            futures.profit_per_contract / futures.risk_per_contract
+        ),
+        None => println!("    ├─ 净赔率 (b): {:.2}", info.net_ratio),
+    }
+    println!(
+        "    ├─ 期望收益 (EV): {:.2}%",
+        result.expected_value * 100.0
+    );
+
+    print_ev_status(
+        result.positive_ev,
+        result.expected_value,
+        "✓ 正期望值 (值得交易)",
+        "✗ 负期望值 (不建议交易)",
+        "○ 中性期望值 (长期不赚不亏，建议不交易)",
+    );
+
+    if context.futures.is_some() {
+        // 期货名义价值由合约乘数决定，按价格比例的建仓仓位与杠杆没有意义
+        if risk_fraction > 0.0 {
+            println!("    └─ 风险建议: {}", format_pct(risk_fraction));
+        } else {
+            println!("    └─ 风险建议: 0% (不交易)");
+        }
+    } else {
+        print_stock_risk_suggestion(risk_fraction, position_fraction);
+    }
+    println!();
+
+    if let Some(leverage) = &context.leverage {
+        print_stock_leverage(info, costs, leverage, capital, money);
+    }
+
+    if let Some(cap) = capital {
+        println!("  基于本金 {} 的仓位金额:", format_capital(money, cap));
+        if position_fraction > 0.0 {
+            let full_risk = cap * risk_fraction;
+            let half_risk = full_risk * 0.5;
+            let quarter_risk = full_risk * 0.25;
+            println!("    ├─ 全凯利风险金: {}", format_money(money, full_risk));
+            println!("    ├─ 半凯利风险金: {}", format_money(money, half_risk));
+
+            if let Some(futures) = &context.futures {
+                println!(
+                    "    └─ 1/4凯利风险金: {}",
+                    format_money(money, quarter_risk)
+                );
+                println!();
+                print_futures_sizing(futures, cap, money);
+            } else {
+                println!(
+                    "    ├─ 1/4凯利风险金: {}",
+                    format_money(money, quarter_risk)
+                );
+                println!(
+                    "    ├─ 全凯利建仓: {}",
+                    format_money(money, cap * position_fraction)
+                );
+                println!(
+                    "    ├─ 半凯利建仓: {}",
+                    format_money(money, cap * (position_fraction * 0.5))
+                );
+                println!(
+                    "    └─ 1/4凯利建仓: {}",
+                    format_money(money, cap * (position_fraction * 0.25))
+                );
+                println!();
+
+                println!("  按整手取整 (每手 {} 股):", costs.lot_size);
+                let positions = stock_kelly_positions(info, costs, cap * risk_fraction);
+                for (i, (label, position)) in positions.iter().enumerate() {
+                    let branch = if i + 1 == positions.len() {
+                        "└─"
+                    } else {
+                        "├─"
+                    };
+                    println!(
+                        "    {} {}: {} 股 / 占用资金 {} / 止损亏损 {} / 止盈净收益 {}",
+                        branch,
+                        label,
+                        position.shares,
+                        format_money(money, position.capital_used),
+                        format_money(money, position.risk_at_stop),
+                        format_money(money, position.profit_at_target)
+                    );
+                }
+            }
+        } else {
+            println!("    └─ 建议: 不交易");
+        }
+        println!();
+
+        if let Some(heat) = heat {
+            print_portfolio_heat(info, costs, heat, cap * risk_fraction, cap, money);
+        }
+        if !sizers.is_empty() {
+            let kelly = size_stock_position(info, costs, cap * risk_fraction);
+            print_sizer_comparison(&kelly, sizers, cap, money);
+        }
+    }
+
+    separator();
+}
+
+/// 打印股票分批止盈结果
+pub fn print_result_stock_scale_out(
+    targets: &[ScaleOutTarget],
+    breakeven_after_first: bool,
+    costs: &StockCosts,
+    result: &ScaleOutResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let info = &result.info;
+    let kelly = &result.kelly;
+    let risk_fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+    let position_fraction = stock_position_fraction(info, risk_fraction);
+
+    println!();
+    separator();
+    println!("                    股票分批止盈计算结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    if info.direction == TradeDirection::Short {
+        println!("    ├─ 方向: {}", info.direction.label());
+    }
+    println!("    ├─ 当前价: {:.2}", info.entry_price);
+    println!("    ├─ 止损价: {:.2}", info.stop_loss);
+    println!(
+        "    ├─ 移动止损: {}",
+        if breakeven_after_first {
+            "到达 T1 后移至保本"
+        } else {
+            "保持原止损"
+        }
+    );
+    println!("    └─ 分批计划:");
+    for (i, target) in targets.iter().enumerate() {
+        let branch = if i + 1 == targets.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        println!(
+            "       {} T{}: {:.2} / 减仓 {} / 到达概率 {}",
+            branch,
+            i + 1,
+            target.price,
+            format_pct(target.portion),
+            format_pct(target.reach_prob)
+        );
+    }
+    println!();
+    println!("  结果分布 (每股，按初始仓位):");
+    for (i, outcome) in result.outcomes.iter().enumerate() {
+        let branch = if i + 1 == result.outcomes.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        println!(
+            "    {} {}: 概率 {} / 每股净盈亏 {:.4} / {:.2}R",
+            branch,
+            scale_out_outcome_label(outcome.targets_hit),
+            format_pct(outcome.probability),
+            outcome.pnl_per_share,
+            outcome.r_multiple
+        );
+    }
+    println!();
+    println!("  分析:");
+    println!("    ├─ 每股净风险 (止损): {:.4}", info.net_risk);
+    println!(
+        "    ├─ 每股期望净盈亏: {:.4}",
+        result.expected_pnl_per_share
+    );
+    println!(
+        "    ├─ 期望收益 (EV): {:.2}% (每单位风险)",
+        kelly.expected_value * 100.0
+    );
+
+    print_ev_status(
+        kelly.positive_ev,
+        kelly.expected_value,
+        "✓ 正期望值 (值得交易)",
+        "✗ 负期望值 (不建议交易)",
+        "○ 中性期望值 (长期不赚不亏，建议不交易)",
+    );
+
+    print_stock_risk_suggestion(risk_fraction, position_fraction);
+    println!();
+
+    if let Some(cap) = capital {
+        println!("  基于本金 {} 的仓位金额:", format_capital(money, cap));
+        if position_fraction > 0.0 {
+            let positions = print_stock_risk_positions(info, costs, cap * risk_fraction, money);
+            println!();
+
+            let full = &positions[0].1;
+            println!("  全凯利分批明细 (共 {} 股):", full.shares);
+            let split = split_scale_out_shares(full.shares, costs.lot_size, targets);
+            for (i, (target, shares)) in targets.iter().zip(split.iter()).enumerate() {
+                let branch = if i + 1 == targets.len() {
+                    "└─"
+                } else {
+                    "├─"
+                };
+                println!(
+                    "    {} T{} {:.2}: 减仓 {} 股 / 毛收益 {}",
+                    branch,
+                    i + 1,
+                    target.price,
+                    shares,
+                    format_money(
+                        money,
+                        (target.price - info.entry_price) * info.direction.sign() * *shares as f64
+                    )
+                );
+            }
+        } else {
+            println!("    └─ 建议: 不交易");
+        }
+        println!();
+    }
+
+    separator();
+}
+
+fn trailing_stop_label(trailing: TrailingStop) -> String {
+    match trailing {
+        TrailingStop::Percent(pct) => format!("回撤 {}", format_pct(pct)),
+        TrailingStop::Atr { atr, mult } => {
+            format!("ATR {:.4} × {:.2} = {:.4}", atr, mult, atr * mult)
+        }
+    }
+}
+
+/// 打印股票移动止损模拟结果
+pub fn print_result_stock_trailing(
+    trailing: TrailingStop,
+    simulation: &PathSimulation,
+    costs: &StockCosts,
+    result: &TrailingStopResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let info = &result.info;
+    let kelly = &result.kelly;
+    let risk_fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+    let position_fraction = stock_position_fraction(info, risk_fraction);
+    let r = &result.r_multiples;
+
+    println!();
+    separator();
+    println!("                    股票移动止损模拟结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    if info.direction == TradeDirection::Short {
+        println!("    ├─ 方向: {}", info.direction.label());
+    }
+    println!("    ├─ 当前价: {:.2}", info.entry_price);
+    println!("    ├─ 移动止损: {}", trailing_stop_label(trailing));
+    println!("    ├─ 初始止损价: {:.2}", info.stop_loss);
+    println!("    ├─ 最长持有: {} 个交易日", simulation.max_days);
+    println!(
+        "    └─ 模拟: GBM 年化漂移 {} / 年化波动率 {} / {} 条路径 / 种子 {}",
+        format_pct(simulation.annual_drift),
+        format_pct(simulation.annual_vol),
+        simulation.paths,
+        simulation.seed
+    );
+    println!();
+    println!("  收益分布 (以初始止损净风险为 1R):");
+    println!(
+        "    ├─ 移动止损出场: {} / 时间止损出场: {}",
+        format_pct(result.stopped_out_prob),
+        format_pct(result.time_stop_prob)
+    );
+    println!("    ├─ 盈利概率: {}", format_pct(result.win_prob));
+    println!("    ├─ 平均持有: {:.1} 个交易日", result.avg_holding_days);
+    println!(
+        "    ├─ 最差 / 最好: {:.2}R / {:.2}R",
+        r.first().copied().unwrap_or(0.0),
+        r.last().copied().unwrap_or(0.0)
+    );
+    println!(
+        "    └─ 分位数: P5 {:.2}R / P25 {:.2}R / P50 {:.2}R / P75 {:.2}R / P95 {:.2}R",
+        r_percentile(r, 0.05),
+        r_percentile(r, 0.25),
+        r_percentile(r, 0.5),
+        r_percentile(r, 0.75),
+        r_percentile(r, 0.95)
+    );
+    println!();
+    println!("  分析:");
+    println!("    ├─ 每股净风险 (初始止损): {:.4}", info.net_risk);
+    println!(
+        "    ├─ 每股期望净盈亏: {:.4}",
+        result.expected_pnl_per_share
+    );
+    println!(
+        "    ├─ 期望收益 (EV): {:.2}% (每单位风险)",
+        kelly.expected_value * 100.0
+    );
+
+    print_ev_status(
+        kelly.positive_ev,
+        kelly.expected_value,
+        "✓ 正期望值 (值得交易)",
+        "✗ 负期望值 (不建议交易)",
+        "○ 中性期望值 (长期不赚不亏，建议不交易)",
+    );
+    print_stock_risk_suggestion(risk_fraction, position_fraction);
+    println!();
+
+    if let Some(cap) = capital {
+        println!(
+            "  基于本金 {} 的仓位金额 (每手 {} 股):",
+            format_capital(money, cap),
+            costs.lot_size
+        );
+        if position_fraction > 0.0 {
+            print_stock_risk_positions(info, costs, cap * risk_fraction, money);
+        } else {
+            println!("    └─ 建议: 不交易");
+        }
+        println!();
+    }
+
+    separator();
+}
+
+/// 打印交易历史凯利估计结果
+pub fn print_result_trade_history(
+    source: &str,
+    r_multiple: bool,
+    result: &TradeHistoryResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let summary = &result.summary;
+    let unit = if r_multiple { "R" } else { "" };
+
+    println!();
+    separator();
+    println!("                        交易历史凯利估计");
+    separator();
+    println!();
+    println!("  输入参数:");
+    println!("    ├─ 文件: {}", source);
+    println!(
+        "    ├─ 数据类型: {}",
+        if r_multiple {
+            "R 倍数"
+        } else {
+            "每笔盈亏"
+        }
+    );
+    println!("    └─ 交易笔数: {}", summary.count);
+    println!();
+    println!("  交易统计:");
+    println!(
+        "    ├─ 盈利 / 亏损 / 持平: {} / {} / {}",
+        summary.wins,
+        summary.losses,
+        summary.count - summary.wins - summary.losses
+    );
+    println!("    ├─ 胜率: {}", format_pct(summary.win_rate));
+    println!("    ├─ 平均盈利: {:.4}{}", summary.avg_win, unit);
+    println!("    ├─ 平均亏损: {:.4}{}", summary.avg_loss, unit);
+    println!("    ├─ 盈亏比: {:.2}", summary.payoff_ratio);
+    println!("    ├─ 每笔期望: {:.4}{}", summary.expectancy, unit);
+    println!("    ├─ 盈利因子: {:.2}", summary.profit_factor);
+    println!("    ├─ 最大盈利: {:.4}{}", summary.best, unit);
+    println!("    └─ 最大亏损: {:.4}{}", summary.worst, unit);
+    println!();
+    println!("  分析:");
+    if result.optimal_f.fraction > 0.0 {
+        println!("    ├─ 状态: ✓ 样本期望为正");
+    } else {
+        println!("    ├─ 状态: ✗ 样本期望非正 (不建议交易)");
+    }
+    println!(
+        "    ├─ 公式凯利 (胜率/盈亏比): {}",
+        format_pct(result.formula_kelly)
+    );
+    println!(
+        "    ├─ 最优 f (Vince): {}",
+        format_pct(result.optimal_f.fraction)
+    );
+    println!(
+        "    ├─ TWR: {:.4} / 几何平均: {:.6}",
+        result.optimal_f.twr, result.optimal_f.geometric_mean
+    );
+    if r_multiple {
+        println!(
+            "    └─ 经验凯利风险比例 (每 1R): {}",
+            format_pct(result.risk_fraction)
+        );
+    } else if result.optimal_f.fraction > 0.0 {
+        println!(
+            "    └─ 每单位所需资金 f$: {:.2}",
+            -result.optimal_f.worst_loss / result.optimal_f.fraction
+        );
+    } else {
+        println!("    └─ 每单位所需资金 f$: - (不交易)");
+    }
+    println!();
+
+    if let Some(interval) = &result.bootstrap {
+        println!(
+            "  Bootstrap 置信区间 ({} 次重抽样, 有效 {}, 置信度 {}):",
+            interval.samples,
+            interval.valid_samples,
+            format_pct(interval.confidence)
+        );
+        let f_branch = if interval.risk_low.is_some() {
+            "├─"
+        } else {
+            "└─"
+        };
+        println!(
+            "    {} 最优 f: [{}, {}] 中位数 {}",
+            f_branch,
+            format_pct(interval.fraction_low),
+            format_pct(interval.fraction_high),
+            format_pct(interval.fraction_median)
+        );
+        if let (Some(low), Some(high)) = (interval.risk_low, interval.risk_high) {
+            println!(
+                "    └─ 风险比例 (每 1R): [{}, {}]",
+                format_pct(low),
+                format_pct(high)
+            );
+        }
+        println!();
+    }
+
+    if let Some(cap) = capital {
+        println!("  基于本金 {} 的仓位:", format_capital(money, cap));
+        let full = cap * result.risk_fraction;
+        if full > 0.0 {
+            if r_multiple {
+                println!("    ├─ 全凯利风险金: {}", format_money(money, full));
+                println!("    ├─ 半凯利风险金: {}", format_money(money, full * 0.5));
+                println!("    └─ 1/4凯利风险金: {}", format_money(money, full * 0.25));
+            } else {
+                println!("    ├─ 全凯利交易单位数: {:.2}", full);
+                println!("    ├─ 半凯利交易单位数: {:.2}", full * 0.5);
+                println!("    └─ 1/4凯利交易单位数: {:.2}", full * 0.25);
+            }
+        } else {
+            println!("    └─ 建议: 不交易");
+        }
+        println!();
+    }
+
+    separator();
+}
+
+fn option_strategy_label(strategy: &OptionStrategy) -> String {
+    match strategy.short_strike {
+        Some(short) => format!(
+            "{}垂直价差: 买入 {:.2} / 卖出 {:.2}",
+            strategy.kind.label(),
+            strategy.long_strike,
+            short
+        ),
+        None => format!(
+            "买入{}期权: 行权价 {:.2}",
+            strategy.kind.label(),
+            strategy.long_strike
+        ),
+    }
+}
+
+/// 按每张合约最大亏损取整的合约张数
+fn option_contracts(result: &OptionResult, multiplier: u64, risk_budget: f64) -> u64 {
+    let per_contract = result.max_loss * multiplier as f64;
+    if per_contract > 0.0 && risk_budget > 0.0 {
+        (risk_budget * (1.0 + 1e-9) / per_contract).floor() as u64
+    } else {
+        0
+    }
+}
+
+fn merton_constraints_label(constraints: &MertonConstraints) -> String {
+    match (constraints.long_only, constraints.max_leverage) {
+        (false, None) => "无".to_string(),
+        (true, None) => "禁止做空".to_string(),
+        (false, Some(cap)) => format!("总敞口 ≤ {:.2}x", cap),
+        (true, Some(cap)) => format!("禁止做空 / 总敞口 ≤ {:.2}x", cap),
+    }
+}
+
+/// 打印连续收益凯利（Merton）结果
+pub fn print_result_merton(
+    means: &[f64],
+    covariance: &[Vec<f64>],
+    risk_free: f64,
+    constraints: &MertonConstraints,
+    result: &MertonResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    println!();
+    separator();
+    println!("                连续收益凯利计算结果（Merton）");
+    separator();
+    println!();
+    println!("  输入参数 ({}个标的):", means.len());
+    println!("    ├─ 无风险利率: {}", format_pct(risk_free));
+    for (i, mean) in means.iter().enumerate() {
+        let vol = covariance[i][i].sqrt();
+        println!(
+            "    ├─ 标的{}: 期望收益 {} / 波动率 {} / 夏普比率 {:.2}",
+            i + 1,
+            format_pct(*mean),
+            format_pct(vol),
+            (mean - risk_free) / vol
+        );
+    }
+    println!("    └─ 约束: {}", merton_constraints_label(constraints));
+    println!();
+    println!("  组合分析:");
+    println!("    ├─ 净仓位: {}", format_pct(result.total_allocation));
+    println!("    ├─ 总敞口: {}", format_pct(result.gross_exposure));
+    println!(
+        "    ├─ 期望年化收益: {}",
+        format_pct(result.expected_return)
+    );
+    println!("    ├─ 年化波动率: {}", format_pct(result.volatility));
+    println!(
+        "    ├─ 期望对数增长: {:.4}%",
+        result.expected_log_growth * 100.0
+    );
+    if result.constrained {
+        println!(
+            "    └─ 收敛状态: {} (迭代 {} 次)",
+            if result.converged {
+                "已收敛"
+            } else {
+                "达到迭代上限"
+            },
+            result.iterations
+        );
+    } else {
+        println!("    └─ 求解: 无约束解析解 Σ⁻¹(μ - r)");
+    }
+    println!();
+    println!("  仓位分配:");
+    for (i, (alloc, free)) in result
+        .allocations
+        .iter()
+        .zip(&result.unconstrained)
+        .enumerate()
+    {
+        let branch = if i + 1 == result.allocations.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        if result.constrained {
+            println!(
+                "    {} 标的{}: {} (无约束 {})",
+                branch,
+                i + 1,
+                format_pct(*alloc),
+                format_pct(*free)
+            );
+        } else {
+            println!("    {} 标的{}: {}", branch, i + 1, format_pct(*alloc));
+        }
+    }
+    println!();
+
+    if let Some(cap) = capital {
+        println!(
+            "  基于本金 {} 的分配金额 (负数为做空):",
+            format_capital(money, cap)
+        );
+        for (i, alloc) in result.allocations.iter().enumerate() {
+            println!(
+                "    ├─ 标的{}: 全凯利 {} / 半凯利 {} / 1/4凯利 {}",
+                i + 1,
+                format_money(money, cap * alloc),
+                format_money(money, cap * alloc * 0.5),
+                format_money(money, cap * alloc * 0.25)
+            );
+        }
+        if result.total_allocation > 1.0 {
+            println!(
+                "    ├─ 全凯利按无风险利率借入: {}",
+                format_money(money, cap * (result.total_allocation - 1.0))
+            );
+        } else {
+            println!(
+                "    ├─ 全凯利无风险资产: {}",
+                format_money(money, cap * (1.0 - result.total_allocation))
+            );
+        }
+        println!(
+            "    └─ 全凯利总敞口: {} (占比 {})",
+            format_money(money, cap * result.gross_exposure),
+            format_pct(result.gross_exposure)
+        );
+        println!();
+    }
+
+    separator();
+}
+
+/// 打印期权仓位计算结果
+pub fn print_result_option(
+    strategy: &OptionStrategy,
+    market: &OptionMarket,
+    view: &PriceView,
+    multiplier: u64,
+    result: &OptionResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let kelly = &result.kelly;
+    let risk_fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+    let premium_label = if result.net_premium >= 0.0 {
+        "支付"
+    } else {
+        "收取"
+    };
+
+    println!();
+    separator();
+    println!("                        期权仓位计算结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    println!("    ├─ 策略: {}", option_strategy_label(strategy));
+    println!("    ├─ 现价: {:.2}", market.spot);
+    println!(
+        "    ├─ 隐含波动率: {} / 无风险利率: {}",
+        format_pct(market.implied_vol),
+        format_pct(market.rate)
+    );
+    println!("    ├─ 到期: {:.0} 天", market.days);
+    match view {
+        PriceView::Lognormal { drift, vol } => println!(
+            "    └─ 观点: 对数正态 年化漂移 {} / 主观波动率 {}",
+            format_pct(*drift),
+            format_pct(*vol)
+        ),
+        PriceView::Scenarios(points) => {
+            println!("    └─ 观点: {} 个到期价格情景", points.len())
+        }
+    }
+    println!();
+    println!("  Black–Scholes 定价 (每股):");
+    let print_leg = |action: &str, quote: &OptionQuote| {
+        println!(
+            "    ├─ {}{} {:.2}: 权利金 {:.4} / Delta {:.4}",
+            action,
+            strategy.kind.label(),
+            quote.strike,
+            quote.price,
+            quote.delta
+        );
+    };
+    print_leg("买入", &result.long_leg);
+    if let Some(short) = &result.short_leg {
+        print_leg("卖出", short);
+    }
+    println!(
+        "    └─ 净权利金: {} {:.4}",
+        premium_label,
+        result.net_premium.abs()
+    );
+    println!();
+    println!("  到期收益 (每股，含权利金资金成本):");
+    println!("    ├─ 最大亏损: {:.4}", result.max_loss);
+    match result.max_profit {
+        Some(profit) => println!("    ├─ 最大盈利: {:.4}", profit),
+        None => println!("    ├─ 最大盈利: 无上限"),
+    }
+    match result.breakeven {
+        Some(price) => println!("    ├─ 盈亏平衡价: {:.2}", price),
+        None => println!("    ├─ 盈亏平衡价: - (到期不可能盈利)"),
+    }
+    if let PriceView::Scenarios(_) = view {
+        for outcome in &result.outcomes {
+            println!(
+                "    ├─ 到期价 {:.2} (概率 {}): 盈亏 {:+.4} ({:+.2} 倍最大亏损)",
+                outcome.spot,
+                format_pct(outcome.probability),
+                outcome.pnl,
+                outcome.pnl / result.max_loss
+            );
+        }
+    }
+    println!("    ├─ 盈利概率: {}", format_pct(result.win_prob));
+    println!("    └─ 期望净盈亏: {:.4}", result.expected_pnl);
+    println!();
+    println!("  分析:");
+    println!(
+        "    ├─ 期望收益 (EV): {:.2}% (每单位最大亏损)",
+        kelly.expected_value * 100.0
+    );
+    print_ev_status(
+        kelly.positive_ev,
+        kelly.expected_value,
+        "✓ 正期望值 (值得交易)",
+        "✗ 负期望值 (不建议交易)",
+        "○ 中性期望值 (长期不赚不亏，建议不交易)",
+    );
+    if risk_fraction <= 0.0 {
+        println!("    └─ 风险建议: 0% (不交易)");
+    } else {
+        println!(
+            "    └─ 风险建议: {} (最大亏损占本金)",
+            format_pct(risk_fraction)
+        );
+    }
+    println!();
+
+    if let Some(cap) = capital {
+        println!(
+            "  基于本金 {} 的仓位 (每张 {} 股):",
+            format_capital(money, cap),
+            multiplier
+        );
+        if risk_fraction > 0.0 {
+            let variants = [("全凯利", 1.0), ("半凯利", 0.5), ("1/4凯利", 0.25)];
+            for (i, (label, scale)) in variants.iter().enumerate() {
+                let budget = cap * risk_fraction * scale;
+                let contracts = option_contracts(result, multiplier, budget);
+                let units = (contracts * multiplier) as f64;
+                println!(
+                    "    {} {}: 风险金 {} / {} 张 / {}权利金 {} / 最大亏损 {}",
+                    if i + 1 == variants.len() {
+                        "└─"
+                    } else {
+                        "├─"
+                    },
+                    label,
+                    format_money(money, budget),
+                    contracts,
+                    premium_label,
+                    format_money(money, result.net_premium.abs() * units),
+                    format_money(money, result.max_loss * units)
+                );
+            }
+        } else {
+            println!("    └─ 建议: 不交易");
+        }
+        println!();
+    }
+
+    separator();
+}
+
+/// 打印单边限额约束下的套利方案
+fn print_limited_arbitrage(limited: &LimitedArbitrage, label: &str, money: &MoneyContext) {
+    println!("  单边限额约束:");
+    for (i, (stake, max)) in limited.stakes.iter().zip(&limited.max_stakes).enumerate() {
+        let mark = if i == limited.limiting_leg && limited.binding {
+            " ← 限制标的"
+        } else {
+            ""
+        };
+        println!(
+            "    ├─ {}{}投注: {} (限额 {}){}",
+            label,
+            i + 1,
+            format_money(money, *stake),
+            format_money(money, *max),
+            mark
+        );
+    }
+    println!(
+        "    ├─ 限额允许的最大总投入: {} (由{}{}决定)",
+        format_money(money, limited.max_total),
+        label,
+        limited.limiting_leg + 1
+    );
+    println!(
+        "    ├─ 可投入总额: {}",
+        format_money(money, limited.total_stake)
+    );
+    println!(
+        "    └─ 保证收益: {}",
+        format_money(money, limited.guaranteed_profit)
+    );
+    println!();
+}
+
+/// 打印按投注单位取整后的套利方案
+fn print_rounded_arbitrage(rounded: &RoundedArbitrage, label: &str, money: &MoneyContext) {
+    let steps: Vec<String> = rounded.steps.iter().map(|s| s.to_string()).collect();
+    println!("  按投注单位取整 (单位 {}):", steps.join(" / "));
+    for (i, stake) in rounded.stakes.iter().enumerate() {
+        println!(
+            "    ├─ {}{}投注: {}",
+            label,
+            i + 1,
+            format_money(money, *stake)
+        );
+    }
+    println!(
+        "    ├─ 总投入: {}",
+        format_money(money, rounded.total_stake)
+    );
+    for (i, profit) in rounded.profits.iter().enumerate() {
+        println!(
+            "    ├─ {}{}胜出收益: {}",
+            label,
+            i + 1,
+            format_money(money, *profit)
+        );
+    }
+    if rounded.survives {
+        println!(
+            "    └─ ✓ 取整后仍保证盈利 (最差收益 {})",
+            format_money(money, rounded.min_profit)
+        );
+    } else {
+        println!(
+            "    └─ ✗ 取整后套利消失 (最差收益 {})",
+            format_money(money, rounded.min_profit)
+        );
+    }
+    println!();
+}
+
+/// 打印各标的佣金、手续费与有效赔率
+fn print_arbitrage_costs(odds: &[f64], options: &ArbitrageOptions, label: &str) {
+    for (i, effective) in effective_odds_for(odds, options).iter().enumerate() {
+        let (commission, fee) = options.leg_costs(i);
+        println!(
+            "    ├─ {}{}有效赔率: {:.4} (佣金 {} / 手续费 {})",
+            label,
+            i + 1,
+            effective,
+            format_pct(commission),
+            format_pct(fee)
+        );
+    }
+}
+
+/// 资金支出对应的投注额，有手续费时注明另付金额
+fn format_stake_outlay(outlay: f64, fee: f64, money: &MoneyContext) -> String {
+    let stake = outlay / (1.0 + fee);
+    if fee > 0.0 {
+        format!(
+            "{} (另付手续费 {})",
+            format_money(money, stake),
+            format_money(money, outlay - stake)
+        )
+    } else {
+        format_money(money, stake)
+    }
+}
+
+/// 打印套利结果
+pub fn print_result_arbitrage(
+    odds1: f64,
+    odds2: f64,
+    options: &ArbitrageOptions,
+    result: &ArbitrageResult,
+    plan: &ArbitragePlan,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let (limited, rounded) = (plan.limited.as_ref(), plan.rounded.as_ref());
+    println!();
+    separator();
+    println!("                      套利/抽水计算结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    println!("    ├─ 方案1赔率: {:.2}", odds1);
+    println!("    ├─ 方案2赔率: {:.2}", odds2);
+    println!();
+    println!("  分析:");
+    let effective = effective_odds_for(&[odds1, odds2], options);
+    if options.has_costs() {
+        print_arbitrage_costs(&[odds1, odds2], options, "方案");
+    }
+    println!("    ├─ 方案1隐含概率: {:.2}%", (1.0 / effective[0]) * 100.0);
+    println!("    ├─ 方案2隐含概率: {:.2}%", (1.0 / effective[1]) * 100.0);
+    println!(
+        "    └─ 隐含概率之和: {:.2}%",
+        result.total_implied_prob * 100.0
+    );
+    println!();
+
+    if result.has_arbitrage {
+        println!("  ✓ 套利机会存在！");
+        println!("    ├─ 套利收益率: {:.2}%", result.arbitrage_profit * 100.0);
+        println!("    ├─ 方案1投注比例: {:.2}%", result.stake1_ratio * 100.0);
+        println!("    └─ 方案2投注比例: {:.2}%", result.stake2_ratio * 100.0);
+        println!();
+
+        if let Some(cap) = capital {
+            println!("  基于本金 {} 的投注方案:", format_capital(money, cap));
+            let stake1 = cap * result.stake1_ratio;
+            let stake2 = cap * result.stake2_ratio;
+            let total_return = cap * (1.0 + result.arbitrage_profit);
+            println!(
+                "    ├─ 方案1投注: {}",
+                format_stake_outlay(stake1, options.leg_costs(0).1, money)
+            );
+            println!(
+                "    ├─ 方案2投注: {}",
+                format_stake_outlay(stake2, options.leg_costs(1).1, money)
+            );
+            println!(
+                "    └─ 获胜总回报: {} (收益: {})",
+                format_money(money, total_return),
+                format_money(money, total_return - cap)
+            );
+            println!();
+        }
+        if let Some(limited) = limited {
+            print_limited_arbitrage(limited, "方案", money);
+        }
+        if let Some(rounded) = rounded {
+            print_rounded_arbitrage(rounded, "方案", money);
+        }
+    } else {
+        println!("  ✗ 无套利机会");
+        println!("    └─ 庄家抽水: {:.2}%", result.juice_rate * 100.0);
+        println!();
+    }
+
+    separator();
+}
+
+/// 打印对冲下注（背注 + 交易所对冲）结果
+pub fn print_result_back_lay(
+    back_odds: f64,
+    lay_odds: f64,
+    back_stake: f64,
+    commission: f64,
+    kind: BackLayKind,
+    result: &BackLayResult,
+    money: &MoneyContext,
+) {
+    println!();
+    separator();
+    println!("                      对冲下注计算结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    println!("    ├─ 类型: {}", kind.label());
+    println!("    ├─ 背注赔率: {:.2}", back_odds);
+    println!("    ├─ 对冲赔率: {:.2}", lay_odds);
+    println!("    ├─ 背注金额: {}", format_money(money, back_stake));
+    match kind {
+        BackLayKind::RiskFree { retention } => {
+            println!("    ├─ 交易所佣金: {}", format_pct(commission));
+            println!("    └─ 退款保留率: {}", format_pct(retention));
+        }
+        _ => println!("    └─ 交易所佣金: {}", format_pct(commission)),
+    }
+    println!();
+
+    println!("  对冲方案:");
+    println!("    ├─ 对冲金额: {}", format_money(money, result.lay_stake));
+    println!("    ├─ 对冲负债: {}", format_money(money, result.liability));
+    println!("    └─ 赔率比: {}", format_pct(result.rating));
+    println!();
+
+    let lay_wins = result.lay_stake * (1.0 - commission);
+    println!("  结果:");
+    println!(
+        "    ├─ 背注赢: 背注 {} / 对冲 {} / 合计 {}",
+        format_money(money, result.back_wins_profit + result.liability),
+        format_money(money, -result.liability),
+        format_money(money, result.back_wins_profit)
+    );
+    println!(
+        "    ├─ 对冲赢: 背注 {} / 对冲 {} / 合计 {}",
+        format_money(money, result.lay_wins_profit - lay_wins),
+        format_money(money, lay_wins),
+        format_money(money, result.lay_wins_profit)
+    );
+    if result.profit >= 0.0 {
+        match result.conversion {
+            Some(conversion) => println!(
+                "    └─ ✓ 锁定盈利: {} (转化率 {})",
+                format_money(money, result.profit),
+                format_pct(conversion)
+            ),
+            None => println!("    └─ ✓ 锁定盈利: {}", format_money(money, result.profit)),
+        }
+    } else {
+        println!("    └─ ✗ 资格亏损: {}", format_money(money, -result.profit));
+    }
+    println!();
+
+    separator();
+}
+
+/// 打印套利扫描结果
+pub fn print_result_scan(scan: &ArbitrageScan, capital: Option<f64>, money: &MoneyContext) {
+    println!();
+    separator();
+    println!("                        套利扫描结果");
+    separator();
+    println!();
+    println!("  快照:");
+    println!("    ├─ 报价数量: {}", scan.quote_count);
+    println!("    ├─ 盘口数量: {}", scan.market_count);
+    println!("    └─ 跳过单结果盘口: {}", scan.skipped_markets);
+    println!();
+    println!(
+        "  命中 {} 个盘口 (套利或抽水率 ≤ {}):",
+        scan.markets.len(),
+        format_pct(scan.max_margin)
+    );
+    if scan.markets.is_empty() {
+        println!("    └─ 未发现套利或低抽水盘口");
+        println!();
+    }
+    for (rank, market) in scan.markets.iter().enumerate() {
+        println!();
+        if market.result.has_arbitrage {
+            println!(
+                "  #{} {} / {}  ✓ 套利 {:+.2}%",
+                rank + 1,
+                market.event,
+                market.market,
+                market.profit * 100.0
+            );
+        } else {
+            println!(
+                "  #{} {} / {}  抽水 {}",
+                rank + 1,
+                market.event,
+                market.market,
+                format_pct(market.result.juice_rate)
+            );
+        }
+        let last = market.legs.len() - 1;
+        for (i, leg) in market.legs.iter().enumerate() {
+            let branch = if i == last && capital.is_none() {
+                "└─"
+            } else {
+                "├─"
+            };
+            let amount = match capital {
+                Some(cap) => format!(" {}", format_money(money, cap * leg.stake_ratio)),
+                None => String::new(),
+            };
+            println!(
+                "    {} {} @ {:.2} ({}): {}{}",
+                branch,
+                leg.outcome,
+                leg.odds,
+                leg.bookmaker,
+                format_pct(leg.stake_ratio),
+                amount
+            );
+        }
+        if let Some(cap) = capital {
+            println!(
+                "    └─ 任一结果回报: {} / 净收益 {}",
+                format_money(money, cap * (1.0 + market.profit)),
+                format_money(money, cap * market.profit)
+            );
+        }
+    }
+    if !scan.markets.is_empty() {
+        println!();
+    }
+
+    separator();
+}
+
+/// 价值投注对应的 `-k` 组合命令；标的数量不在 2-12 之间时返回 None
+fn value_portfolio_command(scan: &ValueScan, capital: Option<f64>) -> Option<String> {
+    if !(2..=12).contains(&scan.bets.len()) {
+        return None;
+    }
+    let mut parts: Vec<String> = scan.bets.iter().map(|b| b.descriptor.clone()).collect();
+    if let Some(cap) = capital {
+        parts.push(format!("{:.2}", cap));
+    }
+    Some(format!("bo -k {}", parts.join(" ")))
+}
+
+/// 账本金额以本金货币记，不做汇率换算
+fn ledger_money(amount: f64) -> String {
+    format_money_with(None, amount)
+}
+
+fn describe_ledger_entry(entry: &LedgerEntry) -> String {
+    match entry {
+        LedgerEntry::Deposit { date, amount } => format!("{date} 入金 {}", ledger_money(*amount)),
+        LedgerEntry::Withdraw { date, amount } => {
+            format!("{date} 出金 {}", ledger_money(*amount))
+        }
+        LedgerEntry::Bet {
+            id,
+            date,
+            odds,
+            stake,
+            label,
+            ..
+        } => format!(
+            "{date} 下注 #{id} {} @ {odds:.2}{}",
+            ledger_money(*stake),
+            if label.is_empty() {
+                String::new()
+            } else {
+                format!(" ({label})")
+            }
+        ),
+        LedgerEntry::Settle {
+            id,
+            date,
+            settlement,
+            returned,
+        } => format!(
+            "{date} 结算 #{id} {}，返还 {}",
+            settlement.label(),
+            ledger_money(*returned)
+        ),
+        LedgerEntry::Close { id, line } => format!(
+            "{} 收盘 #{id} @ {:.2}，{}概率 {}",
+            line.date,
+            line.odds,
+            if line.devigged { "去水" } else { "隐含" },
+            format_pct(line.probability)
+        ),
+    }
+}
+
+fn clv_bucket_label(lower: f64, upper: f64) -> String {
+    match (lower.is_finite(), upper.is_finite()) {
+        (false, _) => format!("< {:+.0}%", upper * 100.0),
+        (_, false) => format!(">= {:+.0}%", lower * 100.0),
+        _ => format!("{:+.0}% ~ {:+.0}%", lower * 100.0, upper * 100.0),
+    }
+}
+
+/// 打印收盘价值（CLV）汇总与最近有收盘价的下注
+fn print_ledger_clv(summary: &LedgerSummary) {
+    let Some(clv) = &summary.clv else {
+        return;
+    };
+    println!("  收盘价值 (CLV):");
+    println!(
+        "    ├─ 有收盘价: {} 笔，赢过收盘价 {}",
+        clv.count,
+        format_pct(clv.beat_rate)
+    );
+    println!(
+        "    ├─ 平均 CLV: {:+.2}%（中位数 {:+.2}%，按金额加权 {:+.2}%）",
+        clv.mean_clv * 100.0,
+        clv.median_clv * 100.0,
+        clv.weighted_clv * 100.0
+    );
+    match (clv.mean_placement_ev, clv.mean_compared_clv) {
+        (Some(ev), Some(closing)) => {
+            println!(
+                "    └─ 下注时 EV 对比: {} 笔平均 EV {:+.2}%，平均 CLV {:+.2}%",
+                clv.compared,
+                ev * 100.0,
+                closing * 100.0
+            );
+            if closing < ev {
+                println!("       ⚠ CLV 低于下注时 EV，胜率估计可能偏乐观");
+            }
+        }
+        _ => println!("    └─ 下注时 EV 对比: 未记录下注时胜率（add --prob）"),
+    }
+    println!();
+
+    let peak = clv
+        .distribution
+        .iter()
+        .map(|(_, _, n)| *n)
+        .max()
+        .unwrap_or(0);
+    println!("  CLV 分布:");
+    for (i, (lower, upper, count)) in clv.distribution.iter().enumerate() {
+        let branch = if i + 1 == clv.distribution.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        let width = if peak > 0 {
+            (*count as f64 / peak as f64 * LEDGER_CURVE_WIDTH).round() as usize
+        } else {
+            0
+        };
+        println!(
+            "    {branch} {:<14} {:>4} {}",
+            clv_bucket_label(*lower, *upper),
+            count,
+            "█".repeat(width)
+        );
+    }
+    println!();
+
+    println!("  按盘口类型:");
+    for (i, group) in clv.by_market.iter().enumerate() {
+        let branch = if i + 1 == clv.by_market.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        println!(
+            "    {branch} {}: {} 笔，平均 CLV {:+.2}%，赢过收盘价 {}",
+            group.market,
+            group.count,
+            group.mean_clv * 100.0,
+            format_pct(group.beat_rate)
+        );
+    }
+    println!();
+
+    let closed: Vec<&LedgerBet> = summary
+        .bets
+        .iter()
+        .filter(|b| b.closing.is_some())
+        .collect();
+    let recent = &closed[closed.len().saturating_sub(LEDGER_CURVE_POINTS)..];
+    println!("  最近收盘价（{} 笔）:", recent.len());
+    for (i, bet) in recent.iter().enumerate() {
+        let branch = if i + 1 == recent.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        let Some(line) = &bet.closing else {
+            continue;
+        };
+        println!(
+            "    {branch} #{} {:.2} → 收盘 {:.2}（{} {}） CLV {:+.2}%{}",
+            bet.id,
+            bet.odds,
+            line.odds,
+            if line.devigged { "去水" } else { "隐含" },
+            format_pct(line.probability),
+            bet_clv(bet).unwrap_or(0.0) * 100.0,
+            match placement_ev(bet) {
+                Some(ev) => format!("，下注时 EV {:+.2}%", ev * 100.0),
+                None => String::new(),
+            }
+        );
+    }
+    println!();
+}
+
+/// 打印 `--record` 写入账本的下注
+pub fn print_recorded_bets(path: &Path, entries: &[LedgerEntry]) {
+    println!("  记入账本: {}", path.display());
+    if entries.is_empty() {
+        println!("    └─ 没有可记录的下注（无正期望或无套利），未记录");
+    }
+    for (i, entry) in entries.iter().enumerate() {
+        let branch = if i + 1 == entries.len() {
+            "└─"
+        } else {
+            "├─"
+        };
+        println!("    {} ✓ {}", branch, describe_ledger_entry(entry));
+    }
+    println!();
+}
+
+/// 资金曲线最多显示的点数
+const LEDGER_CURVE_POINTS: usize = 20;
+const LEDGER_CURVE_WIDTH: f64 = 30.0;
+
+/// 打印账本汇总
+pub fn print_result_ledger(path: &Path, recorded: Option<&LedgerEntry>, summary: &LedgerSummary) {
+    println!();
+    separator();
+    println!("                          投注账本");
+    separator();
+    println!();
+    println!("  账本文件: {}", path.display());
+    if let Some(entry) = recorded {
+        println!("  ✓ 已记录: {}", describe_ledger_entry(entry));
+    }
+    println!();
+
+    println!("  资金:");
+    println!("    ├─ 累计入金: {}", ledger_money(summary.deposits));
+    println!("    ├─ 累计出金: {}", ledger_money(summary.withdrawals));
+    println!("    ├─ 当前资金: {}", ledger_money(summary.bankroll));
+    println!("    ├─ 未结算投注: {}", ledger_money(summary.pending_stake));
+    println!("    └─ 可用余额: {}", ledger_money(summary.balance));
+    println!();
+
+    let settled: Vec<&LedgerBet> = summary
+        .bets
+        .iter()
+        .filter(|b| b.settlement.is_some())
+        .collect();
+    let count = |kind: BetSettlement| {
+        settled
+            .iter()
+            .filter(|b| b.settlement.as_ref().is_some_and(|(s, _, _)| *s == kind))
+            .count()
+    };
+    println!("  收益:");
+    println!(
+        "    ├─ 已结算: {} 笔（赢 {} / 输 {} / 走水 {} / 部分 {}）",
+        settled.len(),
+        count(BetSettlement::Win),
+        count(BetSettlement::Loss),
+        count(BetSettlement::Void),
+        count(BetSettlement::Partial)
+    );
+    println!("    ├─ 投注额: {}", ledger_money(summary.turnover));
+    println!("    ├─ 已实现盈亏: {}", ledger_money(summary.realized_pnl));
+    match summary.roi {
+        Some(roi) => println!("    └─ ROI: {:+.2}%", roi * 100.0),
+        None => println!("    └─ ROI: 尚无已结算投注"),
+    }
+    println!();
+
+    let open: Vec<&LedgerBet> = summary
+        .bets
+        .iter()
+        .filter(|b| b.settlement.is_none())
+        .collect();
+    if !open.is_empty() {
+        println!("  未结算投注:");
+        for (i, bet) in open.iter().enumerate() {
+            let branch = if i + 1 == open.len() {
+                "└─"
+            } else {
+                "├─"
+            };
+            println!(
+                "    {branch} #{} {} {} @ {:.2}{}{}",
+                bet.id,
+                bet.date,
+                ledger_money(bet.stake),
+                bet.odds,
+                if bet.mode.is_empty() {
+                    String::new()
+                } else {
+                    format!(" [{}]", bet.mode)
+                },
+                if bet.label.is_empty() {
+                    String::new()
+                } else {
+                    format!(" {}", bet.label)
+                }
+            );
+        }
+        println!();
+    }
+
+    if !summary.curve.is_empty() {
+        let start = summary.curve.len().saturating_sub(LEDGER_CURVE_POINTS);
+        let points = &summary.curve[start..];
+        let peak = points.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+        println!("  资金曲线（最近 {} 点）:", points.len());
+        for (i, (date, bankroll)) in points.iter().enumerate() {
+            let branch = if i + 1 == points.len() {
+                "└─"
+            } else {
+                "├─"
+            };
+            let width = if peak > 0.0 {
+                (bankroll.max(0.0) / peak * LEDGER_CURVE_WIDTH).round() as usize
+            } else {
+                0
+            };
+            println!(
+                "    {branch} {date} {:>12} {}",
+                ledger_money(*bankroll),
+                "█".repeat(width)
+            );
+        }
+        println!();
+    }
+    print_ledger_clv(summary);
+    if summary.balance > 0.0 {
+        println!("  提示: 其他模式加 --ledger 即以可用余额作为默认本金");
+        println!();
+    }
+    separator();
+}
+
+/// 打印概率校准报告
+pub fn print_result_calibration(source: &str, skipped: Option<usize>, report: &CalibrationReport) {
+    println!();
+    separator();
+    println!("                        概率校准报告");
+    separator();
+    println!();
+    println!("  数据:");
+    println!("    ├─ 来源: {source}");
+    if let Some(skipped) = skipped {
+        println!("    ├─ 跳过下注: {skipped} 笔（未结算、走水/部分结算或未记录 --prob）");
+    }
+    println!(
+        "    ├─ 已结算预测: {} 笔，命中 {} 笔",
+        report.count, report.wins
+    );
+    println!("    ├─ 平均预测概率: {}", format_pct(report.mean_predicted));
+    println!("    └─ 实际命中率: {}", format_pct(report.observed_rate));
+    println!();
+
+    println!("  评分（越低越好）:");
+    println!(
+        "    ├─ Brier 分数: {:.4}（市场 {:.4}）",
+        report.brier, report.market_brier
+    );
+    println!(
+        "    └─ 对数损失: {:.4}（市场 {:.4}）",
+        report.log_loss, report.market_log_loss
+    );
+    if report.log_loss > report.market_log_loss {
+        println!("       ⚠ 你的概率不如市场隐含概率，下注前应向市场收缩");
+    }
+    println!();
+
+    println!("  可靠性表:");
+    println!(
+        "    {:<8} {:>6} {:>10} {:>10} {:>11}",
+        "预测区间", "笔数", "平均预测", "实际命中", "偏差"
+    );
+    for bucket in &report.buckets {
+        println!(
+            "    {:<12} {:>8} {:>14} {:>14} {:>+12.2}%",
+            format!("{:.0}%-{:.0}%", bucket.lower * 100.0, bucket.upper * 100.0),
+            bucket.count,
+            format_pct(bucket.mean_predicted),
+            format_pct(bucket.observed),
+            (bucket.observed - bucket.mean_predicted) * 100.0
+        );
+    }
+    println!();
+
+    println!("  收缩系数 (向市场概率):");
+    if report.shrink_factor >= 0.999 {
+        println!("    └─ k = 1.00，预测优于市场，无需收缩");
+    } else {
+        println!("    ├─ k = {:.2}", report.shrink_factor);
+        println!(
+            "    ├─ 收缩后: Brier {:.4}，对数损失 {:.4}",
+            report.shrunk_brier, report.shrunk_log_loss
+        );
+        println!(
+            "    └─ 以后下注可加 --shrink {:.2}，使 p' = 市场概率 + k × (p - 市场概率)",
+            report.shrink_factor
+        );
+    }
+    println!();
+    separator();
+}
+
+/// 打印价值投注扫描结果
+pub fn print_result_value_scan(scan: &ValueScan, capital: Option<f64>, money: &MoneyContext) {
+    println!();
+    separator();
+    println!("                      价值投注扫描结果");
+    separator();
+    println!();
+    println!("  数据:");
+    println!(
+        "    ├─ 报价: {} 条 ({})",
+        scan.quote_count,
+        if scan.polymarket {
+            "Polymarket 价格"
+        } else {
+            "小数赔率"
+        }
+    );
+    println!("    ├─ 模型概率: {} 条", scan.probability_count);
+    println!(
+        "    ├─ 匹配结果: {} / {} (按最优价格去重)",
+        scan.matched, scan.outcome_count
+    );
+    println!("    └─ 最小优势: {:.2} 个百分点", scan.min_edge * 100.0);
+    println!();
+    println!("  命中 {} 个价值投注 (按期望收益排序):", scan.bets.len());
+    if scan.bets.is_empty() {
+        println!("    └─ 没有优势超过阈值的结果");
+    }
+
+    for (rank, bet) in scan.bets.iter().enumerate() {
+        let fraction = effective_fraction(bet.kelly.expected_value, bet.kelly.optimal_fraction);
+        println!();
+        if scan.polymarket {
+            println!(
+                "  #{} {} / {} / {} @ 价格 {} ({})",
+                rank + 1,
+                bet.event,
+                bet.market,
+                bet.outcome,
+                format_pct(bet.implied_prob),
+                bet.bookmaker
+            );
+        } else {
+            println!(
+                "  #{} {} / {} / {} @ {:.2} ({})",
+                rank + 1,
+                bet.event,
+                bet.market,
+                bet.outcome,
+                bet.odds,
+                bet.bookmaker
+            );
+        }
+        println!(
+            "    ├─ 模型概率 {} / 隐含概率 {} / 优势 {:+.2}%",
+            format_pct(bet.probability),
+            format_pct(bet.implied_prob),
+            bet.edge * 100.0
+        );
+        println!(
+            "    ├─ 期望收益 (EV): {:+.2}%",
+            bet.kelly.expected_value * 100.0
+        );
+        match capital {
+            Some(cap) => {
+                println!("    ├─ 凯利仓位: {}", format_pct(fraction));
+                println!(
+                    "    └─ 全凯利 {} / 半凯利 {} / 1/4凯利 {}",
+                    format_money(money, cap * fraction),
+                    format_money(money, cap * fraction * 0.5),
+                    format_money(money, cap * fraction * 0.25)
+                );
+            }
+            None => println!("    └─ 凯利仓位: {}", format_pct(fraction)),
+        }
+    }
+    println!();
+
+    if !scan.bets.is_empty() {
+        println!("  组合凯利 (-k 联合优化，按独立事件处理):");
+        match value_portfolio_command(scan, capital) {
+            Some(command) => println!("    {}", command),
+            None => println!("    标的数量需在 2-12 之间，当前 {}", scan.bets.len()),
+        }
+        let mut keys: Vec<(&str, &str)> = scan
+            .bets
+            .iter()
+            .map(|b| (b.event.as_str(), b.market.as_str()))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        if keys.len() < scan.bets.len() {
+            println!("    ⚠ 同一盘口有多个结果，它们互斥而非独立，组合结果仅供参考");
+        }
+        println!();
+    }
+
+    separator();
+}
+
+/// 打印分散投注各选项金额
+fn print_dutching_stakes(odds: &[f64], result: &DutchingResult, total: f64, money: &MoneyContext) {
+    for (i, (o, w)) in odds.iter().zip(&result.stake_ratios).enumerate() {
+        println!(
+            "    ├─ 选项{} @ {:.2}: {} ({})",
+            i + 1,
+            o,
+            format_money(money, total * w),
+            format_pct(*w)
+        );
+    }
+    println!(
+        "    ├─ 任一选项胜出: 回报 {} / 净收益 {}",
+        format_money(money, total * result.combined_odds),
+        format_money(money, total * result.win_return)
+    );
+    println!("    └─ 全部落空: 亏损 {}", format_money(money, total));
+}
+
+/// 打印分散投注（Dutching）结果
+pub fn print_result_dutching(
+    odds: &[f64],
+    win_prob: Option<f64>,
+    result: &DutchingResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    println!();
+    separator();
+    println!("                   分散投注 (Dutching) 计算结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    println!("    ├─ 选项数量: {}", odds.len());
+    println!(
+        "    ├─ 赔率: {}",
+        odds.iter()
+            .map(|o| format!("{:.2}", o))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+    match win_prob {
+        Some(p) => println!("    └─ 子集胜率: {}", format_pct(p)),
+        None => println!("    └─ 子集胜率: 未提供"),
+    }
+    println!();
+    println!("  分析:");
+    println!(
+        "    ├─ 合计隐含概率: {}",
+        format_pct(result.total_implied_prob)
+    );
+    println!("    ├─ 等效赔率: {:.4}", result.combined_odds);
+    println!(
+        "    └─ 任一选项胜出收益率: {:+.2}%",
+        result.win_return * 100.0
+    );
+    println!();
+
+    if let Some(total) = result.total_stake {
+        println!("  投注分配 (总投注 {}):", format_money(money, total));
+        print_dutching_stakes(odds, result, total, money);
+        println!();
+    }
+
+    if let Some(kelly) = &result.kelly {
+        let fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+        println!("  凯利仓位:");
+        println!("    ├─ 期望收益 (EV): {:.2}%", kelly.expected_value * 100.0);
+        print_ev_status(
+            kelly.positive_ev,
+            kelly.expected_value,
+            "✓ 正期望值 (值得分散投注)",
+            "✗ 负期望值 (不建议下注)",
+            "○ 中性期望值 (长期不赚不亏，建议不下注)",
+        );
+        if fraction <= 0.0 {
+            println!("    └─ 仓位建议: 0% (不下注)");
+        } else if fraction > 1.0 {
+            println!("    └─ 仓位建议: 100%+ (全仓甚至加杠杆，高风险！)");
+        } else {
+            println!("    └─ 仓位建议 (总投注): {}", format_pct(fraction));
+        }
+        println!();
+
+        if let Some(cap) = capital {
+            println!("  基于本金 {} 的总投注:", format_capital(money, cap));
+            if fraction > 0.0 {
+                println!("    ├─ 全凯利: {}", format_money(money, cap * fraction));
+                println!(
+                    "    ├─ 半凯利: {}",
+                    format_money(money, cap * fraction * 0.5)
+                );
+                println!(
+                    "    └─ 1/4凯利: {}",
+                    format_money(money, cap * fraction * 0.25)
+                );
+                println!();
+                println!("  全凯利投注分配:");
+                print_dutching_stakes(odds, result, cap * fraction, money);
+            } else {
+                println!("    └─ 建议: 不下注");
+            }
+            println!();
+        }
+    }
+
+    separator();
+}
+
+/// 打印已有投注的对冲方案
+pub fn print_result_hedge(
+    bet: &HedgeBet,
+    polymarket: bool,
+    cash_out: Option<f64>,
+    result: &HedgeResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    println!();
+    separator();
+    println!("                        对冲计算结果");
+    separator();
+    println!();
+    println!("  输入参数:");
+    if polymarket {
+        println!(
+            "    ├─ 原持仓: {} @ 买入价格 {}",
+            format_money(money, bet.stake),
+            format_pct(1.0 / bet.odds)
+        );
+        println!(
+            "    ├─ 当前价格: {} (反向价格 {})",
+            format_pct(1.0 - 1.0 / bet.hedge_odds),
+            format_pct(1.0 / bet.hedge_odds)
+        );
+    } else {
+        println!(
+            "    ├─ 原投注: {} @ {:.2}",
+            format_money(money, bet.stake),
+            bet.odds
+        );
+        println!("    ├─ 当前反向赔率: {:.2}", bet.hedge_odds);
+    }
+    match bet.win_prob {
+        Some(p) => println!("    └─ 更新胜率: {}", format_pct(p)),
+        None => println!("    └─ 更新胜率: 未提供"),
+    }
+    println!();
+
+    let full = &result.full;
+    println!("  完全对冲:");
+    println!("    ├─ 对冲金额: {}", format_money(money, full.hedge_stake));
+    if full.profit_if_win >= 0.0 {
+        println!(
+            "    ├─ ✓ 锁定盈利: {} (总投入收益率 {:+.2}%)",
+            format_money(money, full.profit_if_win),
+            result.locked_return * 100.0
+        );
     } else {
-        println!("    ├─ 风险建议: {}", format_pct(risk_fraction));
-        if position_fraction > 1.0 {
+        println!(
+            "    ├─ ✗ 锁定亏损: {} (总投入收益率 {:+.2}%)",
+            format_money(money, -full.profit_if_win),
+            result.locked_return * 100.0
+        );
+    }
+    match cash_out {
+        Some(offer) => {
             println!(
-                "    └─ 建仓仓位: {} (需杠杆 {:.2}x)",
-                format_pct(position_fraction),
-                position_fraction
+                "    ├─ 等效兑现价值: {}",
+                format_money(money, result.cash_out_value)
             );
-        } else {
-            println!("    └─ 建仓仓位: {}", format_pct(position_fraction));
+            if offer >= result.cash_out_value {
+                println!(
+                    "    └─ 兑现报价 {} 不低于对冲等效价值，直接兑现更优",
+                    format_money(money, offer)
+                );
+            } else {
+                println!(
+                    "    └─ 兑现报价 {} 低于对冲等效价值 {}，对冲更优",
+                    format_money(money, offer),
+                    format_money(money, result.cash_out_value - offer)
+                );
+            }
         }
+        None => println!(
+            "    └─ 等效兑现价值: {}",
+            format_money(money, result.cash_out_value)
+        ),
     }
     println!();
 
-    if let Some(cap) = capital {
-        println!("  基于本金 {:.2} 的仓位金额:", cap);
-        if position_fraction > 0.0 {
-            let full_risk = cap * risk_fraction;
-            let half_risk = full_risk * 0.5;
-            let quarter_risk = full_risk * 0.25;
-            println!("    ├─ 全凯利风险金: {:.2}", full_risk);
-            println!("    ├─ 半凯利风险金: {:.2}", half_risk);
-            println!("    ├─ 1/4凯利风险金: {:.2}", quarter_risk);
-            println!("    ├─ 全凯利建仓: {:.2}", cap * position_fraction);
-            println!("    ├─ 半凯利建仓: {:.2}", cap * (position_fraction * 0.5));
+    if !result.partial.is_empty() {
+        println!("  部分对冲 (占完全对冲金额):");
+        let last = result.partial.len() - 1;
+        for (i, outcome) in result.partial.iter().enumerate() {
+            let branch = if i == last { "└─" } else { "├─" };
+            let expected = match outcome.expected_profit {
+                Some(ev) => format!(" / 期望 {}", format_money(money, ev)),
+                None => String::new(),
+            };
             println!(
-                "    └─ 1/4凯利建仓: {:.2}",
-                cap * (position_fraction * 0.25)
+                "    {} {}: 对冲 {} / 原投注赢 {} / 原投注输 {} / 敞口 {}{}",
+                branch,
+                format_pct(outcome.ratio),
+                format_money(money, outcome.hedge_stake),
+                format_money(money, outcome.profit_if_win),
+                format_money(money, outcome.profit_if_lose),
+                format_money(money, outcome.profit_if_win - outcome.profit_if_lose),
+                expected
             );
-        } else {
-            println!("    └─ 建议: 不交易");
         }
         println!();
     }
 
+    match (&result.kelly, capital) {
+        (Some(kelly), Some(cap)) => {
+            println!("  凯利对冲 (可用本金 {}):", format_capital(money, cap));
+            if kelly.hedge_stake <= 0.0 {
+                println!("    ├─ 对冲金额: 0 (按更新胜率持有原投注更优)");
+            } else {
+                println!(
+                    "    ├─ 对冲金额: {} (完全对冲的 {})",
+                    format_money(money, kelly.hedge_stake),
+                    format_pct(kelly.ratio)
+                );
+            }
+            println!(
+                "    ├─ 原投注赢: {}",
+                format_money(money, kelly.profit_if_win)
+            );
+            println!(
+                "    ├─ 原投注输: {}",
+                format_money(money, kelly.profit_if_lose)
+            );
+            println!(
+                "    └─ 期望收益: {}",
+                format_money(money, kelly.expected_profit.unwrap_or(0.0))
+            );
+            println!();
+        }
+        _ if bet.win_prob.is_some() => {
+            println!("  提示: 提供可用本金后可计算凯利对冲金额");
+            println!();
+        }
+        _ => {}
+    }
+
     separator();
 }
 
-/// 打印套利结果
-pub fn print_result_arbitrage(
-    odds1: f64,
-    odds2: f64,
-    result: &ArbitrageResult,
+/// 打印中间盘结果
+pub fn print_result_middle(
+    bet: &MiddleBet,
+    result: &MiddleResult,
     capital: Option<f64>,
+    money: &MoneyContext,
 ) {
+    let fraction = effective_fraction(result.kelly.expected_value, result.kelly.optimal_fraction);
+
     println!();
     separator();
-    println!("                      套利/抽水计算结果");
+    println!("                      中间盘计算结果");
     separator();
     println!();
     println!("  输入参数:");
-    println!("    ├─ 方案1赔率: {:.2}", odds1);
-    println!("    ├─ 方案2赔率: {:.2}", odds2);
+    println!("    ├─ 方案1 (结果 > {}): 赔率 {:.2}", bet.line1, bet.odds1);
+    println!("    ├─ 方案2 (结果 < {}): 赔率 {:.2}", bet.line2, bet.odds2);
+    println!("    └─ 落入中间区概率: {}", format_pct(bet.middle_prob));
     println!();
     println!("  分析:");
-    println!("    ├─ 方案1隐含概率: {:.2}%", (1.0 / odds1) * 100.0);
-    println!("    ├─ 方案2隐含概率: {:.2}%", (1.0 / odds2) * 100.0);
+    println!("    ├─ 中间区: ({}, {})，两边同时赢", bet.line1, bet.line2);
     println!(
-        "    └─ 隐含概率之和: {:.2}%",
-        result.total_implied_prob * 100.0
+        "    ├─ 隐含概率之和: {}",
+        format_pct(result.total_implied_prob)
+    );
+    println!(
+        "    ├─ 投注比例: 方案1 {} / 方案2 {}",
+        format_pct(result.stake_ratios[0]),
+        format_pct(result.stake_ratios[1])
     );
+    if result.guaranteed {
+        println!(
+            "    ├─ ✓ 最差结果 (未落入中间区): {:+.2}%",
+            result.outside_return * 100.0
+        );
+    } else {
+        println!(
+            "    ├─ 最差结果 (未落入中间区): {:+.2}%",
+            result.outside_return * 100.0
+        );
+    }
+    println!("    ├─ 落入中间区: {:+.2}%", result.middle_return * 100.0);
+    println!(
+        "    ├─ 期望收益 (EV): {:.2}%",
+        result.kelly.expected_value * 100.0
+    );
+    print_ev_status(
+        result.kelly.positive_ev,
+        result.kelly.expected_value,
+        "✓ 正期望值 (值得做中间盘)",
+        "✗ 负期望值 (中间区概率不足以弥补抽水)",
+        "○ 中性期望值 (长期不赚不亏，建议不下注)",
+    );
+    if fraction <= 0.0 {
+        println!("    └─ 仓位建议: 0% (不下注)");
+    } else if result.guaranteed {
+        println!("    └─ 仓位建议 (总投注): 100% (最差结果不亏损)");
+    } else {
+        println!("    └─ 仓位建议 (总投注): {}", format_pct(fraction));
+    }
     println!();
 
-    if result.has_arbitrage {
-        println!("  ✓ 套利机会存在！");
-        println!("    ├─ 套利收益率: {:.2}%", result.arbitrage_profit * 100.0);
-        println!("    ├─ 方案1投注比例: {:.2}%", result.stake1_ratio * 100.0);
-        println!("    └─ 方案2投注比例: {:.2}%", result.stake2_ratio * 100.0);
-        println!();
-
-        if let Some(cap) = capital {
-            println!("  基于本金 {:.2} 的投注方案:", cap);
-            let stake1 = cap * result.stake1_ratio;
-            let stake2 = cap * result.stake2_ratio;
-            let total_return = cap * (1.0 + result.arbitrage_profit);
-            println!("    ├─ 方案1投注: {:.2}", stake1);
-            println!("    ├─ 方案2投注: {:.2}", stake2);
+    if let Some(cap) = capital {
+        println!("  基于本金 {} 的投注金额:", format_capital(money, cap));
+        if fraction > 0.0 {
+            for (label, scale, last) in [
+                ("全凯利", 1.0, false),
+                ("半凯利", 0.5, false),
+                ("1/4凯利", 0.25, true),
+            ] {
+                let total = cap * fraction * scale;
+                println!(
+                    "    {} {}: 总投注 {} (方案1 {} / 方案2 {})",
+                    if last { "└─" } else { "├─" },
+                    label,
+                    format_money(money, total),
+                    format_money(money, total * result.stake_ratios[0]),
+                    format_money(money, total * result.stake_ratios[1])
+                );
+            }
+            let total = cap * fraction;
+            println!();
+            println!("  全凯利结果:");
             println!(
-                "    └─ 获胜总回报: {:.2} (收益: {:.2})",
-                total_return,
-                total_return - cap
+                "    ├─ 未落入中间区: {}",
+                format_money(money, total * result.outside_return)
             );
-            println!();
+            println!(
+                "    └─ 落入中间区: {}",
+                format_money(money, total * result.middle_return)
+            );
+        } else {
+            println!("    └─ 建议: 不下注");
         }
-    } else {
-        println!("  ✗ 无套利机会");
-        println!("    └─ 庄家抽水: {:.2}%", result.juice_rate * 100.0);
         println!();
     }
 
@@ -424,9 +3149,13 @@ pub fn print_result_arbitrage(
 /// 打印多标的套利结果
 pub fn print_result_multi_arbitrage(
     odds: &[f64],
+    options: &ArbitrageOptions,
     result: &MultiArbitrageResult,
+    plan: &ArbitragePlan,
     capital: Option<f64>,
+    money: &MoneyContext,
 ) {
+    let (limited, rounded) = (plan.limited.as_ref(), plan.rounded.as_ref());
     println!();
     separator();
     println!("                      多标的套利/抽水计算结果");
@@ -438,7 +3167,10 @@ pub fn print_result_multi_arbitrage(
     }
     println!();
     println!("  分析:");
-    for (i, &o) in odds.iter().enumerate() {
+    if options.has_costs() {
+        print_arbitrage_costs(odds, options, "标的");
+    }
+    for (i, o) in effective_odds_for(odds, options).iter().enumerate() {
         println!("    ├─ 标的{}隐含概率: {:.2}%", i + 1, (1.0 / o) * 100.0);
     }
     println!(
@@ -457,19 +3189,29 @@ pub fn print_result_multi_arbitrage(
         println!();
 
         if let Some(cap) = capital {
-            println!("  基于本金 {:.2} 的投注方案:", cap);
+            println!("  基于本金 {} 的投注方案:", format_capital(money, cap));
             let total_return = cap * (1.0 + result.arbitrage_profit);
             for (i, ratio) in result.stake_ratios.iter().enumerate() {
                 let stake = cap * ratio;
-                println!("    ├─ 标的{}投注: {:.2}", i + 1, stake);
+                println!(
+                    "    ├─ 标的{}投注: {}",
+                    i + 1,
+                    format_stake_outlay(stake, options.leg_costs(i).1, money)
+                );
             }
             println!(
-                "    └─ 获胜总回报: {:.2} (收益: {:.2})",
-                total_return,
-                total_return - cap
+                "    └─ 获胜总回报: {} (收益: {})",
+                format_money(money, total_return),
+                format_money(money, total_return - cap)
             );
             println!();
         }
+        if let Some(limited) = limited {
+            print_limited_arbitrage(limited, "标的", money);
+        }
+        if let Some(rounded) = rounded {
+            print_rounded_arbitrage(rounded, "标的", money);
+        }
     } else {
         println!("  ✗ 无套利机会");
         println!("    └─ 庄家抽水: {:.2}%", result.juice_rate * 100.0);
@@ -482,8 +3224,10 @@ pub fn print_result_multi_arbitrage(
 /// 打印组合凯利结果
 pub fn print_result_portfolio(
     legs: &[PortfolioLeg],
+    leverage: Option<&LeverageSettings>,
     result: &PortfolioKellyResult,
     capital: Option<f64>,
+    money: &MoneyContext,
 ) {
     println!();
     separator();
@@ -531,29 +3275,12 @@ pub fn print_result_portfolio(
         println!("    ├─ 标的{}: {}", i + 1, format_pct(*alloc));
     }
     println!();
+    if let Some(settings) = leverage {
+        print_portfolio_leverage(settings, result);
+    }
 
     if let Some(cap) = capital {
-        println!("  基于本金 {:.2} 的分配金额:", cap);
-        let full_used: f64 = result.allocations.iter().map(|a| cap * a).sum();
-        for (i, alloc) in result.allocations.iter().enumerate() {
-            println!(
-                "    ├─ 标的{}: 全凯利 {:.2} / 半凯利 {:.2} / 1/4凯利 {:.2}",
-                i + 1,
-                cap * alloc,
-                cap * alloc * 0.5,
-                cap * alloc * 0.25
-            );
-        }
-        println!(
-            "    ├─ 全凯利剩余现金: {:.2}",
-            cap * (1.0 - result.total_allocation).max(0.0)
-        );
-        println!(
-            "    └─ 全凯利总投入: {:.2} (占比 {})",
-            full_used,
-            format_pct(result.total_allocation)
-        );
-        println!();
+        print_portfolio_allocation_amounts(result, cap, money);
     }
 
     separator();
@@ -563,8 +3290,10 @@ pub fn print_result_portfolio(
 pub fn print_result_portfolio_correlated(
     leg_count: usize,
     scenarios: &[PortfolioScenario],
+    leverage: Option<&LeverageSettings>,
     result: &PortfolioKellyResult,
     capital: Option<f64>,
+    money: &MoneyContext,
 ) {
     println!();
     separator();
@@ -617,29 +3346,12 @@ pub fn print_result_portfolio_correlated(
         println!("    ├─ 标的{}: {}", i + 1, format_pct(*alloc));
     }
     println!();
+    if let Some(settings) = leverage {
+        print_portfolio_leverage(settings, result);
+    }
 
     if let Some(cap) = capital {
-        println!("  基于本金 {:.2} 的分配金额:", cap);
-        let full_used: f64 = result.allocations.iter().map(|a| cap * a).sum();
-        for (i, alloc) in result.allocations.iter().enumerate() {
-            println!(
-                "    ├─ 标的{}: 全凯利 {:.2} / 半凯利 {:.2} / 1/4凯利 {:.2}",
-                i + 1,
-                cap * alloc,
-                cap * alloc * 0.5,
-                cap * alloc * 0.25
-            );
-        }
-        println!(
-            "    ├─ 全凯利剩余现金: {:.2}",
-            cap * (1.0 - result.total_allocation).max(0.0)
-        );
-        println!(
-            "    └─ 全凯利总投入: {:.2} (占比 {})",
-            full_used,
-            format_pct(result.total_allocation)
-        );
-        println!();
+        print_portfolio_allocation_amounts(result, cap, money);
     }
 
     separator();
@@ -708,8 +3420,28 @@ pub fn print_result_nash(
     separator();
 }
 
+/// `--shrink` 调整输入概率时的校准信息
+fn json_shrink(shrink: Option<ShrinkAdjustment>) -> String {
+    match shrink {
+        Some(shrink) => format!(
+            r#"{{"shrink_factor":{},"raw_probability":{}}}"#,
+            json_number(shrink.factor),
+            json_number(shrink.raw_probability)
+        ),
+        None => "null".to_string(),
+    }
+}
+
 /// 打印标准凯利 JSON 结果
-pub fn print_result_json(odds: f64, win_rate: f64, result: &KellyResult, capital: Option<f64>) {
+pub fn print_result_json(
+    odds: f64,
+    win_rate: f64,
+    shrink: Option<ShrinkAdjustment>,
+    result: &KellyResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+    recorded: Option<&[LedgerEntry]>,
+) {
     let fraction = effective_fraction(result.expected_value, result.optimal_fraction);
     let sizing = match capital {
         Some(cap) => format!(
@@ -722,15 +3454,20 @@ pub fn print_result_json(odds: f64, win_rate: f64, result: &KellyResult, capital
     };
 
     println!(
-        r#"{{"ok":true,"mode":"standard","inputs":{{"odds":{},"win_rate":{},"capital":{}}},"result":{{"expected_value":{},"positive_ev":{},"optimal_fraction":{},"recommended_fraction":{}}},"sizing":{}}}"#,
+        r#"{{"ok":true,"mode":"standard","currency":{},"inputs":{{"odds":{},"win_rate":{},"capital":{},"entered_capital":{},"capital_source":{},"calibration":{}}},"result":{{"expected_value":{},"positive_ev":{},"optimal_fraction":{},"recommended_fraction":{}}},"sizing":{},"recorded":{}}}"#,
+        json_currency(money),
         json_number(odds),
         json_number(win_rate),
         json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_shrink(shrink),
         json_number(result.expected_value),
         result.positive_ev,
         json_number(result.optimal_fraction),
         json_number(fraction),
-        sizing
+        sizing,
+        json_recorded(recorded)
     );
 }
 
@@ -738,8 +3475,11 @@ pub fn print_result_json(odds: f64, win_rate: f64, result: &KellyResult, capital
 pub fn print_result_polymarket_json(
     market_price: f64,
     your_probability: f64,
+    shrink: Option<ShrinkAdjustment>,
     result: &KellyResult,
     capital: Option<f64>,
+    money: &MoneyContext,
+    recorded: Option<&[LedgerEntry]>,
 ) {
     let fraction = effective_fraction(result.expected_value, result.optimal_fraction);
     let sizing = match capital {
@@ -753,88 +3493,559 @@ pub fn print_result_polymarket_json(
     };
 
     println!(
-        r#"{{"ok":true,"mode":"polymarket","inputs":{{"market_price":{},"your_probability":{},"implied_odds":{},"capital":{}}},"result":{{"expected_value":{},"positive_ev":{},"optimal_fraction":{},"recommended_fraction":{}}},"sizing":{}}}"#,
+        r#"{{"ok":true,"mode":"polymarket","currency":{},"inputs":{{"market_price":{},"your_probability":{},"implied_odds":{},"capital":{},"entered_capital":{},"capital_source":{},"calibration":{}}},"result":{{"expected_value":{},"positive_ev":{},"optimal_fraction":{},"recommended_fraction":{}}},"sizing":{},"recorded":{}}}"#,
+        json_currency(money),
         json_number(market_price),
         json_number(your_probability),
         json_number(1.0 / market_price),
         json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_shrink(shrink),
         json_number(result.expected_value),
         result.positive_ev,
         json_number(result.optimal_fraction),
         json_number(fraction),
-        sizing
+        sizing,
+        json_recorded(recorded)
     );
 }
 
 /// 打印股票 JSON 结果
 pub fn print_result_stock_json(
     info: &StockInfo,
+    costs: &StockCosts,
     win_rate: f64,
     result: &KellyResult,
+    context: &StockSizingContext,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let volatility = context.volatility.as_ref();
+    let sizers = &context.sizers;
+    let heat = context.heat.as_ref();
+    let risk_fraction = effective_fraction(result.expected_value, result.optimal_fraction);
+    let stop_loss_pct = info.risk / info.entry_price;
+    // 期货名义价值由合约乘数决定，不输出按价格比例的建仓仓位与杠杆
+    let position_fraction = match context.futures {
+        Some(_) => None,
+        None => Some(stock_position_fraction(info, risk_fraction)),
+    };
+    let leverage = position_fraction.filter(|&fraction| fraction > 1.0);
+
+    let sizing = match (capital, position_fraction) {
+        (Some(cap), None) => format!(
+            r#"{{"risk":{{"full":{},"half":{},"quarter":{}}},"position":null,"shares":null}}"#,
+            json_number(cap * risk_fraction),
+            json_number(cap * risk_fraction * 0.5),
+            json_number(cap * risk_fraction * 0.25)
+        ),
+        (Some(cap), Some(position_fraction)) => {
+            let [full, half, quarter] = stock_kelly_positions(info, costs, cap * risk_fraction);
+            format!(
+                r#"{{"risk":{{"full":{},"half":{},"quarter":{}}},"position":{{"full":{},"half":{},"quarter":{}}},"shares":{{"full":{},"half":{},"quarter":{}}}}}"#,
+                json_number(cap * risk_fraction),
+                json_number(cap * risk_fraction * 0.5),
+                json_number(cap * risk_fraction * 0.25),
+                json_number(cap * position_fraction),
+                json_number(cap * position_fraction * 0.5),
+                json_number(cap * position_fraction * 0.25),
+                json_stock_position(&full.1),
+                json_stock_position(&half.1),
+                json_stock_position(&quarter.1)
+            )
+        }
+        (None, _) => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"ok":true,"mode":"stock","currency":{},"inputs":{{"direction":"{}","entry_price":{},"target_price":{},"stop_loss":{},"win_rate":{},"capital":{},"entered_capital":{},"capital_source":{},"costs":{{"lot_size":{},"commission_per_share":{},"commission_rate":{},"min_commission":{},"stamp_duty_rate":{},"borrow_rate":{},"holding_days":{}}}}},"volatility":{},"analysis":{{"profit":{},"risk":{},"stop_loss_pct":{},"ratio":{},"net_profit":{},"net_risk":{},"net_ratio":{}}},"result":{{"expected_value":{},"positive_ev":{},"risk_fraction":{},"position_fraction":{},"leverage":{}}},"sizing":{},"alternative_sizing":{},"portfolio_heat":{},"margin_financing":{},"futures":{}}}"#,
+        json_currency(money),
+        info.direction.as_str(),
+        json_number(info.entry_price),
+        json_number(info.target_price),
+        json_number(info.stop_loss),
+        json_number(win_rate),
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        costs.lot_size,
+        json_number(costs.commission_per_share),
+        json_number(costs.commission_rate),
+        json_number(costs.min_commission),
+        json_number(costs.stamp_duty_rate),
+        json_number(costs.borrow_rate),
+        json_number(costs.holding_days),
+        json_volatility_levels(volatility),
+        json_number(info.profit),
+        json_number(info.risk),
+        json_number(stop_loss_pct),
+        json_number(info.ratio),
+        json_number(info.net_profit),
+        json_number(info.net_risk),
+        json_number(info.net_ratio),
+        json_number(result.expected_value),
+        result.positive_ev,
+        json_number(risk_fraction),
+        json_optional_number(position_fraction),
+        json_optional_number(leverage),
+        sizing,
+        json_sizer_results(sizers, capital),
+        json_portfolio_heat(
+            info,
+            costs,
+            heat,
+            capital.map_or(0.0, |cap| cap * risk_fraction)
+        ),
+        json_stock_leverage(info, costs, context.leverage.as_ref(), capital),
+        json_futures_sizing(context.futures.as_ref())
+    );
+}
+
+/// 打印股票分批止盈 JSON 结果
+pub fn print_result_stock_scale_out_json(
+    targets: &[ScaleOutTarget],
+    breakeven_after_first: bool,
+    costs: &StockCosts,
+    result: &ScaleOutResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let info = &result.info;
+    let kelly = &result.kelly;
+    let risk_fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+    let position_fraction = stock_position_fraction(info, risk_fraction);
+
+    let targets_json = targets
+        .iter()
+        .map(|t| {
+            format!(
+                r#"{{"price":{},"portion":{},"reach_prob":{}}}"#,
+                json_number(t.price),
+                json_number(t.portion),
+                json_number(t.reach_prob)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    let outcomes_json = result
+        .outcomes
+        .iter()
+        .map(|o| {
+            format!(
+                r#"{{"targets_hit":{},"probability":{},"pnl_per_share":{},"r_multiple":{}}}"#,
+                o.targets_hit,
+                json_number(o.probability),
+                json_number(o.pnl_per_share),
+                json_number(o.r_multiple)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+
+    let sizing = match capital {
+        Some(cap) => {
+            let [full, half, quarter] = stock_kelly_positions(info, costs, cap * risk_fraction);
+            let split = split_scale_out_shares(full.1.shares, costs.lot_size, targets);
+            format!(
+                r#"{{"risk":{{"full":{},"half":{},"quarter":{}}},"shares":{{"full":{},"half":{},"quarter":{}}},"full_kelly_split":[{}]}}"#,
+                json_number(cap * risk_fraction),
+                json_number(cap * risk_fraction * 0.5),
+                json_number(cap * risk_fraction * 0.25),
+                json_stock_position(&full.1),
+                json_stock_position(&half.1),
+                json_stock_position(&quarter.1),
+                split
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",")
+            )
+        }
+        None => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"ok":true,"mode":"stock_scale_out","currency":{},"inputs":{{"direction":"{}","entry_price":{},"stop_loss":{},"targets":[{}],"breakeven_after_first":{},"capital":{},"entered_capital":{},"capital_source":{}}},"analysis":{{"net_risk":{},"expected_pnl_per_share":{},"outcomes":[{}]}},"result":{{"expected_value":{},"positive_ev":{},"risk_fraction":{},"position_fraction":{}}},"sizing":{}}}"#,
+        json_currency(money),
+        info.direction.as_str(),
+        json_number(info.entry_price),
+        json_number(info.stop_loss),
+        targets_json,
+        breakeven_after_first,
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_number(info.net_risk),
+        json_number(result.expected_pnl_per_share),
+        outcomes_json,
+        json_number(kelly.expected_value),
+        kelly.positive_ev,
+        json_number(risk_fraction),
+        json_number(position_fraction),
+        sizing
+    );
+}
+
+/// 打印股票移动止损模拟 JSON 结果
+pub fn print_result_stock_trailing_json(
+    trailing: TrailingStop,
+    simulation: &PathSimulation,
+    costs: &StockCosts,
+    result: &TrailingStopResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let info = &result.info;
+    let kelly = &result.kelly;
+    let risk_fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+    let position_fraction = stock_position_fraction(info, risk_fraction);
+    let r = &result.r_multiples;
+
+    let trailing_json = match trailing {
+        TrailingStop::Percent(pct) => {
+            format!(r#"{{"type":"percent","percent":{}}}"#, json_number(pct))
+        }
+        TrailingStop::Atr { atr, mult } => format!(
+            r#"{{"type":"atr","atr":{},"mult":{}}}"#,
+            json_number(atr),
+            json_number(mult)
+        ),
+    };
+    let sizing = match capital {
+        Some(cap) => {
+            let [full, half, quarter] = stock_kelly_positions(info, costs, cap * risk_fraction);
+            format!(
+                r#"{{"risk":{{"full":{},"half":{},"quarter":{}}},"shares":{{"full":{},"half":{},"quarter":{}}}}}"#,
+                json_number(cap * risk_fraction),
+                json_number(cap * risk_fraction * 0.5),
+                json_number(cap * risk_fraction * 0.25),
+                json_stock_position(&full.1),
+                json_stock_position(&half.1),
+                json_stock_position(&quarter.1)
+            )
+        }
+        None => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"ok":true,"mode":"stock_trailing","currency":{},"inputs":{{"direction":"{}","entry_price":{},"trailing":{},"initial_stop":{},"annual_drift":{},"annual_vol":{},"max_days":{},"paths":{},"seed":{},"capital":{},"entered_capital":{},"capital_source":{}}},"distribution":{{"stopped_out_prob":{},"time_stop_prob":{},"win_prob":{},"avg_holding_days":{},"worst_r":{},"best_r":{},"percentiles":{{"p5":{},"p25":{},"p50":{},"p75":{},"p95":{}}}}},"analysis":{{"net_risk":{},"expected_pnl_per_share":{}}},"result":{{"expected_value":{},"positive_ev":{},"risk_fraction":{},"position_fraction":{}}},"sizing":{}}}"#,
+        json_currency(money),
+        info.direction.as_str(),
+        json_number(info.entry_price),
+        trailing_json,
+        json_number(info.stop_loss),
+        json_number(simulation.annual_drift),
+        json_number(simulation.annual_vol),
+        simulation.max_days,
+        simulation.paths,
+        simulation.seed,
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_number(result.stopped_out_prob),
+        json_number(result.time_stop_prob),
+        json_number(result.win_prob),
+        json_number(result.avg_holding_days),
+        json_number(r.first().copied().unwrap_or(0.0)),
+        json_number(r.last().copied().unwrap_or(0.0)),
+        json_number(r_percentile(r, 0.05)),
+        json_number(r_percentile(r, 0.25)),
+        json_number(r_percentile(r, 0.5)),
+        json_number(r_percentile(r, 0.75)),
+        json_number(r_percentile(r, 0.95)),
+        json_number(info.net_risk),
+        json_number(result.expected_pnl_per_share),
+        json_number(kelly.expected_value),
+        kelly.positive_ev,
+        json_number(risk_fraction),
+        json_number(position_fraction),
+        sizing
+    );
+}
+
+/// 打印交易历史凯利估计 JSON 结果
+pub fn print_result_trade_history_json(
+    source: &str,
+    r_multiple: bool,
+    result: &TradeHistoryResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let summary = &result.summary;
+    let optimal = &result.optimal_f;
+    let unit_capital = if optimal.fraction > 0.0 {
+        Some(-optimal.worst_loss / optimal.fraction)
+    } else {
+        None
+    };
+    let bootstrap = match &result.bootstrap {
+        Some(interval) => format!(
+            r#"{{"samples":{},"valid_samples":{},"confidence":{},"fraction":{{"low":{},"median":{},"high":{}}},"risk_fraction":{}}}"#,
+            interval.samples,
+            interval.valid_samples,
+            json_number(interval.confidence),
+            json_number(interval.fraction_low),
+            json_number(interval.fraction_median),
+            json_number(interval.fraction_high),
+            match (interval.risk_low, interval.risk_high) {
+                (Some(low), Some(high)) => format!(
+                    r#"{{"low":{},"high":{}}}"#,
+                    json_number(low),
+                    json_number(high)
+                ),
+                _ => "null".to_string(),
+            }
+        ),
+        None => "null".to_string(),
+    };
+    let sizing = match capital {
+        Some(cap) => {
+            let full = cap * result.risk_fraction;
+            format!(
+                r#"{{"unit":"{}","full":{},"half":{},"quarter":{}}}"#,
+                if r_multiple { "risk" } else { "units" },
+                json_number(full),
+                json_number(full * 0.5),
+                json_number(full * 0.25)
+            )
+        }
+        None => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"ok":true,"mode":"trade_history","currency":{},"inputs":{{"source":"{}","data_type":"{}","capital":{},"entered_capital":{},"capital_source":{}}},"summary":{{"count":{},"wins":{},"losses":{},"win_rate":{},"avg_win":{},"avg_loss":{},"payoff_ratio":{},"expectancy":{},"profit_factor":{},"best":{},"worst":{}}},"result":{{"formula_kelly":{},"optimal_f":{},"twr":{},"geometric_mean":{},"risk_fraction":{},"unit_capital":{}}},"bootstrap":{},"sizing":{}}}"#,
+        json_currency(money),
+        json_escape(source),
+        if r_multiple { "r_multiple" } else { "pnl" },
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        summary.count,
+        summary.wins,
+        summary.losses,
+        json_number(summary.win_rate),
+        json_number(summary.avg_win),
+        json_number(summary.avg_loss),
+        json_number(summary.payoff_ratio),
+        json_number(summary.expectancy),
+        json_number(summary.profit_factor),
+        json_number(summary.best),
+        json_number(summary.worst),
+        json_number(result.formula_kelly),
+        json_number(optimal.fraction),
+        json_number(optimal.twr),
+        json_number(optimal.geometric_mean),
+        json_number(result.risk_fraction),
+        json_optional_number(unit_capital),
+        bootstrap,
+        sizing
+    );
+}
+
+/// 打印期权仓位计算 JSON 结果
+pub fn print_result_merton_json(
+    means: &[f64],
+    covariance: &[Vec<f64>],
+    risk_free: f64,
+    constraints: &MertonConstraints,
+    result: &MertonResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let covariance_json: Vec<String> = covariance.iter().map(|row| json_array(row)).collect();
+    let sizing = match capital {
+        Some(cap) => {
+            let scaled = |scale: f64| {
+                let amounts: Vec<f64> =
+                    result.allocations.iter().map(|a| cap * a * scale).collect();
+                json_array(&amounts)
+            };
+            format!(
+                r#"{{"full_kelly":{},"half_kelly":{},"quarter_kelly":{},"full_net":{},"full_gross":{}}}"#,
+                scaled(1.0),
+                scaled(0.5),
+                scaled(0.25),
+                json_number(cap * result.total_allocation),
+                json_number(cap * result.gross_exposure)
+            )
+        }
+        None => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"ok":true,"mode":"merton","currency":{},"inputs":{{"means":{},"covariance":[{}],"risk_free":{},"long_only":{},"max_leverage":{},"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"unconstrained":{},"allocations":{},"total_allocation":{},"gross_exposure":{},"expected_return":{},"volatility":{},"expected_log_growth":{},"constrained":{},"converged":{},"iterations":{}}},"sizing":{}}}"#,
+        json_currency(money),
+        json_array(means),
+        covariance_json.join(","),
+        json_number(risk_free),
+        constraints.long_only,
+        json_optional_number(constraints.max_leverage),
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_array(&result.unconstrained),
+        json_array(&result.allocations),
+        json_number(result.total_allocation),
+        json_number(result.gross_exposure),
+        json_number(result.expected_return),
+        json_number(result.volatility),
+        json_number(result.expected_log_growth),
+        result.constrained,
+        result.converged,
+        result.iterations,
+        sizing
+    );
+}
+
+pub fn print_result_option_json(
+    strategy: &OptionStrategy,
+    market: &OptionMarket,
+    view: &PriceView,
+    multiplier: u64,
+    result: &OptionResult,
     capital: Option<f64>,
+    money: &MoneyContext,
 ) {
-    let risk_fraction = effective_fraction(result.expected_value, result.optimal_fraction);
-    let stop_loss_pct = info.risk / info.entry_price;
-    let position_fraction = if stop_loss_pct > 0.0 {
-        risk_fraction / stop_loss_pct
-    } else {
-        0.0
+    let kelly = &result.kelly;
+    let risk_fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+    let quote_json = |quote: &OptionQuote| {
+        format!(
+            r#"{{"strike":{},"premium":{},"delta":{}}}"#,
+            json_number(quote.strike),
+            json_number(quote.price),
+            json_number(quote.delta)
+        )
     };
-    let leverage = if position_fraction > 1.0 {
-        Some(position_fraction)
-    } else {
-        None
+    let view_json = match view {
+        PriceView::Lognormal { drift, vol } => format!(
+            r#"{{"type":"lognormal","drift":{},"vol":{}}}"#,
+            json_number(*drift),
+            json_number(*vol)
+        ),
+        PriceView::Scenarios(_) => r#"{"type":"scenarios"}"#.to_string(),
     };
-
-    let sizing = match capital {
-        Some(cap) => format!(
-            r#"{{"risk":{{"full":{},"half":{},"quarter":{}}},"position":{{"full":{},"half":{},"quarter":{}}}}}"#,
-            json_number(cap * risk_fraction),
-            json_number(cap * risk_fraction * 0.5),
-            json_number(cap * risk_fraction * 0.25),
-            json_number(cap * position_fraction),
-            json_number(cap * position_fraction * 0.5),
-            json_number(cap * position_fraction * 0.25)
+    let scenarios = match view {
+        PriceView::Scenarios(_) => format!(
+            "[{}]",
+            result
+                .outcomes
+                .iter()
+                .map(|o| format!(
+                    r#"{{"spot":{},"probability":{},"pnl":{}}}"#,
+                    json_number(o.spot),
+                    json_number(o.probability),
+                    json_number(o.pnl)
+                ))
+                .collect::<Vec<String>>()
+                .join(",")
         ),
+        PriceView::Lognormal { .. } => "null".to_string(),
+    };
+    let sizing = match capital {
+        Some(cap) => {
+            let full = cap * risk_fraction;
+            format!(
+                r#"{{"risk":{{"full":{},"half":{},"quarter":{}}},"contracts":{{"full":{},"half":{},"quarter":{}}}}}"#,
+                json_number(full),
+                json_number(full * 0.5),
+                json_number(full * 0.25),
+                option_contracts(result, multiplier, full),
+                option_contracts(result, multiplier, full * 0.5),
+                option_contracts(result, multiplier, full * 0.25)
+            )
+        }
         None => "null".to_string(),
     };
 
     println!(
-        r#"{{"ok":true,"mode":"stock","inputs":{{"entry_price":{},"target_price":{},"stop_loss":{},"win_rate":{},"capital":{}}},"analysis":{{"profit":{},"risk":{},"stop_loss_pct":{},"ratio":{}}},"result":{{"expected_value":{},"positive_ev":{},"risk_fraction":{},"position_fraction":{},"leverage":{}}},"sizing":{}}}"#,
-        json_number(info.entry_price),
-        json_number(info.target_price),
-        json_number(info.stop_loss),
-        json_number(win_rate),
+        r#"{{"ok":true,"mode":"option","currency":{},"inputs":{{"kind":"{}","spot":{},"long_strike":{},"short_strike":{},"implied_vol":{},"rate":{},"days":{},"multiplier":{},"view":{},"capital":{},"entered_capital":{},"capital_source":{}}},"pricing":{{"long":{},"short":{},"net_premium":{}}},"payoff":{{"max_loss":{},"max_profit":{},"breakeven":{},"win_prob":{},"expected_pnl":{},"scenarios":{}}},"result":{{"expected_value":{},"positive_ev":{},"risk_fraction":{}}},"sizing":{}}}"#,
+        json_currency(money),
+        strategy.kind.as_str(),
+        json_number(market.spot),
+        json_number(strategy.long_strike),
+        json_optional_number(strategy.short_strike),
+        json_number(market.implied_vol),
+        json_number(market.rate),
+        json_number(market.days),
+        multiplier,
+        view_json,
         json_optional_number(capital),
-        json_number(info.profit),
-        json_number(info.risk),
-        json_number(stop_loss_pct),
-        json_number(info.ratio),
-        json_number(result.expected_value),
-        result.positive_ev,
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        quote_json(&result.long_leg),
+        result
+            .short_leg
+            .as_ref()
+            .map_or("null".to_string(), quote_json),
+        json_number(result.net_premium),
+        json_number(result.max_loss),
+        json_optional_number(result.max_profit),
+        json_optional_number(result.breakeven),
+        json_number(result.win_prob),
+        json_number(result.expected_pnl),
+        scenarios,
+        json_number(kelly.expected_value),
+        kelly.positive_ev,
         json_number(risk_fraction),
-        json_number(position_fraction),
-        json_optional_number(leverage),
         sizing
     );
 }
 
+fn json_limited_arbitrage(limited: Option<&LimitedArbitrage>) -> String {
+    match limited {
+        Some(l) => format!(
+            r#"{{"max_stakes":{},"max_total":{},"limiting_leg":{},"binding":{},"total_stake":{},"stakes":{},"guaranteed_profit":{}}}"#,
+            json_array(&l.max_stakes),
+            json_number(l.max_total),
+            l.limiting_leg + 1,
+            l.binding,
+            json_number(l.total_stake),
+            json_array(&l.stakes),
+            json_number(l.guaranteed_profit)
+        ),
+        None => "null".to_string(),
+    }
+}
+
+fn json_rounded_arbitrage(rounded: Option<&RoundedArbitrage>) -> String {
+    match rounded {
+        Some(r) => format!(
+            r#"{{"steps":{},"stakes":{},"total_stake":{},"profits":{},"min_profit":{},"survives":{}}}"#,
+            json_array(&r.steps),
+            json_array(&r.stakes),
+            json_number(r.total_stake),
+            json_array(&r.profits),
+            json_number(r.min_profit),
+            r.survives
+        ),
+        None => "null".to_string(),
+    }
+}
+
 /// 打印双标套利 JSON 结果
 pub fn print_result_arbitrage_json(
-    odds1: f64,
-    odds2: f64,
+    odds: [f64; 2],
+    options: &ArbitrageOptions,
     result: &ArbitrageResult,
+    plan: &ArbitragePlan,
     capital: Option<f64>,
+    money: &MoneyContext,
+    recorded: Option<&[LedgerEntry]>,
 ) {
+    let [odds1, odds2] = odds;
+    let (limited, rounded) = (plan.limited.as_ref(), plan.rounded.as_ref());
     let stake_plan = match (result.has_arbitrage, capital) {
         (true, Some(cap)) => {
-            let stake1 = cap * result.stake1_ratio;
-            let stake2 = cap * result.stake2_ratio;
+            let stakes = placed_stakes(
+                &[cap * result.stake1_ratio, cap * result.stake2_ratio],
+                options,
+            );
             let total_return = cap * (1.0 + result.arbitrage_profit);
             format!(
                 r#"{{"stake1":{},"stake2":{},"total_return":{},"profit":{}}}"#,
-                json_number(stake1),
-                json_number(stake2),
+                json_number(stakes[0]),
+                json_number(stakes[1]),
                 json_number(total_return),
                 json_number(total_return - cap)
             )
@@ -843,29 +4054,44 @@ pub fn print_result_arbitrage_json(
     };
 
     println!(
-        r#"{{"ok":true,"mode":"arbitrage","inputs":{{"odds1":{},"odds2":{},"capital":{}}},"result":{{"has_arbitrage":{},"total_implied_prob":{},"arbitrage_profit":{},"juice_rate":{},"stake_ratios":[{},{}]}},"stake_plan":{}}}"#,
+        r#"{{"ok":true,"mode":"arbitrage","currency":{},"inputs":{{"odds1":{},"odds2":{},"win_commissions":{},"stake_fees":{},"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"effective_odds":{},"has_arbitrage":{},"total_implied_prob":{},"arbitrage_profit":{},"juice_rate":{},"stake_ratios":[{},{}]}},"stake_plan":{},"stake_limits":{},"rounded_stakes":{},"recorded":{}}}"#,
+        json_currency(money),
         json_number(odds1),
         json_number(odds2),
+        json_optional_array(options.commissions.as_deref()),
+        json_optional_array(options.fees.as_deref()),
         json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_array(&effective_odds_for(&[odds1, odds2], options)),
         result.has_arbitrage,
         json_number(result.total_implied_prob),
         json_number(result.arbitrage_profit),
         json_number(result.juice_rate),
         json_number(result.stake1_ratio),
         json_number(result.stake2_ratio),
-        stake_plan
+        stake_plan,
+        json_limited_arbitrage(limited),
+        json_rounded_arbitrage(rounded),
+        json_recorded(recorded)
     );
 }
 
 /// 打印多标套利 JSON 结果
 pub fn print_result_multi_arbitrage_json(
     odds: &[f64],
+    options: &ArbitrageOptions,
     result: &MultiArbitrageResult,
+    plan: &ArbitragePlan,
     capital: Option<f64>,
+    money: &MoneyContext,
+    recorded: Option<&[LedgerEntry]>,
 ) {
+    let (limited, rounded) = (plan.limited.as_ref(), plan.rounded.as_ref());
     let stake_plan = match (result.has_arbitrage, capital) {
         (true, Some(cap)) => {
-            let stakes: Vec<f64> = result.stake_ratios.iter().map(|r| cap * r).collect();
+            let outlays: Vec<f64> = result.stake_ratios.iter().map(|r| cap * r).collect();
+            let stakes = placed_stakes(&outlays, options);
             let total_return = cap * (1.0 + result.arbitrage_profit);
             format!(
                 r#"{{"stakes":{},"total_return":{},"profit":{}}}"#,
@@ -878,15 +4104,576 @@ pub fn print_result_multi_arbitrage_json(
     };
 
     println!(
-        r#"{{"ok":true,"mode":"multi_arbitrage","inputs":{{"odds":{},"capital":{}}},"result":{{"has_arbitrage":{},"total_implied_prob":{},"arbitrage_profit":{},"juice_rate":{},"stake_ratios":{}}},"stake_plan":{}}}"#,
+        r#"{{"ok":true,"mode":"multi_arbitrage","currency":{},"inputs":{{"odds":{},"win_commissions":{},"stake_fees":{},"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"effective_odds":{},"has_arbitrage":{},"total_implied_prob":{},"arbitrage_profit":{},"juice_rate":{},"stake_ratios":{}}},"stake_plan":{},"stake_limits":{},"rounded_stakes":{},"recorded":{}}}"#,
+        json_currency(money),
         json_array(odds),
+        json_optional_array(options.commissions.as_deref()),
+        json_optional_array(options.fees.as_deref()),
         json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_array(&effective_odds_for(odds, options)),
         result.has_arbitrage,
         json_number(result.total_implied_prob),
         json_number(result.arbitrage_profit),
         json_number(result.juice_rate),
         json_array(&result.stake_ratios),
-        stake_plan
+        stake_plan,
+        json_limited_arbitrage(limited),
+        json_rounded_arbitrage(rounded),
+        json_recorded(recorded)
+    );
+}
+
+/// 打印对冲下注 JSON 结果
+pub fn print_result_back_lay_json(
+    back_odds: f64,
+    lay_odds: f64,
+    back_stake: f64,
+    commission: f64,
+    kind: BackLayKind,
+    result: &BackLayResult,
+    money: &MoneyContext,
+) {
+    let retention = match kind {
+        BackLayKind::RiskFree { retention } => Some(retention),
+        _ => None,
+    };
+    println!(
+        r#"{{"ok":true,"mode":"back_lay","currency":{},"inputs":{{"kind":"{}","back_odds":{},"lay_odds":{},"back_stake":{},"commission":{},"refund_retention":{}}},"result":{{"lay_stake":{},"liability":{},"back_wins_profit":{},"lay_wins_profit":{},"profit":{},"rating":{},"conversion":{}}}}}"#,
+        json_currency(money),
+        kind.code(),
+        json_number(back_odds),
+        json_number(lay_odds),
+        json_number(back_stake),
+        json_number(commission),
+        json_optional_number(retention),
+        json_number(result.lay_stake),
+        json_number(result.liability),
+        json_number(result.back_wins_profit),
+        json_number(result.lay_wins_profit),
+        json_number(result.profit),
+        json_number(result.rating),
+        json_optional_number(result.conversion)
+    );
+}
+
+/// 打印价值投注扫描 JSON 结果
+pub fn print_result_value_scan_json(
+    scan: &ValueScan,
+    capital: Option<f64>,
+    money: &MoneyContext,
+    recorded: Option<&[LedgerEntry]>,
+) {
+    let bets = scan
+        .bets
+        .iter()
+        .map(|bet| {
+            let fraction = effective_fraction(bet.kelly.expected_value, bet.kelly.optimal_fraction);
+            format!(
+                r#"{{"event":"{}","market":"{}","outcome":"{}","bookmaker":"{}","odds":{},"implied_prob":{},"probability":{},"edge":{},"expected_value":{},"optimal_fraction":{},"recommended_fraction":{},"full_kelly":{},"descriptor":"{}"}}"#,
+                json_escape(&bet.event),
+                json_escape(&bet.market),
+                json_escape(&bet.outcome),
+                json_escape(&bet.bookmaker),
+                json_number(bet.odds),
+                json_number(bet.implied_prob),
+                json_number(bet.probability),
+                json_number(bet.edge),
+                json_number(bet.kelly.expected_value),
+                json_number(bet.kelly.optimal_fraction),
+                json_number(fraction),
+                json_optional_number(capital.map(|cap| cap * fraction)),
+                json_escape(&bet.descriptor)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    let command = match value_portfolio_command(scan, capital) {
+        Some(command) => format!(r#""{}""#, json_escape(&command)),
+        None => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"ok":true,"mode":"value_scan","currency":{},"inputs":{{"polymarket":{},"min_edge":{},"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"quote_count":{},"probability_count":{},"outcome_count":{},"matched":{},"bets":[{}],"portfolio_command":{}}},"recorded":{}}}"#,
+        json_currency(money),
+        scan.polymarket,
+        json_number(scan.min_edge),
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        scan.quote_count,
+        scan.probability_count,
+        scan.outcome_count,
+        scan.matched,
+        bets,
+        command,
+        json_recorded(recorded)
+    );
+}
+
+fn json_ledger_entry(entry: &LedgerEntry) -> String {
+    match entry {
+        LedgerEntry::Deposit { date, amount } => format!(
+            r#"{{"type":"deposit","date":"{}","amount":{}}}"#,
+            date,
+            json_number(*amount)
+        ),
+        LedgerEntry::Withdraw { date, amount } => format!(
+            r#"{{"type":"withdraw","date":"{}","amount":{}}}"#,
+            date,
+            json_number(*amount)
+        ),
+        LedgerEntry::Bet {
+            id,
+            date,
+            odds,
+            stake,
+            mode,
+            market,
+            probability,
+            label,
+        } => format!(
+            r#"{{"type":"bet","id":{},"date":"{}","odds":{},"stake":{},"mode":"{}","market":"{}","probability":{},"label":"{}"}}"#,
+            id,
+            date,
+            json_number(*odds),
+            json_number(*stake),
+            json_escape(mode),
+            json_escape(market),
+            json_optional_number(*probability),
+            json_escape(label)
+        ),
+        LedgerEntry::Settle {
+            id,
+            date,
+            settlement,
+            returned,
+        } => format!(
+            r#"{{"type":"settle","id":{},"date":"{}","settlement":"{}","returned":{}}}"#,
+            id,
+            date,
+            settlement.code(),
+            json_number(*returned)
+        ),
+        LedgerEntry::Close { id, line } => format!(
+            r#"{{"type":"close","id":{},"closing":{}}}"#,
+            id,
+            json_closing_line(line)
+        ),
+    }
+}
+
+/// `--record` 写入账本的下注（未使用 `--record` 时为 null）
+fn json_recorded(recorded: Option<&[LedgerEntry]>) -> String {
+    match recorded {
+        Some(entries) => format!(
+            "[{}]",
+            entries
+                .iter()
+                .map(json_ledger_entry)
+                .collect::<Vec<String>>()
+                .join(",")
+        ),
+        None => "null".to_string(),
+    }
+}
+
+fn json_closing_line(line: &ClosingLine) -> String {
+    format!(
+        r#"{{"date":"{}","odds":{},"probability":{},"devigged":{}}}"#,
+        line.date,
+        json_number(line.odds),
+        json_number(line.probability),
+        line.devigged
+    )
+}
+
+fn json_clv_summary(clv: Option<&ClvSummary>) -> String {
+    let Some(clv) = clv else {
+        return "null".to_string();
+    };
+    let distribution = clv
+        .distribution
+        .iter()
+        .map(|(lower, upper, count)| {
+            format!(
+                r#"{{"lower":{},"upper":{},"count":{}}}"#,
+                json_number(*lower),
+                json_number(*upper),
+                count
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    let by_market = clv
+        .by_market
+        .iter()
+        .map(|group| {
+            format!(
+                r#"{{"market":"{}","count":{},"mean_clv":{},"beat_rate":{}}}"#,
+                json_escape(&group.market),
+                group.count,
+                json_number(group.mean_clv),
+                json_number(group.beat_rate)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    format!(
+        r#"{{"count":{},"mean_clv":{},"median_clv":{},"weighted_clv":{},"beat_rate":{},"compared":{},"mean_placement_ev":{},"mean_compared_clv":{},"distribution":[{}],"by_market":[{}]}}"#,
+        clv.count,
+        json_number(clv.mean_clv),
+        json_number(clv.median_clv),
+        json_number(clv.weighted_clv),
+        json_number(clv.beat_rate),
+        clv.compared,
+        json_optional_number(clv.mean_placement_ev),
+        json_optional_number(clv.mean_compared_clv),
+        distribution,
+        by_market
+    )
+}
+
+/// 打印账本 JSON 汇总
+pub fn print_result_ledger_json(
+    path: &Path,
+    recorded: Option<&LedgerEntry>,
+    summary: &LedgerSummary,
+    money: &MoneyContext,
+) {
+    let bets = summary
+        .bets
+        .iter()
+        .map(|bet| {
+            let (status, returned, settled_on) = match &bet.settlement {
+                Some((settlement, returned, date)) => (
+                    format!(r#""{}""#, settlement.code()),
+                    json_number(*returned),
+                    format!(r#""{date}""#),
+                ),
+                None => (
+                    r#""open""#.to_string(),
+                    "null".to_string(),
+                    "null".to_string(),
+                ),
+            };
+            format!(
+                r#"{{"id":{},"date":"{}","odds":{},"stake":{},"mode":"{}","market":"{}","label":"{}","status":{},"returned":{},"settled_on":{},"closing":{},"clv":{},"placement_ev":{}}}"#,
+                bet.id,
+                bet.date,
+                json_number(bet.odds),
+                json_number(bet.stake),
+                json_escape(&bet.mode),
+                json_escape(&bet.market),
+                json_escape(&bet.label),
+                status,
+                returned,
+                settled_on,
+                bet.closing
+                    .as_ref()
+                    .map_or("null".to_string(), json_closing_line),
+                json_optional_number(bet_clv(bet)),
+                json_optional_number(placement_ev(bet))
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    let curve = summary
+        .curve
+        .iter()
+        .map(|(date, bankroll)| {
+            format!(
+                r#"{{"date":"{}","bankroll":{}}}"#,
+                date,
+                json_number(*bankroll)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+
+    println!(
+        r#"{{"ok":true,"mode":"ledger","currency":{},"inputs":{{"path":"{}"}},"result":{{"recorded":{},"deposits":{},"withdrawals":{},"bankroll":{},"balance":{},"pending_stake":{},"realized_pnl":{},"turnover":{},"roi":{},"clv":{},"bets":[{}],"curve":[{}]}}}}"#,
+        json_currency(money),
+        json_escape(&path.display().to_string()),
+        recorded.map_or("null".to_string(), json_ledger_entry),
+        json_number(summary.deposits),
+        json_number(summary.withdrawals),
+        json_number(summary.bankroll),
+        json_number(summary.balance),
+        json_number(summary.pending_stake),
+        json_number(summary.realized_pnl),
+        json_number(summary.turnover),
+        json_optional_number(summary.roi),
+        json_clv_summary(summary.clv.as_ref()),
+        bets,
+        curve
+    );
+}
+
+/// 打印概率校准 JSON 报告
+pub fn print_result_calibration_json(
+    source: &str,
+    skipped: Option<usize>,
+    report: &CalibrationReport,
+    money: &MoneyContext,
+) {
+    let buckets = report
+        .buckets
+        .iter()
+        .map(|bucket| {
+            format!(
+                r#"{{"lower":{},"upper":{},"count":{},"mean_predicted":{},"observed":{}}}"#,
+                json_number(bucket.lower),
+                json_number(bucket.upper),
+                bucket.count,
+                json_number(bucket.mean_predicted),
+                json_number(bucket.observed)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+
+    println!(
+        r#"{{"ok":true,"mode":"calibration","currency":{},"inputs":{{"source":"{}","skipped":{}}},"result":{{"count":{},"wins":{},"mean_predicted":{},"observed_rate":{},"brier":{},"log_loss":{},"market_brier":{},"market_log_loss":{},"buckets":[{}],"shrink_factor":{},"shrunk_brier":{},"shrunk_log_loss":{}}}}}"#,
+        json_currency(money),
+        json_escape(source),
+        skipped.map_or("null".to_string(), |n| n.to_string()),
+        report.count,
+        report.wins,
+        json_number(report.mean_predicted),
+        json_number(report.observed_rate),
+        json_number(report.brier),
+        json_number(report.log_loss),
+        json_number(report.market_brier),
+        json_number(report.market_log_loss),
+        buckets,
+        json_number(report.shrink_factor),
+        json_number(report.shrunk_brier),
+        json_number(report.shrunk_log_loss)
+    );
+}
+
+/// 打印套利扫描 JSON 结果
+pub fn print_result_scan_json(
+    scan: &ArbitrageScan,
+    capital: Option<f64>,
+    money: &MoneyContext,
+    recorded: Option<&[LedgerEntry]>,
+) {
+    let markets = scan
+        .markets
+        .iter()
+        .map(|market| {
+            let legs = market
+                .legs
+                .iter()
+                .map(|leg| {
+                    format!(
+                        r#"{{"outcome":"{}","bookmaker":"{}","odds":{},"stake_ratio":{},"stake":{}}}"#,
+                        json_escape(&leg.outcome),
+                        json_escape(&leg.bookmaker),
+                        json_number(leg.odds),
+                        json_number(leg.stake_ratio),
+                        json_optional_number(capital.map(|cap| cap * leg.stake_ratio))
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(",");
+            format!(
+                r#"{{"event":"{}","market":"{}","has_arbitrage":{},"total_implied_prob":{},"profit":{},"juice_rate":{},"legs":[{}]}}"#,
+                json_escape(&market.event),
+                json_escape(&market.market),
+                market.result.has_arbitrage,
+                json_number(market.result.total_implied_prob),
+                json_number(market.profit),
+                json_number(market.result.juice_rate),
+                legs
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+
+    println!(
+        r#"{{"ok":true,"mode":"scan","currency":{},"inputs":{{"max_margin":{},"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"quote_count":{},"market_count":{},"skipped_markets":{},"markets":[{}]}},"recorded":{}}}"#,
+        json_currency(money),
+        json_number(scan.max_margin),
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        scan.quote_count,
+        scan.market_count,
+        scan.skipped_markets,
+        markets,
+        json_recorded(recorded)
+    );
+}
+
+/// 打印分散投注 JSON 结果
+pub fn print_result_dutching_json(
+    odds: &[f64],
+    win_prob: Option<f64>,
+    result: &DutchingResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+    recorded: Option<&[LedgerEntry]>,
+) {
+    let kelly = match &result.kelly {
+        Some(kelly) => {
+            let fraction = effective_fraction(kelly.expected_value, kelly.optimal_fraction);
+            let sizing = match capital {
+                Some(cap) => format!(
+                    r#"{{"full_kelly":{},"half_kelly":{},"quarter_kelly":{},"full_kelly_stakes":{}}}"#,
+                    json_number(cap * fraction),
+                    json_number(cap * fraction * 0.5),
+                    json_number(cap * fraction * 0.25),
+                    json_array(
+                        &result
+                            .stake_ratios
+                            .iter()
+                            .map(|w| cap * fraction.max(0.0) * w)
+                            .collect::<Vec<f64>>()
+                    )
+                ),
+                None => "null".to_string(),
+            };
+            format!(
+                r#"{{"expected_value":{},"positive_ev":{},"optimal_fraction":{},"recommended_fraction":{},"sizing":{}}}"#,
+                json_number(kelly.expected_value),
+                kelly.positive_ev,
+                json_number(kelly.optimal_fraction),
+                json_number(fraction),
+                sizing
+            )
+        }
+        None => "null".to_string(),
+    };
+    let stakes = match result.total_stake {
+        Some(_) => json_array(&result.stakes),
+        None => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"ok":true,"mode":"dutching","currency":{},"inputs":{{"odds":{},"win_prob":{},"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"total_implied_prob":{},"combined_odds":{},"win_return":{},"stake_ratios":{},"total_stake":{},"stakes":{},"profit_if_win":{}}},"kelly":{},"recorded":{}}}"#,
+        json_currency(money),
+        json_array(odds),
+        json_optional_number(win_prob),
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_number(result.total_implied_prob),
+        json_number(result.combined_odds),
+        json_number(result.win_return),
+        json_array(&result.stake_ratios),
+        json_optional_number(result.total_stake),
+        stakes,
+        json_optional_number(result.total_stake.map(|t| t * result.win_return)),
+        kelly,
+        json_recorded(recorded)
+    );
+}
+
+fn json_hedge_outcome(outcome: &HedgeOutcome) -> String {
+    format!(
+        r#"{{"ratio":{},"hedge_stake":{},"profit_if_win":{},"profit_if_lose":{},"expected_profit":{}}}"#,
+        json_number(outcome.ratio),
+        json_number(outcome.hedge_stake),
+        json_number(outcome.profit_if_win),
+        json_number(outcome.profit_if_lose),
+        json_optional_number(outcome.expected_profit)
+    )
+}
+
+/// 打印对冲 JSON 结果
+pub fn print_result_hedge_json(
+    bet: &HedgeBet,
+    polymarket: bool,
+    cash_out: Option<f64>,
+    result: &HedgeResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+) {
+    let partial = result
+        .partial
+        .iter()
+        .map(json_hedge_outcome)
+        .collect::<Vec<String>>()
+        .join(",");
+    println!(
+        r#"{{"ok":true,"mode":"hedge","currency":{},"inputs":{{"stake":{},"odds":{},"hedge_odds":{},"polymarket":{},"win_prob":{},"cash_out_offer":{},"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"total_implied_prob":{},"has_arbitrage":{},"locked_return":{},"cash_out_value":{},"full_hedge":{},"partial_hedges":[{}],"kelly_hedge":{}}}}}"#,
+        json_currency(money),
+        json_number(bet.stake),
+        json_number(bet.odds),
+        json_number(bet.hedge_odds),
+        polymarket,
+        json_optional_number(bet.win_prob),
+        json_optional_number(cash_out),
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_number(result.arbitrage.total_implied_prob),
+        result.arbitrage.has_arbitrage,
+        json_number(result.locked_return),
+        json_number(result.cash_out_value),
+        json_hedge_outcome(&result.full),
+        partial,
+        result
+            .kelly
+            .as_ref()
+            .map_or("null".to_string(), json_hedge_outcome)
+    );
+}
+
+/// 打印中间盘 JSON 结果
+pub fn print_result_middle_json(
+    bet: &MiddleBet,
+    result: &MiddleResult,
+    capital: Option<f64>,
+    money: &MoneyContext,
+    recorded: Option<&[LedgerEntry]>,
+) {
+    let fraction = effective_fraction(result.kelly.expected_value, result.kelly.optimal_fraction);
+    let sizing = match capital {
+        Some(cap) => format!(
+            r#"{{"full_kelly":{},"half_kelly":{},"quarter_kelly":{},"full_kelly_stakes":{},"outside_profit":{},"middle_profit":{}}}"#,
+            json_number(cap * fraction),
+            json_number(cap * fraction * 0.5),
+            json_number(cap * fraction * 0.25),
+            json_array(
+                &result
+                    .stake_ratios
+                    .iter()
+                    .map(|r| cap * fraction * r)
+                    .collect::<Vec<f64>>()
+            ),
+            json_number(cap * fraction * result.outside_return),
+            json_number(cap * fraction * result.middle_return)
+        ),
+        None => "null".to_string(),
+    };
+
+    println!(
+        r#"{{"ok":true,"mode":"middle","currency":{},"inputs":{{"line1":{},"odds1":{},"line2":{},"odds2":{},"middle_prob":{},"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"total_implied_prob":{},"stake_ratios":{},"outside_return":{},"middle_return":{},"guaranteed":{},"expected_value":{},"positive_ev":{},"optimal_fraction":{},"recommended_fraction":{}}},"sizing":{},"recorded":{}}}"#,
+        json_currency(money),
+        json_number(bet.line1),
+        json_number(bet.odds1),
+        json_number(bet.line2),
+        json_number(bet.odds2),
+        json_number(bet.middle_prob),
+        json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
+        json_number(result.total_implied_prob),
+        json_array(&result.stake_ratios),
+        json_number(result.outside_return),
+        json_number(result.middle_return),
+        result.guaranteed,
+        json_number(result.kelly.expected_value),
+        result.kelly.positive_ev,
+        json_number(result.kelly.optimal_fraction),
+        json_number(fraction),
+        sizing,
+        json_recorded(recorded)
     );
 }
 
@@ -934,8 +4721,10 @@ pub fn print_result_nash_json(
 /// 打印组合凯利 JSON 结果
 pub fn print_result_portfolio_json(
     legs: &[PortfolioLeg],
+    leverage: Option<&LeverageSettings>,
     result: &PortfolioKellyResult,
     capital: Option<f64>,
+    money: &MoneyContext,
 ) {
     let legs_json = legs
         .iter()
@@ -958,21 +4747,25 @@ pub fn print_result_portfolio_json(
             let half: Vec<f64> = result.allocations.iter().map(|a| cap * a * 0.5).collect();
             let quarter: Vec<f64> = result.allocations.iter().map(|a| cap * a * 0.25).collect();
             format!(
-                r#"{{"full_kelly":{},"half_kelly":{},"quarter_kelly":{},"full_used":{},"full_remaining":{}}}"#,
+                r#"{{"full_kelly":{},"half_kelly":{},"quarter_kelly":{},"full_used":{},"full_remaining":{},"full_borrowed":{}}}"#,
                 json_array(&full),
                 json_array(&half),
                 json_array(&quarter),
                 json_number(full.iter().sum()),
-                json_number(cap * (1.0 - result.total_allocation).max(0.0))
+                json_number(cap * (1.0 - result.total_allocation).max(0.0)),
+                json_number(cap * result.borrowed)
             )
         }
         None => "null".to_string(),
     };
 
     println!(
-        r#"{{"ok":true,"mode":"portfolio_kelly","inputs":{{"legs":[{}],"capital":{}}},"result":{{"allocations":{},"total_allocation":{},"expected_log_growth":{},"expected_arithmetic_return":{},"worst_case_multiplier":{},"converged":{},"iterations":{}}},"sizing":{}}}"#,
+        r#"{{"ok":true,"mode":"portfolio_kelly","currency":{},"inputs":{{"legs":[{}],"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"allocations":{},"total_allocation":{},"expected_log_growth":{},"expected_arithmetic_return":{},"worst_case_multiplier":{},"converged":{},"iterations":{}}},"leverage":{},"sizing":{}}}"#,
+        json_currency(money),
         legs_json,
         json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
         json_array(&result.allocations),
         json_number(result.total_allocation),
         json_number(result.expected_log_growth),
@@ -980,6 +4773,7 @@ pub fn print_result_portfolio_json(
         json_number(result.worst_case_multiplier),
         result.converged,
         result.iterations,
+        json_portfolio_leverage(leverage, result),
         sizing
     );
 }
@@ -988,8 +4782,10 @@ pub fn print_result_portfolio_json(
 pub fn print_result_portfolio_correlated_json(
     leg_count: usize,
     scenarios: &[PortfolioScenario],
+    leverage: Option<&LeverageSettings>,
     result: &PortfolioKellyResult,
     capital: Option<f64>,
+    money: &MoneyContext,
 ) {
     let scenarios_json = scenarios
         .iter()
@@ -1009,22 +4805,26 @@ pub fn print_result_portfolio_correlated_json(
             let half: Vec<f64> = result.allocations.iter().map(|a| cap * a * 0.5).collect();
             let quarter: Vec<f64> = result.allocations.iter().map(|a| cap * a * 0.25).collect();
             format!(
-                r#"{{"full_kelly":{},"half_kelly":{},"quarter_kelly":{},"full_used":{},"full_remaining":{}}}"#,
+                r#"{{"full_kelly":{},"half_kelly":{},"quarter_kelly":{},"full_used":{},"full_remaining":{},"full_borrowed":{}}}"#,
                 json_array(&full),
                 json_array(&half),
                 json_array(&quarter),
                 json_number(full.iter().sum()),
-                json_number(cap * (1.0 - result.total_allocation).max(0.0))
+                json_number(cap * (1.0 - result.total_allocation).max(0.0)),
+                json_number(cap * result.borrowed)
             )
         }
         None => "null".to_string(),
     };
 
     println!(
-        r#"{{"ok":true,"mode":"portfolio_kelly_correlated","inputs":{{"leg_count":{},"scenarios":[{}],"capital":{}}},"result":{{"allocations":{},"total_allocation":{},"expected_log_growth":{},"expected_arithmetic_return":{},"worst_case_multiplier":{},"converged":{},"iterations":{}}},"sizing":{}}}"#,
+        r#"{{"ok":true,"mode":"portfolio_kelly_correlated","currency":{},"inputs":{{"leg_count":{},"scenarios":[{}],"capital":{},"entered_capital":{},"capital_source":{}}},"result":{{"allocations":{},"total_allocation":{},"expected_log_growth":{},"expected_arithmetic_return":{},"worst_case_multiplier":{},"converged":{},"iterations":{}}},"leverage":{},"sizing":{}}}"#,
+        json_currency(money),
         leg_count,
         scenarios_json,
         json_optional_number(capital),
+        json_optional_number(money.entered_capital),
+        json_capital_source(money),
         json_array(&result.allocations),
         json_number(result.total_allocation),
         json_number(result.expected_log_growth),
@@ -1032,6 +4832,7 @@ pub fn print_result_portfolio_correlated_json(
         json_number(result.worst_case_multiplier),
         result.converged,
         result.iterations,
+        json_portfolio_leverage(leverage, result),
         sizing
     );
 }
@@ -1043,6 +4844,9 @@ pub fn print_usage() {
     println!("  bo -v | -version             # 显示版本");
     println!("  bo                           # 交互式模式");
     println!("  bo --json ...                # JSON 输出（仅命令行参数模式）");
+    println!(
+        "  bo ... --currency <下注货币> [--capital-currency <本金货币>] [--fx <汇率> | --fx-file <汇率文件>]"
+    );
     println!("  bo <赔率> <胜率>              # 命令行模式");
     println!("  bo <赔率> <胜率> <本金>        # 指定本金");
     println!();
@@ -1053,23 +4857,105 @@ pub fn print_usage() {
     println!("  bo -s                         # 股票交易交互式");
     println!("  bo -s <当前价> <止盈价> <止损价> <胜率>");
     println!("  bo -s <当前价> <止盈价> <止损价> <胜率> <本金>");
+    println!("  bo -s <当前价> <止盈价> <止损价> <胜率> [本金] --short  # 做空");
+    println!(
+        "  bo -s <当前价> <止损价> [本金] --plan <价格:减仓%:到达概率%,...> [--breakeven]  # 分批止盈"
+    );
+    println!(
+        "     仓位算法对比: --risk-pct <风险%> --fixed-ratio <Δ>[:<累计利润>] --target-vol <目标波动率%> --asset-vol <标的波动率%> --trades <R1,R2,...>"
+    );
+    println!("     组合热度: --positions <持仓文件> --max-heat <热度上限%>");
+    println!(
+        "     期货合约: (--multiplier <合约乘数> | --tick <最小变动价位>[:<每跳价值>]) --margin <每手保证金> [--contract-fee <每手单边手续费>]"
+    );
+    println!(
+        "     杠杆融资: --max-leverage <最大杠杆> [--financing-rate <融资年利率%> --days <持有天数>] [--margin-call <维持保证金%>]"
+    );
+    println!(
+        "  bo -s <当前价> [本金] (--trail-pct <回撤%> | --trail-atr <ATR> [--trail-mult <k>]) --gbm <漂移%>:<波动率%> [--max-days <天数>] [--paths <路径数>] [--seed <种子>]  # 移动止损模拟"
+    );
+    println!(
+        "     成本选项: --lot <每手股数> --commission <每股佣金> --commission-pct <佣金费率%> --min-commission <最低佣金> --stamp-duty <印花税率%> --borrow-rate <融券年费率%> --days <持有天数>"
+    );
+    println!(
+        "     --days 同时决定融券费用 (--borrow-rate，仅做空)、--vol 的波动率周期与杠杆融资成本 (--financing-rate)"
+    );
     println!();
     println!("  bo -a                         # 套利交互式");
     println!("  bo -a <赔率1> <赔率2>         # 套利命令行");
     println!("  bo -a <赔率1> <赔率2> <本金>");
     println!();
     println!("  bo -A <标的数量> <赔率1> ... <赔率N> [本金]  # 多标的套利");
+    println!("  bo -a|-A ... <本金> --round <投注单位>[,...]   # 投注金额按单位取整");
+    println!("  bo -a|-A ... [本金] --max-stake <限额>[,...]   # 单边最大投注额");
+    println!(
+        "  bo -a|-A ... --win-commission <佣金%>[,...] --stake-fee <手续费%>[,...]  # 佣金与手续费"
+    );
+    println!(
+        "  bo -b <背注赔率> <对冲赔率> <背注金额> [佣金%] [--free-bet snr|sr | --risk-free <保留率%>]  # 对冲下注"
+    );
+    println!(
+        "  bo -V <模型概率文件> <价格快照文件> [本金] [--polymarket] [--min-edge <百分点>]  # 价值投注扫描"
+    );
+    println!(
+        "  bo -S <赔率快照文件> [本金] [--max-margin <抽水%>]  # 扫描快照中的套利与低抽水盘口"
+    );
+    println!(
+        "  bo -d <选项数量> <赔率1> ... <赔率N> [本金] [--stake <总投注> | --target-profit <目标收益>] [--win-prob <子集胜率%>]  # 分散投注"
+    );
+    println!(
+        "  bo -H <原投注金额> <原赔率> <当前反向赔率> [更新胜率%] [可用本金] [--partial <比例%>[,...]] [--cash-out <报价>] [--polymarket]  # 对冲/兑现"
+    );
+    println!(
+        "  bo -M <线1> <赔率1> <线2> <赔率2> <中间区概率%> [本金]  # 中间盘（让分/大小分跨线）"
+    );
     println!("  bo -n                         # 纳什均衡交互式");
     println!("  bo -n <a11> <a12> <a21> <a22> <b11> <b12> <b21> <b22>  # 2x2 纳什均衡");
     println!("  bo -k                         # 组合凯利交互式");
     println!("  bo -K                         # 相关情景组合凯利交互式");
     println!("  bo -K <标的数量> <情景数量> <p1> <r11> ... <r1N> ... <pM> <rM1> ... <rMN> [本金]");
     println!("     说明: p/r 单位均为百分数，r 不得小于 -100%");
+    println!(
+        "     杠杆融资 (-k/-K): --max-leverage <最大杠杆> [--financing-rate <融资年利率%> --days <持有天数>] [--margin-call <维持保证金%>]"
+    );
     println!("  bo -k <标的数量> <赔率1> <胜率1> ... <赔率N> <胜率N> [本金]  # 组合凯利");
     println!("  bo -k <descriptor1> <descriptor2> ... [本金]  # 跨模式组合凯利");
     println!(
-        "     descriptor: std:赔率:胜率 | pm:市场价:概率 | stock:入场:止盈:止损:胜率 | arb:赔率1:赔率2 | marb:赔率1,赔率2,..."
+        "     descriptor: std:赔率:胜率 | pm:市场价:概率 | stock:入场:止盈:止损:胜率[:long|short[:融券费率:天数]] | opt:call|put:现价:行权价:IV:利率:天数:主观波动率[:卖出行权价] | arb:赔率1:赔率2[:佣金%[:手续费%]] | marb:赔率1,赔率2,...[:佣金%[:手续费%]]"
+    );
+    println!(
+        "  bo -T <交易历史文件> [本金] [--r-multiple] [--column <列>] [--bootstrap <次数>] [--seed <种子>] [--confidence <置信度%>]  # 交易历史凯利"
+    );
+    println!(
+        "  bo -o <call|put> <现价> <行权价> <隐含波动率%> <利率%> <到期天数> [本金] [--spread <卖出行权价>] [--multiplier <乘数>] (--view-vol <波动率%> [--view-drift <漂移%>] | --view <价格:概率%,...>)  # 期权"
+    );
+    println!(
+        "  bo -m <预期年化收益%> <年化波动率%> <无风险利率%> [本金] [--long-only] [--max-leverage <总敞口上限>]  # 连续收益凯利"
+    );
+    println!(
+        "  bo -m <标的数量> <无风险利率%> <μ1%> ... <μN%> <协方差11> ... <协方差NN> [本金]  # 多标的，协方差单位 %²"
+    );
+    println!("  bo -L deposit|withdraw <金额>  # 账本入金/出金");
+    println!(
+        "  bo -L add <赔率> <金额|比例%> [备注...] [--mode <模式>] [--market <盘口类型>] [--prob <胜率%>]  # 记录下注"
+    );
+    println!("  bo -L settle <编号> win|loss|void|partial [返还金额]  # 结算");
+    println!("  bo -L close <编号> <收盘赔率> [同盘口其他结果收盘赔率...]  # 收盘价与 CLV");
+    println!("  bo -L [show]                  # 资金、盈亏、ROI 与资金曲线");
+    println!(
+        "     账本选项: --date <YYYY-MM-DD> --data-dir <目录> --polymarket（赔率按价格%输入）；其他模式加 --ledger 以账本余额为默认本金"
+    );
+    println!(
+        "     --record full|half|quarter 记录凯利仓位（标准、Polymarket、-V、-M、带 --win-prob 的 -d）"
+    );
+    println!(
+        "     --record stake 按命令行输入的总投注记录各腿金额（-a、-A、-S、带金额目标的 -d）；可加 --market、--label"
+    );
+    println!(
+        "     股票、期权、期货、默顿与组合仓位不是单笔小数赔率投注，-H、-b 针对已有投注或交易所反向投注，不支持 --record"
     );
+    println!("  bo -C [预测文件] [--polymarket]  # 概率校准：Brier、对数损失、可靠性表与收缩系数");
+    println!("     标准模式与 Polymarket 模式加 --shrink <系数> 把胜率向市场概率收缩");
     println!();
     println!("示例:");
     println!("  bo 2.0 60                    # 赔率2.0，胜率60%");
@@ -1081,21 +4967,61 @@ pub fn print_usage() {
     println!();
     println!("  bo -s 100 120 90 60            # 当前价100，止盈120，止损90，胜率60%");
     println!("  bo -s 100 120 90 60 10000       # 本金10000");
+    println!("  bo -s 10 12 9 60 100000 --lot 100 --commission-pct 0.025 --min-commission 5");
+    println!("  bo -s 100 80 110 60 10000 --short --borrow-rate 8 --days 20");
+    println!("  bo -s 100 90 10000 --plan 110:50:70,120:30:45,130:20:25 --breakeven");
+    println!("  bo -s 100 55 10000 --atr 2.5 --stop-mult 2 --target-mult 3");
+    println!("  bo -s 100 10000 --vol 2 --days 10 --stop-mult 1 --target-mult 2 --drift 0.1");
+    println!("  bo -s 100 120 90 60 100000 --lot 100 --risk-pct 1 --trades 2,-1,1.5,-1,3");
+    println!("  bo -s 100 120 90 60 100000 --lot 100 --positions positions.csv --max-heat 6");
+    println!("  bo -s 100 100000 --lot 100 --trail-pct 8 --gbm 20:30 --max-days 60");
+    println!("  bo -s 5000 5040 4980 55 200000 --tick 0.25:12.5 --margin 12000 --contract-fee 2.5");
+    println!(
+        "  bo -s 100 120 90 60 100000 --max-leverage 3 --financing-rate 6 --days 30 --margin-call 25"
+    );
     println!();
     println!("  bo -a 1.9 2.1                # 方案1赔率1.9，方案2赔率2.1");
     println!("  bo -a 1.9 2.1 1000            # 本金1000");
     println!();
     println!("  bo -A 3 2.0 3.5 4.0           # 3个标的，赔率分别为2.0, 3.5, 4.0");
     println!("  bo -A 3 2.0 3.5 4.0 1000      # 本金1000");
+    println!("  bo -a 2.1 2.0 1000 --round 5  # 投注金额按 5 取整");
+    println!("  bo -b 3.0 3.1 100 2           # 背注 100 @ 3.0，交易所 3.1 对冲，佣金 2%");
+    println!("  bo -V model.csv odds.csv 10000  # 模型概率对比快照，输出可传给 -k 的组合描述");
+    println!("  bo -S odds.csv 1000           # 扫描快照，按 1000 本金给出各盘口投注金额");
+    println!("  bo -d 3 4.0 6.0 12.0 --stake 100  # 3 个选项共投 100，任一胜出回报相同");
+    println!("  bo -H 100 3.0 1.8 50 1000     # 100 @ 3.0 已下注，反向赔率 1.8，更新胜率 50%");
+    println!("  bo -M 45.5 1.95 47.5 1.95 4 10000  # 大 45.5 + 小 47.5，落在 46/47 的概率 4%");
     println!();
     println!("  bo -n 3 0 5 1 3 5 0 1         # 囚徒困境收益矩阵");
     println!("  bo --json -n 1 -1 -1 1 -1 1 1 -1");
     println!();
     println!("  bo -K 2 2 50 20 -10 50 -10 20 10000");
     println!("  bo --json -K 2 3 30 25 -15 40 5 5 30 -10 20");
+    println!("  bo -K 2 2 50 20 -10 50 -10 20 10000 --max-leverage 2 --margin-call 30");
     println!();
     println!("  bo -k 2 2.0 60 2.5 55         # 2个标的组合凯利");
     println!("  bo -k 2 2.0 60 2.5 55 10000   # 本金10000");
     println!("  bo -k std:2.0:60 pm:60:75 stock:100:120:90:60 10000");
     println!("  bo --json -k std:2.0:60 arb:2.1:2.2 marb:2.5,4.0,5.0 10000");
+    println!();
+    println!("  bo -T trades.csv 100000 --r-multiple   # 交易历史（R 倍数）");
+    println!();
+    println!("  bo -o call 100 100 25 3 30 10000 --spread 110 --view-vol 35");
+    println!("  bo -o put 50 48 40 2 45 20000 --view 40:30,50:40,60:30");
+    println!();
+    println!("  bo -m 8 20 3 100000");
+    println!("  bo -m 2 2 10 12 400 300 300 900 100000 --long-only");
+    println!();
+    println!("  bo -L deposit 10000           # 入金 10000");
+    println!("  bo 2.5 48 --ledger            # 以账本可用余额为本金");
+    println!("  bo -L add 2.5 6.67% 曼联胜    # 按建议比例记录下注");
+    println!("  bo 2.5 48 --ledger --record half  # 按半凯利金额直接记账");
+    println!("  bo -S odds.csv 500 --record stake # 按总投注 500 记录套利各腿");
+    println!("  bo -L settle 1 win            # 结算第 1 笔");
+    println!("  bo -L close 1 2.30 3.40 3.20  # 三项盘口收盘赔率，去水计算 CLV");
+    println!();
+    println!("  bo -C predictions.csv         # 校准预测文件（结果,赔率,概率%）");
+    println!("  bo -C                         # 校准账本中记录了 --prob 的已结算下注");
+    println!("  bo 2.5 48 10000 --shrink 0.6  # 按拟合系数收缩胜率后再计算");
 }
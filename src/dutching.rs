@@ -0,0 +1,83 @@
+//! 分散投注（Dutching）：从多个选项中选出子集，按赔率分配投注使任一选项胜出时收益相等
+
+use crate::kelly::kelly_criterion;
+use crate::types::{DutchingResult, DutchingTarget};
+
+/// 选定选项的合计隐含概率
+pub fn dutching_implied_prob(odds: &[f64]) -> f64 {
+    odds.iter().map(|o| 1.0 / o).sum()
+}
+
+/// 计算分散投注方案
+///
+/// 投注比例 w_i = (1/o_i) / S，任一选项胜出时回报均为总投注 / S。
+/// 子集视作赔率为 1/S 的单个投注，按子集胜率计算凯利仓位。
+/// 调用方需保证 S < 1（否则任一选项胜出也不盈利）。
+pub fn calculate_dutching(
+    odds: &[f64],
+    target: Option<DutchingTarget>,
+    win_prob: Option<f64>,
+) -> DutchingResult {
+    let total_implied_prob = dutching_implied_prob(odds);
+    let stake_ratios: Vec<f64> = odds
+        .iter()
+        .map(|o| (1.0 / o) / total_implied_prob)
+        .collect();
+    let combined_odds = 1.0 / total_implied_prob;
+    let win_return = combined_odds - 1.0;
+    let total_stake = target.map(|t| match t {
+        DutchingTarget::TotalStake(stake) => stake,
+        DutchingTarget::TargetProfit(profit) => profit / win_return,
+    });
+
+    DutchingResult {
+        total_implied_prob,
+        combined_odds,
+        win_return,
+        stakes: total_stake
+            .map(|total| stake_ratios.iter().map(|w| total * w).collect())
+            .unwrap_or_default(),
+        stake_ratios,
+        total_stake,
+        kelly: win_prob.map(|p| kelly_criterion(combined_odds, p)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::calculate_dutching;
+    use crate::types::DutchingTarget;
+
+    #[test]
+    fn stakes_return_the_same_amount_on_any_winner() {
+        let odds = [4.0, 6.0, 12.0];
+        let result = calculate_dutching(&odds, Some(DutchingTarget::TotalStake(120.0)), None);
+        // S = 1/4 + 1/6 + 1/12 = 0.5
+        assert!((result.total_implied_prob - 0.5).abs() < 1e-12);
+        assert!((result.combined_odds - 2.0).abs() < 1e-12);
+        for (stake, o) in result.stakes.iter().zip(odds) {
+            assert!((stake * o - 240.0).abs() < 1e-9);
+        }
+        assert!(result.kelly.is_none());
+    }
+
+    #[test]
+    fn target_profit_sets_total_stake() {
+        let result = calculate_dutching(
+            &[4.0, 6.0, 12.0],
+            Some(DutchingTarget::TargetProfit(50.0)),
+            None,
+        );
+        assert!((result.total_stake.unwrap() - 50.0).abs() < 1e-9);
+        assert!((result.stakes[0] - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn kelly_treats_subset_as_single_bet() {
+        // 等效赔率 2.0，子集胜率 60%：f = (1 × 0.6 - 0.4) / 1 = 20%
+        let result = calculate_dutching(&[4.0, 6.0, 12.0], None, Some(0.6));
+        let kelly = result.kelly.unwrap();
+        assert!((kelly.optimal_fraction - 0.2).abs() < 1e-12);
+        assert!(result.stakes.is_empty());
+    }
+}
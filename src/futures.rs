@@ -0,0 +1,216 @@
+//! 期货合约：按合约乘数、最小变动价位与保证金把凯利风险金换算为整数手
+
+use crate::types::{FuturesContract, FuturesPosition, FuturesSizing, KellyResult, StockInfo};
+
+/// 跳数取整时容忍的浮点误差
+const TICK_EPSILON: f64 = 1e-9;
+
+/// 价格距离换算为跳数：止损向外取整（不低估风险），止盈向内取整（不高估收益）
+fn ticks(distance: f64, tick_size: f64, round_up: bool) -> u64 {
+    let raw = distance / tick_size;
+    if round_up {
+        (raw - TICK_EPSILON).ceil().max(0.0) as u64
+    } else {
+        (raw + TICK_EPSILON).floor().max(0.0) as u64
+    }
+}
+
+/// 每手止损风险与止盈净收益：(止损跳数, 止盈跳数, 每手风险, 每手净收益)
+///
+/// 每手风险 = 止损跳数 × 每跳价值 + 往返手续费，每手净收益 = 止盈跳数 × 每跳价值 − 往返手续费。
+fn per_contract(
+    info: &StockInfo,
+    contract: &FuturesContract,
+) -> (Option<u64>, Option<u64>, f64, f64) {
+    let stop_distance = (info.entry_price - info.stop_loss).abs();
+    let target_distance = (info.target_price - info.entry_price).abs();
+    let round_trip_fee = 2.0 * contract.fee_per_contract;
+
+    let (ticks_to_stop, ticks_to_target, stop_value, target_value) =
+        match (contract.tick_size, contract.tick_value()) {
+            (Some(tick), Some(tick_value)) => {
+                let stop = ticks(stop_distance, tick, true);
+                let target = ticks(target_distance, tick, false);
+                (
+                    Some(stop),
+                    Some(target),
+                    stop as f64 * tick_value,
+                    target as f64 * tick_value,
+                )
+            }
+            _ => (
+                None,
+                None,
+                stop_distance * contract.multiplier,
+                target_distance * contract.multiplier,
+            ),
+        };
+    (
+        ticks_to_stop,
+        ticks_to_target,
+        stop_value + round_trip_fee,
+        target_value - round_trip_fee,
+    )
+}
+
+/// 期货凯利：净赔率取每手止盈净收益 / 每手止损风险（按跳取整并含往返手续费）
+pub fn kelly_futures(info: &StockInfo, contract: &FuturesContract, win_rate: f64) -> KellyResult {
+    let (_, _, risk, profit) = per_contract(info, contract);
+    let b = profit / risk;
+
+    let p = win_rate;
+    let q = 1.0 - p;
+
+    // 手续费吞掉全部止盈收益时不存在正的凯利仓位
+    let optimal_fraction = if b > 0.0 { (b * p - q) / b } else { 0.0 };
+    let expected_value = p * b - q;
+
+    KellyResult {
+        optimal_fraction,
+        positive_ev: expected_value > 0.0,
+        expected_value,
+    }
+}
+
+/// 按凯利风险金计算各变体的期货手数
+///
+/// 手数取 `min(⌊风险金 / 每手风险⌋, ⌊本金 / 每手保证金⌋)`。
+pub fn size_futures(
+    info: &StockInfo,
+    contract: &FuturesContract,
+    full_risk: f64,
+    capital: f64,
+) -> FuturesSizing {
+    let (ticks_to_stop, ticks_to_target, risk_per_contract, profit_per_contract) =
+        per_contract(info, contract);
+    let max_contracts_by_margin = (capital / contract.initial_margin).floor().max(0.0) as u64;
+
+    let positions = [1.0, 0.5, 0.25].map(|scale| {
+        let budget = full_risk * scale;
+        let by_risk = if risk_per_contract > 0.0 {
+            (budget / risk_per_contract).floor().max(0.0) as u64
+        } else {
+            0
+        };
+        let contracts = by_risk.min(max_contracts_by_margin);
+        FuturesPosition {
+            contracts,
+            margin_used: contracts as f64 * contract.initial_margin,
+            risk_at_stop: contracts as f64 * risk_per_contract,
+            profit_at_target: contracts as f64 * profit_per_contract,
+            margin_limited: by_risk > max_contracts_by_margin,
+        }
+    });
+
+    FuturesSizing {
+        contract: *contract,
+        ticks_to_stop,
+        ticks_to_target,
+        risk_per_contract,
+        profit_per_contract,
+        max_contracts_by_margin,
+        positions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{kelly_futures, size_futures};
+    use crate::kelly::build_stock_info;
+    use crate::types::{FuturesContract, StockCosts, TradeDirection};
+
+    fn contract(tick_size: Option<f64>, initial_margin: f64, fee: f64) -> FuturesContract {
+        FuturesContract {
+            multiplier: 50.0,
+            tick_size,
+            initial_margin,
+            fee_per_contract: fee,
+        }
+    }
+
+    #[test]
+    fn risk_budget_becomes_whole_contracts() {
+        // 股指期货：入场 5000，止损 4980（20 点 × 50 = 1000/手），手续费单边 2.5
+        let info = build_stock_info(
+            TradeDirection::Long,
+            5000.0,
+            5040.0,
+            4980.0,
+            &StockCosts::default(),
+        );
+        let sizing = size_futures(
+            &info,
+            &contract(Some(0.25), 12000.0, 2.5),
+            10000.0,
+            200000.0,
+        );
+        assert_eq!(sizing.ticks_to_stop, Some(80));
+        assert_eq!(sizing.ticks_to_target, Some(160));
+        assert!((sizing.risk_per_contract - 1005.0).abs() < 1e-9);
+        assert!((sizing.profit_per_contract - 1995.0).abs() < 1e-9);
+        assert_eq!(sizing.max_contracts_by_margin, 16);
+
+        let [full, half, quarter] = sizing.positions;
+        assert_eq!(full.contracts, 9);
+        assert_eq!(half.contracts, 4);
+        assert_eq!(quarter.contracts, 2);
+        assert!((full.margin_used - 108000.0).abs() < 1e-9);
+        assert!(full.risk_at_stop <= 10000.0);
+        assert!(!full.margin_limited);
+    }
+
+    #[test]
+    fn kelly_uses_net_per_contract_odds() {
+        // 止损 10 点 × 50 + 往返手续费 10 = 510，止盈 40 点 × 50 − 10 = 1990，b ≈ 3.90 而非 4
+        let info = build_stock_info(
+            TradeDirection::Long,
+            5000.0,
+            5040.0,
+            4990.0,
+            &StockCosts::default(),
+        );
+        let result = kelly_futures(&info, &contract(None, 12000.0, 5.0), 0.55);
+        let b = 1990.0 / 510.0;
+        assert!((result.optimal_fraction - (0.55 - 0.45 / b)).abs() < 1e-12);
+        assert!((result.expected_value - (0.55 * b - 0.45)).abs() < 1e-12);
+
+        // 手续费吞掉全部止盈收益时不下注
+        let result = kelly_futures(&info, &contract(None, 12000.0, 1000.0), 0.55);
+        assert_eq!(result.optimal_fraction, 0.0);
+        assert!(!result.positive_ev);
+    }
+
+    #[test]
+    fn stop_rounds_outward_to_the_next_tick() {
+        let info = build_stock_info(
+            TradeDirection::Short,
+            100.0,
+            95.05,
+            101.05,
+            &StockCosts::default(),
+        );
+        let sizing = size_futures(&info, &contract(Some(0.1), 1000.0, 0.0), 10000.0, 100000.0);
+        // 止损 1.05 点向外取 11 跳，止盈 4.95 点向内取 49 跳
+        assert_eq!(sizing.ticks_to_stop, Some(11));
+        assert_eq!(sizing.ticks_to_target, Some(49));
+        assert!((sizing.risk_per_contract - 55.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn margin_caps_contracts() {
+        let info = build_stock_info(
+            TradeDirection::Long,
+            100.0,
+            110.0,
+            99.0,
+            &StockCosts::default(),
+        );
+        // 每手风险 50，风险金 5000 可开 100 手，但保证金只够 10 手
+        let sizing = size_futures(&info, &contract(None, 2000.0, 0.0), 5000.0, 20000.0);
+        let full = sizing.positions[0];
+        assert_eq!(full.contracts, 10);
+        assert!(full.margin_limited);
+        assert_eq!(sizing.positions[2].contracts, 10);
+        assert!(sizing.positions[2].margin_limited);
+    }
+}
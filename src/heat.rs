@@ -0,0 +1,135 @@
+//! 组合热度：汇总现有持仓的止损风险，并限制新交易的风险预算
+
+use std::fs;
+
+use crate::types::{HeatSettings, OpenPosition, PortfolioHeat, TradeDirection};
+use crate::validation::{parse_direction, parse_positive, parse_positive_integer};
+
+/// 解析持仓文件内容
+///
+/// 每行 `代码,入场价,止损价,股数[,long|short]`，空行与 `#` 开头的注释行忽略，
+/// 首行入场价不是数字时视为表头。
+pub fn parse_open_positions(content: &str) -> Result<Vec<OpenPosition>, String> {
+    let mut positions = Vec::new();
+    let mut first = true;
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split([',', '\t', ';']).map(str::trim).collect();
+        if first {
+            first = false;
+            if fields.get(1).is_some_and(|f| f.parse::<f64>().is_err()) {
+                continue;
+            }
+        }
+
+        let line_no = i + 1;
+        if fields.len() != 4 && fields.len() != 5 {
+            return Err(format!(
+                "第 {line_no} 行格式错误，应为 代码,入场价,止损价,股数[,long|short]"
+            ));
+        }
+        let with_line = |e: String| format!("第 {line_no} 行: {e}");
+        let direction = match fields.get(4) {
+            Some(v) => parse_direction(v).map_err(with_line)?,
+            None => TradeDirection::Long,
+        };
+        positions.push(OpenPosition {
+            symbol: fields[0].to_string(),
+            direction,
+            entry_price: parse_positive(fields[1], "入场价").map_err(with_line)?,
+            stop_loss: parse_positive(fields[2], "止损价").map_err(with_line)?,
+            shares: parse_positive_integer(fields[3], "股数").map_err(with_line)?,
+        });
+    }
+
+    Ok(positions)
+}
+
+/// 读取持仓文件
+pub fn load_open_positions(path: &str) -> Result<Vec<OpenPosition>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("无法读取持仓文件 '{path}': {e}"))?;
+    parse_open_positions(&content)
+}
+
+/// 单个持仓触发止损时的亏损；止损已移至入场价之外（锁定利润）时为 0
+pub fn position_risk(position: &OpenPosition) -> f64 {
+    let per_share = (position.entry_price - position.stop_loss) * position.direction.sign();
+    per_share.max(0.0) * position.shares as f64
+}
+
+/// 计算组合热度与剩余风险额度
+pub fn calculate_portfolio_heat(settings: &HeatSettings, capital: f64) -> PortfolioHeat {
+    let position_risks: Vec<f64> = settings.positions.iter().map(position_risk).collect();
+    let total_risk: f64 = position_risks.iter().sum();
+
+    PortfolioHeat {
+        settings: settings.clone(),
+        position_risks,
+        total_risk,
+        heat: total_risk / capital,
+        remaining_risk: settings
+            .max_heat
+            .map(|limit| (capital * limit - total_risk).max(0.0)),
+    }
+}
+
+/// 按剩余热度额度限制新交易的风险预算
+pub fn cap_risk_budget(heat: &PortfolioHeat, risk_budget: f64) -> f64 {
+    match heat.remaining_risk {
+        Some(remaining) => risk_budget.min(remaining),
+        None => risk_budget,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calculate_portfolio_heat, cap_risk_budget, parse_open_positions};
+    use crate::types::{HeatSettings, TradeDirection};
+
+    #[test]
+    fn parses_positions_with_header_and_direction() {
+        let content = "symbol,entry,stop,shares,side\n# 注释\nAAA,10,9,500\nBBB,50,55,100,short\n";
+        let positions = parse_open_positions(content).unwrap();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0].direction, TradeDirection::Long);
+        assert_eq!(positions[1].direction, TradeDirection::Short);
+        assert_eq!(positions[1].shares, 100);
+
+        assert!(parse_open_positions("AAA,10,9").is_err());
+        assert!(parse_open_positions("AAA,10,9,1.5").is_err());
+    }
+
+    #[test]
+    fn heat_sums_risk_and_ignores_locked_in_profit() {
+        // AAA 风险 500，BBB 做空风险 500，CCC 止损已移到入场价上方，风险 0
+        let content = "AAA,10,9,500\nBBB,50,55,100,short\nCCC,20,22,300\n";
+        let settings = HeatSettings {
+            source: None,
+            positions: parse_open_positions(content).unwrap(),
+            max_heat: Some(0.06),
+        };
+        let heat = calculate_portfolio_heat(&settings, 100000.0);
+        assert!((heat.total_risk - 1000.0).abs() < 1e-9);
+        assert!((heat.heat - 0.01).abs() < 1e-12);
+        assert!((heat.remaining_risk.unwrap() - 5000.0).abs() < 1e-9);
+        assert!((cap_risk_budget(&heat, 40000.0) - 5000.0).abs() < 1e-9);
+        assert!((cap_risk_budget(&heat, 2000.0) - 2000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn full_heat_leaves_no_budget() {
+        let settings = HeatSettings {
+            source: None,
+            positions: parse_open_positions("AAA,10,9,10000").unwrap(),
+            max_heat: Some(0.05),
+        };
+        let heat = calculate_portfolio_heat(&settings, 100000.0);
+        assert_eq!(heat.remaining_risk, Some(0.0));
+        assert_eq!(cap_risk_budget(&heat, 1000.0), 0.0);
+    }
+}
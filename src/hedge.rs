@@ -0,0 +1,114 @@
+//! 已有投注的对冲与兑现：按当前反向赔率锁定收益、部分对冲与凯利对冲
+
+use crate::arbitrage::calculate_arbitrage;
+use crate::types::{HedgeBet, HedgeOutcome, HedgeResult};
+
+/// 给定对冲金额下两种结果的收益（原投注本金已支付）
+fn hedge_outcome(bet: &HedgeBet, ratio: f64, hedge_stake: f64) -> HedgeOutcome {
+    let profit_if_win = bet.stake * (bet.odds - 1.0) - hedge_stake;
+    let profit_if_lose = hedge_stake * (bet.hedge_odds - 1.0) - bet.stake;
+    HedgeOutcome {
+        ratio,
+        hedge_stake,
+        profit_if_win,
+        profit_if_lose,
+        expected_profit: bet
+            .win_prob
+            .map(|p| p * profit_if_win + (1.0 - p) * profit_if_lose),
+    }
+}
+
+/// 凯利对冲金额：最大化 p·ln(C - h + S·O) + (1 - p)·ln(C + h·(H - 1))
+///
+/// 一阶条件解出 h = (1 - p)(C + S·O) - p·C / (H - 1)，限制在 [0, C]。
+fn kelly_hedge_stake(bet: &HedgeBet, win_prob: f64, capital: f64) -> f64 {
+    let payout = bet.stake * bet.odds;
+    let stake = (1.0 - win_prob) * (capital + payout) - win_prob * capital / (bet.hedge_odds - 1.0);
+    stake.clamp(0.0, capital)
+}
+
+/// 计算对冲方案
+///
+/// 原投注与反向对冲视作一组双边投注，由套利计算得到隐含概率之和，
+/// 使两种结果收益相等的总投入 = 原投注金额 × 原赔率 × 隐含概率之和。
+/// `ratios` 为部分对冲占完全对冲金额的比例；凯利对冲需要更新胜率与当前可用本金。
+pub fn calculate_hedge(bet: &HedgeBet, ratios: &[f64], capital: Option<f64>) -> HedgeResult {
+    let arbitrage = calculate_arbitrage(bet.odds, bet.hedge_odds);
+    let total_outlay = bet.stake * bet.odds * arbitrage.total_implied_prob;
+    let full_stake = total_outlay - bet.stake;
+    let full = hedge_outcome(bet, 1.0, full_stake);
+
+    let partial = ratios
+        .iter()
+        .map(|&ratio| hedge_outcome(bet, ratio, full_stake * ratio))
+        .collect();
+    let kelly = match (bet.win_prob, capital) {
+        (Some(p), Some(cap)) => {
+            let stake = kelly_hedge_stake(bet, p, cap);
+            Some(hedge_outcome(bet, stake / full_stake, stake))
+        }
+        _ => None,
+    };
+
+    HedgeResult {
+        locked_return: 1.0 / arbitrage.total_implied_prob - 1.0,
+        cash_out_value: bet.stake + full.profit_if_win,
+        arbitrage,
+        full,
+        partial,
+        kelly,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::calculate_hedge;
+    use crate::types::HedgeBet;
+
+    fn bet(win_prob: Option<f64>) -> HedgeBet {
+        // 100 @ 3.0，赔率回落后反向 1.8
+        HedgeBet {
+            stake: 100.0,
+            odds: 3.0,
+            hedge_odds: 1.8,
+            win_prob,
+        }
+    }
+
+    #[test]
+    fn full_hedge_locks_equal_profit() {
+        let result = calculate_hedge(&bet(None), &[0.5], None);
+        // h = 100 × 3.0 / 1.8
+        assert!((result.full.hedge_stake - 500.0 / 3.0).abs() < 1e-9);
+        assert!((result.full.profit_if_win - result.full.profit_if_lose).abs() < 1e-9);
+        assert!((result.full.profit_if_win - (200.0 - 500.0 / 3.0)).abs() < 1e-9);
+        assert!(result.arbitrage.has_arbitrage);
+        assert!((result.cash_out_value - 100.0 * 3.0 * 0.8 / 1.8).abs() < 1e-9);
+
+        let half = &result.partial[0];
+        assert!((half.hedge_stake - 250.0 / 3.0).abs() < 1e-9);
+        assert!(half.profit_if_win > result.full.profit_if_win);
+        assert!(half.profit_if_lose < result.full.profit_if_lose);
+        assert!(result.kelly.is_none());
+    }
+
+    #[test]
+    fn kelly_hedge_balances_log_growth() {
+        let b = bet(Some(0.5));
+        let result = calculate_hedge(&b, &[], Some(1000.0));
+        let kelly = result.kelly.unwrap();
+        // 一阶条件：p / (C + 胜时收益 + S) = (1 - p)(H - 1) / (C + 负时收益 + S)
+        let win_wealth = 1000.0 + 100.0 + kelly.profit_if_win;
+        let lose_wealth = 1000.0 + 100.0 + kelly.profit_if_lose;
+        assert!((0.5 / win_wealth - 0.5 * 0.8 / lose_wealth).abs() < 1e-12);
+        assert!(kelly.hedge_stake > 0.0);
+    }
+
+    #[test]
+    fn confident_holder_does_not_hedge() {
+        let result = calculate_hedge(&bet(Some(0.7)), &[], Some(1000.0));
+        let kelly = result.kelly.unwrap();
+        assert_eq!(kelly.hedge_stake, 0.0);
+        assert_eq!(kelly.ratio, 0.0);
+    }
+}
@@ -8,7 +8,11 @@ use crate::display::{
     print_title_portfolio, print_title_stock, separator,
 };
 use crate::portfolio_input::parse_portfolio_leg_descriptor;
-use crate::validation::{parse_f64, parse_market_price, parse_odds, parse_percent, parse_positive};
+use crate::types::{ArbitrageOptions, CapitalSource, StockCosts, TradeDirection};
+use crate::validation::{
+    parse_direction, parse_f64, parse_market_price, parse_odds, parse_percent, parse_positive,
+    validate_stock_prices,
+};
 
 fn parse_return_percent_input(input: &str, field_name: &str) -> Result<f64, String> {
     let value = parse_f64(input, field_name)? / 100.0;
@@ -90,8 +94,12 @@ pub fn interactive() {
                 odds,
                 win_rate,
                 capital,
+                shrink: None,
             },
             OutputFormat::Text,
+            None,
+            CapitalSource::Input,
+            None,
         );
         println!();
     }
@@ -161,8 +169,12 @@ pub fn interactive_polymarket() {
                 market_price,
                 your_probability,
                 capital,
+                shrink: None,
             },
             OutputFormat::Text,
+            None,
+            CapitalSource::Input,
+            None,
         );
         println!();
     }
@@ -193,6 +205,25 @@ pub fn interactive_stock() {
             }
         };
 
+        println!("请输入交易方向 (long/short，直接回车默认 long):");
+        print!("> ");
+        io::stdout().flush().unwrap();
+
+        let mut direction_input = String::new();
+        io::stdin().read_line(&mut direction_input).unwrap();
+
+        let direction = if direction_input.trim().is_empty() {
+            TradeDirection::Long
+        } else {
+            match parse_direction(direction_input.trim()) {
+                Ok(d) => d,
+                Err(e) => {
+                    println!("✗ {}\n", e);
+                    continue;
+                }
+            }
+        };
+
         println!("请输入止盈价:");
         print!("> ");
         io::stdout().flush().unwrap();
@@ -201,11 +232,7 @@ pub fn interactive_stock() {
         io::stdin().read_line(&mut target_input).unwrap();
 
         let target_price: f64 = match parse_positive(target_input.trim(), "止盈价") {
-            Ok(n) if n > entry_price => n,
-            Ok(_) => {
-                println!("✗ 止盈价必须大于当前价\n");
-                continue;
-            }
+            Ok(n) => n,
             Err(e) => {
                 println!("✗ {}\n", e);
                 continue;
@@ -220,17 +247,18 @@ pub fn interactive_stock() {
         io::stdin().read_line(&mut stop_input).unwrap();
 
         let stop_loss: f64 = match parse_positive(stop_input.trim(), "止损价") {
-            Ok(n) if n < entry_price => n,
-            Ok(_) => {
-                println!("✗ 止损价必须小于当前价\n");
-                continue;
-            }
+            Ok(n) => n,
             Err(e) => {
                 println!("✗ {}\n", e);
                 continue;
             }
         };
 
+        if let Err(e) = validate_stock_prices(direction, entry_price, target_price, stop_loss) {
+            println!("✗ {}\n", e);
+            continue;
+        }
+
         println!("请输入胜率 (0-100):");
         print!("> ");
         io::stdout().flush().unwrap();
@@ -267,13 +295,20 @@ pub fn interactive_stock() {
 
         execute_mode(
             ModeRequest::Stock {
+                direction,
                 entry_price,
                 target_price,
                 stop_loss,
                 win_rate,
+                costs: StockCosts::default(),
+                volatility: None,
+                sizing: Box::default(),
                 capital,
             },
             OutputFormat::Text,
+            None,
+            CapitalSource::Input,
+            None,
         );
         println!();
     }
@@ -342,9 +377,13 @@ pub fn interactive_arbitrage() {
             ModeRequest::Arbitrage {
                 odds1,
                 odds2,
+                options: ArbitrageOptions::default(),
                 capital,
             },
             OutputFormat::Text,
+            None,
+            CapitalSource::Input,
+            None,
         );
         println!();
     }
@@ -424,8 +463,15 @@ pub fn interactive_multi_arbitrage() {
         };
 
         execute_mode(
-            ModeRequest::MultiArbitrage { odds, capital },
+            ModeRequest::MultiArbitrage {
+                odds,
+                options: ArbitrageOptions::default(),
+                capital,
+            },
             OutputFormat::Text,
+            None,
+            CapitalSource::Input,
+            None,
         );
         println!();
     }
@@ -480,6 +526,9 @@ pub fn interactive_nash() {
                 col_payoffs: [[values[4], values[5]], [values[6], values[7]]],
             },
             OutputFormat::Text,
+            None,
+            CapitalSource::Input,
+            None,
         );
         println!();
     }
@@ -625,9 +674,13 @@ pub fn interactive_portfolio_correlated() {
             ModeRequest::PortfolioCorrelated {
                 leg_count,
                 scenarios,
+                leverage: None,
                 capital,
             },
             OutputFormat::Text,
+            None,
+            CapitalSource::Input,
+            None,
         );
         println!();
     }
@@ -709,9 +762,13 @@ pub fn interactive_portfolio() {
         execute_mode(
             ModeRequest::Portfolio {
                 legs: bets,
+                leverage: None,
                 capital,
             },
             OutputFormat::Text,
+            None,
+            CapitalSource::Input,
+            None,
         );
         println!();
     }
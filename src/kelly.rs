@@ -2,7 +2,7 @@
 //! f* = (bp - q) / b
 //! 其中 b 为赔率-1，p 为胜率，q = 1-p
 
-use crate::types::{KellyResult, StockInfo};
+use crate::types::{KellyResult, StockCosts, StockInfo, StockPositionSize, TradeDirection};
 
 /// 标准凯利公式计算
 pub fn kelly_criterion(odds: f64, win_rate: f64) -> KellyResult {
@@ -38,15 +38,115 @@ pub fn kelly_polymarket(market_price: f64, your_probability: f64) -> KellyResult
     }
 }
 
-/// 股票交易凯利公式计算
+/// 多结果分布凯利公式：max Σ p_i · ln(1 + f · r_i)
+/// outcomes 为 (概率, 单位风险收益)，r = -1 表示亏掉全部风险金
+pub fn kelly_multi_outcome(outcomes: &[(f64, f64)]) -> KellyResult {
+    let expected_value: f64 = outcomes.iter().map(|(p, r)| p * r).sum();
+    if expected_value <= 0.0 {
+        return KellyResult {
+            optimal_fraction: 0.0,
+            positive_ev: false,
+            expected_value,
+        };
+    }
+
+    let worst = outcomes
+        .iter()
+        .filter(|(p, _)| *p > 0.0)
+        .map(|(_, r)| *r)
+        .fold(f64::INFINITY, f64::min);
+    // 最差结果非负时对数增长无上界，按 1000 倍风险截断
+    let upper = if worst < 0.0 {
+        (1.0 / -worst) * (1.0 - 1e-9)
+    } else {
+        1000.0
+    };
+
+    // 目标函数为凹函数，对导数二分求根
+    let derivative = |f: f64| -> f64 { outcomes.iter().map(|(p, r)| p * r / (1.0 + f * r)).sum() };
+    let mut lo = 0.0;
+    let mut hi = upper;
+    if derivative(hi) > 0.0 {
+        lo = hi;
+    } else {
+        for _ in 0..200 {
+            let mid = 0.5 * (lo + hi);
+            if derivative(mid) > 0.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+    }
+
+    KellyResult {
+        optimal_fraction: lo,
+        positive_ev: true,
+        expected_value,
+    }
+}
+
+/// 单笔订单费用（佣金 + 卖出印花税），不足最低佣金按最低佣金收取
+fn stock_order_cost(costs: &StockCosts, price: f64, shares: u64, is_sell: bool) -> f64 {
+    if shares == 0 {
+        return 0.0;
+    }
+    let qty = shares as f64;
+    let notional = price * qty;
+    let commission = (costs.commission_per_share * qty + notional * costs.commission_rate)
+        .max(costs.min_commission);
+    let stamp_duty = if is_sell {
+        notional * costs.stamp_duty_rate
+    } else {
+        0.0
+    };
+    commission + stamp_duty
+}
+
+/// 每股单边费用（佣金 + 卖出印花税，不含最低佣金）
+pub fn per_share_cost(costs: &StockCosts, price: f64, is_sell: bool) -> f64 {
+    let commission = costs.commission_per_share + price * costs.commission_rate;
+    let stamp_duty = if is_sell {
+        price * costs.stamp_duty_rate
+    } else {
+        0.0
+    };
+    commission + stamp_duty
+}
+
+/// 每股净收益与净风险（扣除往返费用与融券费用，不含最低佣金）
+fn stock_net_per_share(
+    direction: TradeDirection,
+    entry_price: f64,
+    target_price: f64,
+    stop_loss: f64,
+    costs: &StockCosts,
+) -> (f64, f64) {
+    // 做多买入开仓、卖出平仓；做空相反
+    let opens_with_sell = direction == TradeDirection::Short;
+    let open_cost = per_share_cost(costs, entry_price, opens_with_sell)
+        + costs.borrow_cost_per_share(direction, entry_price);
+    let sign = direction.sign();
+    let net_profit = (target_price - entry_price) * sign
+        - open_cost
+        - per_share_cost(costs, target_price, !opens_with_sell);
+    let net_risk = (entry_price - stop_loss) * sign
+        + open_cost
+        + per_share_cost(costs, stop_loss, !opens_with_sell);
+    (net_profit, net_risk)
+}
+
+/// 股票交易凯利公式计算（按扣除费用后的净盈亏比）
 pub fn kelly_stock(
+    direction: TradeDirection,
     entry_price: f64,
     target_price: f64,
     stop_loss: f64,
     win_rate: f64,
+    costs: &StockCosts,
 ) -> KellyResult {
-    let profit = target_price - entry_price;
-    let risk = entry_price - stop_loss;
+    let (profit, risk) =
+        stock_net_per_share(direction, entry_price, target_price, stop_loss, costs);
     let b = profit / risk;
 
     let p = win_rate;
@@ -63,24 +163,87 @@ pub fn kelly_stock(
 }
 
 /// 构建股票交易信息
-pub fn build_stock_info(entry_price: f64, target_price: f64, stop_loss: f64) -> StockInfo {
-    let profit = target_price - entry_price;
-    let risk = entry_price - stop_loss;
+pub fn build_stock_info(
+    direction: TradeDirection,
+    entry_price: f64,
+    target_price: f64,
+    stop_loss: f64,
+    costs: &StockCosts,
+) -> StockInfo {
+    let profit = (target_price - entry_price) * direction.sign();
+    let risk = (entry_price - stop_loss) * direction.sign();
     let ratio = profit / risk;
+    let (net_profit, net_risk) =
+        stock_net_per_share(direction, entry_price, target_price, stop_loss, costs);
 
     StockInfo {
+        direction,
         entry_price,
         target_price,
         stop_loss,
         profit,
         risk,
         ratio,
+        net_profit,
+        net_risk,
+        net_ratio: net_profit / net_risk,
     }
 }
 
+/// 按指定股数计算占用资金、止损亏损与止盈净收益（含费用）
+pub fn stock_position_at(info: &StockInfo, costs: &StockCosts, shares: u64) -> StockPositionSize {
+    let qty = shares as f64;
+    let opens_with_sell = info.direction == TradeDirection::Short;
+    let open_cost = stock_order_cost(costs, info.entry_price, shares, opens_with_sell)
+        + costs.borrow_cost_per_share(info.direction, info.entry_price) * qty;
+    StockPositionSize {
+        shares,
+        capital_used: info.entry_price * qty + open_cost,
+        risk_at_stop: info.risk * qty
+            + open_cost
+            + stock_order_cost(costs, info.stop_loss, shares, !opens_with_sell),
+        profit_at_target: info.profit * qty
+            - open_cost
+            - stock_order_cost(costs, info.target_price, shares, !opens_with_sell),
+    }
+}
+
+/// 按风险预算计算整手股数：止损时的实际亏损（含费用）不超过风险预算
+pub fn size_stock_position(
+    info: &StockInfo,
+    costs: &StockCosts,
+    risk_budget: f64,
+) -> StockPositionSize {
+    let lot = costs.lot_size.max(1);
+    if !(risk_budget > 0.0 && info.net_risk > 0.0) {
+        return stock_position_at(info, costs, 0);
+    }
+
+    // 容忍浮点误差，避免 3999.9999 被向下取整为少一手
+    let budget = risk_budget * (1.0 + 1e-9);
+    let raw_shares = (budget / info.net_risk).floor();
+    let mut lots = if raw_shares.is_finite() {
+        (raw_shares as u64) / lot
+    } else {
+        0
+    };
+
+    // 最低佣金可能让实际风险超出预算，逐手回退
+    let mut position = stock_position_at(info, costs, lots * lot);
+    while lots > 0 && position.risk_at_stop > budget {
+        lots -= 1;
+        position = stock_position_at(info, costs, lots * lot);
+    }
+    position
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{build_stock_info, kelly_criterion, kelly_polymarket, kelly_stock};
+    use super::{
+        build_stock_info, kelly_criterion, kelly_multi_outcome, kelly_polymarket, kelly_stock,
+        size_stock_position,
+    };
+    use crate::types::{StockCosts, TradeDirection};
 
     const EPS: f64 = 1e-10;
 
@@ -109,7 +272,14 @@ mod tests {
 
     #[test]
     fn stock_kelly_calculation_is_correct() {
-        let result = kelly_stock(100.0, 120.0, 90.0, 0.6);
+        let result = kelly_stock(
+            TradeDirection::Long,
+            100.0,
+            120.0,
+            90.0,
+            0.6,
+            &StockCosts::default(),
+        );
         assert_almost_eq(result.optimal_fraction, 0.4);
         assert_almost_eq(result.expected_value, 0.8);
         assert!(result.positive_ev);
@@ -117,10 +287,71 @@ mod tests {
 
     #[test]
     fn stock_info_ratio_is_correct() {
-        let info = build_stock_info(100.0, 120.0, 90.0);
+        let info = build_stock_info(
+            TradeDirection::Long,
+            100.0,
+            120.0,
+            90.0,
+            &StockCosts::default(),
+        );
         assert_almost_eq(info.profit, 20.0);
         assert_almost_eq(info.risk, 10.0);
         assert_almost_eq(info.ratio, 2.0);
+        assert_almost_eq(info.net_ratio, 2.0);
+    }
+
+    #[test]
+    fn stock_costs_reduce_net_ratio() {
+        let costs = StockCosts {
+            commission_rate: 0.001,
+            stamp_duty_rate: 0.001,
+            ..StockCosts::default()
+        };
+        let info = build_stock_info(TradeDirection::Long, 100.0, 120.0, 90.0, &costs);
+        // 买入 0.1 + 止盈卖出 0.12 + 0.12；止损卖出 0.09 + 0.09
+        assert_almost_eq(info.net_profit, 20.0 - 0.1 - 0.24);
+        assert_almost_eq(info.net_risk, 10.0 + 0.1 + 0.18);
+        assert!(info.net_ratio < info.ratio);
+
+        let gross = kelly_stock(
+            TradeDirection::Long,
+            100.0,
+            120.0,
+            90.0,
+            0.6,
+            &StockCosts::default(),
+        );
+        let net = kelly_stock(TradeDirection::Long, 100.0, 120.0, 90.0, 0.6, &costs);
+        assert!(net.optimal_fraction < gross.optimal_fraction);
+    }
+
+    #[test]
+    fn stock_position_rounds_down_to_whole_lots() {
+        let costs = StockCosts {
+            lot_size: 100,
+            ..StockCosts::default()
+        };
+        let info = build_stock_info(TradeDirection::Long, 10.0, 12.0, 9.0, &costs);
+        let position = size_stock_position(&info, &costs, 1550.0);
+        assert_eq!(position.shares, 1500);
+        assert_almost_eq(position.capital_used, 15000.0);
+        assert_almost_eq(position.risk_at_stop, 1500.0);
+        assert_almost_eq(position.profit_at_target, 3000.0);
+    }
+
+    #[test]
+    fn stock_position_min_commission_keeps_risk_within_budget() {
+        let costs = StockCosts {
+            lot_size: 100,
+            min_commission: 5.0,
+            ..StockCosts::default()
+        };
+        let info = build_stock_info(TradeDirection::Long, 10.0, 12.0, 9.0, &costs);
+        let position = size_stock_position(&info, &costs, 1505.0);
+        // 1500 股需要 1500 + 5 + 5 = 1510 > 1505，回退到 1400 股
+        assert_eq!(position.shares, 1400);
+        assert_almost_eq(position.risk_at_stop, 1410.0);
+        assert!(position.risk_at_stop <= 1505.0);
     }
 
     #[test]
@@ -130,4 +361,58 @@ mod tests {
         assert!(!result.positive_ev);
         assert!(result.optimal_fraction <= 0.0);
     }
+
+    #[test]
+    fn short_stock_kelly_mirrors_long() {
+        let costs = StockCosts::default();
+        let info = build_stock_info(TradeDirection::Short, 100.0, 80.0, 110.0, &costs);
+        assert_almost_eq(info.profit, 20.0);
+        assert_almost_eq(info.risk, 10.0);
+        assert_almost_eq(info.ratio, 2.0);
+
+        let result = kelly_stock(TradeDirection::Short, 100.0, 80.0, 110.0, 0.6, &costs);
+        assert_almost_eq(result.optimal_fraction, 0.4);
+        assert_almost_eq(result.expected_value, 0.8);
+    }
+
+    #[test]
+    fn short_borrow_fee_reduces_payoff() {
+        let costs = StockCosts {
+            borrow_rate: 0.0365,
+            holding_days: 10.0,
+            ..StockCosts::default()
+        };
+        // 每股融券费 = 100 * 3.65% * 10 / 365 = 0.1
+        let info = build_stock_info(TradeDirection::Short, 100.0, 80.0, 110.0, &costs);
+        assert_almost_eq(info.net_profit, 19.9);
+        assert_almost_eq(info.net_risk, 10.1);
+
+        let long = build_stock_info(TradeDirection::Long, 100.0, 120.0, 90.0, &costs);
+        assert_almost_eq(long.net_ratio, 2.0);
+    }
+
+    #[test]
+    fn short_stamp_duty_is_charged_on_opening_sell() {
+        let costs = StockCosts {
+            stamp_duty_rate: 0.001,
+            ..StockCosts::default()
+        };
+        let info = build_stock_info(TradeDirection::Short, 100.0, 80.0, 110.0, &costs);
+        assert_almost_eq(info.net_profit, 19.9);
+        assert_almost_eq(info.net_risk, 10.1);
+    }
+
+    #[test]
+    fn multi_outcome_kelly_matches_binary_formula() {
+        let result = kelly_multi_outcome(&[(0.6, 2.0), (0.4, -1.0)]);
+        assert!((result.optimal_fraction - 0.4).abs() < 1e-9);
+        assert_almost_eq(result.expected_value, 0.8);
+    }
+
+    #[test]
+    fn multi_outcome_kelly_is_zero_without_edge() {
+        let result = kelly_multi_outcome(&[(0.5, 1.0), (0.5, -1.0)]);
+        assert_almost_eq(result.optimal_fraction, 0.0);
+        assert!(!result.positive_ev);
+    }
 }
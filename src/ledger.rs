@@ -0,0 +1,939 @@
+//! 投注账本：在本地数据目录记录入金/出金、下注、结算与收盘价，统计资金曲线、收益与 CLV
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::kelly::kelly_criterion;
+use crate::types::{
+    BetSettlement, ClosingLine, ClvGroup, ClvSummary, LedgerBet, LedgerCommand, LedgerEntry,
+    LedgerRecord, LedgerStake, LedgerSummary, RecordSize, SizedBet,
+};
+use crate::validation::{parse_non_negative, parse_odds, parse_positive, parse_positive_integer};
+
+const LEDGER_FILE: &str = "ledger.csv";
+const LEDGER_HEADER: &str = "# bo 投注账本：deposit|withdraw,日期,金额 / bet,编号,日期,赔率,金额,模式,盘口类型,胜率,备注 / settle,编号,日期,结果,返还金额 / close,编号,日期,收盘赔率,收盘概率,devig|raw";
+
+/// 未记录盘口类型（`--market`）的下注在 CLV 分组中的名称
+const UNCLASSIFIED_MARKET: &str = "未分类";
+
+/// CLV 分布的区间边界
+const CLV_BUCKET_EDGES: [f64; 7] = [-0.10, -0.05, -0.02, 0.0, 0.02, 0.05, 0.10];
+
+/// 数据目录：`--data-dir` > 环境变量 `BO_DATA_DIR` > `~/.bo`
+pub fn data_dir(flag: Option<PathBuf>) -> PathBuf {
+    if let Some(dir) = flag {
+        return dir;
+    }
+    if let Some(dir) = env::var_os("BO_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".bo"))
+        .unwrap_or_else(|| PathBuf::from(".bo"))
+}
+
+pub fn ledger_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(LEDGER_FILE)
+}
+
+/// 由 1970-01-01 起的天数换算公历日期
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// 当前 UTC 日期（YYYY-MM-DD）
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// 校验日期格式 YYYY-MM-DD
+pub fn parse_ledger_date(input: &str) -> Result<String, String> {
+    let parts: Vec<&str> = input.split('-').collect();
+    let valid = parts.len() == 3
+        && parts[0].len() == 4
+        && parts[1].len() == 2
+        && parts[2].len() == 2
+        && parts.iter().all(|p| p.chars().all(|c| c.is_ascii_digit()))
+        && (1..=12).contains(&parts[1].parse::<u32>().unwrap_or(0))
+        && (1..=31).contains(&parts[2].parse::<u32>().unwrap_or(0));
+    if valid {
+        Ok(input.to_string())
+    } else {
+        Err(format!("日期 '{input}' 格式错误，应为 YYYY-MM-DD"))
+    }
+}
+
+/// 账本中的文本字段不能包含换行，逗号只允许出现在最后的备注中
+/// 计算出的金额保留到分再写入账本，避免记录浮点误差（如 99.99999999999997）
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+fn clean_text(input: &str) -> String {
+    input.replace(['\n', '\r'], " ").trim().to_string()
+}
+
+/// 格式化账本记录为一行
+pub fn format_ledger_entry(entry: &LedgerEntry) -> String {
+    match entry {
+        LedgerEntry::Deposit { date, amount } => format!("deposit,{date},{amount}"),
+        LedgerEntry::Withdraw { date, amount } => format!("withdraw,{date},{amount}"),
+        LedgerEntry::Bet {
+            id,
+            date,
+            odds,
+            stake,
+            mode,
+            market,
+            probability,
+            label,
+        } => format!(
+            "bet,{id},{date},{odds},{stake},{},{},{},{}",
+            clean_text(mode).replace(',', " "),
+            clean_text(market).replace(',', " "),
+            probability.map_or(String::new(), |p| p.to_string()),
+            clean_text(label)
+        ),
+        LedgerEntry::Settle {
+            id,
+            date,
+            settlement,
+            returned,
+        } => format!("settle,{id},{date},{},{returned}", settlement.code()),
+        LedgerEntry::Close { id, line } => format!(
+            "close,{id},{},{},{},{}",
+            line.date,
+            line.odds,
+            line.probability,
+            if line.devigged { "devig" } else { "raw" }
+        ),
+    }
+}
+
+/// 解析账本内容，空行与 `#` 开头的注释行忽略
+pub fn parse_ledger(content: &str) -> Result<Vec<LedgerEntry>, String> {
+    let mut entries = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_start();
+        if line.trim_end().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let with_line = |e: String| format!("账本第 {} 行: {e}", i + 1);
+        // 只拆分固定字段；下注备注为第 9 个字段起的剩余原文（可含逗号与空格）
+        let fields: Vec<&str> = line
+            .splitn(9, ',')
+            .enumerate()
+            .map(|(i, field)| if i < 8 { field.trim() } else { field })
+            .collect();
+        let entry = match (fields[0], fields.len()) {
+            ("deposit", 3) => LedgerEntry::Deposit {
+                date: parse_ledger_date(fields[1]).map_err(with_line)?,
+                amount: parse_positive(fields[2], "金额").map_err(with_line)?,
+            },
+            ("withdraw", 3) => LedgerEntry::Withdraw {
+                date: parse_ledger_date(fields[1]).map_err(with_line)?,
+                amount: parse_positive(fields[2], "金额").map_err(with_line)?,
+            },
+            ("bet", n) if n >= 8 => LedgerEntry::Bet {
+                id: parse_positive_integer(fields[1], "编号").map_err(with_line)?,
+                date: parse_ledger_date(fields[2]).map_err(with_line)?,
+                odds: parse_odds(fields[3], "赔率").map_err(with_line)?,
+                stake: parse_positive(fields[4], "金额").map_err(with_line)?,
+                mode: fields[5].to_string(),
+                market: fields[6].to_string(),
+                probability: match fields[7] {
+                    "" => None,
+                    p => Some(parse_probability(p).map_err(with_line)?),
+                },
+                label: fields.get(8).copied().unwrap_or_default().to_string(),
+            },
+            ("settle", 5) => LedgerEntry::Settle {
+                id: parse_positive_integer(fields[1], "编号").map_err(with_line)?,
+                date: parse_ledger_date(fields[2]).map_err(with_line)?,
+                settlement: BetSettlement::from_code(fields[3]).map_err(with_line)?,
+                returned: parse_non_negative(fields[4], "返还金额").map_err(with_line)?,
+            },
+            ("close", 6) => LedgerEntry::Close {
+                id: parse_positive_integer(fields[1], "编号").map_err(with_line)?,
+                line: ClosingLine {
+                    date: parse_ledger_date(fields[2]).map_err(with_line)?,
+                    odds: parse_odds(fields[3], "收盘赔率").map_err(with_line)?,
+                    probability: parse_probability(fields[4]).map_err(with_line)?,
+                    devigged: match fields[5] {
+                        "devig" => true,
+                        "raw" => false,
+                        other => return Err(with_line(format!("收盘类型 '{other}' 无效"))),
+                    },
+                },
+            },
+            _ => return Err(with_line("无法识别的记录".to_string())),
+        };
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// 账本中的概率以小数记录，必须在 (0, 1) 之间
+fn parse_probability(input: &str) -> Result<f64, String> {
+    match input.parse::<f64>() {
+        Ok(p) if p > 0.0 && p < 1.0 => Ok(p),
+        _ => Err(format!("概率 '{input}' 必须在 (0, 1) 之间")),
+    }
+}
+
+/// 读取账本文件，文件不存在时视为空账本
+pub fn load_ledger(path: &Path) -> Result<Vec<LedgerEntry>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => parse_ledger(&content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("无法读取账本 '{}': {e}", path.display())),
+    }
+}
+
+/// 追加一条记录，必要时创建数据目录与带表头的账本文件
+pub fn append_ledger_entry(path: &Path, entry: &LedgerEntry) -> Result<(), String> {
+    let error = |e: std::io::Error| format!("无法写入账本 '{}': {e}", path.display());
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(error)?;
+    }
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(error)?;
+    if is_new {
+        writeln!(file, "{LEDGER_HEADER}").map_err(error)?;
+    }
+    writeln!(file, "{}", format_ledger_entry(entry)).map_err(error)
+}
+
+/// 下一笔下注的编号
+pub fn next_bet_id(entries: &[LedgerEntry]) -> u64 {
+    entries
+        .iter()
+        .filter_map(|e| match e {
+            LedgerEntry::Bet { id, .. } => Some(*id),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+/// 结算返还金额：赢 = 金额 × 赔率，输 = 0，走水 = 金额，部分结算按输入金额
+pub fn settlement_return(
+    settlement: BetSettlement,
+    odds: f64,
+    stake: f64,
+    amount: Option<f64>,
+) -> f64 {
+    match settlement {
+        BetSettlement::Win => stake * odds,
+        BetSettlement::Loss => 0.0,
+        BetSettlement::Void => stake,
+        BetSettlement::Partial => amount.unwrap_or(0.0),
+    }
+}
+
+/// 检查新记录与现有账本是否一致（余额充足、结算的下注存在且未结算）
+pub fn validate_ledger_entry(entries: &[LedgerEntry], entry: &LedgerEntry) -> Result<(), String> {
+    let summary = summarize_ledger(entries);
+    match entry {
+        LedgerEntry::Deposit { .. } => Ok(()),
+        LedgerEntry::Withdraw { amount, .. } | LedgerEntry::Bet { stake: amount, .. }
+            if *amount > summary.balance + 1e-9 =>
+        {
+            Err(format!(
+                "可用余额不足：当前 {:.2}，需要 {:.2}",
+                summary.balance, amount
+            ))
+        }
+        LedgerEntry::Withdraw { .. } | LedgerEntry::Bet { .. } => Ok(()),
+        LedgerEntry::Settle { id, .. } => match summary.bets.iter().find(|b| b.id == *id) {
+            None => Err(format!("账本中没有编号为 {id} 的下注")),
+            Some(bet) if bet.settlement.is_some() => Err(format!("下注 #{id} 已结算")),
+            Some(_) => Ok(()),
+        },
+        // 收盘价可在结算前后记录，重复记录时以最后一次为准
+        LedgerEntry::Close { id, .. } if summary.bets.iter().all(|b| b.id != *id) => {
+            Err(format!("账本中没有编号为 {id} 的下注"))
+        }
+        LedgerEntry::Close { .. } => Ok(()),
+    }
+}
+
+/// 由账本命令生成新记录并检查；`show` 不产生记录
+pub fn build_ledger_entry(
+    entries: &[LedgerEntry],
+    command: &LedgerCommand,
+    date: &str,
+) -> Result<Option<LedgerEntry>, String> {
+    let date = date.to_string();
+    let entry = match command {
+        LedgerCommand::Show => return Ok(None),
+        LedgerCommand::Deposit(amount) => LedgerEntry::Deposit {
+            date,
+            amount: *amount,
+        },
+        LedgerCommand::Withdraw(amount) => LedgerEntry::Withdraw {
+            date,
+            amount: *amount,
+        },
+        LedgerCommand::Add {
+            odds,
+            stake,
+            mode,
+            market,
+            probability,
+            label,
+        } => LedgerEntry::Bet {
+            id: next_bet_id(entries),
+            date,
+            odds: *odds,
+            stake: round_cents(match stake {
+                LedgerStake::Amount(amount) => *amount,
+                LedgerStake::Fraction(fraction) => {
+                    let balance = summarize_ledger(entries).balance;
+                    if balance <= 0.0 {
+                        return Err("可用余额为零，无法按比例下注，请先入金".to_string());
+                    }
+                    balance * fraction
+                }
+            }),
+            mode: mode.clone(),
+            market: market.clone(),
+            probability: *probability,
+            label: label.clone(),
+        },
+        LedgerCommand::Close { id, odds, others } => LedgerEntry::Close {
+            id: *id,
+            line: closing_line(date, *odds, others),
+        },
+        LedgerCommand::Settle {
+            id,
+            settlement,
+            returned,
+        } => {
+            let summary = summarize_ledger(entries);
+            let bet = summary
+                .bets
+                .iter()
+                .find(|b| b.id == *id)
+                .ok_or_else(|| format!("账本中没有编号为 {id} 的下注"))?;
+            if returned.is_some_and(|r| r > bet.stake * bet.odds + 1e-9) {
+                return Err(format!(
+                    "返还金额不能超过全赢返还 {:.2}",
+                    bet.stake * bet.odds
+                ));
+            }
+            LedgerEntry::Settle {
+                id: *id,
+                date,
+                settlement: *settlement,
+                returned: round_cents(settlement_return(
+                    *settlement,
+                    bet.odds,
+                    bet.stake,
+                    *returned,
+                )),
+            }
+        }
+    };
+    validate_ledger_entry(entries, &entry)?;
+    Ok(Some(entry))
+}
+
+/// 账本下注可标注的计算模式（与各模式 JSON 输出的 `mode` 一致）
+pub const LEDGER_MODES: [&str; 18] = [
+    "standard",
+    "polymarket",
+    "stock",
+    "stock_scale_out",
+    "stock_trailing",
+    "trade_history",
+    "merton",
+    "option",
+    "arbitrage",
+    "multi_arbitrage",
+    "back_lay",
+    "value_scan",
+    "scan",
+    "dutching",
+    "hedge",
+    "middle",
+    "portfolio_kelly",
+    "portfolio_kelly_correlated",
+];
+
+/// `--record`：生成下注并逐笔检查，全部通过后才写入账本，返回写入的记录
+pub fn record_ledger_bets(
+    record: &LedgerRecord,
+    mode: &str,
+    bets: &[SizedBet],
+) -> Result<Vec<LedgerEntry>, String> {
+    let entries = load_ledger(&record.path)?;
+    let recorded = plan_recorded_bets(entries, record, mode, bets)?;
+    for entry in &recorded {
+        append_ledger_entry(&record.path, entry)?;
+    }
+    Ok(recorded)
+}
+
+/// 生成下注：凯利仓位乘以凯利倍数，分配金额原样记录；金额为零（无正期望）的下注跳过
+fn plan_recorded_bets(
+    mut entries: Vec<LedgerEntry>,
+    record: &LedgerRecord,
+    mode: &str,
+    bets: &[SizedBet],
+) -> Result<Vec<LedgerEntry>, String> {
+    let multiplier = match record.size {
+        RecordSize::Kelly(kelly) => kelly,
+        RecordSize::Stake => 1.0,
+    };
+    let mut recorded = Vec::new();
+    for bet in bets.iter().filter(|b| b.stake > 0.0) {
+        let label = match (record.label.is_empty(), bet.label.is_empty()) {
+            (false, false) => format!("{} / {}", record.label, bet.label),
+            (false, true) => record.label.clone(),
+            _ => bet.label.clone(),
+        };
+        let command = LedgerCommand::Add {
+            odds: bet.odds,
+            stake: LedgerStake::Amount(bet.stake * multiplier),
+            mode: mode.to_string(),
+            market: if bet.market.is_empty() {
+                record.market.clone()
+            } else {
+                bet.market.clone()
+            },
+            probability: bet.probability,
+            label,
+        };
+        if let Some(entry) = build_ledger_entry(&entries, &command, &record.date)? {
+            entries.push(entry.clone());
+            recorded.push(entry);
+        }
+    }
+    Ok(recorded)
+}
+
+/// 收盘概率：提供同一盘口其他结果的收盘赔率时按比例去水，否则取 1 / 收盘赔率
+pub fn closing_line(date: String, odds: f64, others: &[f64]) -> ClosingLine {
+    let implied = 1.0 / odds;
+    let overround: f64 = implied + others.iter().map(|o| 1.0 / o).sum::<f64>();
+    ClosingLine {
+        date,
+        odds,
+        probability: if others.is_empty() {
+            implied
+        } else {
+            implied / overround
+        },
+        devigged: !others.is_empty(),
+    }
+}
+
+/// 收盘价值 CLV：按收盘概率计算的下注期望收益
+pub fn bet_clv(bet: &LedgerBet) -> Option<f64> {
+    bet.closing
+        .as_ref()
+        .map(|c| kelly_criterion(bet.odds, c.probability).expected_value)
+}
+
+/// 下注时按自己胜率计算的期望收益
+pub fn placement_ev(bet: &LedgerBet) -> Option<f64> {
+    bet.probability
+        .map(|p| kelly_criterion(bet.odds, p).expected_value)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn beat_rate(values: &[f64]) -> f64 {
+    values.iter().filter(|v| **v > 0.0).count() as f64 / values.len() as f64
+}
+
+/// 汇总有收盘价的下注：均值、中位数、金额加权均值、赢过收盘价比例、分布与按盘口类型分组
+pub fn summarize_clv(bets: &[LedgerBet]) -> Option<ClvSummary> {
+    let tracked: Vec<(&LedgerBet, f64)> = bets
+        .iter()
+        .filter_map(|b| bet_clv(b).map(|clv| (b, clv)))
+        .collect();
+    if tracked.is_empty() {
+        return None;
+    }
+    let values: Vec<f64> = tracked.iter().map(|(_, clv)| *clv).collect();
+    let mut sorted = values.clone();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    let median_clv = if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    };
+    let total_stake: f64 = tracked.iter().map(|(b, _)| b.stake).sum();
+    let weighted_clv = tracked.iter().map(|(b, clv)| b.stake * clv).sum::<f64>() / total_stake;
+
+    let compared: Vec<(f64, f64)> = tracked
+        .iter()
+        .filter_map(|(b, clv)| placement_ev(b).map(|ev| (ev, *clv)))
+        .collect();
+    let (evs, clvs): (Vec<f64>, Vec<f64>) = compared.iter().copied().unzip();
+
+    let mut bounds = vec![f64::NEG_INFINITY];
+    bounds.extend(CLV_BUCKET_EDGES);
+    bounds.push(f64::INFINITY);
+    let distribution = bounds
+        .windows(2)
+        .map(|w| {
+            let count = values.iter().filter(|v| **v >= w[0] && **v < w[1]).count();
+            (w[0], w[1], count)
+        })
+        .collect();
+
+    let mut markets: Vec<&str> = tracked.iter().map(|(b, _)| b.market.as_str()).collect();
+    markets.sort_unstable();
+    markets.dedup();
+    let by_market = markets
+        .into_iter()
+        .map(|market| {
+            let group: Vec<f64> = tracked
+                .iter()
+                .filter(|(b, _)| b.market == market)
+                .map(|(_, clv)| *clv)
+                .collect();
+            ClvGroup {
+                market: if market.is_empty() {
+                    UNCLASSIFIED_MARKET
+                } else {
+                    market
+                }
+                .to_string(),
+                count: group.len(),
+                mean_clv: mean(&group),
+                beat_rate: beat_rate(&group),
+            }
+        })
+        .collect();
+
+    Some(ClvSummary {
+        count: values.len(),
+        mean_clv: mean(&values),
+        median_clv,
+        weighted_clv,
+        beat_rate: beat_rate(&values),
+        compared: compared.len(),
+        mean_placement_ev: (!evs.is_empty()).then(|| mean(&evs)),
+        mean_compared_clv: (!clvs.is_empty()).then(|| mean(&clvs)),
+        distribution,
+        by_market,
+    })
+}
+
+/// 汇总账本：资金、已实现盈亏、投注额、ROI 与资金曲线
+///
+/// 资金 = 入金 - 出金 + 已实现盈亏；可用余额 = 资金 - 未结算投注金额。
+/// 资金曲线在每次入金、出金与结算后记录一个点。
+pub fn summarize_ledger(entries: &[LedgerEntry]) -> LedgerSummary {
+    let mut summary = LedgerSummary::default();
+    let mut bankroll = 0.0;
+
+    for entry in entries {
+        match entry {
+            LedgerEntry::Deposit { date, amount } => {
+                summary.deposits += amount;
+                bankroll += amount;
+                summary.curve.push((date.clone(), bankroll));
+            }
+            LedgerEntry::Withdraw { date, amount } => {
+                summary.withdrawals += amount;
+                bankroll -= amount;
+                summary.curve.push((date.clone(), bankroll));
+            }
+            LedgerEntry::Bet {
+                id,
+                date,
+                odds,
+                stake,
+                mode,
+                market,
+                probability,
+                label,
+            } => summary.bets.push(LedgerBet {
+                id: *id,
+                date: date.clone(),
+                odds: *odds,
+                stake: *stake,
+                mode: mode.clone(),
+                market: market.clone(),
+                probability: *probability,
+                label: label.clone(),
+                settlement: None,
+                closing: None,
+            }),
+            LedgerEntry::Settle {
+                id,
+                date,
+                settlement,
+                returned,
+            } => {
+                if let Some(bet) = summary.bets.iter_mut().find(|b| b.id == *id) {
+                    let pnl = returned - bet.stake;
+                    bet.settlement = Some((*settlement, *returned, date.clone()));
+                    bankroll += pnl;
+                    summary.realized_pnl += pnl;
+                    summary.turnover += bet.stake;
+                    summary.curve.push((date.clone(), bankroll));
+                }
+            }
+            LedgerEntry::Close { id, line } => {
+                if let Some(bet) = summary.bets.iter_mut().find(|b| b.id == *id) {
+                    bet.closing = Some(line.clone());
+                }
+            }
+        }
+    }
+
+    summary.pending_stake = summary
+        .bets
+        .iter()
+        .filter(|b| b.settlement.is_none())
+        .fold(0.0, |acc, b| acc + b.stake);
+    summary.bankroll = bankroll;
+    summary.balance = bankroll - summary.pending_stake;
+    summary.roi = (summary.turnover > 0.0).then(|| summary.realized_pnl / summary.turnover);
+    summary.clv = summarize_clv(&summary.bets);
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_ledger_entry, civil_from_days, closing_line, format_ledger_entry, parse_ledger,
+        parse_ledger_date, plan_recorded_bets, summarize_ledger, validate_ledger_entry,
+    };
+    use crate::types::{
+        BetSettlement, LedgerCommand, LedgerEntry, LedgerRecord, LedgerStake, RecordSize, SizedBet,
+    };
+
+    const LEDGER: &str = "# 注释
+deposit,2024-01-01,1000
+bet,1,2024-01-02,2.5,100,standard,1x2,0.48,A 队胜, 主场
+bet,2,2024-01-02,1.8,200,arbitrage,,,
+settle,1,2024-01-03,win,250
+bet,3,2024-01-04,3.0,50,standard,大小球,,
+settle,2,2024-01-05,loss,0
+withdraw,2024-01-06,100
+";
+
+    #[test]
+    fn entries_round_trip_through_text() {
+        let entries = parse_ledger(LEDGER).unwrap();
+        assert_eq!(entries.len(), 7);
+        match &entries[1] {
+            LedgerEntry::Bet { label, mode, .. } => {
+                assert_eq!(label, "A 队胜, 主场");
+                assert_eq!(mode, "standard");
+            }
+            other => panic!("unexpected entry {other:?}"),
+        }
+        for entry in &entries {
+            let line = format_ledger_entry(entry);
+            assert_eq!(format_ledger_entry(&parse_ledger(&line).unwrap()[0]), line);
+        }
+        assert!(parse_ledger("settle,1,2024-01-03,draw,0").is_err());
+        assert!(parse_ledger("deposit,2024/01/01,1000").is_err());
+    }
+
+    #[test]
+    fn computed_stakes_are_rounded_to_cents() {
+        let entries = parse_ledger(LEDGER).unwrap();
+        let add = |stake| LedgerCommand::Add {
+            odds: 2.0,
+            stake,
+            mode: String::new(),
+            market: String::new(),
+            probability: None,
+            label: " 曼联, 主场".to_string(),
+        };
+        // 可用余额 800 × 1/3
+        let entry = build_ledger_entry(
+            &entries,
+            &add(LedgerStake::Fraction(1.0 / 3.0)),
+            "2024-01-07",
+        )
+        .unwrap()
+        .unwrap();
+        assert!(format_ledger_entry(&entry).starts_with("bet,4,2024-01-07,2,266.67,"));
+        let entry = build_ledger_entry(
+            &entries,
+            &add(LedgerStake::Amount(0.1 + 0.2 + 99.7 - 1e-13)),
+            "2024-01-07",
+        )
+        .unwrap()
+        .unwrap();
+        let line = format_ledger_entry(&entry);
+        assert_eq!(line, "bet,4,2024-01-07,2,100,,,,曼联, 主场");
+        assert!(
+            matches!(&parse_ledger(&line).unwrap()[0], LedgerEntry::Bet { label, .. } if label == "曼联, 主场")
+        );
+    }
+
+    #[test]
+    fn summary_tracks_bankroll_and_roi() {
+        let summary = summarize_ledger(&parse_ledger(LEDGER).unwrap());
+        // 1000 + 150 - 200 - 100 = 850，未结算 50
+        assert!((summary.bankroll - 850.0).abs() < 1e-9);
+        assert!((summary.balance - 800.0).abs() < 1e-9);
+        assert!((summary.pending_stake - 50.0).abs() < 1e-9);
+        assert!((summary.realized_pnl + 50.0).abs() < 1e-9);
+        assert!((summary.turnover - 300.0).abs() < 1e-9);
+        assert!((summary.roi.unwrap() + 50.0 / 300.0).abs() < 1e-12);
+        let curve: Vec<f64> = summary.curve.iter().map(|(_, v)| *v).collect();
+        assert_eq!(curve, vec![1000.0, 1150.0, 950.0, 850.0]);
+    }
+
+    #[test]
+    fn validation_checks_balance_and_settlement() {
+        let entries = parse_ledger(LEDGER).unwrap();
+        let bet = |stake| LedgerEntry::Bet {
+            id: 4,
+            date: "2024-01-07".to_string(),
+            odds: 2.0,
+            stake,
+            mode: String::new(),
+            market: String::new(),
+            probability: None,
+            label: String::new(),
+        };
+        assert!(validate_ledger_entry(&entries, &bet(800.0)).is_ok());
+        assert!(validate_ledger_entry(&entries, &bet(800.01)).is_err());
+        let settle = |id| LedgerEntry::Settle {
+            id,
+            date: "2024-01-07".to_string(),
+            settlement: BetSettlement::Void,
+            returned: 50.0,
+        };
+        assert!(validate_ledger_entry(&entries, &settle(3)).is_ok());
+        assert!(validate_ledger_entry(&entries, &settle(1)).is_err());
+        assert!(validate_ledger_entry(&entries, &settle(9)).is_err());
+    }
+
+    #[test]
+    fn commands_build_entries() {
+        let entries = parse_ledger(LEDGER).unwrap();
+        let add = LedgerCommand::Add {
+            odds: 2.0,
+            stake: LedgerStake::Fraction(0.05),
+            mode: "standard".to_string(),
+            market: String::new(),
+            probability: Some(0.6),
+            label: String::new(),
+        };
+        match build_ledger_entry(&entries, &add, "2024-01-07").unwrap() {
+            Some(LedgerEntry::Bet { id, stake, .. }) => {
+                assert_eq!(id, 4);
+                assert!((stake - 40.0).abs() < 1e-9);
+            }
+            other => panic!("unexpected entry {other:?}"),
+        }
+
+        let settle = |settlement, returned| LedgerCommand::Settle {
+            id: 3,
+            settlement,
+            returned,
+        };
+        let returned = |command| match build_ledger_entry(&entries, &command, "2024-01-07") {
+            Ok(Some(LedgerEntry::Settle { returned, .. })) => returned,
+            other => panic!("unexpected entry {other:?}"),
+        };
+        assert_eq!(returned(settle(BetSettlement::Win, None)), 150.0);
+        assert_eq!(returned(settle(BetSettlement::Void, None)), 50.0);
+        assert_eq!(returned(settle(BetSettlement::Partial, Some(100.0))), 100.0);
+        assert!(
+            build_ledger_entry(
+                &entries,
+                &settle(BetSettlement::Partial, Some(151.0)),
+                "2024-01-07"
+            )
+            .is_err()
+        );
+        assert!(build_ledger_entry(&[], &LedgerCommand::Withdraw(1.0), "2024-01-07").is_err());
+        let close = |id| LedgerCommand::Close {
+            id,
+            odds: 2.0,
+            others: Vec::new(),
+        };
+        // 已结算的下注仍可补记收盘价
+        assert!(build_ledger_entry(&entries, &close(1), "2024-01-07").is_ok());
+        assert!(build_ledger_entry(&entries, &close(9), "2024-01-07").is_err());
+        assert!(
+            build_ledger_entry(&entries, &LedgerCommand::Show, "2024-01-07")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn clv_uses_devigged_closing_probability() {
+        // 三项盘口收盘 2.2 / 3.4 / 3.2：去水概率 = (1/2.2) / Σ(1/o)
+        let line = closing_line("2024-01-02".to_string(), 2.2, &[3.4, 3.2]);
+        let total = 1.0 / 2.2 + 1.0 / 3.4 + 1.0 / 3.2;
+        assert!((line.probability - (1.0 / 2.2) / total).abs() < 1e-12);
+        assert!(line.devigged);
+        let raw = closing_line("2024-01-02".to_string(), 2.5, &[]);
+        assert!(!raw.devigged);
+        assert!((raw.probability - 0.4).abs() < 1e-12);
+
+        let content = format!(
+            "{LEDGER}close,1,2024-01-02,2.0,0.5,raw\nclose,2,2024-01-02,1.9,0.5,devig\nclose,3,2024-01-04,2.6,0.4,raw\nclose,3,2024-01-04,2.5,0.4,raw\n"
+        );
+        let summary = summarize_ledger(&parse_ledger(&content).unwrap());
+        let clv = summary.clv.unwrap();
+        // 各笔 CLV：2.5 × 0.5 - 1 = 25%，1.8 × 0.5 - 1 = -10%，3.0 × 0.4 - 1 = 20%
+        assert_eq!(clv.count, 3);
+        assert!((clv.mean_clv - 0.35 / 3.0).abs() < 1e-12);
+        assert!((clv.median_clv - 0.2).abs() < 1e-12);
+        assert!((clv.weighted_clv - 15.0 / 350.0).abs() < 1e-12);
+        assert!((clv.beat_rate - 2.0 / 3.0).abs() < 1e-12);
+        // 只有第 1 笔记录了下注时胜率：EV = 0.48 × 2.5 - 1 = 20%
+        assert_eq!(clv.compared, 1);
+        assert!((clv.mean_placement_ev.unwrap() - 0.2).abs() < 1e-12);
+        assert!((clv.mean_compared_clv.unwrap() - 0.25).abs() < 1e-12);
+        let counts: Vec<usize> = clv.distribution.iter().map(|(_, _, n)| *n).collect();
+        assert_eq!(counts, vec![0, 1, 0, 0, 0, 0, 0, 2]);
+        let markets: Vec<&str> = clv.by_market.iter().map(|g| g.market.as_str()).collect();
+        assert_eq!(markets, vec!["未分类", "1x2", "大小球"]);
+        assert_eq!(summary.bets[2].closing.as_ref().unwrap().odds, 2.5);
+    }
+
+    #[test]
+    fn closing_line_recorded_before_settlement_counts() {
+        let mut entries =
+            parse_ledger("deposit,2024-01-01,1000\nbet,1,2024-01-02,2.0,100,standard,,,\n")
+                .unwrap();
+        let close = LedgerCommand::Close {
+            id: 1,
+            odds: 1.8,
+            others: Vec::new(),
+        };
+        let settle = LedgerCommand::Settle {
+            id: 1,
+            settlement: BetSettlement::Loss,
+            returned: None,
+        };
+        for (command, date) in [(close, "2024-01-03"), (settle, "2024-01-04")] {
+            let entry = build_ledger_entry(&entries, &command, date)
+                .unwrap()
+                .unwrap();
+            entries.push(entry);
+        }
+        let summary = summarize_ledger(&entries);
+        assert!(summary.bets[0].settlement.is_some());
+        let clv = summary.clv.unwrap();
+        // 2.0 × (1 / 1.8) - 1 ≈ 11.1%，与结算结果无关
+        assert_eq!(clv.count, 1);
+        assert!((clv.mean_clv - (2.0 / 1.8 - 1.0)).abs() < 1e-9);
+        // 未记录下注时胜率，无法对比下注时 EV
+        assert_eq!(clv.compared, 0);
+        assert!(clv.mean_placement_ev.is_none());
+        assert!(clv.mean_compared_clv.is_none());
+        assert_eq!(clv.by_market[0].market, "未分类");
+    }
+
+    #[test]
+    fn dates_convert_from_unix_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert!(parse_ledger_date("2024-13-01").is_err());
+    }
+
+    #[test]
+    fn recorded_bets_scale_kelly_stakes_only() {
+        let entries = parse_ledger(LEDGER).unwrap();
+        let record = LedgerRecord {
+            path: "ledger.csv".into(),
+            date: "2024-01-07".to_string(),
+            size: RecordSize::Kelly(0.5),
+            market: "1x2".to_string(),
+            label: "模型".to_string(),
+        };
+        let bet = |stake: f64, market: &str, label: &str| SizedBet {
+            odds: 2.0,
+            stake,
+            probability: Some(0.6),
+            market: market.to_string(),
+            label: label.to_string(),
+        };
+        let bets = [
+            bet(160.0, "", ""),
+            bet(0.0, "", ""),
+            bet(400.0, "大小球", "大 2.5"),
+        ];
+        let recorded = plan_recorded_bets(entries.clone(), &record, "value_scan", &bets).unwrap();
+        assert_eq!(recorded.len(), 2);
+        match (&recorded[0], &recorded[1]) {
+            (
+                LedgerEntry::Bet {
+                    id: 4,
+                    stake: first,
+                    market: first_market,
+                    label: first_label,
+                    mode,
+                    ..
+                },
+                LedgerEntry::Bet {
+                    id: 5,
+                    stake: second,
+                    market: second_market,
+                    label: second_label,
+                    ..
+                },
+            ) => {
+                assert!((first - 80.0).abs() < 1e-9);
+                assert!((second - 200.0).abs() < 1e-9);
+                assert_eq!(mode, "value_scan");
+                assert_eq!(
+                    (first_market.as_str(), first_label.as_str()),
+                    ("1x2", "模型")
+                );
+                assert_eq!(
+                    (second_market.as_str(), second_label.as_str()),
+                    ("大小球", "模型 / 大 2.5")
+                );
+            }
+            other => panic!("unexpected entries {other:?}"),
+        }
+
+        // 分配金额不乘凯利倍数；合计超出可用余额（800）时整批拒绝
+        let stake = LedgerRecord {
+            size: RecordSize::Stake,
+            ..record
+        };
+        let recorded =
+            plan_recorded_bets(entries.clone(), &stake, "scan", &[bet(300.0, "", "")]).unwrap();
+        assert!(matches!(recorded[0], LedgerEntry::Bet { stake, .. } if stake == 300.0));
+        let bets = [bet(480.0, "", ""), bet(480.0, "", "")];
+        assert!(plan_recorded_bets(entries, &stake, "scan", &bets).is_err());
+    }
+}
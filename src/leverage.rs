@@ -0,0 +1,199 @@
+//! 杠杆与融资：扣除融资成本的杠杆凯利仓位与强平价检查
+
+use crate::types::{LeverageSettings, LeveragedStockResult, StockInfo, TradeDirection};
+
+/// 强平价：权益 / 持仓市值跌破维持保证金比例 m 时的价格
+///
+/// 做多：P = P0 · (L - 1) / (L · (1 - m))，不借钱（L ≤ 1）时不会强平；
+/// 做空：P = P0 · (1 + 1/L) / (1 + m)。
+pub fn liquidation_price(
+    direction: TradeDirection,
+    entry_price: f64,
+    leverage: f64,
+    maintenance_margin: f64,
+) -> Option<f64> {
+    if leverage <= 0.0 {
+        return None;
+    }
+    match direction {
+        TradeDirection::Long => (leverage > 1.0)
+            .then(|| entry_price * (leverage - 1.0) / (leverage * (1.0 - maintenance_margin))),
+        TradeDirection::Short => {
+            Some(entry_price * (1.0 + 1.0 / leverage) / (1.0 + maintenance_margin))
+        }
+    }
+}
+
+/// 名义仓位 x（占本金）下的持有期融资成本；做空的融券费用已计入交易成本
+pub fn financing_cost(direction: TradeDirection, settings: &LeverageSettings, x: f64) -> f64 {
+    match direction {
+        TradeDirection::Long => settings.period_cost() * (x - 1.0).max(0.0),
+        TradeDirection::Short => 0.0,
+    }
+}
+
+/// 扣除融资成本后最大化期望对数增长的杠杆仓位，并检查止损是否先于强平触发
+pub fn leveraged_stock_kelly(
+    info: &StockInfo,
+    win_rate: f64,
+    settings: &LeverageSettings,
+) -> LeveragedStockResult {
+    let win = info.net_profit / info.entry_price;
+    let loss = info.net_risk / info.entry_price;
+    let lose_rate = 1.0 - win_rate;
+    let growth = |x: f64| {
+        let drag = financing_cost(info.direction, settings, x);
+        let up = 1.0 + x * win - drag;
+        let down = 1.0 - x * loss - drag;
+        if up <= 0.0 || down <= 0.0 {
+            f64::NEG_INFINITY
+        } else {
+            win_rate * up.ln() + lose_rate * down.ln()
+        }
+    };
+
+    let unconstrained_fraction = if win_rate * win - lose_rate * loss <= 0.0 {
+        0.0
+    } else {
+        // 止损情景权益归零处为上界：做多借钱后 1 - x·l - c·(x - 1) = 0
+        let c = match info.direction {
+            TradeDirection::Long => settings.period_cost(),
+            TradeDirection::Short => 0.0,
+        };
+        let upper = if loss >= 1.0 {
+            1.0 / loss
+        } else {
+            (1.0 + c) / (loss + c)
+        };
+        // 目标函数为凹函数，三分法求最大值
+        let mut lo = 0.0;
+        let mut hi = upper * (1.0 - 1e-9);
+        for _ in 0..200 {
+            let m1 = lo + (hi - lo) / 3.0;
+            let m2 = hi - (hi - lo) / 3.0;
+            if growth(m1) < growth(m2) {
+                lo = m1;
+            } else {
+                hi = m2;
+            }
+        }
+        0.5 * (lo + hi)
+    };
+
+    let position_fraction = unconstrained_fraction.min(settings.max_leverage);
+    let liquidation = liquidation_price(
+        info.direction,
+        info.entry_price,
+        position_fraction,
+        settings.maintenance_margin,
+    );
+    let sign = info.direction.sign();
+
+    LeveragedStockResult {
+        settings: *settings,
+        unconstrained_fraction,
+        position_fraction,
+        borrowed_fraction: match info.direction {
+            TradeDirection::Long => (position_fraction - 1.0).max(0.0),
+            TradeDirection::Short => 0.0,
+        },
+        financing_cost: financing_cost(info.direction, settings, position_fraction),
+        expected_log_growth: if position_fraction > 0.0 {
+            growth(position_fraction)
+        } else {
+            0.0
+        },
+        liquidation_price: liquidation,
+        stop_before_liquidation: liquidation.is_none_or(|p| (info.stop_loss - p) * sign > 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{leveraged_stock_kelly, liquidation_price};
+    use crate::kelly::build_stock_info;
+    use crate::types::{LeverageSettings, StockCosts, TradeDirection};
+
+    fn settings(
+        max_leverage: f64,
+        financing_rate: f64,
+        maintenance_margin: f64,
+    ) -> LeverageSettings {
+        LeverageSettings {
+            max_leverage,
+            financing_rate,
+            holding_days: 365.0,
+            maintenance_margin,
+        }
+    }
+
+    #[test]
+    fn without_financing_matches_classic_kelly() {
+        // p=60%，+20% / -10%：最优名义仓位 (0.6·0.2 - 0.4·0.1) / (0.2·0.1) = 4 倍
+        let info = build_stock_info(
+            TradeDirection::Long,
+            100.0,
+            120.0,
+            90.0,
+            &StockCosts::default(),
+        );
+        let result = leveraged_stock_kelly(&info, 0.6, &settings(10.0, 0.0, 0.0));
+        assert!((result.unconstrained_fraction - 4.0).abs() < 1e-6);
+        assert!((result.borrowed_fraction - 3.0).abs() < 1e-6);
+        assert_eq!(result.financing_cost, 0.0);
+    }
+
+    #[test]
+    fn cap_and_financing_reduce_position() {
+        let info = build_stock_info(
+            TradeDirection::Long,
+            100.0,
+            120.0,
+            90.0,
+            &StockCosts::default(),
+        );
+        let capped = leveraged_stock_kelly(&info, 0.6, &settings(2.0, 0.0, 0.25));
+        assert_eq!(capped.position_fraction, 2.0);
+        // 2 倍杠杆、维持保证金 25%：强平价 100 × 1 / (2 × 0.75) = 66.67，止损 90 先触发
+        assert!((capped.liquidation_price.unwrap() - 200.0 / 3.0).abs() < 1e-9);
+        assert!(capped.stop_before_liquidation);
+
+        // 年化 2% 融资一年：0.6 × 0.18 / (1.02 + 0.18x) = 0.4 × 0.12 / (1.02 - 0.12x)，x = 2.8333
+        let financed = leveraged_stock_kelly(&info, 0.6, &settings(10.0, 0.02, 0.0));
+        assert!((financed.unconstrained_fraction - 0.0612 / 0.0216).abs() < 1e-6);
+        assert!(
+            (financed.financing_cost - 0.02 * (financed.position_fraction - 1.0)).abs() < 1e-12
+        );
+    }
+
+    #[test]
+    fn liquidation_before_stop_is_flagged() {
+        let info = build_stock_info(
+            TradeDirection::Long,
+            100.0,
+            120.0,
+            90.0,
+            &StockCosts::default(),
+        );
+        // 4 倍杠杆时权益仅 25%，低于 30% 维持保证金，开仓即强平
+        let result = leveraged_stock_kelly(&info, 0.6, &settings(5.0, 0.0, 0.3));
+        assert!(result.liquidation_price.unwrap() > 100.0);
+        assert!(!result.stop_before_liquidation);
+    }
+
+    #[test]
+    fn liquidation_price_follows_direction() {
+        assert_eq!(
+            liquidation_price(TradeDirection::Long, 100.0, 1.0, 0.25),
+            None
+        );
+        assert!(
+            (liquidation_price(TradeDirection::Long, 100.0, 2.0, 0.0).unwrap() - 50.0).abs()
+                < 1e-12
+        );
+        assert!(
+            (liquidation_price(TradeDirection::Short, 100.0, 2.0, 0.0).unwrap() - 150.0).abs()
+                < 1e-12
+        );
+    }
+}
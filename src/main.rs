@@ -4,15 +4,35 @@
 
 mod app;
 mod arbitrage;
+mod back_lay;
+mod calibration;
 mod cli;
+mod currency;
 mod display;
+mod dutching;
+mod futures;
+mod heat;
+mod hedge;
 mod interactive;
 mod kelly;
+mod ledger;
+mod leverage;
+mod merton;
+mod middle;
 mod nash;
+mod options;
 mod portfolio;
 mod portfolio_input;
+mod rng;
+mod scale_out;
+mod scanner;
+mod sizing;
+mod trade_history;
+mod trailing;
 mod types;
 mod validation;
+mod value_scan;
+mod volatility;
 
 use std::env;
 
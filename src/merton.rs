@@ -0,0 +1,279 @@
+//! 连续收益凯利（Merton 比例）：f* = Σ⁻¹(μ - r)，可选禁止做空与总敞口上限
+
+use crate::portfolio::project_to_simplex;
+use crate::types::{MertonConstraints, MertonResult};
+
+const MAX_ITERATIONS: usize = 100_000;
+const TOLERANCE: f64 = 1e-12;
+const POWER_ITERATIONS: usize = 200;
+const SYMMETRY_TOLERANCE: f64 = 1e-9;
+
+/// Cholesky 分解 Σ = LLᵀ；非正定时返回 None
+fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut lower = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in 0..=i {
+            let dot: f64 = (0..j).map(|k| lower[i][k] * lower[j][k]).sum();
+            if i == j {
+                let diagonal = matrix[i][i] - dot;
+                if diagonal <= 0.0 || !diagonal.is_finite() {
+                    return None;
+                }
+                lower[i][j] = diagonal.sqrt();
+            } else {
+                lower[i][j] = (matrix[i][j] - dot) / lower[j][j];
+            }
+        }
+    }
+    Some(lower)
+}
+
+/// 由 Cholesky 因子求解 LLᵀx = b
+fn cholesky_solve(lower: &[Vec<f64>], rhs: &[f64]) -> Vec<f64> {
+    let n = rhs.len();
+    let mut y = vec![0.0; n];
+    for i in 0..n {
+        let dot: f64 = (0..i).map(|k| lower[i][k] * y[k]).sum();
+        y[i] = (rhs[i] - dot) / lower[i][i];
+    }
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let dot: f64 = (i + 1..n).map(|k| lower[k][i] * x[k]).sum();
+        x[i] = (y[i] - dot) / lower[i][i];
+    }
+    x
+}
+
+fn mat_vec(matrix: &[Vec<f64>], v: &[f64]) -> Vec<f64> {
+    matrix
+        .iter()
+        .map(|row| row.iter().zip(v).map(|(a, b)| a * b).sum())
+        .collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// 检查协方差矩阵为对称正定矩阵
+pub fn validate_covariance(matrix: &[Vec<f64>]) -> Result<(), String> {
+    let n = matrix.len();
+    if matrix.iter().any(|row| row.len() != n) {
+        return Err("协方差矩阵必须为方阵".to_string());
+    }
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, &value) in row.iter().enumerate().take(i) {
+            let mirror = matrix[j][i];
+            if (value - mirror).abs() > SYMMETRY_TOLERANCE * value.abs().max(mirror.abs()).max(1.0)
+            {
+                return Err(format!(
+                    "协方差矩阵必须对称：第 {} 行第 {} 列与第 {} 行第 {} 列不相等",
+                    i + 1,
+                    j + 1,
+                    j + 1,
+                    i + 1
+                ));
+            }
+        }
+    }
+    if cholesky(matrix).is_none() {
+        return Err("协方差矩阵必须为正定矩阵（波动率为正且相关系数矩阵满秩）".to_string());
+    }
+    Ok(())
+}
+
+/// 投影到可行域：禁止做空时为 {f ≥ 0, Σf ≤ L}，允许做空时为 {Σ|f| ≤ L}
+fn project(values: &[f64], constraints: &MertonConstraints) -> Vec<f64> {
+    let cap = constraints.max_leverage.unwrap_or(f64::INFINITY);
+    if constraints.long_only {
+        return project_to_simplex(values, cap);
+    }
+    if constraints.max_leverage.is_none() {
+        return values.to_vec();
+    }
+    let magnitudes: Vec<f64> = values.iter().map(|v| v.abs()).collect();
+    project_to_simplex(&magnitudes, cap)
+        .into_iter()
+        .zip(values)
+        .map(|(m, v)| m.copysign(*v))
+        .collect()
+}
+
+/// 幂迭代估计协方差矩阵最大特征值，用作投影梯度的步长倒数
+fn largest_eigenvalue(matrix: &[Vec<f64>]) -> f64 {
+    let n = matrix.len();
+    let mut v = vec![1.0 / (n as f64).sqrt(); n];
+    let mut lambda = 0.0;
+    for _ in 0..POWER_ITERATIONS {
+        let w = mat_vec(matrix, &v);
+        let norm = dot(&w, &w).sqrt();
+        if norm <= 0.0 {
+            break;
+        }
+        lambda = dot(&v, &w);
+        v = w.into_iter().map(|x| x / norm).collect();
+    }
+    let trace: f64 = (0..n).map(|i| matrix[i][i]).sum();
+    if lambda > 0.0 { lambda } else { trace }
+}
+
+/// 计算连续收益凯利仓位
+///
+/// 最大化 g(f) = r + fᵀ(μ - r) - fᵀΣf / 2。无约束最优解为 Σ⁻¹(μ - r)；
+/// 不满足约束时以投影梯度法求解约束最优。协方差矩阵需先经 `validate_covariance` 检查。
+pub fn calculate_merton(
+    means: &[f64],
+    covariance: &[Vec<f64>],
+    risk_free: f64,
+    constraints: &MertonConstraints,
+) -> MertonResult {
+    let excess: Vec<f64> = means.iter().map(|m| m - risk_free).collect();
+    let unconstrained = match cholesky(covariance) {
+        Some(lower) => cholesky_solve(&lower, &excess),
+        None => vec![0.0; means.len()],
+    };
+
+    let mut allocations = project(&unconstrained, constraints);
+    let constrained = allocations
+        .iter()
+        .zip(&unconstrained)
+        .any(|(a, u)| (a - u).abs() > TOLERANCE);
+    let mut converged = true;
+    let mut iterations = 0;
+    if constrained {
+        converged = false;
+        let step = 1.0 / largest_eigenvalue(covariance);
+        while iterations < MAX_ITERATIONS {
+            iterations += 1;
+            let sigma_f = mat_vec(covariance, &allocations);
+            let candidate: Vec<f64> = allocations
+                .iter()
+                .zip(excess.iter().zip(&sigma_f))
+                .map(|(f, (e, s))| f + step * (e - s))
+                .collect();
+            let next = project(&candidate, constraints);
+            let change = next
+                .iter()
+                .zip(&allocations)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            allocations = next;
+            if change < TOLERANCE {
+                converged = true;
+                break;
+            }
+        }
+    }
+
+    let variance = dot(&allocations, &mat_vec(covariance, &allocations)).max(0.0);
+    let expected_return = risk_free + dot(&allocations, &excess);
+    MertonResult {
+        total_allocation: allocations.iter().sum(),
+        gross_exposure: allocations.iter().map(|f| f.abs()).sum(),
+        expected_return,
+        volatility: variance.sqrt(),
+        expected_log_growth: expected_return - 0.5 * variance,
+        unconstrained,
+        allocations,
+        constrained,
+        converged,
+        iterations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calculate_merton, validate_covariance};
+    use crate::types::MertonConstraints;
+
+    #[test]
+    fn single_asset_matches_merton_fraction() {
+        // μ = 8%，σ = 20%，r = 3%：f* = 0.05 / 0.04 = 1.25
+        let result = calculate_merton(&[0.08], &[vec![0.04]], 0.03, &MertonConstraints::default());
+        assert!((result.allocations[0] - 1.25).abs() < 1e-12);
+        assert!(!result.constrained);
+        // g* = r + (μ - r)² / (2σ²)
+        assert!((result.expected_log_growth - (0.03 + 0.0025 / 0.08)).abs() < 1e-12);
+        assert!((result.volatility - 0.25).abs() < 1e-12);
+
+        let capped = calculate_merton(
+            &[0.08],
+            &[vec![0.04]],
+            0.03,
+            &MertonConstraints {
+                long_only: true,
+                max_leverage: Some(1.0),
+            },
+        );
+        assert!((capped.allocations[0] - 1.0).abs() < 1e-9);
+        assert!(capped.constrained);
+    }
+
+    #[test]
+    fn multi_asset_inverts_covariance() {
+        // 两资产：σ = 20% / 30%，相关系数 0.5
+        let covariance = vec![vec![0.04, 0.03], vec![0.03, 0.09]];
+        let result = calculate_merton(
+            &[0.10, 0.12],
+            &covariance,
+            0.02,
+            &MertonConstraints::default(),
+        );
+        // Σ⁻¹ = [[0.09, -0.03], [-0.03, 0.04]] / 0.0027
+        let expected = [
+            (0.09 * 0.08 - 0.03 * 0.10) / 0.0027,
+            (-0.03 * 0.08 + 0.04 * 0.10) / 0.0027,
+        ];
+        for (a, e) in result.allocations.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{a} vs {e}");
+        }
+    }
+
+    #[test]
+    fn long_only_drops_negative_edge_and_respects_cap() {
+        // 第二个资产期望收益低于无风险利率，无约束时做空
+        let covariance = vec![vec![0.04, 0.0], vec![0.0, 0.04]];
+        let free = calculate_merton(
+            &[0.10, 0.0],
+            &covariance,
+            0.02,
+            &MertonConstraints::default(),
+        );
+        assert!(free.allocations[1] < 0.0);
+
+        let long_only = calculate_merton(
+            &[0.10, 0.0],
+            &covariance,
+            0.02,
+            &MertonConstraints {
+                long_only: true,
+                max_leverage: None,
+            },
+        );
+        assert!((long_only.allocations[0] - 2.0).abs() < 1e-9);
+        assert_eq!(long_only.allocations[1], 0.0);
+
+        // 总敞口上限 2：边际增长相等 0.08 - 0.04a = 0.02 - 0.04b，a + b = 2
+        let capped = calculate_merton(
+            &[0.10, 0.0],
+            &covariance,
+            0.02,
+            &MertonConstraints {
+                long_only: false,
+                max_leverage: Some(2.0),
+            },
+        );
+        assert!(capped.converged);
+        assert!((capped.gross_exposure - 2.0).abs() < 1e-9);
+        assert!((capped.allocations[0] - 1.75).abs() < 1e-9);
+        assert!((capped.allocations[1] + 0.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_invalid_covariance() {
+        assert!(validate_covariance(&[vec![0.04, 0.01], vec![0.02, 0.09]]).is_err());
+        assert!(validate_covariance(&[vec![0.04, 0.06], vec![0.06, 0.09]]).is_err());
+        assert!(validate_covariance(&[vec![0.04, 0.03], vec![0.03, 0.09]]).is_ok());
+    }
+}
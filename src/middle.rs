@@ -0,0 +1,114 @@
+//! 中间盘（让分 / 大小分跨线）：两条不同盘口线之间存在两边同时赢的区间
+
+use crate::kelly::kelly_multi_outcome;
+use crate::types::{MiddleBet, MiddleResult};
+
+/// 检查两条盘口线能否构成中间盘
+///
+/// 只支持半分线（x.5）：整数线或四分之一线在结果恰好等于线时走水或半赢半输，
+/// 这里的三结果模型（区间外 / 中间区）无法表示。线1 < 线2 时两条半分线之间至少有一个整数结果。
+pub fn validate_middle_lines(line1: f64, line2: f64) -> Result<(), String> {
+    for (line, name) in [(line1, "线1"), (line2, "线2")] {
+        if (line - line.floor() - 0.5).abs() > 1e-9 {
+            return Err(format!(
+                "{name} {line} 不是半分线（x.5），整数线与四分之一线存在走水结果，暂不支持"
+            ));
+        }
+    }
+    if line1 == line2 {
+        return Err("两条线相同时没有中间区，请使用套利模式 -a".to_string());
+    }
+    if line1 > line2 {
+        return Err(format!(
+            "线1 必须小于线2（方案1赢于结果 > 线1，方案2赢于结果 < 线2），当前 {line1} > {line2}"
+        ));
+    }
+    Ok(())
+}
+
+/// 计算中间盘投注方案
+///
+/// 两边按套利比例分配（区间外两种结果回报相等），最差结果为区间外的收益，
+/// 落入中间区时两边同时赢。以总投注为 1 单位，按落入概率计算期望与凯利仓位。
+pub fn calculate_middle(bet: &MiddleBet) -> MiddleResult {
+    let total_implied_prob = 1.0 / bet.odds1 + 1.0 / bet.odds2;
+    let stake_ratios = [
+        (1.0 / bet.odds1) / total_implied_prob,
+        (1.0 / bet.odds2) / total_implied_prob,
+    ];
+    let outside_return = 1.0 / total_implied_prob - 1.0;
+    let middle_return = stake_ratios[0] * bet.odds1 + stake_ratios[1] * bet.odds2 - 1.0;
+    let p = bet.middle_prob;
+    let mut kelly = kelly_multi_outcome(&[(p, middle_return), (1.0 - p, outside_return)]);
+    // 区间外不亏损时对数增长无上界，总投注以全部本金为限
+    kelly.optimal_fraction = kelly.optimal_fraction.min(1.0);
+
+    MiddleResult {
+        total_implied_prob,
+        stake_ratios,
+        outside_return,
+        middle_return,
+        guaranteed: outside_return >= 0.0,
+        kelly,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calculate_middle, validate_middle_lines};
+    use crate::types::MiddleBet;
+
+    fn bet(odds1: f64, odds2: f64, middle_prob: f64) -> MiddleBet {
+        MiddleBet {
+            line1: 45.5,
+            odds1,
+            line2: 47.5,
+            odds2,
+            middle_prob,
+        }
+    }
+
+    #[test]
+    fn standard_juice_middle_pays_almost_double() {
+        // 两边 1.95：区间外每单位亏 2.5%，中间区赚 95%
+        let result = calculate_middle(&bet(1.95, 1.95, 0.05));
+        assert!((result.outside_return - (0.975 - 1.0)).abs() < 1e-12);
+        assert!((result.middle_return - 0.95).abs() < 1e-12);
+        assert!(!result.guaranteed);
+        // EV = 0.05 × 0.95 + 0.95 × (-0.025)
+        let ev = 0.05 * 0.95 - 0.95 * 0.025;
+        assert!((result.kelly.expected_value - ev).abs() < 1e-12);
+        // 二元凯利：f = p / a - q / b
+        let f = 0.05 / 0.025 - 0.95 / 0.95;
+        assert!((result.kelly.optimal_fraction - f).abs() < 1e-6);
+    }
+
+    #[test]
+    fn only_ordered_half_point_lines_form_a_middle() {
+        assert!(validate_middle_lines(45.5, 47.5).is_ok());
+        assert!(validate_middle_lines(-7.5, -3.5).is_ok());
+        assert!(validate_middle_lines(3.5, 7.5).is_ok());
+        // 整数线与四分之一线有走水
+        assert!(validate_middle_lines(45.0, 47.5).is_err());
+        assert!(validate_middle_lines(45.5, 47.0).is_err());
+        assert!(validate_middle_lines(45.25, 47.5).is_err());
+        // 相同或颠倒的线没有两边同时赢的结果
+        assert!(validate_middle_lines(45.5, 45.5).is_err());
+        assert!(validate_middle_lines(47.5, 45.5).is_err());
+    }
+
+    #[test]
+    fn low_middle_probability_is_not_worth_it() {
+        let result = calculate_middle(&bet(1.91, 1.91, 0.02));
+        assert!(!result.kelly.positive_ev);
+        assert_eq!(result.kelly.optimal_fraction, 0.0);
+    }
+
+    #[test]
+    fn arbitrage_middle_is_capped_at_full_capital() {
+        let result = calculate_middle(&bet(2.1, 2.05, 0.05));
+        assert!(result.guaranteed);
+        assert!(result.outside_return > 0.0);
+        assert_eq!(result.kelly.optimal_fraction, 1.0);
+    }
+}
@@ -0,0 +1,311 @@
+//! 期权：Black–Scholes 定价、到期收益分布与权利金风险的凯利仓位
+
+use std::f64::consts::SQRT_2;
+
+use crate::kelly::kelly_multi_outcome;
+use crate::types::{
+    OptionKind, OptionMarket, OptionOutcome, OptionQuote, OptionResult, OptionStrategy, PriceView,
+};
+
+/// 对数正态观点离散化的标准正态网格范围与步长
+const GRID_HALF_WIDTH: f64 = 8.0;
+const GRID_STEP: f64 = 0.01;
+
+/// 互补误差函数（Chebyshev 有理逼近，相对误差 < 1.2e-7）
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+/// 标准正态分布累积函数
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / SQRT_2)
+}
+
+/// 欧式期权 Black–Scholes 价格与 Delta（每股）
+pub fn black_scholes(kind: OptionKind, market: &OptionMarket, strike: f64) -> OptionQuote {
+    let t = market.years();
+    let vol_sqrt_t = market.implied_vol * t.sqrt();
+    let discount = (-market.rate * t).exp();
+    let d1 = ((market.spot / strike).ln() + (market.rate + 0.5 * market.implied_vol.powi(2)) * t)
+        / vol_sqrt_t;
+    let d2 = d1 - vol_sqrt_t;
+
+    let (price, delta) = match kind {
+        OptionKind::Call => (
+            market.spot * normal_cdf(d1) - strike * discount * normal_cdf(d2),
+            normal_cdf(d1),
+        ),
+        OptionKind::Put => (
+            strike * discount * normal_cdf(-d2) - market.spot * normal_cdf(-d1),
+            normal_cdf(d1) - 1.0,
+        ),
+    };
+
+    OptionQuote {
+        strike,
+        price,
+        delta,
+    }
+}
+
+/// 到期内在价值
+pub fn intrinsic_value(kind: OptionKind, spot: f64, strike: f64) -> f64 {
+    match kind {
+        OptionKind::Call => (spot - strike).max(0.0),
+        OptionKind::Put => (strike - spot).max(0.0),
+    }
+}
+
+/// 策略到期时的每股收益（不含权利金）
+pub fn strategy_payoff(strategy: &OptionStrategy, spot: f64) -> f64 {
+    let long = intrinsic_value(strategy.kind, spot, strategy.long_strike);
+    match strategy.short_strike {
+        Some(short) => long - intrinsic_value(strategy.kind, spot, short),
+        None => long,
+    }
+}
+
+/// 将主观对数正态观点离散为到期价格分布：(价格, 概率)
+fn lognormal_grid(spot: f64, drift: f64, vol: f64, years: f64) -> Vec<(f64, f64)> {
+    let steps = (2.0 * GRID_HALF_WIDTH / GRID_STEP).round() as usize;
+    let mean = (drift - 0.5 * vol * vol) * years;
+    let scale = vol * years.sqrt();
+
+    let mut grid: Vec<(f64, f64)> = (0..=steps)
+        .map(|i| {
+            let z = -GRID_HALF_WIDTH + i as f64 * GRID_STEP;
+            (spot * (mean + scale * z).exp(), (-0.5 * z * z).exp())
+        })
+        .collect();
+    let total: f64 = grid.iter().map(|(_, w)| w).sum();
+    for (_, w) in grid.iter_mut() {
+        *w /= total;
+    }
+    grid
+}
+
+/// 分段线性盈亏曲线上的盈亏平衡价
+fn find_breakeven(points: &[(f64, f64)]) -> Option<f64> {
+    points.windows(2).find_map(|w| {
+        let (x0, y0) = w[0];
+        let (x1, y1) = w[1];
+        if y0 == 0.0 {
+            Some(x0)
+        } else if y0 * y1 < 0.0 {
+            Some(x0 + (x1 - x0) * y0 / (y0 - y1))
+        } else {
+            None
+        }
+    })
+}
+
+/// 定价期权策略，并按价格观点计算到期收益分布与凯利仓位
+///
+/// 权利金按无风险利率计入持有期资金成本；凯利以每股最大亏损为 1 单位风险，
+/// 建议比例即投入风险（买方为权利金）占本金的比例。
+pub fn price_option_strategy(
+    strategy: &OptionStrategy,
+    market: &OptionMarket,
+    view: &PriceView,
+) -> OptionResult {
+    let long_leg = black_scholes(strategy.kind, market, strategy.long_strike);
+    let short_leg = strategy
+        .short_strike
+        .map(|strike| black_scholes(strategy.kind, market, strike));
+    let net_premium = long_leg.price - short_leg.map_or(0.0, |q| q.price);
+    let cost = net_premium * (market.rate * market.years()).exp();
+    let pnl_at = |spot: f64| strategy_payoff(strategy, spot) - cost;
+
+    // 收益曲线只在行权价处转折，关键点即可确定极值与盈亏平衡价
+    let mut kinks = vec![0.0, strategy.long_strike];
+    kinks.extend(strategy.short_strike);
+    kinks.sort_by(f64::total_cmp);
+    let far = kinks[kinks.len() - 1] * 2.0 + cost.abs() + 1.0;
+    kinks.push(far);
+    let curve: Vec<(f64, f64)> = kinks.iter().map(|&s| (s, pnl_at(s))).collect();
+
+    let max_loss = -curve.iter().map(|(_, p)| *p).fold(f64::INFINITY, f64::min);
+    let max_profit = match (strategy.kind, strategy.short_strike) {
+        (OptionKind::Call, None) => None,
+        _ => Some(
+            curve
+                .iter()
+                .map(|(_, p)| *p)
+                .fold(f64::NEG_INFINITY, f64::max),
+        ),
+    };
+
+    let distribution = match view {
+        PriceView::Lognormal { drift, vol } => {
+            lognormal_grid(market.spot, *drift, *vol, market.years())
+        }
+        PriceView::Scenarios(scenarios) => scenarios.clone(),
+    };
+    let outcomes: Vec<OptionOutcome> = distribution
+        .iter()
+        .map(|&(spot, probability)| OptionOutcome {
+            spot,
+            probability,
+            pnl: pnl_at(spot),
+        })
+        .collect();
+
+    let unit = max_loss.max(f64::EPSILON);
+    let weighted: Vec<(f64, f64)> = outcomes
+        .iter()
+        .map(|o| (o.probability, o.pnl / unit))
+        .collect();
+
+    OptionResult {
+        long_leg,
+        short_leg,
+        net_premium,
+        max_loss,
+        max_profit,
+        breakeven: find_breakeven(&curve),
+        win_prob: outcomes
+            .iter()
+            .filter(|o| o.pnl > 0.0)
+            .map(|o| o.probability)
+            .sum(),
+        expected_pnl: outcomes.iter().map(|o| o.probability * o.pnl).sum(),
+        kelly: kelly_multi_outcome(&weighted),
+        outcomes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{black_scholes, normal_cdf, price_option_strategy};
+    use crate::types::{OptionKind, OptionMarket, OptionStrategy, PriceView};
+
+    fn market(spot: f64, vol: f64, rate: f64, days: f64) -> OptionMarket {
+        OptionMarket {
+            spot,
+            implied_vol: vol,
+            rate,
+            days,
+        }
+    }
+
+    #[test]
+    fn normal_cdf_matches_reference_values() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975_002).abs() < 1e-6);
+        assert!((normal_cdf(-1.0) - 0.158_655).abs() < 1e-6);
+    }
+
+    #[test]
+    fn black_scholes_matches_textbook_and_parity() {
+        // S=K=100，σ=20%，r=5%，T=1 年：看涨 10.4506，看跌 5.5735
+        let m = market(100.0, 0.2, 0.05, 365.0);
+        let call = black_scholes(OptionKind::Call, &m, 100.0);
+        let put = black_scholes(OptionKind::Put, &m, 100.0);
+        assert!((call.price - 10.4506).abs() < 1e-3, "{call:?}");
+        assert!((put.price - 5.5735).abs() < 1e-3, "{put:?}");
+        let parity = call.price - put.price - (100.0 - 100.0 * (-0.05f64).exp());
+        assert!(parity.abs() < 1e-6);
+        assert!((call.delta - put.delta - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn risk_neutral_view_has_no_edge() {
+        let m = market(100.0, 0.25, 0.03, 60.0);
+        let view = PriceView::Lognormal {
+            drift: 0.03,
+            vol: 0.25,
+        };
+        for strategy in [
+            OptionStrategy {
+                kind: OptionKind::Call,
+                long_strike: 105.0,
+                short_strike: None,
+            },
+            OptionStrategy {
+                kind: OptionKind::Put,
+                long_strike: 100.0,
+                short_strike: Some(90.0),
+            },
+        ] {
+            let result = price_option_strategy(&strategy, &m, &view);
+            assert!(
+                result.kelly.expected_value.abs() < 1e-3,
+                "EV={}",
+                result.kelly.expected_value
+            );
+        }
+    }
+
+    #[test]
+    fn debit_spread_risks_the_premium() {
+        let m = market(100.0, 0.25, 0.0, 30.0);
+        let strategy = OptionStrategy {
+            kind: OptionKind::Call,
+            long_strike: 100.0,
+            short_strike: Some(110.0),
+        };
+        // 到期 120 (60%) 拿满价差，90 (40%) 归零
+        let view = PriceView::Scenarios(vec![(120.0, 0.6), (90.0, 0.4)]);
+        let result = price_option_strategy(&strategy, &m, &view);
+        let debit = result.net_premium;
+        assert!(debit > 0.0 && debit < 10.0);
+        assert!((result.max_loss - debit).abs() < 1e-12);
+        assert!((result.max_profit.unwrap() - (10.0 - debit)).abs() < 1e-12);
+        assert!((result.breakeven.unwrap() - (100.0 + debit)).abs() < 1e-9);
+        assert!((result.win_prob - 0.6).abs() < 1e-12);
+
+        // 二元结果：b = (10 - 权利金) / 权利金，f = p - q / b
+        let b = (10.0 - debit) / debit;
+        assert!((result.kelly.optimal_fraction - (0.6 - 0.4 / b)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn credit_spread_risks_width_minus_credit() {
+        let m = market(100.0, 0.3, 0.0, 45.0);
+        // 买入 110 看涨、卖出 100 看涨：收取权利金
+        let strategy = OptionStrategy {
+            kind: OptionKind::Call,
+            long_strike: 110.0,
+            short_strike: Some(100.0),
+        };
+        let view = PriceView::Scenarios(vec![(95.0, 1.0)]);
+        let result = price_option_strategy(&strategy, &m, &view);
+        let credit = -result.net_premium;
+        assert!(credit > 0.0);
+        assert!((result.max_loss - (10.0 - credit)).abs() < 1e-12);
+        assert!((result.max_profit.unwrap() - credit).abs() < 1e-12);
+        assert!((result.breakeven.unwrap() - (100.0 + credit)).abs() < 1e-9);
+        assert!(result.kelly.positive_ev);
+    }
+
+    #[test]
+    fn long_call_profit_is_unbounded() {
+        let m = market(50.0, 0.4, 0.02, 90.0);
+        let strategy = OptionStrategy {
+            kind: OptionKind::Call,
+            long_strike: 55.0,
+            short_strike: None,
+        };
+        let view = PriceView::Lognormal {
+            drift: 0.02,
+            vol: 0.6,
+        };
+        let result = price_option_strategy(&strategy, &m, &view);
+        assert!(result.max_profit.is_none());
+        // 主观波动率高于隐含波动率时买入期权为正期望
+        assert!(result.kelly.positive_ev);
+        assert!(result.kelly.optimal_fraction > 0.0);
+        assert!(result.kelly.optimal_fraction < 1.0);
+    }
+}
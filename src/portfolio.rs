@@ -1,6 +1,6 @@
 //! 组合凯利（独立二项标的 / 相关情景）计算
 
-use crate::types::{PortfolioKellyResult, PortfolioLeg, PortfolioScenario};
+use crate::types::{LeverageSettings, PortfolioKellyResult, PortfolioLeg, PortfolioScenario};
 
 const MAX_TOTAL_ALLOCATION: f64 = 0.999_999;
 const MAX_ITERATIONS: usize = 800;
@@ -61,17 +61,27 @@ fn states_from_scenarios(leg_count: usize, scenarios: &[PortfolioScenario]) -> V
         .collect()
 }
 
-fn objective_and_gradient(allocations: &[f64], states: &[OutcomeState]) -> (f64, Vec<f64>) {
+/// 总仓位超过本金部分的融资成本（financing 为持有期每单位借入资金的成本）
+fn financing_drag(allocations: &[f64], financing: f64) -> f64 {
+    financing * (allocations.iter().sum::<f64>() - 1.0).max(0.0)
+}
+
+fn objective_and_gradient(
+    allocations: &[f64],
+    states: &[OutcomeState],
+    financing: f64,
+) -> (f64, Vec<f64>) {
     let mut objective = 0.0;
     let mut gradient = vec![0.0; allocations.len()];
+    let drag = financing_drag(allocations, financing);
+    let marginal_cost = if allocations.iter().sum::<f64>() > 1.0 {
+        financing
+    } else {
+        0.0
+    };
 
     for state in states {
-        let wealth = 1.0
-            + allocations
-                .iter()
-                .zip(state.returns.iter())
-                .map(|(f, r)| f * r)
-                .sum::<f64>();
+        let wealth = state_wealth(allocations, &state.returns, drag);
 
         if wealth <= 0.0 {
             return (f64::NEG_INFINITY, gradient);
@@ -79,22 +89,23 @@ fn objective_and_gradient(allocations: &[f64], states: &[OutcomeState]) -> (f64,
 
         objective += state.prob * wealth.ln();
         for (i, ret) in state.returns.iter().enumerate() {
-            gradient[i] += state.prob * ret / wealth;
+            gradient[i] += state.prob * (ret - marginal_cost) / wealth;
         }
     }
 
     (objective, gradient)
 }
 
-fn state_wealth(allocations: &[f64], returns: &[f64]) -> f64 {
+fn state_wealth(allocations: &[f64], returns: &[f64], drag: f64) -> f64 {
     1.0 + allocations
         .iter()
         .zip(returns.iter())
         .map(|(f, r)| f * r)
         .sum::<f64>()
+        - drag
 }
 
-fn expected_arithmetic_return(allocations: &[f64], states: &[OutcomeState]) -> f64 {
+fn expected_arithmetic_return(allocations: &[f64], states: &[OutcomeState], drag: f64) -> f64 {
     states
         .iter()
         .map(|s| {
@@ -105,10 +116,42 @@ fn expected_arithmetic_return(allocations: &[f64], states: &[OutcomeState]) -> f
                     .map(|(f, r)| f * r)
                     .sum::<f64>()
         })
-        .sum()
+        .sum::<f64>()
+        - drag
+}
+
+/// 总仓位上限：默认不超过本金，杠杆模式为最大杠杆倍数
+fn allocation_cap(leverage: Option<&LeverageSettings>) -> f64 {
+    match leverage {
+        Some(settings) if settings.max_leverage > 1.0 => settings.max_leverage,
+        Some(settings) => settings.max_leverage.min(MAX_TOTAL_ALLOCATION),
+        None => MAX_TOTAL_ALLOCATION,
+    }
+}
+
+/// 触发追加保证金的情景概率：情景结束后权益 / 持仓市值低于维持保证金比例（或权益归零）
+fn margin_call_probability(
+    allocations: &[f64],
+    states: &[OutcomeState],
+    drag: f64,
+    maintenance_margin: f64,
+) -> f64 {
+    states
+        .iter()
+        .filter(|s| {
+            let equity = state_wealth(allocations, &s.returns, drag);
+            let exposure: f64 = allocations
+                .iter()
+                .zip(&s.returns)
+                .map(|(f, r)| f * (1.0 + r))
+                .sum();
+            equity <= 0.0 || equity < maintenance_margin * exposure
+        })
+        .fold(0.0, |total, s| total + s.prob)
 }
 
-fn project_to_simplex(values: &[f64], cap: f64) -> Vec<f64> {
+/// 投影到 {f ≥ 0, Σf ≤ cap}
+pub fn project_to_simplex(values: &[f64], cap: f64) -> Vec<f64> {
     let mut non_negative: Vec<f64> = values.iter().map(|v| v.max(0.0)).collect();
     let sum: f64 = non_negative.iter().sum();
     if sum <= cap {
@@ -159,12 +202,12 @@ fn single_leg_kelly_fraction(leg: &PortfolioLeg) -> f64 {
     if !f.is_finite() || f <= 0.0 { 0.0 } else { f }
 }
 
-fn initial_allocations_independent(legs: &[PortfolioLeg]) -> Vec<f64> {
+fn initial_allocations_independent(legs: &[PortfolioLeg], cap: f64) -> Vec<f64> {
     let allocations: Vec<f64> = legs.iter().map(single_leg_kelly_fraction).collect();
-    project_to_simplex(&allocations, MAX_TOTAL_ALLOCATION)
+    project_to_simplex(&allocations, cap)
 }
 
-fn initial_allocations_correlated(leg_count: usize, states: &[OutcomeState]) -> Vec<f64> {
+fn initial_allocations_correlated(leg_count: usize, states: &[OutcomeState], cap: f64) -> Vec<f64> {
     let mut edges = vec![0.0; leg_count];
     for state in states {
         for (i, ret) in state.returns.iter().enumerate() {
@@ -173,14 +216,16 @@ fn initial_allocations_correlated(leg_count: usize, states: &[OutcomeState]) ->
     }
 
     let non_negative: Vec<f64> = edges.into_iter().map(|e| e.max(0.0)).collect();
-    project_to_simplex(&non_negative, MAX_TOTAL_ALLOCATION)
+    project_to_simplex(&non_negative, cap)
 }
 
 fn solve_with_states(
     leg_count: usize,
     states: &[OutcomeState],
     mut allocations: Vec<f64>,
+    leverage: Option<&LeverageSettings>,
 ) -> PortfolioKellyResult {
+    let margin = leverage.map(|settings| settings.maintenance_margin);
     if leg_count == 0 || states.is_empty() {
         return PortfolioKellyResult {
             allocations: vec![0.0; leg_count],
@@ -188,18 +233,24 @@ fn solve_with_states(
             expected_log_growth: 0.0,
             expected_arithmetic_return: 0.0,
             worst_case_multiplier: 1.0,
+            borrowed: 0.0,
+            financing_cost: 0.0,
+            margin_call_prob: margin.map(|_| 0.0),
             converged: true,
             iterations: 0,
         };
     }
 
+    let cap = allocation_cap(leverage);
+    let financing = leverage.map_or(0.0, LeverageSettings::period_cost);
+
     let mut step = 0.25;
     let mut iterations = 0usize;
     let mut converged = false;
 
     for _ in 0..MAX_ITERATIONS {
         iterations += 1;
-        let (objective, gradient) = objective_and_gradient(&allocations, states);
+        let (objective, gradient) = objective_and_gradient(&allocations, states, financing);
 
         if !objective.is_finite() {
             break;
@@ -215,8 +266,8 @@ fn solve_with_states(
                 .zip(gradient.iter())
                 .map(|(f, g)| f + local_step * g)
                 .collect();
-            let projected = project_to_simplex(&candidate, MAX_TOTAL_ALLOCATION);
-            let (next_objective, _) = objective_and_gradient(&projected, states);
+            let projected = project_to_simplex(&candidate, cap);
+            let (next_objective, _) = objective_and_gradient(&projected, states, financing);
 
             if next_objective > objective + IMPROVEMENT_EPS {
                 accepted_improvement = next_objective - objective;
@@ -238,15 +289,17 @@ fn solve_with_states(
         }
     }
 
-    let (expected_log_growth, _) = objective_and_gradient(&allocations, states);
+    let (expected_log_growth, _) = objective_and_gradient(&allocations, states, financing);
     let total_allocation: f64 = allocations.iter().sum();
-    let expected_arithmetic_return = expected_arithmetic_return(&allocations, states);
+    let drag = financing_drag(&allocations, financing);
+    let expected_arithmetic_return = expected_arithmetic_return(&allocations, states, drag);
 
     let worst_case_multiplier = states
         .iter()
         .filter(|s| s.prob > STATE_PROB_EPS)
-        .map(|s| state_wealth(&allocations, &s.returns))
+        .map(|s| state_wealth(&allocations, &s.returns, drag))
         .fold(f64::INFINITY, f64::min);
+    let margin_call_prob = margin.map(|m| margin_call_probability(&allocations, states, drag, m));
 
     PortfolioKellyResult {
         allocations,
@@ -258,32 +311,41 @@ fn solve_with_states(
         } else {
             0.0
         },
+        borrowed: (total_allocation - 1.0).max(0.0),
+        financing_cost: drag,
+        margin_call_prob,
         converged,
         iterations,
     }
 }
 
 /// 计算独立二项标的的组合凯利仓位
-pub fn calculate_portfolio_kelly(legs: &[PortfolioLeg]) -> PortfolioKellyResult {
+///
+/// 提供杠杆设置时总仓位上限为最大杠杆倍数，超过本金部分扣除融资成本。
+pub fn calculate_portfolio_kelly(
+    legs: &[PortfolioLeg],
+    leverage: Option<&LeverageSettings>,
+) -> PortfolioKellyResult {
     let states = enumerate_independent_states(legs);
-    let allocations = initial_allocations_independent(legs);
-    solve_with_states(legs.len(), &states, allocations)
+    let allocations = initial_allocations_independent(legs, allocation_cap(leverage));
+    solve_with_states(legs.len(), &states, allocations, leverage)
 }
 
 /// 计算相关情景输入下的组合凯利仓位
 pub fn calculate_portfolio_kelly_correlated(
     leg_count: usize,
     scenarios: &[PortfolioScenario],
+    leverage: Option<&LeverageSettings>,
 ) -> PortfolioKellyResult {
     let states = states_from_scenarios(leg_count, scenarios);
-    let allocations = initial_allocations_correlated(leg_count, &states);
-    solve_with_states(leg_count, &states, allocations)
+    let allocations = initial_allocations_correlated(leg_count, &states, allocation_cap(leverage));
+    solve_with_states(leg_count, &states, allocations, leverage)
 }
 
 #[cfg(test)]
 mod tests {
     use super::{calculate_portfolio_kelly, calculate_portfolio_kelly_correlated};
-    use crate::types::{PortfolioLeg, PortfolioLegSource, PortfolioScenario};
+    use crate::types::{LeverageSettings, PortfolioLeg, PortfolioLegSource, PortfolioScenario};
 
     fn leg(odds: f64, win_rate: f64) -> PortfolioLeg {
         PortfolioLeg {
@@ -298,7 +360,7 @@ mod tests {
     #[test]
     fn symmetric_bets_have_symmetric_allocations() {
         let legs = vec![leg(2.0, 0.6), leg(2.0, 0.6)];
-        let result = calculate_portfolio_kelly(&legs);
+        let result = calculate_portfolio_kelly(&legs, None);
         let diff = (result.allocations[0] - result.allocations[1]).abs();
         assert!(diff < 1e-6);
         assert!(result.allocations[0] > 0.0);
@@ -307,7 +369,7 @@ mod tests {
     #[test]
     fn negative_edge_bet_gets_near_zero_allocation() {
         let legs = vec![leg(2.0, 0.6), leg(2.0, 0.4)];
-        let result = calculate_portfolio_kelly(&legs);
+        let result = calculate_portfolio_kelly(&legs, None);
         assert!(result.allocations[0] > 0.0);
         assert!(result.allocations[1] < 1e-8);
     }
@@ -315,7 +377,7 @@ mod tests {
     #[test]
     fn total_allocation_respects_budget_constraint() {
         let legs = vec![leg(2.0, 0.6), leg(2.5, 0.5), leg(3.0, 0.4)];
-        let result = calculate_portfolio_kelly(&legs);
+        let result = calculate_portfolio_kelly(&legs, None);
         assert!(result.total_allocation < 1.0);
         assert!(result.worst_case_multiplier > 0.0);
     }
@@ -329,7 +391,7 @@ mod tests {
             win_return: 0.2,
             loss_return: -0.1,
         }];
-        let result = calculate_portfolio_kelly(&legs);
+        let result = calculate_portfolio_kelly(&legs, None);
         assert!(result.total_allocation > 0.0);
     }
 
@@ -342,7 +404,7 @@ mod tests {
             win_return: 0.2,
             loss_return: -0.1,
         }];
-        let result = calculate_portfolio_kelly(&legs);
+        let result = calculate_portfolio_kelly(&legs, None);
         assert!(result.total_allocation > 0.95);
         assert!(result.worst_case_multiplier > 0.85);
         assert!(result.worst_case_multiplier <= 1.0);
@@ -357,7 +419,7 @@ mod tests {
             win_return: 0.05,
             loss_return: 0.05,
         }];
-        let result = calculate_portfolio_kelly(&legs);
+        let result = calculate_portfolio_kelly(&legs, None);
         assert!(result.total_allocation > 0.95);
         assert!(result.worst_case_multiplier > 1.04);
     }
@@ -365,7 +427,7 @@ mod tests {
     #[test]
     fn worst_case_ignores_zero_probability_states() {
         let legs = vec![leg(2.0, 1.0), leg(2.0, 1.0)];
-        let result = calculate_portfolio_kelly(&legs);
+        let result = calculate_portfolio_kelly(&legs, None);
         assert!(result.worst_case_multiplier > 1.9);
     }
 
@@ -381,7 +443,7 @@ mod tests {
                 returns: vec![-0.9, -0.9],
             },
         ];
-        let result = calculate_portfolio_kelly_correlated(2, &scenarios);
+        let result = calculate_portfolio_kelly_correlated(2, &scenarios, None);
         assert!(result.total_allocation < 0.5);
         assert!(result.allocations[0] >= 0.0);
         assert!(result.allocations[1] >= 0.0);
@@ -399,7 +461,7 @@ mod tests {
                 returns: vec![-0.1, 0.2],
             },
         ];
-        let result = calculate_portfolio_kelly_correlated(2, &scenarios);
+        let result = calculate_portfolio_kelly_correlated(2, &scenarios, None);
         assert!(result.allocations[0] > 0.2);
         assert!(result.allocations[1] > 0.2);
         let diff = (result.allocations[0] - result.allocations[1]).abs();
@@ -418,7 +480,61 @@ mod tests {
                 returns: vec![-0.9],
             },
         ];
-        let result = calculate_portfolio_kelly_correlated(1, &scenarios);
+        let result = calculate_portfolio_kelly_correlated(1, &scenarios, None);
         assert!(result.total_allocation > 0.95);
     }
+
+    fn stock_leg() -> PortfolioLeg {
+        PortfolioLeg {
+            source: PortfolioLegSource::Stock,
+            summary: "entry=100,target=120,stop=90,win=60%".to_string(),
+            win_prob: 0.6,
+            win_return: 0.2,
+            loss_return: -0.1,
+        }
+    }
+
+    #[test]
+    fn leverage_lifts_total_allocation_cap() {
+        let settings = LeverageSettings {
+            max_leverage: 3.0,
+            financing_rate: 0.0,
+            holding_days: 0.0,
+            maintenance_margin: 0.25,
+        };
+        let result = calculate_portfolio_kelly(&[stock_leg()], Some(&settings));
+        // 单腿无约束凯利为 4 倍，受 3 倍杠杆上限约束
+        assert!((result.total_allocation - 3.0).abs() < 1e-6);
+        assert!((result.borrowed - 2.0).abs() < 1e-6);
+        assert!((result.worst_case_multiplier - 0.7).abs() < 1e-6);
+        // 亏损情景：权益 0.7 / 持仓 2.7 ≈ 26% ≥ 25%，不触发追加保证金
+        assert_eq!(result.margin_call_prob, Some(0.0));
+
+        let tight = LeverageSettings {
+            maintenance_margin: 0.3,
+            ..settings
+        };
+        let result = calculate_portfolio_kelly(&[stock_leg()], Some(&tight));
+        assert!((result.margin_call_prob.unwrap() - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn financing_cost_reduces_leveraged_allocation() {
+        let free = LeverageSettings {
+            max_leverage: 10.0,
+            financing_rate: 0.0,
+            holding_days: 365.0,
+            maintenance_margin: 0.0,
+        };
+        let costly = LeverageSettings {
+            financing_rate: 0.05,
+            ..free
+        };
+        let free_result = calculate_portfolio_kelly(&[stock_leg()], Some(&free));
+        let costly_result = calculate_portfolio_kelly(&[stock_leg()], Some(&costly));
+        assert!((free_result.total_allocation - 4.0).abs() < 1e-4);
+        assert!(costly_result.total_allocation < free_result.total_allocation);
+        assert!(costly_result.total_allocation > 1.0);
+        assert!((costly_result.financing_cost - 0.05 * costly_result.borrowed).abs() < 1e-12);
+    }
 }
@@ -1,8 +1,17 @@
 //! 组合凯利输入转换（各模式 -> 统一组合腿）
 
-use crate::arbitrage::{calculate_arbitrage, calculate_multi_arbitrage};
-use crate::types::{PortfolioLeg, PortfolioLegSource};
-use crate::validation::{parse_market_price, parse_odds, parse_percent, parse_positive};
+use crate::arbitrage::{calculate_arbitrage, calculate_multi_arbitrage, effective_odds_for};
+use crate::kelly::build_stock_info;
+use crate::options::price_option_strategy;
+use crate::types::{
+    ArbitrageOptions, OptionMarket, OptionStrategy, PortfolioLeg, PortfolioLegSource, PriceView,
+    StockCosts, TradeDirection,
+};
+use crate::validation::{
+    expand_per_leg, parse_direction, parse_f64, parse_market_price, parse_non_negative, parse_odds,
+    parse_option_kind, parse_per_leg_percents, parse_percent, parse_positive,
+    validate_stock_prices,
+};
 
 fn pct(v: f64) -> String {
     format!("{:.2}%", v * 100.0)
@@ -34,35 +43,111 @@ pub fn build_polymarket_leg(market_price: f64, your_probability: f64) -> Portfol
 }
 
 pub fn build_stock_leg(
+    direction: TradeDirection,
     entry_price: f64,
     target_price: f64,
     stop_loss: f64,
     win_rate: f64,
+    costs: &StockCosts,
 ) -> Result<PortfolioLeg, String> {
-    if target_price <= entry_price || stop_loss >= entry_price {
-        return Err("参数错误: 止盈价必须大于当前价，止损价必须小于当前价".to_string());
-    }
+    validate_stock_prices(direction, entry_price, target_price, stop_loss)?;
+
+    let info = build_stock_info(direction, entry_price, target_price, stop_loss, costs);
+    let win_return = info.net_profit / entry_price;
+    let loss_return = -info.net_risk / entry_price;
 
-    let win_return = (target_price - entry_price) / entry_price;
-    let loss_return = -(entry_price - stop_loss) / entry_price;
+    let mut summary = format!(
+        "入场 {:.2} / 止盈 {:.2} / 止损 {:.2} / 胜率 {}",
+        entry_price,
+        target_price,
+        stop_loss,
+        pct(win_rate)
+    );
+    if direction == TradeDirection::Short {
+        summary = format!("{} {}", direction.label(), summary);
+        if costs.borrow_rate > 0.0 && costs.holding_days > 0.0 {
+            summary.push_str(&format!(
+                " / 融券 {} x {:.0} 天",
+                pct(costs.borrow_rate),
+                costs.holding_days
+            ));
+        }
+    }
 
     Ok(PortfolioLeg {
         source: PortfolioLegSource::Stock,
-        summary: format!(
-            "入场 {:.2} / 止盈 {:.2} / 止损 {:.2} / 胜率 {}",
-            entry_price,
-            target_price,
-            stop_loss,
-            pct(win_rate)
-        ),
+        summary,
         win_prob: win_rate,
         win_return,
         loss_return,
     })
 }
 
-pub fn build_arbitrage_two_leg(odds1: f64, odds2: f64) -> PortfolioLeg {
-    let result = calculate_arbitrage(odds1, odds2);
+/// 期权腿：按主观波动率得到到期收益分布，再折算为二元腿
+///
+/// 收益率相对最大亏损（买方即权利金）计算；胜/负两侧取条件期望收益，期望值保持不变。
+pub fn build_option_leg(
+    strategy: &OptionStrategy,
+    market: &OptionMarket,
+    view_vol: f64,
+) -> PortfolioLeg {
+    let view = PriceView::Lognormal {
+        drift: market.rate,
+        vol: view_vol,
+    };
+    let result = price_option_strategy(strategy, market, &view);
+    let unit = result.max_loss.max(f64::EPSILON);
+    let conditional = |win: bool| {
+        let (prob, sum) = result
+            .outcomes
+            .iter()
+            .filter(|o| (o.pnl > 0.0) == win)
+            .fold((0.0, 0.0), |(p, s), o| {
+                (p + o.probability, s + o.probability * o.pnl)
+            });
+        if prob > 0.0 { sum / prob / unit } else { 0.0 }
+    };
+
+    let strikes = match strategy.short_strike {
+        Some(short) => format!("{:.2}/{:.2}", strategy.long_strike, short),
+        None => format!("{:.2}", strategy.long_strike),
+    };
+    PortfolioLeg {
+        source: PortfolioLegSource::Option,
+        summary: format!(
+            "{} {} / 现价 {:.2} / IV {} / 主观波动率 {} / {:.0} 天",
+            strategy.kind.label(),
+            strikes,
+            market.spot,
+            pct(market.implied_vol),
+            pct(view_vol),
+            market.days
+        ),
+        win_prob: result.win_prob,
+        win_return: conditional(true),
+        loss_return: conditional(false),
+    }
+}
+
+/// 套利腿摘要中的佣金/手续费说明
+fn arbitrage_costs_summary(options: &ArbitrageOptions) -> String {
+    if !options.has_costs() {
+        return String::new();
+    }
+    let list = |values: &Option<Vec<f64>>| match values {
+        Some(v) => v.iter().map(|&x| pct(x)).collect::<Vec<String>>().join(","),
+        None => pct(0.0),
+    };
+    format!(
+        " / 佣金 {} / 手续费 {}",
+        list(&options.commissions),
+        list(&options.fees)
+    )
+}
+
+pub fn build_arbitrage_two_leg(odds1: f64, odds2: f64, options: &ArbitrageOptions) -> PortfolioLeg {
+    let effective = effective_odds_for(&[odds1, odds2], options);
+    let result = calculate_arbitrage(effective[0], effective[1]);
     let r = if result.has_arbitrage {
         result.arbitrage_profit
     } else {
@@ -72,9 +157,10 @@ pub fn build_arbitrage_two_leg(odds1: f64, odds2: f64) -> PortfolioLeg {
     PortfolioLeg {
         source: PortfolioLegSource::Arbitrage2,
         summary: format!(
-            "双边赔率 {:.3}/{:.3} / {}",
+            "双边赔率 {:.3}/{:.3}{} / {}",
             odds1,
             odds2,
+            arbitrage_costs_summary(options),
             if result.has_arbitrage {
                 format!("套利 {:.2}%", result.arbitrage_profit * 100.0)
             } else {
@@ -87,8 +173,8 @@ pub fn build_arbitrage_two_leg(odds1: f64, odds2: f64) -> PortfolioLeg {
     }
 }
 
-pub fn build_arbitrage_multi_leg(odds: &[f64]) -> PortfolioLeg {
-    let result = calculate_multi_arbitrage(odds);
+pub fn build_arbitrage_multi_leg(odds: &[f64], options: &ArbitrageOptions) -> PortfolioLeg {
+    let result = calculate_multi_arbitrage(&effective_odds_for(odds, options));
     let r = if result.has_arbitrage {
         result.arbitrage_profit
     } else {
@@ -98,11 +184,12 @@ pub fn build_arbitrage_multi_leg(odds: &[f64]) -> PortfolioLeg {
     PortfolioLeg {
         source: PortfolioLegSource::ArbitrageN,
         summary: format!(
-            "多边赔率 {} / {}",
+            "多边赔率 {}{} / {}",
             odds.iter()
                 .map(|o| format!("{:.3}", o))
                 .collect::<Vec<String>>()
                 .join(","),
+            arbitrage_costs_summary(options),
             if result.has_arbitrage {
                 format!("套利 {:.2}%", result.arbitrage_profit * 100.0)
             } else {
@@ -115,12 +202,31 @@ pub fn build_arbitrage_multi_leg(odds: &[f64]) -> PortfolioLeg {
     }
 }
 
+/// 解析套利腿描述尾部的 `佣金%[:手续费%]`
+fn parse_arbitrage_costs(parts: &[&str], legs: usize) -> Result<ArbitrageOptions, String> {
+    let mut options = ArbitrageOptions::default();
+    if let Some(raw) = parts.first() {
+        let mut commissions = parse_per_leg_percents(raw, "佣金率")?;
+        expand_per_leg(&mut commissions, legs, "佣金")?;
+        options.commissions = Some(commissions);
+    }
+    if let Some(raw) = parts.get(1) {
+        let mut fees = parse_per_leg_percents(raw, "手续费率")?;
+        expand_per_leg(&mut fees, legs, "手续费")?;
+        options.fees = Some(fees);
+    }
+    Ok(options)
+}
+
 /// 解析组合腿描述:
 /// - `std:赔率:胜率`
 /// - `pm:市场价格:你的概率`
-/// - `stock:当前价:止盈价:止损价:胜率`
-/// - `arb:赔率1:赔率2`
-/// - `marb:赔率1,赔率2,...`
+/// - `stock:当前价:止盈价:止损价:胜率[:long|short[:融券年费率:持有天数]]`
+/// - `opt:call|put:现价:行权价:隐含波动率:利率:天数:主观波动率[:卖出行权价]`
+/// - `arb:赔率1:赔率2[:佣金%[,...][:手续费%[,...]]]`
+/// - `marb:赔率1,赔率2,...[:佣金%[,...][:手续费%[,...]]]`
+///
+/// 佣金与手续费可给 1 个值（所有标的相同）或逐标的逗号分隔。
 pub fn parse_portfolio_leg_descriptor(token: &str) -> Result<PortfolioLeg, String> {
     let parts: Vec<&str> = token.split(':').collect();
     if parts.is_empty() {
@@ -145,26 +251,72 @@ pub fn parse_portfolio_leg_descriptor(token: &str) -> Result<PortfolioLeg, Strin
             Ok(build_polymarket_leg(market_price, your_prob))
         }
         "stock" | "stk" => {
-            if parts.len() != 5 {
-                return Err("股票标的格式错误，应为 stock:当前价:止盈价:止损价:胜率".to_string());
+            if !matches!(parts.len(), 5 | 6 | 8) {
+                return Err(
+                    "股票标的格式错误，应为 stock:当前价:止盈价:止损价:胜率[:long|short[:融券年费率:持有天数]]"
+                        .to_string(),
+                );
             }
             let entry = parse_positive(parts[1], "当前价")?;
             let target = parse_positive(parts[2], "止盈价")?;
             let stop = parse_positive(parts[3], "止损价")?;
             let win_rate = parse_percent(parts[4], "胜率")?;
-            build_stock_leg(entry, target, stop, win_rate)
+            let direction = match parts.get(5) {
+                Some(v) => parse_direction(v)?,
+                None => TradeDirection::Long,
+            };
+            let mut costs = StockCosts::default();
+            if parts.len() == 8 {
+                costs.borrow_rate = parse_percent(parts[6], "融券年费率")?;
+                costs.holding_days = parse_non_negative(parts[7], "持有天数")?;
+            }
+            build_stock_leg(direction, entry, target, stop, win_rate, &costs)
+        }
+        "opt" | "option" => {
+            if !matches!(parts.len(), 8 | 9) {
+                return Err(
+                    "期权标的格式错误，应为 opt:call|put:现价:行权价:隐含波动率:利率:天数:主观波动率[:卖出行权价]"
+                        .to_string(),
+                );
+            }
+            let strategy = OptionStrategy {
+                kind: parse_option_kind(parts[1])?,
+                long_strike: parse_positive(parts[3], "行权价")?,
+                short_strike: match parts.get(8) {
+                    Some(v) => Some(parse_positive(v, "卖出行权价")?),
+                    None => None,
+                },
+            };
+            if strategy.short_strike == Some(strategy.long_strike) {
+                return Err("卖出行权价不能与买入行权价相同".to_string());
+            }
+            let market = OptionMarket {
+                spot: parse_positive(parts[2], "现价")?,
+                implied_vol: parse_positive(parts[4], "隐含波动率")? / 100.0,
+                rate: parse_f64(parts[5], "无风险利率")? / 100.0,
+                days: parse_positive(parts[6], "到期天数")?,
+            };
+            let view_vol = parse_positive(parts[7], "主观波动率")? / 100.0;
+            Ok(build_option_leg(&strategy, &market, view_vol))
         }
         "arb" => {
-            if parts.len() != 3 {
-                return Err("套利标的格式错误，应为 arb:赔率1:赔率2".to_string());
+            if !matches!(parts.len(), 3..=5) {
+                return Err(
+                    "套利标的格式错误，应为 arb:赔率1:赔率2[:佣金%[,...][:手续费%[,...]]]"
+                        .to_string(),
+                );
             }
             let odds1 = parse_odds(parts[1], "赔率1")?;
             let odds2 = parse_odds(parts[2], "赔率2")?;
-            Ok(build_arbitrage_two_leg(odds1, odds2))
+            let options = parse_arbitrage_costs(&parts[3..], 2)?;
+            Ok(build_arbitrage_two_leg(odds1, odds2, &options))
         }
         "marb" => {
-            if parts.len() != 2 {
-                return Err("多边套利标的格式错误，应为 marb:赔率1,赔率2,...".to_string());
+            if !matches!(parts.len(), 2..=4) {
+                return Err(
+                    "多边套利标的格式错误，应为 marb:赔率1,赔率2,...[:佣金%[,...][:手续费%[,...]]]"
+                        .to_string(),
+                );
             }
             let raw = parts[1];
             let mut odds = Vec::new();
@@ -175,8 +327,80 @@ pub fn parse_portfolio_leg_descriptor(token: &str) -> Result<PortfolioLeg, Strin
             if odds.len() < 2 {
                 return Err("marb 至少需要 2 个赔率".to_string());
             }
-            Ok(build_arbitrage_multi_leg(&odds))
+            let options = parse_arbitrage_costs(&parts[2..], odds.len())?;
+            Ok(build_arbitrage_multi_leg(&odds, &options))
         }
-        _ => Err("不支持的组合标的类型，支持 std/pm/stock/arb/marb".to_string()),
+        _ => Err("不支持的组合标的类型，支持 std/pm/stock/opt/arb/marb".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_portfolio_leg_descriptor;
+    use crate::options::price_option_strategy;
+    use crate::types::{OptionKind, OptionMarket, OptionStrategy, PriceView};
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-10
+    }
+
+    #[test]
+    fn stock_descriptor_defaults_to_long() {
+        let leg = parse_portfolio_leg_descriptor("stock:100:120:90:60").unwrap();
+        assert!(approx(leg.win_return, 0.2));
+        assert!(approx(leg.loss_return, -0.1));
+    }
+
+    #[test]
+    fn stock_descriptor_supports_short_with_borrow_fee() {
+        let leg = parse_portfolio_leg_descriptor("stock:100:80:110:60:short:3.65:10").unwrap();
+        assert!(approx(leg.win_return, 0.199));
+        assert!(approx(leg.loss_return, -0.101));
+        assert!(parse_portfolio_leg_descriptor("stock:100:120:90:60:short").is_err());
+    }
+
+    #[test]
+    fn option_descriptor_keeps_expected_value() {
+        let leg = parse_portfolio_leg_descriptor("opt:call:100:100:25:3:30:40:110").unwrap();
+        let result = price_option_strategy(
+            &OptionStrategy {
+                kind: OptionKind::Call,
+                long_strike: 100.0,
+                short_strike: Some(110.0),
+            },
+            &OptionMarket {
+                spot: 100.0,
+                implied_vol: 0.25,
+                rate: 0.03,
+                days: 30.0,
+            },
+            &PriceView::Lognormal {
+                drift: 0.03,
+                vol: 0.4,
+            },
+        );
+        // 二元折算不改变每单位风险的期望收益
+        let ev = leg.win_prob * leg.win_return + (1.0 - leg.win_prob) * leg.loss_return;
+        assert!(approx(ev, result.kelly.expected_value));
+        assert!(approx(leg.win_prob, result.win_prob));
+        assert!(leg.loss_return >= -1.0 - 1e-12);
+
+        assert!(parse_portfolio_leg_descriptor("opt:call:100:100:25:3:30").is_err());
+        assert!(parse_portfolio_leg_descriptor("opt:straddle:100:100:25:3:30:40").is_err());
+        assert!(parse_portfolio_leg_descriptor("opt:put:100:100:25:3:30:40:100").is_err());
+    }
+
+    #[test]
+    fn arbitrage_descriptor_applies_commission() {
+        let plain = parse_portfolio_leg_descriptor("arb:2.02:2.02").unwrap();
+        assert!(plain.win_return > 0.0);
+        let taxed = parse_portfolio_leg_descriptor("arb:2.02:2.02:0,5").unwrap();
+        assert!(taxed.win_return < 0.0);
+        assert!(approx(taxed.win_return, taxed.loss_return));
+
+        let multi = parse_portfolio_leg_descriptor("marb:2.5,4.0,5.0:2:1").unwrap();
+        let raw = parse_portfolio_leg_descriptor("marb:2.5,4.0,5.0").unwrap();
+        assert!(multi.win_return < raw.win_return);
+        assert!(parse_portfolio_leg_descriptor("marb:2.5,4.0,5.0:2,2").is_err());
     }
 }
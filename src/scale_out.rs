@@ -0,0 +1,206 @@
+//! 分批止盈计划：多目标减仓 + 移动止损的结果分布与凯利仓位
+
+use crate::kelly::{build_stock_info, kelly_multi_outcome, per_share_cost};
+use crate::types::{ScaleOutOutcome, ScaleOutResult, ScaleOutTarget, StockCosts, TradeDirection};
+use crate::validation::{parse_percent, parse_positive};
+
+const MAX_TARGETS: usize = 8;
+const PORTION_SUM_TOLERANCE: f64 = 1e-6;
+
+/// 解析分批计划：`价格:减仓%:到达概率%,价格:减仓%:到达概率%,...`
+pub fn parse_scale_out_plan(input: &str) -> Result<Vec<ScaleOutTarget>, String> {
+    let mut targets = Vec::new();
+    for (i, item) in input.split(',').enumerate() {
+        let parts: Vec<&str> = item.trim().split(':').collect();
+        if parts.len() != 3 {
+            return Err(format!(
+                "分批目标{}格式错误，应为 价格:减仓比例:到达概率，例如 110:50:70",
+                i + 1
+            ));
+        }
+        targets.push(ScaleOutTarget {
+            price: parse_positive(parts[0], &format!("目标{}价格", i + 1))?,
+            portion: parse_percent(parts[1], &format!("目标{}减仓比例", i + 1))?,
+            reach_prob: parse_percent(parts[2], &format!("目标{}到达概率", i + 1))?,
+        });
+    }
+    Ok(targets)
+}
+
+/// 校验分批计划：目标按有利方向递增、减仓比例之和为 100%、到达概率递减
+pub fn validate_scale_out_plan(
+    direction: TradeDirection,
+    entry_price: f64,
+    stop_loss: f64,
+    targets: &[ScaleOutTarget],
+) -> Result<(), String> {
+    if targets.is_empty() || targets.len() > MAX_TARGETS {
+        return Err(format!("分批目标数量必须在 1-{MAX_TARGETS} 之间"));
+    }
+    if (stop_loss - entry_price) * direction.sign() >= 0.0 {
+        return Err(match direction {
+            TradeDirection::Long => "参数错误: 止损价必须小于当前价".to_string(),
+            TradeDirection::Short => "参数错误: 做空时止损价必须大于当前价".to_string(),
+        });
+    }
+
+    let mut prev_price = entry_price;
+    let mut prev_prob = 1.0;
+    for (i, target) in targets.iter().enumerate() {
+        if (target.price - prev_price) * direction.sign() <= 0.0 {
+            return Err(format!(
+                "目标{}价格必须沿{}方向依次远离当前价",
+                i + 1,
+                direction.label()
+            ));
+        }
+        if target.portion <= 0.0 {
+            return Err(format!("目标{}减仓比例必须大于 0", i + 1));
+        }
+        if target.reach_prob > prev_prob {
+            return Err(format!(
+                "目标{}到达概率不能高于前一个目标（越远的目标越难到达）",
+                i + 1
+            ));
+        }
+        prev_price = target.price;
+        prev_prob = target.reach_prob;
+    }
+
+    let portion_sum: f64 = targets.iter().map(|t| t.portion).sum();
+    if (portion_sum - 1.0).abs() > PORTION_SUM_TOLERANCE {
+        return Err(format!(
+            "各目标减仓比例之和必须为 100%，当前为 {:.2}%",
+            portion_sum * 100.0
+        ));
+    }
+    Ok(())
+}
+
+/// 计算分批止盈计划的结果分布与凯利仓位
+///
+/// 到达第 k 个目标后前 k 批已减仓，剩余仓位在止损处出场；
+/// `breakeven_after_first` 为真时，到达第一个目标后止损移至入场价。
+pub fn calculate_scale_out(
+    direction: TradeDirection,
+    entry_price: f64,
+    stop_loss: f64,
+    targets: &[ScaleOutTarget],
+    breakeven_after_first: bool,
+    costs: &StockCosts,
+) -> ScaleOutResult {
+    let final_target = targets.last().map(|t| t.price).unwrap_or(entry_price);
+    let info = build_stock_info(direction, entry_price, final_target, stop_loss, costs);
+
+    let opens_with_sell = direction == TradeDirection::Short;
+    let open_cost = per_share_cost(costs, entry_price, opens_with_sell)
+        + costs.borrow_cost_per_share(direction, entry_price);
+    let exit_pnl = |price: f64| -> f64 {
+        (price - entry_price) * direction.sign() - per_share_cost(costs, price, !opens_with_sell)
+    };
+
+    let mut outcomes = Vec::with_capacity(targets.len() + 1);
+    let mut realized = 0.0;
+    let mut exited = 0.0;
+    for hit in 0..=targets.len() {
+        if hit > 0 {
+            let target = &targets[hit - 1];
+            realized += target.portion * exit_pnl(target.price);
+            exited += target.portion;
+        }
+
+        let probability = match hit {
+            0 => 1.0 - targets[0].reach_prob,
+            h if h == targets.len() => targets[h - 1].reach_prob,
+            h => targets[h - 1].reach_prob - targets[h].reach_prob,
+        };
+        let stop_price = if hit > 0 && breakeven_after_first {
+            entry_price
+        } else {
+            stop_loss
+        };
+        let remaining = (1.0 - exited).max(0.0);
+        let pnl_per_share = realized + remaining * exit_pnl(stop_price) - open_cost;
+
+        outcomes.push(ScaleOutOutcome {
+            targets_hit: hit,
+            probability: probability.max(0.0),
+            pnl_per_share,
+            r_multiple: pnl_per_share / info.net_risk,
+        });
+    }
+
+    let distribution: Vec<(f64, f64)> = outcomes
+        .iter()
+        .map(|o| (o.probability, o.r_multiple))
+        .collect();
+    let kelly = kelly_multi_outcome(&distribution);
+    let expected_pnl_per_share = outcomes
+        .iter()
+        .map(|o| o.probability * o.pnl_per_share)
+        .sum();
+
+    ScaleOutResult {
+        info,
+        outcomes,
+        kelly,
+        expected_pnl_per_share,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calculate_scale_out, parse_scale_out_plan, validate_scale_out_plan};
+    use crate::types::{StockCosts, TradeDirection};
+
+    const EPS: f64 = 1e-9;
+
+    #[test]
+    fn single_target_plan_matches_binary_stock_kelly() {
+        let targets = parse_scale_out_plan("120:100:60").unwrap();
+        let result = calculate_scale_out(
+            TradeDirection::Long,
+            100.0,
+            90.0,
+            &targets,
+            false,
+            &StockCosts::default(),
+        );
+        assert_eq!(result.outcomes.len(), 2);
+        assert!((result.kelly.optimal_fraction - 0.4).abs() < EPS);
+        assert!((result.kelly.expected_value - 0.8).abs() < EPS);
+    }
+
+    #[test]
+    fn breakeven_stop_removes_loss_after_first_target() {
+        let targets = parse_scale_out_plan("110:50:70,120:30:40,130:20:20").unwrap();
+        let result = calculate_scale_out(
+            TradeDirection::Long,
+            100.0,
+            90.0,
+            &targets,
+            true,
+            &StockCosts::default(),
+        );
+        let probs: Vec<f64> = result.outcomes.iter().map(|o| o.probability).collect();
+        assert!((probs.iter().sum::<f64>() - 1.0).abs() < EPS);
+        // 只到 T1：50% 在 110 出场，剩余 50% 保本出场
+        assert!((result.outcomes[1].pnl_per_share - 5.0).abs() < EPS);
+        // 全部到达：0.5*10 + 0.3*20 + 0.2*30
+        assert!((result.outcomes[3].pnl_per_share - 17.0).abs() < EPS);
+        assert!((result.outcomes[0].r_multiple + 1.0).abs() < EPS);
+        assert!(result.kelly.optimal_fraction > 0.0);
+    }
+
+    #[test]
+    fn plan_validation_rejects_bad_portions_and_probabilities() {
+        let long = TradeDirection::Long;
+        let bad_sum = parse_scale_out_plan("110:50:70,120:30:40").unwrap();
+        assert!(validate_scale_out_plan(long, 100.0, 90.0, &bad_sum).is_err());
+        let rising_prob = parse_scale_out_plan("110:50:40,120:50:70").unwrap();
+        assert!(validate_scale_out_plan(long, 100.0, 90.0, &rising_prob).is_err());
+        let short_plan = parse_scale_out_plan("90:50:70,80:50:40").unwrap();
+        assert!(validate_scale_out_plan(TradeDirection::Short, 100.0, 110.0, &short_plan).is_ok());
+        assert!(validate_scale_out_plan(long, 100.0, 90.0, &short_plan).is_err());
+    }
+}
@@ -0,0 +1,496 @@
+//! 套利扫描：读取本地赔率快照，按盘口取各结果的最优赔率并检测套利与低抽水盘口
+
+use std::fs;
+
+use crate::arbitrage::calculate_multi_arbitrage;
+use crate::types::{ArbitrageScan, OddsQuote, ScanLeg, ScannedMarket};
+use crate::validation::{parse_market_price, parse_odds};
+
+const QUOTE_FIELDS: [&str; 5] = ["event", "market", "outcome", "bookmaker", "odds"];
+
+/// 一条记录：位置说明（用于错误提示）与按字段顺序排列的值
+pub type Record = (String, Vec<String>);
+
+/// 检查除最后一个数值字段外的文本字段非空
+fn check_text_fields(record: &Record, fields: &[&str]) -> Result<(), String> {
+    let (place, values) = record;
+    for (name, value) in fields.iter().zip(&values[..fields.len() - 1]) {
+        if value.is_empty() {
+            return Err(format!("{place}: {name} 不能为空"));
+        }
+    }
+    Ok(())
+}
+
+/// 解析 CSV 记录：每行按 `fields` 顺序排列，最后一个字段为数值
+///
+/// 空行与 `#` 开头的注释行忽略，首行数值字段不是数字时视为表头。
+fn parse_csv_records(content: &str, fields: &[&str]) -> Result<Vec<Record>, String> {
+    let mut records = Vec::new();
+    let mut first = true;
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let values: Vec<&str> = line.split([',', '\t', ';']).map(str::trim).collect();
+        if first {
+            first = false;
+            if values
+                .get(fields.len() - 1)
+                .is_some_and(|f| f.parse::<f64>().is_err())
+            {
+                continue;
+            }
+        }
+        if values.len() != fields.len() {
+            return Err(format!(
+                "第 {} 行格式错误，应为 {}",
+                i + 1,
+                fields.join(",")
+            ));
+        }
+        let record = (
+            format!("第 {} 行", i + 1),
+            values.iter().map(|v| v.to_string()).collect(),
+        );
+        check_text_fields(&record, fields)?;
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// 快照用的 JSON 读取器：对象数组，所需字段的值为字符串或数字，其余字段的任意值跳过
+struct JsonReader {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonReader {
+    fn new(content: &str) -> Self {
+        Self {
+            chars: content.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!(
+                "JSON 格式错误：位置 {} 应为 '{expected}'，实际为 '{c}'",
+                self.pos
+            )),
+            None => Err(format!("JSON 格式错误：缺少 '{expected}'")),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let c = *self
+                .chars
+                .get(self.pos)
+                .ok_or("JSON 格式错误：字符串未结束")?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = *self
+                        .chars
+                        .get(self.pos)
+                        .ok_or("JSON 格式错误：字符串未结束")?;
+                    self.pos += 1;
+                    match escaped {
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        'r' => value.push('\r'),
+                        'b' => value.push('\u{8}'),
+                        'f' => value.push('\u{c}'),
+                        'u' => value.push(self.read_unicode_escape()?),
+                        other => value.push(other),
+                    }
+                }
+                _ => value.push(c),
+            }
+        }
+    }
+
+    fn read_hex4(&mut self) -> Result<u32, String> {
+        let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+        let code = (hex.len() == 4)
+            .then(|| u32::from_str_radix(&hex, 16).ok())
+            .flatten()
+            .ok_or("JSON 格式错误：无效的 \\u 转义")?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    /// 读取 `\u` 之后的码点，高位代理后须紧跟 `\uDC00`-`\uDFFF` 低位代理
+    fn read_unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.read_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            let low = match self.chars.get(self.pos..self.pos + 2) {
+                Some(['\\', 'u']) => {
+                    self.pos += 2;
+                    self.read_hex4()?
+                }
+                _ => return Err("JSON 格式错误：\\u 高位代理后缺少低位代理".to_string()),
+            };
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err("JSON 格式错误：无效的 \\u 低位代理".to_string());
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| "JSON 格式错误：无效的 \\u 转义".to_string())
+    }
+
+    /// 跳过不需要的字段值：字符串、数字、true/false/null、对象或数组
+    fn skip_value(&mut self) -> Result<(), String> {
+        match self.peek() {
+            Some('"') => self.read_string().map(|_| ()),
+            Some(open @ ('{' | '[')) => {
+                let close = if open == '{' { '}' } else { ']' };
+                self.pos += 1;
+                if self.peek() == Some(close) {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                loop {
+                    if open == '{' {
+                        self.read_string()?;
+                        self.expect(':')?;
+                    }
+                    self.skip_value()?;
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        _ => return self.expect(close),
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.'))
+                {
+                    self.pos += 1;
+                }
+                let literal: String = self.chars[start..self.pos].iter().collect();
+                match literal.as_str() {
+                    "true" | "false" | "null" => Ok(()),
+                    _ if literal.parse::<f64>().is_ok() => Ok(()),
+                    _ => Err(format!("JSON 格式错误：位置 {start} 的值无效")),
+                }
+            }
+        }
+    }
+
+    /// 读取字符串或数字值，均以文本返回
+    fn read_scalar(&mut self) -> Result<String, String> {
+        if self.peek() == Some('"') {
+            return self.read_string();
+        }
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(format!(
+                "JSON 格式错误：位置 {} 的值必须为字符串或数字",
+                self.pos
+            ));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// 读取对象中 `wanted` 字段的值，其他字段跳过
+    fn read_object(&mut self, wanted: &[&str]) -> Result<Vec<(String, String)>, String> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(fields);
+        }
+        loop {
+            let key = self.read_string()?;
+            self.expect(':')?;
+            if wanted.contains(&key.as_str()) {
+                fields.push((key, self.read_scalar()?));
+            } else {
+                self.skip_value()?;
+            }
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                _ => {
+                    self.expect('}')?;
+                    return Ok(fields);
+                }
+            }
+        }
+    }
+}
+
+/// 解析 JSON 记录：对象数组，按 `fields` 取值
+fn parse_json_records(content: &str, fields: &[&str]) -> Result<Vec<Record>, String> {
+    let mut reader = JsonReader::new(content);
+    let mut records = Vec::new();
+    reader.expect('[')?;
+    if reader.peek() == Some(']') {
+        return Ok(records);
+    }
+    loop {
+        let place = format!("第 {} 条记录", records.len() + 1);
+        let object = reader.read_object(fields)?;
+        let values = fields
+            .iter()
+            .map(|name| {
+                object
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.trim().to_string())
+                    .ok_or_else(|| format!("{place}缺少字段 {name}"))
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+        let record = (place, values);
+        check_text_fields(&record, fields)?;
+        records.push(record);
+        match reader.peek() {
+            Some(',') => reader.pos += 1,
+            _ => {
+                reader.expect(']')?;
+                return Ok(records);
+            }
+        }
+    }
+}
+
+/// 解析 CSV 或 JSON 记录，以 `[` 开头时按 JSON 解析
+pub fn parse_records(content: &str, fields: &[&str]) -> Result<Vec<Record>, String> {
+    if content.trim_start().starts_with('[') {
+        parse_json_records(content, fields)
+    } else {
+        parse_csv_records(content, fields)
+    }
+}
+
+/// 解析赔率快照：字段为 事件、盘口、结果、庄家、赔率
+///
+/// `polymarket` 时最后一列为市场价格（%），按 1 / 价格换算为小数赔率。
+pub fn parse_odds_snapshot(content: &str, polymarket: bool) -> Result<Vec<OddsQuote>, String> {
+    let quotes = parse_records(content, &QUOTE_FIELDS)?
+        .into_iter()
+        .map(|(place, values)| {
+            let with_place = |e: String| format!("{place}: {e}");
+            Ok(OddsQuote {
+                odds: if polymarket {
+                    1.0 / parse_market_price(&values[4]).map_err(with_place)?
+                } else {
+                    parse_odds(&values[4], "赔率").map_err(with_place)?
+                },
+                event: values[0].clone(),
+                market: values[1].clone(),
+                outcome: values[2].clone(),
+                bookmaker: values[3].clone(),
+            })
+        })
+        .collect::<Result<Vec<OddsQuote>, String>>()?;
+    if quotes.is_empty() {
+        return Err("赔率快照中没有报价".to_string());
+    }
+    Ok(quotes)
+}
+
+/// 读取赔率快照文件
+pub fn load_odds_snapshot(path: &str, polymarket: bool) -> Result<Vec<OddsQuote>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("无法读取赔率快照 '{path}': {e}"))?;
+    parse_odds_snapshot(&content, polymarket)
+}
+
+/// 扫描快照中的所有盘口
+///
+/// 按（事件, 盘口）分组，每个结果取各庄家中的最高赔率，视为该盘口的完整结果集
+/// 计算套利。保留套利盘口与抽水率不超过 `max_margin` 的盘口，按收益率从高到低排序。
+pub fn scan_arbitrage(quotes: &[OddsQuote], max_margin: f64) -> ArbitrageScan {
+    let mut groups: Vec<(&str, &str, Vec<ScanLeg>)> = Vec::new();
+    for quote in quotes {
+        let index = match groups
+            .iter()
+            .position(|(e, m, _)| *e == quote.event && *m == quote.market)
+        {
+            Some(i) => i,
+            None => {
+                groups.push((&quote.event, &quote.market, Vec::new()));
+                groups.len() - 1
+            }
+        };
+        let legs = &mut groups[index].2;
+        match legs.iter_mut().find(|leg| leg.outcome == quote.outcome) {
+            Some(leg) if quote.odds > leg.odds => {
+                leg.odds = quote.odds;
+                leg.bookmaker = quote.bookmaker.clone();
+            }
+            Some(_) => {}
+            None => legs.push(ScanLeg {
+                outcome: quote.outcome.clone(),
+                bookmaker: quote.bookmaker.clone(),
+                odds: quote.odds,
+                stake_ratio: 0.0,
+            }),
+        }
+    }
+
+    let market_count = groups.len();
+    let mut skipped_markets = 0;
+    let mut markets = Vec::new();
+    for (event, market, mut legs) in groups {
+        if legs.len() < 2 {
+            skipped_markets += 1;
+            continue;
+        }
+        let odds: Vec<f64> = legs.iter().map(|leg| leg.odds).collect();
+        let result = calculate_multi_arbitrage(&odds);
+        if !result.has_arbitrage && result.juice_rate > max_margin {
+            continue;
+        }
+        // 低抽水盘口同样按隐含概率分配，使各结果回报相等
+        for leg in legs.iter_mut() {
+            leg.stake_ratio = (1.0 / leg.odds) / result.total_implied_prob;
+        }
+        markets.push(ScannedMarket {
+            event: event.to_string(),
+            market: market.to_string(),
+            legs,
+            profit: 1.0 / result.total_implied_prob - 1.0,
+            result,
+        });
+    }
+    markets.sort_by(|a, b| b.profit.total_cmp(&a.profit));
+
+    ArbitrageScan {
+        quote_count: quotes.len(),
+        market_count,
+        skipped_markets,
+        max_margin,
+        markets,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_odds_snapshot, scan_arbitrage};
+
+    const CSV: &str = "event,market,outcome,bookmaker,odds
+# 两个庄家的报价
+A vs B,1x2,A,BookX,2.60
+A vs B,1x2,A,BookY,2.75
+A vs B,1x2,Draw,BookX,3.70
+A vs B,1x2,B,BookY,3.40
+C vs D,ou2.5,Over,BookX,1.95
+C vs D,ou2.5,Under,BookX,1.90
+C vs D,ou2.5,Under,BookY,2.00
+E vs F,winner,E,BookX,1.50
+";
+
+    #[test]
+    fn csv_and_json_snapshots_parse_to_same_quotes() {
+        let csv = parse_odds_snapshot(CSV, false).unwrap();
+        assert_eq!(csv.len(), 8);
+        assert_eq!(csv[1].bookmaker, "BookY");
+
+        let json = parse_odds_snapshot(
+            r#"[{"event":"A vs B","market":"1x2","outcome":"A","bookmaker":"BookX","odds":2.6},
+                {"event":"A vs B","market":"1x2","outcome":"B","bookmaker":"Book\"Y\"","odds":"3.4"}]"#,
+            false,
+        )
+        .unwrap();
+        assert_eq!(json.len(), 2);
+        assert_eq!(json[0].odds, 2.6);
+        assert_eq!(json[1].bookmaker, "Book\"Y\"");
+
+        assert!(parse_odds_snapshot("A,B,C,D,1.0", false).is_err());
+        assert!(
+            parse_odds_snapshot(
+                r#"[{"event":"A","market":"B","outcome":"C","odds":2}]"#,
+                false
+            )
+            .is_err()
+        );
+        assert!(parse_odds_snapshot("", false).is_err());
+    }
+
+    #[test]
+    fn json_snapshots_skip_unused_fields_and_decode_escapes() {
+        let json = parse_odds_snapshot(
+            r#"[{"event":"A\u00e9 \ud83d\ude00","market":"1x2","outcome":"A\bB\fC","bookmaker":"X\/Y",
+                 "odds":2.2,"extra":{"x":[1,{"y":null}],"z":"}"},"live":true,"limit":null,"tags":[]}]"#,
+            false,
+        )
+        .unwrap();
+        assert_eq!(json[0].event, "Aé 😀");
+        assert_eq!(json[0].outcome, "A\u{8}B\u{c}C");
+        assert_eq!(json[0].bookmaker, "X/Y");
+        assert_eq!(json[0].odds, 2.2);
+
+        // 所需字段仍必须为字符串或数字
+        let with_odds = |odds: &str| {
+            format!(r#"[{{"event":"A","market":"B","outcome":"C","bookmaker":"D","odds":{odds}}}]"#)
+        };
+        assert!(parse_odds_snapshot(&with_odds("null"), false).is_err());
+        assert!(parse_odds_snapshot(&with_odds("[2.2]"), false).is_err());
+        // 孤立或无效的代理对
+        assert!(parse_odds_snapshot(&with_odds(r#""\ud83d""#), false).is_err());
+        assert!(parse_odds_snapshot(&with_odds(r#""\ud83d\u0041""#), false).is_err());
+        assert!(parse_odds_snapshot(r#"[{"event":"A","x":tru}]"#, false).is_err());
+    }
+
+    #[test]
+    fn scan_uses_best_price_and_ranks_by_profit() {
+        let quotes = parse_odds_snapshot(CSV, false).unwrap();
+        let scan = scan_arbitrage(&quotes, 0.03);
+        assert_eq!(scan.market_count, 3);
+        assert_eq!(scan.skipped_markets, 1);
+        assert_eq!(scan.markets.len(), 2);
+
+        // 1/2.75 + 1/3.7 + 1/3.4 < 1：套利，排在第一
+        let first = &scan.markets[0];
+        assert_eq!(first.market, "1x2");
+        assert!(first.result.has_arbitrage);
+        assert_eq!(first.legs[0].bookmaker, "BookY");
+        assert_eq!(first.legs[0].odds, 2.75);
+        let ratio_sum: f64 = first.legs.iter().map(|leg| leg.stake_ratio).sum();
+        assert!((ratio_sum - 1.0).abs() < 1e-12);
+
+        // 1.95 / 2.00：抽水约 1.3%，低抽水盘口
+        let second = &scan.markets[1];
+        assert!(!second.result.has_arbitrage);
+        assert!(second.profit < 0.0);
+
+        assert_eq!(scan_arbitrage(&quotes, 0.01).markets.len(), 1);
+    }
+}
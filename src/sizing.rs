@@ -0,0 +1,246 @@
+//! 凯利之外的仓位算法：固定比例风险、固定比率、波动率目标、最优 f
+
+use crate::kelly::{kelly_multi_outcome, size_stock_position, stock_position_at};
+use crate::types::{
+    OptimalFResult, PositionSizer, SizerResult, StockCosts, StockInfo, StockPositionSize,
+};
+
+/// 解析历史交易结果列表（R 倍数，逗号分隔）
+pub fn parse_trade_results(input: &str) -> Result<Vec<f64>, String> {
+    let mut trades = Vec::new();
+    for part in input.split(',') {
+        let part = part.trim();
+        let value: f64 = part
+            .parse()
+            .map_err(|_| format!("交易结果 '{part}' 不是有效数字"))?;
+        if !value.is_finite() {
+            return Err(format!("交易结果 '{part}' 不是有效数字"));
+        }
+        trades.push(value);
+    }
+    if trades.len() < 2 {
+        return Err("最优 f 至少需要 2 笔历史交易".to_string());
+    }
+    if !trades.iter().any(|&r| r < 0.0) {
+        return Err("最优 f 需要至少一笔亏损交易".to_string());
+    }
+    Ok(trades)
+}
+
+/// Vince 最优 f：max TWR(f) = Π (1 + f · r_i / |最大亏损|)
+///
+/// 等价于以 r_i / |最大亏损| 为收益、等概率的多结果凯利。
+pub fn optimal_f(trades: &[f64]) -> OptimalFResult {
+    let worst_loss = trades.iter().copied().fold(0.0_f64, f64::min);
+    if trades.is_empty() || worst_loss >= 0.0 {
+        return OptimalFResult {
+            fraction: 0.0,
+            worst_loss,
+            twr: 1.0,
+            geometric_mean: 1.0,
+        };
+    }
+
+    let n = trades.len() as f64;
+    let outcomes: Vec<(f64, f64)> = trades.iter().map(|r| (1.0 / n, r / -worst_loss)).collect();
+    let fraction = kelly_multi_outcome(&outcomes).optimal_fraction.max(0.0);
+    let twr: f64 = trades
+        .iter()
+        .map(|r| 1.0 + fraction * r / -worst_loss)
+        .product();
+
+    OptimalFResult {
+        fraction,
+        worst_loss,
+        twr,
+        geometric_mean: twr.powf(1.0 / n),
+    }
+}
+
+/// 固定比率（Ryan Jones）单位数：N = ⌊(1 + √(1 + 8P/Δ)) / 2⌋，至少 1 个单位
+pub fn fixed_ratio_units(delta: f64, accumulated_profit: f64) -> u64 {
+    if delta <= 0.0 || accumulated_profit <= 0.0 {
+        return 1;
+    }
+    let units = ((1.0 + (1.0 + 8.0 * accumulated_profit / delta).sqrt()) / 2.0).floor();
+    // 容忍浮点误差：恰好达到门槛时应进入下一单位
+    let next = units + 1.0;
+    if delta * next * (next - 1.0) / 2.0 <= accumulated_profit * (1.0 + 1e-9) {
+        next as u64
+    } else {
+        units.max(1.0) as u64
+    }
+}
+
+/// 按目标名义金额取整手股数
+pub fn position_for_notional(
+    info: &StockInfo,
+    costs: &StockCosts,
+    notional: f64,
+) -> StockPositionSize {
+    let lot = costs.lot_size.max(1);
+    let raw_shares = (notional * (1.0 + 1e-9) / info.entry_price).floor();
+    let lots = if raw_shares.is_finite() && raw_shares > 0.0 {
+        (raw_shares as u64) / lot
+    } else {
+        0
+    };
+    stock_position_at(info, costs, lots * lot)
+}
+
+/// 计算单个仓位算法的建议仓位
+pub fn apply_position_sizer(
+    sizer: &PositionSizer,
+    info: &StockInfo,
+    costs: &StockCosts,
+    capital: f64,
+) -> SizerResult {
+    let lot = costs.lot_size.max(1);
+    let (units, optimal, budget, position) = match sizer {
+        PositionSizer::FixedFractional { risk_fraction } => {
+            let budget = capital * risk_fraction;
+            (None, None, budget, size_stock_position(info, costs, budget))
+        }
+        PositionSizer::FixedRatio {
+            delta,
+            accumulated_profit,
+        } => {
+            let units = fixed_ratio_units(*delta, *accumulated_profit);
+            let shares = units * lot;
+            let notional = shares as f64 * info.entry_price;
+            (
+                Some(units),
+                None,
+                notional,
+                stock_position_at(info, costs, shares),
+            )
+        }
+        PositionSizer::VolatilityTarget {
+            target_vol,
+            asset_vol,
+        } => {
+            let notional = capital * target_vol / asset_vol;
+            (
+                None,
+                None,
+                notional,
+                position_for_notional(info, costs, notional),
+            )
+        }
+        PositionSizer::OptimalF { trades } => {
+            let result = optimal_f(trades);
+            // f$ = |最大亏损| / f，每 1R 风险对应本金 × f / |最大亏损|
+            let budget = if result.worst_loss < 0.0 {
+                capital * result.fraction / -result.worst_loss
+            } else {
+                0.0
+            };
+            (
+                None,
+                Some(result),
+                budget,
+                size_stock_position(info, costs, budget),
+            )
+        }
+    };
+
+    SizerResult {
+        sizer: sizer.clone(),
+        units,
+        optimal_f: optimal,
+        budget,
+        position,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_position_sizer, fixed_ratio_units, optimal_f, parse_trade_results};
+    use crate::kelly::build_stock_info;
+    use crate::types::{PositionSizer, StockCosts, TradeDirection};
+
+    #[test]
+    fn optimal_f_matches_vince_example() {
+        // Vince 经典样例：9, 18, 7, 1, 10, -5, -3, -17, -7 → f ≈ 0.24，TWR ≈ 1.0957
+        let trades = [9.0, 18.0, 7.0, 1.0, 10.0, -5.0, -3.0, -17.0, -7.0];
+        let result = optimal_f(&trades);
+        assert!((result.fraction - 0.24).abs() < 0.01, "{result:?}");
+        assert_eq!(result.worst_loss, -17.0);
+        assert!((result.twr - 1.0957).abs() < 0.0001, "{result:?}");
+    }
+
+    #[test]
+    fn optimal_f_is_zero_for_negative_expectancy() {
+        let result = optimal_f(&[1.0, -1.0, -1.0]);
+        assert_eq!(result.fraction, 0.0);
+        assert_eq!(result.twr, 1.0);
+    }
+
+    #[test]
+    fn fixed_ratio_adds_units_at_jones_thresholds() {
+        // Δ = 5000：2 单位需 5000，3 单位需 15000，4 单位需 30000
+        assert_eq!(fixed_ratio_units(5000.0, 0.0), 1);
+        assert_eq!(fixed_ratio_units(5000.0, 4999.0), 1);
+        assert_eq!(fixed_ratio_units(5000.0, 5000.0), 2);
+        assert_eq!(fixed_ratio_units(5000.0, 15000.0), 3);
+        assert_eq!(fixed_ratio_units(5000.0, 29999.0), 3);
+        assert_eq!(fixed_ratio_units(5000.0, 30000.0), 4);
+        assert_eq!(fixed_ratio_units(5000.0, -2000.0), 1);
+    }
+
+    #[test]
+    fn sizers_round_to_whole_lots() {
+        let costs = StockCosts {
+            lot_size: 100,
+            ..StockCosts::default()
+        };
+        let info = build_stock_info(TradeDirection::Long, 100.0, 120.0, 90.0, &costs);
+
+        // 风险 1%：100000 × 1% / 10 = 100 股
+        let fixed = apply_position_sizer(
+            &PositionSizer::FixedFractional {
+                risk_fraction: 0.01,
+            },
+            &info,
+            &costs,
+            100000.0,
+        );
+        assert_eq!(fixed.position.shares, 100);
+        assert!((fixed.position.risk_at_stop - 1000.0).abs() < 1e-9);
+
+        // 目标波动率 10% / 标的 30% → 名义 33333，取整 300 股
+        let vol = apply_position_sizer(
+            &PositionSizer::VolatilityTarget {
+                target_vol: 0.10,
+                asset_vol: 0.30,
+            },
+            &info,
+            &costs,
+            100000.0,
+        );
+        assert_eq!(vol.position.shares, 300);
+
+        let ratio = apply_position_sizer(
+            &PositionSizer::FixedRatio {
+                delta: 5000.0,
+                accumulated_profit: 15000.0,
+            },
+            &info,
+            &costs,
+            100000.0,
+        );
+        assert_eq!(ratio.units, Some(3));
+        assert_eq!(ratio.position.shares, 300);
+    }
+
+    #[test]
+    fn trade_results_require_a_loss() {
+        assert!(parse_trade_results("1,2,3").is_err());
+        assert!(parse_trade_results("2").is_err());
+        assert!(parse_trade_results("2,x,-1").is_err());
+        assert_eq!(
+            parse_trade_results("2, -1, 1.5").unwrap(),
+            vec![2.0, -1.0, 1.5]
+        );
+    }
+}
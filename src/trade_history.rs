@@ -0,0 +1,281 @@
+//! 交易历史：从历史盈亏 / R 倍数估计最优 f、经验凯利与 bootstrap 置信区间
+
+use std::fs;
+
+use crate::rng::SplitMix64;
+use crate::sizing::optimal_f;
+use crate::types::{BootstrapInterval, TradeHistoryResult, TradeHistorySummary};
+
+fn split_fields(line: &str) -> Vec<&str> {
+    line.split([',', '\t', ';']).map(str::trim).collect()
+}
+
+/// 解析交易历史 CSV 内容
+///
+/// 每行一笔交易，空行与 `#` 开头的注释行忽略；首行所选列无法解析为数字时视为表头。
+/// `column` 为列号（从 1 开始）或表头列名，省略时取最后一列。
+pub fn parse_trade_history(content: &str, column: Option<&str>) -> Result<Vec<f64>, String> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    let Some(&(_, first)) = lines.peek() else {
+        return Err("交易历史文件为空".to_string());
+    };
+    let first_fields = split_fields(first);
+
+    let index = match column {
+        None => None,
+        Some(col) => match col.parse::<usize>() {
+            Ok(0) => return Err("列号从 1 开始".to_string()),
+            Ok(n) => Some(n - 1),
+            Err(_) => Some(
+                first_fields
+                    .iter()
+                    .position(|f| f.eq_ignore_ascii_case(col))
+                    .ok_or_else(|| format!("表头中找不到列 '{col}'"))?,
+            ),
+        },
+    };
+    let first_value = match index {
+        Some(i) => first_fields.get(i),
+        None => first_fields.last(),
+    };
+    let has_header = first_value.is_none_or(|f| f.parse::<f64>().is_err());
+    if has_header {
+        lines.next();
+    }
+
+    let mut trades = Vec::new();
+    for (line_no, line) in lines {
+        let fields = split_fields(line);
+        let field = match index {
+            Some(i) => *fields
+                .get(i)
+                .ok_or_else(|| format!("第 {line_no} 行缺少第 {} 列", i + 1))?,
+            None => fields[fields.len() - 1],
+        };
+        let value: f64 = field
+            .parse()
+            .map_err(|_| format!("第 {line_no} 行 '{field}' 不是有效数字"))?;
+        if !value.is_finite() {
+            return Err(format!("第 {line_no} 行 '{field}' 不是有效数字"));
+        }
+        trades.push(value);
+    }
+
+    if trades.len() < 2 {
+        return Err("交易历史至少需要 2 笔交易".to_string());
+    }
+    if !trades.iter().any(|&r| r < 0.0) {
+        return Err("交易历史至少需要一笔亏损交易".to_string());
+    }
+    Ok(trades)
+}
+
+/// 读取交易历史文件
+pub fn load_trade_history(path: &str, column: Option<&str>) -> Result<Vec<f64>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("无法读取交易历史文件 '{path}': {e}"))?;
+    parse_trade_history(&content, column)
+}
+
+/// 计算胜率、盈亏比等统计摘要
+pub fn summarize_trades(trades: &[f64]) -> TradeHistorySummary {
+    let count = trades.len();
+    let wins: Vec<f64> = trades.iter().copied().filter(|&r| r > 0.0).collect();
+    let losses: Vec<f64> = trades.iter().copied().filter(|&r| r < 0.0).collect();
+    let gross_win: f64 = wins.iter().sum();
+    let gross_loss: f64 = losses.iter().sum();
+    let avg_win = if wins.is_empty() {
+        0.0
+    } else {
+        gross_win / wins.len() as f64
+    };
+    let avg_loss = if losses.is_empty() {
+        0.0
+    } else {
+        gross_loss / losses.len() as f64
+    };
+
+    TradeHistorySummary {
+        count,
+        wins: wins.len(),
+        losses: losses.len(),
+        win_rate: wins.len() as f64 / count as f64,
+        avg_win,
+        avg_loss,
+        payoff_ratio: if avg_loss < 0.0 {
+            avg_win / -avg_loss
+        } else {
+            0.0
+        },
+        expectancy: trades.iter().sum::<f64>() / count as f64,
+        profit_factor: if gross_loss < 0.0 {
+            gross_win / -gross_loss
+        } else {
+            0.0
+        },
+        best: trades.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        worst: trades.iter().copied().fold(f64::INFINITY, f64::min),
+    }
+}
+
+/// 排序样本的分位数（线性插值）
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let pos = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
+
+/// 有放回重抽样估计最优 f 的置信区间
+///
+/// 不含亏损交易的重抽样无法定义最优 f，不计入有效样本。
+pub fn bootstrap_optimal_f(
+    trades: &[f64],
+    samples: usize,
+    confidence: f64,
+    seed: u64,
+    r_multiple: bool,
+) -> BootstrapInterval {
+    let mut rng = SplitMix64::new(seed);
+    let mut fractions = Vec::with_capacity(samples);
+    let mut risks = Vec::with_capacity(samples);
+    let mut resample = vec![0.0; trades.len()];
+
+    for _ in 0..samples {
+        for slot in resample.iter_mut() {
+            *slot = trades[rng.next_index(trades.len())];
+        }
+        let result = optimal_f(&resample);
+        if result.worst_loss < 0.0 {
+            fractions.push(result.fraction);
+            risks.push(result.fraction / -result.worst_loss);
+        }
+    }
+
+    fractions.sort_by(f64::total_cmp);
+    risks.sort_by(f64::total_cmp);
+    let tail = (1.0 - confidence) / 2.0;
+
+    BootstrapInterval {
+        samples,
+        valid_samples: fractions.len(),
+        confidence,
+        fraction_low: quantile(&fractions, tail),
+        fraction_median: quantile(&fractions, 0.5),
+        fraction_high: quantile(&fractions, 1.0 - tail),
+        risk_low: r_multiple.then(|| quantile(&risks, tail)),
+        risk_high: r_multiple.then(|| quantile(&risks, 1.0 - tail)),
+    }
+}
+
+/// 分析交易历史：统计摘要、公式凯利、最优 f 与 bootstrap 置信区间
+pub fn analyze_trade_history(
+    trades: &[f64],
+    r_multiple: bool,
+    bootstrap_samples: usize,
+    confidence: f64,
+    seed: u64,
+) -> TradeHistoryResult {
+    let summary = summarize_trades(trades);
+    let formula_kelly = if summary.payoff_ratio > 0.0 {
+        summary.win_rate - (1.0 - summary.win_rate) / summary.payoff_ratio
+    } else {
+        0.0
+    };
+    let optimal = optimal_f(trades);
+    let risk_fraction = if optimal.worst_loss < 0.0 {
+        optimal.fraction / -optimal.worst_loss
+    } else {
+        0.0
+    };
+    let bootstrap = (bootstrap_samples > 0)
+        .then(|| bootstrap_optimal_f(trades, bootstrap_samples, confidence, seed, r_multiple));
+
+    TradeHistoryResult {
+        summary,
+        formula_kelly,
+        optimal_f: optimal,
+        risk_fraction,
+        bootstrap,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze_trade_history, parse_trade_history, quantile, summarize_trades};
+
+    #[test]
+    fn parses_header_and_selects_column() {
+        let csv = "# 导出自券商\ndate,symbol,pnl\n2024-01-02,AAA,120\n2024-01-03,BBB,-80\n\n2024-01-05,CCC,45.5\n";
+        assert_eq!(
+            parse_trade_history(csv, None).unwrap(),
+            vec![120.0, -80.0, 45.5]
+        );
+        assert_eq!(
+            parse_trade_history(csv, Some("PNL")).unwrap(),
+            vec![120.0, -80.0, 45.5]
+        );
+        assert!(parse_trade_history(csv, Some("2")).is_err());
+        assert!(parse_trade_history(csv, Some("r")).is_err());
+    }
+
+    #[test]
+    fn parses_single_column_without_header() {
+        assert_eq!(
+            parse_trade_history("2\n-1\n1.5\n", None).unwrap(),
+            vec![2.0, -1.0, 1.5]
+        );
+        assert!(parse_trade_history("2\n3\n", None).is_err());
+        assert!(parse_trade_history("", None).is_err());
+    }
+
+    #[test]
+    fn summary_reports_win_rate_and_payoff() {
+        let summary = summarize_trades(&[2.0, -1.0, 3.0, -1.0, 0.0]);
+        assert_eq!(summary.wins, 2);
+        assert_eq!(summary.losses, 2);
+        assert!((summary.win_rate - 0.4).abs() < 1e-12);
+        assert!((summary.payoff_ratio - 2.5).abs() < 1e-12);
+        assert!((summary.profit_factor - 2.5).abs() < 1e-12);
+        assert!((summary.expectancy - 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn r_multiple_kelly_matches_binary_formula() {
+        // 胜率 60%，+2R / -1R：公式凯利 = 0.6 - 0.4/2 = 40%，经验凯利应一致
+        let trades = [2.0, 2.0, 2.0, -1.0, -1.0];
+        let result = analyze_trade_history(&trades, true, 200, 0.9, 42);
+        assert!((result.formula_kelly - 0.4).abs() < 1e-12);
+        assert!((result.risk_fraction - 0.4).abs() < 1e-6);
+        assert!((result.optimal_f.fraction - 0.4).abs() < 1e-6);
+
+        let bootstrap = result.bootstrap.unwrap();
+        assert!(bootstrap.fraction_low <= bootstrap.fraction_median);
+        assert!(bootstrap.fraction_median <= bootstrap.fraction_high);
+        assert!(bootstrap.valid_samples <= 200);
+        assert!(bootstrap.risk_low.is_some());
+
+        let again = analyze_trade_history(&trades, true, 200, 0.9, 42)
+            .bootstrap
+            .unwrap();
+        assert_eq!(again.fraction_low, bootstrap.fraction_low);
+        assert_eq!(again.fraction_high, bootstrap.fraction_high);
+    }
+
+    #[test]
+    fn quantile_interpolates() {
+        let sorted = [0.0, 1.0, 2.0, 3.0];
+        assert_eq!(quantile(&sorted, 0.0), 0.0);
+        assert_eq!(quantile(&sorted, 0.5), 1.5);
+        assert_eq!(quantile(&sorted, 1.0), 3.0);
+    }
+}
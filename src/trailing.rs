@@ -0,0 +1,235 @@
+//! 移动止损 / 时间止损：在几何布朗运动价格路径上模拟收益分布并计算凯利仓位
+
+use crate::kelly::{build_stock_info, kelly_multi_outcome, per_share_cost};
+use crate::rng::SplitMix64;
+use crate::types::{PathSimulation, StockCosts, TradeDirection, TrailingStop, TrailingStopResult};
+
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// 单条路径的出场结果
+struct PathExit {
+    price: f64,
+    days: usize,
+    stopped_out: bool,
+}
+
+/// 模拟单条路径：按日收盘价更新极值与移动止损，盘中触及止损（含布朗桥估计）时以止损价出场
+fn simulate_path(
+    direction: TradeDirection,
+    entry_price: f64,
+    trailing: TrailingStop,
+    simulation: &PathSimulation,
+    rng: &mut SplitMix64,
+) -> PathExit {
+    let dt = 1.0 / TRADING_DAYS_PER_YEAR;
+    let drift = (simulation.annual_drift - 0.5 * simulation.annual_vol.powi(2)) * dt;
+    let diffusion = simulation.annual_vol * dt.sqrt();
+    let sign = direction.sign();
+
+    let mut price = entry_price;
+    let mut extreme = entry_price;
+    let mut stop = entry_price - sign * trailing.distance(entry_price);
+
+    for day in 1..=simulation.max_days {
+        let previous = price;
+        price *= (drift + diffusion * rng.next_normal()).exp();
+        // 两个收盘价都在止损之外时，用布朗桥概率判断盘中是否触及止损
+        let crossed = (price - stop) * sign <= 0.0 || {
+            let a = sign * (previous / stop).ln();
+            let b = sign * (price / stop).ln();
+            rng.next_f64() < (-2.0 * a * b / (diffusion * diffusion)).exp()
+        };
+        if crossed {
+            return PathExit {
+                price: stop,
+                days: day,
+                stopped_out: true,
+            };
+        }
+        if (price - extreme) * sign > 0.0 {
+            extreme = price;
+            let candidate = extreme - sign * trailing.distance(extreme);
+            // 移动止损只朝有利方向移动
+            if (candidate - stop) * sign > 0.0 {
+                stop = candidate;
+            }
+        }
+    }
+
+    PathExit {
+        price,
+        days: simulation.max_days,
+        stopped_out: false,
+    }
+}
+
+/// 模拟移动止损交易的收益分布，并以初始止损净风险为 1R 计算凯利仓位
+pub fn simulate_trailing_stop(
+    direction: TradeDirection,
+    entry_price: f64,
+    trailing: TrailingStop,
+    simulation: &PathSimulation,
+    costs: &StockCosts,
+) -> TrailingStopResult {
+    let sign = direction.sign();
+    let distance = trailing.distance(entry_price);
+    let initial_stop = entry_price - sign * distance;
+    let info = build_stock_info(
+        direction,
+        entry_price,
+        entry_price + sign * distance,
+        initial_stop,
+        costs,
+    );
+
+    let opens_with_sell = direction == TradeDirection::Short;
+    let open_cost = per_share_cost(costs, entry_price, opens_with_sell);
+    let borrow_per_day = match direction {
+        TradeDirection::Long => 0.0,
+        TradeDirection::Short => entry_price * costs.borrow_rate / 365.0,
+    };
+
+    let mut rng = SplitMix64::new(simulation.seed);
+    let mut pnls = Vec::with_capacity(simulation.paths);
+    let mut stopped = 0usize;
+    let mut total_days = 0usize;
+    for _ in 0..simulation.paths {
+        let exit = simulate_path(direction, entry_price, trailing, simulation, &mut rng);
+        // 交易日换算为自然日计息
+        let calendar_days = exit.days as f64 * 365.0 / TRADING_DAYS_PER_YEAR;
+        let pnl = (exit.price - entry_price) * sign
+            - open_cost
+            - per_share_cost(costs, exit.price, !opens_with_sell)
+            - borrow_per_day * calendar_days;
+        pnls.push(pnl);
+        if exit.stopped_out {
+            stopped += 1;
+        }
+        total_days += exit.days;
+    }
+
+    let n = pnls.len().max(1) as f64;
+    let mut r_multiples: Vec<f64> = pnls.iter().map(|p| p / info.net_risk).collect();
+    let distribution: Vec<(f64, f64)> = r_multiples.iter().map(|&r| (1.0 / n, r)).collect();
+    let kelly = kelly_multi_outcome(&distribution);
+    r_multiples.sort_by(f64::total_cmp);
+
+    TrailingStopResult {
+        info,
+        kelly,
+        expected_pnl_per_share: pnls.iter().sum::<f64>() / n,
+        stopped_out_prob: stopped as f64 / n,
+        time_stop_prob: (simulation.paths - stopped) as f64 / n,
+        win_prob: pnls.iter().filter(|&&p| p > 0.0).count() as f64 / n,
+        avg_holding_days: total_days as f64 / n,
+        r_multiples,
+    }
+}
+
+/// 升序样本的分位数（最近秩）
+pub fn r_percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let index = (q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64).round() as usize;
+    sorted[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{r_percentile, simulate_trailing_stop};
+    use crate::types::{PathSimulation, StockCosts, TradeDirection, TrailingStop};
+
+    fn simulation(drift: f64, max_days: usize) -> PathSimulation {
+        PathSimulation {
+            annual_drift: drift,
+            annual_vol: 0.3,
+            max_days,
+            paths: 4000,
+            seed: 42,
+        }
+    }
+
+    #[test]
+    fn losses_never_exceed_initial_risk_without_costs() {
+        let result = simulate_trailing_stop(
+            TradeDirection::Long,
+            100.0,
+            TrailingStop::Percent(0.08),
+            &simulation(0.1, 120),
+            &StockCosts::default(),
+        );
+        assert!((result.info.stop_loss - 92.0).abs() < 1e-9);
+        assert!(result.r_multiples[0] >= -1.0 - 1e-9);
+        assert!((result.stopped_out_prob + result.time_stop_prob - 1.0).abs() < 1e-12);
+        assert!(result.avg_holding_days <= 120.0);
+    }
+
+    #[test]
+    fn same_seed_is_reproducible() {
+        let run = || {
+            simulate_trailing_stop(
+                TradeDirection::Short,
+                50.0,
+                TrailingStop::Atr {
+                    atr: 1.5,
+                    mult: 3.0,
+                },
+                &simulation(-0.2, 60),
+                &StockCosts::default(),
+            )
+        };
+        let a = run();
+        let b = run();
+        assert_eq!(a.expected_pnl_per_share, b.expected_pnl_per_share);
+        assert!((a.info.stop_loss - 54.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn strong_drift_makes_trend_following_positive() {
+        let up = simulate_trailing_stop(
+            TradeDirection::Long,
+            100.0,
+            TrailingStop::Percent(0.1),
+            &simulation(0.8, 250),
+            &StockCosts::default(),
+        );
+        assert!(up.kelly.positive_ev);
+        assert!(up.kelly.optimal_fraction > 0.0);
+
+        let down = simulate_trailing_stop(
+            TradeDirection::Long,
+            100.0,
+            TrailingStop::Percent(0.1),
+            &simulation(-0.8, 250),
+            &StockCosts::default(),
+        );
+        assert!(!down.kelly.positive_ev);
+        assert_eq!(down.kelly.optimal_fraction, 0.0);
+    }
+
+    #[test]
+    fn driftless_walk_has_near_zero_expectancy() {
+        // 连续监控的止损在鞅过程上不改变期望
+        let result = simulate_trailing_stop(
+            TradeDirection::Long,
+            100.0,
+            TrailingStop::Percent(0.08),
+            &simulation(0.0, 60),
+            &StockCosts::default(),
+        );
+        assert!(
+            result.kelly.expected_value.abs() < 0.08,
+            "EV={}",
+            result.kelly.expected_value
+        );
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let sorted = [-1.0, 0.0, 1.0, 2.0, 3.0];
+        assert_eq!(r_percentile(&sorted, 0.0), -1.0);
+        assert_eq!(r_percentile(&sorted, 0.5), 1.0);
+        assert_eq!(r_percentile(&sorted, 1.0), 3.0);
+    }
+}
@@ -1,5 +1,7 @@
 //! 数据结构定义
 
+use std::path::PathBuf;
+
 /// 凯利公式计算结果
 #[derive(Debug, Clone)]
 pub struct KellyResult {
@@ -43,6 +45,580 @@ pub struct MultiArbitrageResult {
     pub stake_ratios: Vec<f64>,
 }
 
+/// 赔率快照中的一条报价
+#[derive(Debug, Clone)]
+pub struct OddsQuote {
+    pub event: String,
+    pub market: String,
+    pub outcome: String,
+    pub bookmaker: String,
+    pub odds: f64,
+}
+
+/// 扫描盘口中某个结果的最优报价
+#[derive(Debug, Clone)]
+pub struct ScanLeg {
+    pub outcome: String,
+    pub bookmaker: String,
+    pub odds: f64,
+    /// 占总投注的比例
+    pub stake_ratio: f64,
+}
+
+/// 扫描命中的盘口
+#[derive(Debug, Clone)]
+pub struct ScannedMarket {
+    pub event: String,
+    pub market: String,
+    pub legs: Vec<ScanLeg>,
+    pub result: MultiArbitrageResult,
+    /// 总投注收益率（套利为正，低抽水盘口为负）
+    pub profit: f64,
+}
+
+/// 赔率快照扫描结果
+#[derive(Debug, Clone)]
+pub struct ArbitrageScan {
+    pub quote_count: usize,
+    pub market_count: usize,
+    /// 只有一个结果、无法计算的盘口数
+    pub skipped_markets: usize,
+    /// 列出低抽水盘口的抽水率上限
+    pub max_margin: f64,
+    pub markets: Vec<ScannedMarket>,
+}
+
+/// 模型给出的结果概率
+#[derive(Debug, Clone)]
+pub struct ModelProbability {
+    pub event: String,
+    pub market: String,
+    pub outcome: String,
+    pub probability: f64,
+}
+
+/// 扫描命中的价值投注
+#[derive(Debug, Clone)]
+pub struct ValueBet {
+    pub event: String,
+    pub market: String,
+    pub outcome: String,
+    pub bookmaker: String,
+    /// 最优小数赔率（Polymarket 为 1 / 价格）
+    pub odds: f64,
+    /// 隐含概率（Polymarket 为市场价格）
+    pub implied_prob: f64,
+    /// 模型概率
+    pub probability: f64,
+    /// 优势 = 模型概率 - 隐含概率
+    pub edge: f64,
+    pub kelly: KellyResult,
+    /// 可直接传给 `-k` 的组合描述
+    pub descriptor: String,
+}
+
+/// 价值投注扫描结果
+#[derive(Debug, Clone)]
+pub struct ValueScan {
+    pub quote_count: usize,
+    pub probability_count: usize,
+    /// 去重后的结果数量
+    pub outcome_count: usize,
+    /// 有模型概率的结果数量
+    pub matched: usize,
+    pub polymarket: bool,
+    pub min_edge: f64,
+    pub bets: Vec<ValueBet>,
+}
+
+/// 记账下注的金额：固定金额或占可用余额的比例（如凯利建议比例）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LedgerStake {
+    Amount(f64),
+    Fraction(f64),
+}
+
+/// `--record`：把模式计算出的凯利仓位记入账本
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerRecord {
+    pub path: PathBuf,
+    pub date: String,
+    pub size: RecordSize,
+    /// 下注未自带盘口类型时使用
+    pub market: String,
+    /// 记在每笔下注备注之前
+    pub label: String,
+}
+
+/// `--record` 的记账金额
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordSize {
+    /// 凯利仓位 × 倍数（全凯利 1、半凯利 0.5、1/4凯利 0.25），用于标准、Polymarket 与价值投注扫描
+    Kelly(f64),
+    /// 按输入的总投注金额分配出的各腿金额，用于套利、分散投注与中间盘
+    Stake,
+}
+
+/// 模式计算出的待记账下注
+#[derive(Debug, Clone)]
+pub struct SizedBet {
+    pub odds: f64,
+    /// 投注金额（本金货币）；凯利仓位模式为全凯利金额
+    pub stake: f64,
+    pub probability: Option<f64>,
+    pub market: String,
+    pub label: String,
+}
+
+/// 账本命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum LedgerCommand {
+    Deposit(f64),
+    Withdraw(f64),
+    Add {
+        odds: f64,
+        stake: LedgerStake,
+        mode: String,
+        /// 盘口类型（如 1x2、让分、大小分），用于分组统计 CLV
+        market: String,
+        /// 下注时的胜率，用于对比下注时 EV 与 CLV
+        probability: Option<f64>,
+        label: String,
+    },
+    Settle {
+        id: u64,
+        settlement: BetSettlement,
+        /// 部分结算的返还金额
+        returned: Option<f64>,
+    },
+    /// 记录收盘赔率；提供同一盘口其他结果的收盘赔率时去水
+    Close {
+        id: u64,
+        odds: f64,
+        others: Vec<f64>,
+    },
+    Show,
+}
+
+/// 账本中下注的结算结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BetSettlement {
+    Win,
+    Loss,
+    /// 走水 / 取消，退回本金
+    Void,
+    /// 部分结算（半赢半输、提前兑现等），按实际返还金额
+    Partial,
+}
+
+impl BetSettlement {
+    pub fn from_code(input: &str) -> Result<Self, String> {
+        match input.to_ascii_lowercase().as_str() {
+            "win" => Ok(Self::Win),
+            "loss" | "lose" => Ok(Self::Loss),
+            "void" => Ok(Self::Void),
+            "partial" => Ok(Self::Partial),
+            _ => Err(format!(
+                "结算结果 '{input}' 无效，应为 win、loss、void 或 partial"
+            )),
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Win => "win",
+            Self::Loss => "loss",
+            Self::Void => "void",
+            Self::Partial => "partial",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Win => "赢",
+            Self::Loss => "输",
+            Self::Void => "走水",
+            Self::Partial => "部分结算",
+        }
+    }
+}
+
+/// 账本中的一条记录（金额均以本金货币记）
+#[derive(Debug, Clone)]
+pub enum LedgerEntry {
+    Deposit {
+        date: String,
+        amount: f64,
+    },
+    Withdraw {
+        date: String,
+        amount: f64,
+    },
+    Bet {
+        id: u64,
+        date: String,
+        odds: f64,
+        stake: f64,
+        /// 计算该仓位的模式
+        mode: String,
+        market: String,
+        probability: Option<f64>,
+        label: String,
+    },
+    Settle {
+        id: u64,
+        date: String,
+        settlement: BetSettlement,
+        /// 返还金额（含本金）
+        returned: f64,
+    },
+    Close {
+        id: u64,
+        line: ClosingLine,
+    },
+}
+
+/// 下注的收盘价
+#[derive(Debug, Clone)]
+pub struct ClosingLine {
+    pub date: String,
+    pub odds: f64,
+    /// 收盘概率：提供完整盘口时为去水后的公平概率，否则为 1 / 收盘赔率
+    pub probability: f64,
+    pub devigged: bool,
+}
+
+/// 账本中的下注及其结算状态
+#[derive(Debug, Clone)]
+pub struct LedgerBet {
+    pub id: u64,
+    pub date: String,
+    pub odds: f64,
+    pub stake: f64,
+    pub mode: String,
+    pub market: String,
+    pub probability: Option<f64>,
+    pub label: String,
+    /// (结算结果, 返还金额, 结算日期)，未结算为 None
+    pub settlement: Option<(BetSettlement, f64, String)>,
+    /// 多次记录时以最后一次为准
+    pub closing: Option<ClosingLine>,
+}
+
+/// 按盘口类型分组的 CLV
+#[derive(Debug, Clone)]
+pub struct ClvGroup {
+    pub market: String,
+    pub count: usize,
+    pub mean_clv: f64,
+    /// CLV 为正（赢过收盘价）的比例
+    pub beat_rate: f64,
+}
+
+/// 收盘价值汇总
+#[derive(Debug, Clone)]
+pub struct ClvSummary {
+    /// 有收盘价的下注数量
+    pub count: usize,
+    pub mean_clv: f64,
+    pub median_clv: f64,
+    /// 按金额加权的平均 CLV
+    pub weighted_clv: f64,
+    pub beat_rate: f64,
+    /// 同时有下注时胜率与收盘价的下注数量，及其下注时 EV 与 CLV 的均值
+    pub compared: usize,
+    pub mean_placement_ev: Option<f64>,
+    pub mean_compared_clv: Option<f64>,
+    /// CLV 分布：(区间下限, 区间上限, 数量)，首尾区间无界
+    pub distribution: Vec<(f64, f64, usize)>,
+    pub by_market: Vec<ClvGroup>,
+}
+
+/// 账本汇总
+#[derive(Debug, Clone, Default)]
+pub struct LedgerSummary {
+    pub deposits: f64,
+    pub withdrawals: f64,
+    /// 当前资金 = 入金 - 出金 + 已实现盈亏
+    pub bankroll: f64,
+    /// 可用余额 = 当前资金 - 未结算投注金额
+    pub balance: f64,
+    pub pending_stake: f64,
+    pub realized_pnl: f64,
+    /// 已结算投注总额
+    pub turnover: f64,
+    /// 已实现盈亏 / 已结算投注额，尚无结算时为 None
+    pub roi: Option<f64>,
+    pub bets: Vec<LedgerBet>,
+    /// 资金曲线：(日期, 资金)
+    pub curve: Vec<(String, f64)>,
+    /// 尚无收盘价时为 None
+    pub clv: Option<ClvSummary>,
+}
+
+/// 一条已结算的概率预测
+#[derive(Debug, Clone, Copy)]
+pub struct Prediction {
+    /// 你估计的概率
+    pub probability: f64,
+    /// 市场隐含概率（1 / 赔率或 Polymarket 价格），收缩的目标
+    pub market_prob: f64,
+    pub outcome: bool,
+}
+
+/// 可靠性表中的一个概率区间
+#[derive(Debug, Clone, Copy)]
+pub struct ReliabilityBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+    pub mean_predicted: f64,
+    /// 实际命中频率
+    pub observed: f64,
+}
+
+/// `--shrink` 对输入概率的调整：收缩系数与调整前的概率
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShrinkAdjustment {
+    pub factor: f64,
+    pub raw_probability: f64,
+}
+
+/// 概率校准报告
+#[derive(Debug, Clone)]
+pub struct CalibrationReport {
+    pub count: usize,
+    pub wins: usize,
+    pub mean_predicted: f64,
+    pub observed_rate: f64,
+    pub brier: f64,
+    pub log_loss: f64,
+    /// 直接使用市场概率时的得分，用于对比
+    pub market_brier: f64,
+    pub market_log_loss: f64,
+    pub buckets: Vec<ReliabilityBucket>,
+    /// 拟合的收缩系数 k：p' = 市场概率 + k × (p - 市场概率)
+    pub shrink_factor: f64,
+    pub shrunk_brier: f64,
+    pub shrunk_log_loss: f64,
+}
+
+/// 套利下注选项
+#[derive(Debug, Clone, Default)]
+pub struct ArbitrageOptions {
+    /// 各标的投注单位（庄家接受的最小金额步长），None 表示不取整
+    pub stake_steps: Option<Vec<f64>>,
+    /// 各标的单边最大投注额（庄家限额），None 表示不限
+    pub max_stakes: Option<Vec<f64>>,
+    /// 各标的获胜时对净赢额收取的佣金率（交易所佣金），None 表示无佣金
+    pub commissions: Option<Vec<f64>>,
+    /// 各标的按投注额另行支付的手续费率，None 表示无手续费
+    pub fees: Option<Vec<f64>>,
+}
+
+impl ArbitrageOptions {
+    /// 第 i 个标的的 (佣金率, 手续费率)
+    pub fn leg_costs(&self, i: usize) -> (f64, f64) {
+        let pick = |values: &Option<Vec<f64>>| values.as_ref().map_or(0.0, |v| v[i]);
+        (pick(&self.commissions), pick(&self.fees))
+    }
+
+    /// 是否设置了佣金或手续费
+    pub fn has_costs(&self) -> bool {
+        let any =
+            |values: &Option<Vec<f64>>| values.as_ref().is_some_and(|v| v.iter().any(|&x| x > 0.0));
+        any(&self.commissions) || any(&self.fees)
+    }
+}
+
+/// 单边限额约束下的套利方案
+#[derive(Debug, Clone)]
+pub struct LimitedArbitrage {
+    /// 各标的最大投注额
+    pub max_stakes: Vec<f64>,
+    /// 各限额允许的最大总投注
+    pub max_total: f64,
+    /// 决定规模的标的（索引）
+    pub limiting_leg: usize,
+    /// 限额是否小于本金（未指定本金时总为 true）
+    pub binding: bool,
+    /// 可投入总额（投注 + 手续费）
+    pub total_stake: f64,
+    /// 各标的实际投注金额
+    pub stakes: Vec<f64>,
+    /// 保证收益
+    pub guaranteed_profit: f64,
+}
+
+/// 按投注单位取整后的套利方案
+#[derive(Debug, Clone)]
+pub struct RoundedArbitrage {
+    /// 各标的投注单位
+    pub steps: Vec<f64>,
+    /// 取整后的各标的投注金额
+    pub stakes: Vec<f64>,
+    /// 总资金支出（投注 + 手续费）
+    pub total_stake: f64,
+    /// 各结果发生时的净收益
+    pub profits: Vec<f64>,
+    /// 最差结果的净收益
+    pub min_profit: f64,
+    /// 取整后是否仍保证盈利
+    pub survives: bool,
+}
+
+/// 按下注选项调整后的套利方案（单边限额、投注单位取整）
+#[derive(Debug, Clone, Default)]
+pub struct ArbitragePlan {
+    pub limited: Option<LimitedArbitrage>,
+    pub rounded: Option<RoundedArbitrage>,
+}
+
+/// 分散投注的金额目标
+#[derive(Debug, Clone, Copy)]
+pub enum DutchingTarget {
+    /// 总投注金额
+    TotalStake(f64),
+    /// 任一选项胜出时的目标净收益
+    TargetProfit(f64),
+}
+
+/// 分散投注计算结果
+#[derive(Debug, Clone)]
+pub struct DutchingResult {
+    /// 选定选项的合计隐含概率
+    pub total_implied_prob: f64,
+    /// 子集等效赔率 = 1 / 合计隐含概率
+    pub combined_odds: f64,
+    /// 任一选项胜出时总投注的收益率
+    pub win_return: f64,
+    /// 各选项占总投注的比例
+    pub stake_ratios: Vec<f64>,
+    /// 指定金额目标时的总投注与各选项金额
+    pub total_stake: Option<f64>,
+    pub stakes: Vec<f64>,
+    /// 按子集胜率计算的凯利结果
+    pub kelly: Option<KellyResult>,
+}
+
+/// 中间盘投注：方案1在结果高于线1时赢，方案2在结果低于线2时赢（线1 < 线2）
+#[derive(Debug, Clone, Copy)]
+pub struct MiddleBet {
+    pub line1: f64,
+    pub odds1: f64,
+    pub line2: f64,
+    pub odds2: f64,
+    /// 结果落入 (线1, 线2) 中间区的概率
+    pub middle_prob: f64,
+}
+
+/// 中间盘计算结果（收益均按总投注为 1 单位）
+#[derive(Debug, Clone)]
+pub struct MiddleResult {
+    /// 隐含概率之和
+    pub total_implied_prob: f64,
+    /// 两边投注占总投注的比例
+    pub stake_ratios: [f64; 2],
+    /// 未落入中间区时的收益（最差结果）
+    pub outside_return: f64,
+    /// 落入中间区时的收益
+    pub middle_return: f64,
+    /// 最差结果是否不亏损
+    pub guaranteed: bool,
+    /// 以总投注为仓位的凯利结果（上限 100%）
+    pub kelly: KellyResult,
+}
+
+/// 对冲下注类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackLayKind {
+    /// 普通（资格）投注：锁定小额亏损或盈利
+    Qualifying,
+    /// 免费投注，赢时不退本金（SNR）
+    FreeBetSnr,
+    /// 免费投注，赢时退本金（SR）
+    FreeBetSr,
+    /// 无风险投注：背注输时按保留率退回免费投注价值
+    RiskFree { retention: f64 },
+}
+
+impl BackLayKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Qualifying => "资格投注",
+            Self::FreeBetSnr => "免费投注 (不退本金)",
+            Self::FreeBetSr => "免费投注 (退本金)",
+            Self::RiskFree { .. } => "无风险投注",
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Qualifying => "qualifying",
+            Self::FreeBetSnr => "free_bet_snr",
+            Self::FreeBetSr => "free_bet_sr",
+            Self::RiskFree { .. } => "risk_free",
+        }
+    }
+}
+
+/// 对冲下注计算结果
+#[derive(Debug, Clone)]
+pub struct BackLayResult {
+    /// 交易所对冲金额
+    pub lay_stake: f64,
+    /// 对冲负债（对冲输时需支付）
+    pub liability: f64,
+    /// 背注赢时的净收益
+    pub back_wins_profit: f64,
+    /// 背注输（对冲赢）时的净收益
+    pub lay_wins_profit: f64,
+    /// 锁定收益（两者较小值，负数为资格亏损）
+    pub profit: f64,
+    /// 赔率比 = 背注赔率 / 对冲赔率
+    pub rating: f64,
+    /// 免费投注转化率 = 锁定收益 / 免费投注面额
+    pub conversion: Option<f64>,
+}
+
+/// 已有投注：原投注金额与赔率、当前反向对冲赔率
+#[derive(Debug, Clone, Copy)]
+pub struct HedgeBet {
+    pub stake: f64,
+    pub odds: f64,
+    pub hedge_odds: f64,
+    /// 更新后的原投注胜率
+    pub win_prob: Option<f64>,
+}
+
+/// 某个对冲金额下的结果
+#[derive(Debug, Clone)]
+pub struct HedgeOutcome {
+    /// 占完全对冲金额的比例
+    pub ratio: f64,
+    pub hedge_stake: f64,
+    /// 原投注赢时的净收益
+    pub profit_if_win: f64,
+    /// 原投注输（对冲赢）时的净收益
+    pub profit_if_lose: f64,
+    /// 按更新胜率的期望收益
+    pub expected_profit: Option<f64>,
+}
+
+/// 对冲计算结果
+#[derive(Debug, Clone)]
+pub struct HedgeResult {
+    /// 原赔率与对冲赔率组成的双边套利结果
+    pub arbitrage: ArbitrageResult,
+    /// 完全对冲时总投入的锁定收益率
+    pub locked_return: f64,
+    /// 完全对冲的等效兑现价值（原投注金额 + 锁定收益）
+    pub cash_out_value: f64,
+    pub full: HedgeOutcome,
+    pub partial: Vec<HedgeOutcome>,
+    pub kelly: Option<HedgeOutcome>,
+}
+
 /// 2x2 纯策略纳什均衡
 #[derive(Debug, Clone)]
 pub struct NashPureEquilibrium {
@@ -78,15 +654,629 @@ pub struct NashResult {
     pub mixed_equilibrium: Option<NashMixedEquilibrium>,
 }
 
+/// 交易方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeDirection {
+    Long,
+    Short,
+}
+
+impl TradeDirection {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Long => "long",
+            Self::Short => "short",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Long => "做多",
+            Self::Short => "做空",
+        }
+    }
+
+    /// 价格上涨时的盈亏符号（做多 +1，做空 -1）
+    pub fn sign(self) -> f64 {
+        match self {
+            Self::Long => 1.0,
+            Self::Short => -1.0,
+        }
+    }
+}
+
+/// 股票交易成本（整手、佣金、印花税、融券费用）
+#[derive(Debug, Clone, Copy)]
+pub struct StockCosts {
+    /// 每手股数（A股为 100）
+    pub lot_size: u64,
+    /// 每股佣金
+    pub commission_per_share: f64,
+    /// 按成交额计算的佣金费率（0-1）
+    pub commission_rate: f64,
+    /// 单笔最低佣金
+    pub min_commission: f64,
+    /// 卖出印花税率（0-1）
+    pub stamp_duty_rate: f64,
+    /// 融券年化费率（0-1，仅做空时收取）
+    pub borrow_rate: f64,
+    /// 持有天数（用于计算融券费用）
+    pub holding_days: f64,
+}
+
+impl Default for StockCosts {
+    fn default() -> Self {
+        Self {
+            lot_size: 1,
+            commission_per_share: 0.0,
+            commission_rate: 0.0,
+            min_commission: 0.0,
+            stamp_duty_rate: 0.0,
+            borrow_rate: 0.0,
+            holding_days: 0.0,
+        }
+    }
+}
+
+impl StockCosts {
+    /// 是否设置了任何交易费用
+    pub fn has_fees(&self) -> bool {
+        self.commission_per_share > 0.0
+            || self.commission_rate > 0.0
+            || self.min_commission > 0.0
+            || self.stamp_duty_rate > 0.0
+            || (self.borrow_rate > 0.0 && self.holding_days > 0.0)
+    }
+
+    /// 每股融券费用（按入场价计息）
+    pub fn borrow_cost_per_share(&self, direction: TradeDirection, entry_price: f64) -> f64 {
+        match direction {
+            TradeDirection::Long => 0.0,
+            TradeDirection::Short => entry_price * self.borrow_rate * self.holding_days / 365.0,
+        }
+    }
+}
+
 /// 股票交易信息
 #[derive(Debug, Clone)]
 pub struct StockInfo {
+    pub direction: TradeDirection,
     pub entry_price: f64,
     pub target_price: f64,
     pub stop_loss: f64,
     pub profit: f64,
     pub risk: f64,
     pub ratio: f64,
+    /// 每股净收益（扣除往返费用，不含最低佣金）
+    pub net_profit: f64,
+    /// 每股净风险（含往返费用，不含最低佣金）
+    pub net_risk: f64,
+    /// 净盈亏比
+    pub net_ratio: f64,
+}
+
+/// 按整手取整后的股票仓位
+#[derive(Debug, Clone)]
+pub struct StockPositionSize {
+    /// 股数（整手）
+    pub shares: u64,
+    /// 实际占用资金（含买入佣金）
+    pub capital_used: f64,
+    /// 触发止损时的实际亏损（含往返费用）
+    pub risk_at_stop: f64,
+    /// 到达止盈时的实际净收益（扣除往返费用）
+    pub profit_at_target: f64,
+}
+
+/// 凯利之外的股票仓位算法
+#[derive(Debug, Clone)]
+pub enum PositionSizer {
+    /// 固定比例风险：止损亏损占本金的比例（0-1）
+    FixedFractional { risk_fraction: f64 },
+    /// 固定比率（Ryan Jones）：每增加一个单位所需的利润增量与已实现累计利润
+    FixedRatio { delta: f64, accumulated_profit: f64 },
+    /// 波动率目标：目标年化波动率与标的年化波动率（0-1）
+    VolatilityTarget { target_vol: f64, asset_vol: f64 },
+    /// 最优 f（Vince）：历史交易结果（R 倍数，亏损至止损为 -1）
+    OptimalF { trades: Vec<f64> },
+}
+
+impl PositionSizer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FixedFractional { .. } => "fixed_fractional",
+            Self::FixedRatio { .. } => "fixed_ratio",
+            Self::VolatilityTarget { .. } => "volatility_target",
+            Self::OptimalF { .. } => "optimal_f",
+        }
+    }
+}
+
+/// 最优 f 计算结果
+#[derive(Debug, Clone)]
+pub struct OptimalFResult {
+    /// 最优 f (0-1)
+    pub fraction: f64,
+    /// 历史最大单笔亏损（R 倍数，负数）
+    pub worst_loss: f64,
+    /// 最终财富相对值 TWR
+    pub twr: f64,
+    /// 每笔几何平均持有期收益 (TWR^(1/n))
+    pub geometric_mean: f64,
+}
+
+/// 单个仓位算法的结果
+#[derive(Debug, Clone)]
+pub struct SizerResult {
+    pub sizer: PositionSizer,
+    /// 固定比率模型给出的单位数（每单位一手）
+    pub units: Option<u64>,
+    /// 最优 f 模型的计算结果
+    pub optimal_f: Option<OptimalFResult>,
+    /// 止损风险预算（固定比例风险、最优 f）或目标名义仓位（波动率目标）
+    pub budget: f64,
+    pub position: StockPositionSize,
+}
+
+/// 交易历史统计摘要
+#[derive(Debug, Clone)]
+pub struct TradeHistorySummary {
+    pub count: usize,
+    pub wins: usize,
+    pub losses: usize,
+    /// 胜率（持平交易计入分母）
+    pub win_rate: f64,
+    pub avg_win: f64,
+    /// 平均亏损（负数）
+    pub avg_loss: f64,
+    /// 盈亏比：平均盈利 / |平均亏损|
+    pub payoff_ratio: f64,
+    /// 每笔平均盈亏
+    pub expectancy: f64,
+    /// 盈利因子：总盈利 / |总亏损|
+    pub profit_factor: f64,
+    pub best: f64,
+    pub worst: f64,
+}
+
+/// 最优 f 的 bootstrap 置信区间
+#[derive(Debug, Clone)]
+pub struct BootstrapInterval {
+    /// 重抽样次数
+    pub samples: usize,
+    /// 含亏损交易、可计算最优 f 的有效样本数
+    pub valid_samples: usize,
+    /// 置信水平（0-1）
+    pub confidence: f64,
+    pub fraction_low: f64,
+    pub fraction_median: f64,
+    pub fraction_high: f64,
+    /// 每笔风险比例 f / |最大亏损|（仅 R 倍数数据）
+    pub risk_low: Option<f64>,
+    pub risk_high: Option<f64>,
+}
+
+/// 交易历史凯利估计结果
+#[derive(Debug, Clone)]
+pub struct TradeHistoryResult {
+    pub summary: TradeHistorySummary,
+    /// 按胜率与盈亏比计算的公式凯利 W - (1-W)/盈亏比
+    pub formula_kelly: f64,
+    pub optimal_f: OptimalFResult,
+    /// 经验凯利风险比例 f / |最大亏损|：R 倍数数据为每 1R 风险占本金比例
+    pub risk_fraction: f64,
+    pub bootstrap: Option<BootstrapInterval>,
+}
+
+/// 已持有的仓位（用于计算组合热度）
+#[derive(Debug, Clone)]
+pub struct OpenPosition {
+    pub symbol: String,
+    pub direction: TradeDirection,
+    pub entry_price: f64,
+    pub stop_loss: f64,
+    pub shares: u64,
+}
+
+/// 组合热度设置：持仓列表与总风险上限
+#[derive(Debug, Clone)]
+pub struct HeatSettings {
+    /// 持仓文件路径（未提供时为 None）
+    pub source: Option<String>,
+    pub positions: Vec<OpenPosition>,
+    /// 总热度上限（占本金比例，0-1）
+    pub max_heat: Option<f64>,
+}
+
+/// 组合热度：现有持仓触发止损时的总亏损
+#[derive(Debug, Clone)]
+pub struct PortfolioHeat {
+    pub settings: HeatSettings,
+    /// 各持仓的止损风险（止损已越过入场价时为 0）
+    pub position_risks: Vec<f64>,
+    pub total_risk: f64,
+    /// 当前热度（占本金比例）
+    pub heat: f64,
+    /// 上限内可用于新交易的风险额度
+    pub remaining_risk: Option<f64>,
+}
+
+/// 杠杆与融资设置
+#[derive(Debug, Clone, Copy)]
+pub struct LeverageSettings {
+    /// 最大杠杆倍数（名义仓位 / 本金）
+    pub max_leverage: f64,
+    /// 融资年利率（0-1）
+    pub financing_rate: f64,
+    /// 持有天数（融资计息）
+    pub holding_days: f64,
+    /// 维持保证金比例（0-1），权益 / 持仓市值低于该比例时强平；0 表示穿仓价
+    pub maintenance_margin: f64,
+}
+
+impl LeverageSettings {
+    /// 持有期内每单位借入资金的融资成本
+    pub fn period_cost(&self) -> f64 {
+        self.financing_rate * self.holding_days / 365.0
+    }
+}
+
+/// 货币设置：下注货币（价格与投注金额）与本金货币
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrencySettings {
+    /// 下注 / 交易所用货币代码
+    pub stake: String,
+    /// 本金货币代码
+    pub capital: String,
+    /// 汇率：1 单位下注货币折合的本金货币数量
+    pub rate: f64,
+}
+
+impl CurrencySettings {
+    /// 本金货币金额换算为下注货币
+    pub fn to_stake(&self, amount: f64) -> f64 {
+        amount / self.rate
+    }
+
+    /// 下注货币金额换算为本金货币
+    pub fn to_capital(&self, amount: f64) -> f64 {
+        amount * self.rate
+    }
+}
+
+/// 本金来源
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CapitalSource {
+    /// 命令行或交互输入
+    #[default]
+    Input,
+    /// `--ledger` 账本可用余额
+    Ledger,
+}
+
+impl CapitalSource {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CapitalSource::Input => "input",
+            CapitalSource::Ledger => "ledger",
+        }
+    }
+}
+
+/// 金额显示上下文：货币设置、换算为下注货币前的本金及其来源
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MoneyContext {
+    pub currency: Option<CurrencySettings>,
+    /// 输入的本金（本金货币）
+    pub entered_capital: Option<f64>,
+    pub capital_source: CapitalSource,
+}
+
+/// 期货合约规格
+#[derive(Debug, Clone, Copy)]
+pub struct FuturesContract {
+    /// 合约乘数（每点价值）
+    pub multiplier: f64,
+    /// 最小变动价位；None 时不按跳取整
+    pub tick_size: Option<f64>,
+    /// 每手初始保证金
+    pub initial_margin: f64,
+    /// 每手单边手续费
+    pub fee_per_contract: f64,
+}
+
+impl FuturesContract {
+    /// 每跳价值
+    pub fn tick_value(&self) -> Option<f64> {
+        self.tick_size.map(|tick| tick * self.multiplier)
+    }
+}
+
+/// 单个凯利变体的期货手数
+#[derive(Debug, Clone, Copy)]
+pub struct FuturesPosition {
+    pub contracts: u64,
+    pub margin_used: f64,
+    /// 触发止损时的亏损（含往返手续费）
+    pub risk_at_stop: f64,
+    /// 到达止盈时的净收益（扣往返手续费）
+    pub profit_at_target: f64,
+    /// 是否受可用保证金限制
+    pub margin_limited: bool,
+}
+
+/// 期货合约仓位结果
+#[derive(Debug, Clone)]
+pub struct FuturesSizing {
+    pub contract: FuturesContract,
+    /// 入场到止损 / 止盈的跳数（未设置最小变动价位时为 None）
+    pub ticks_to_stop: Option<u64>,
+    pub ticks_to_target: Option<u64>,
+    /// 每手止损风险（含往返手续费）
+    pub risk_per_contract: f64,
+    /// 每手止盈净收益
+    pub profit_per_contract: f64,
+    /// 本金可支付保证金的最大手数
+    pub max_contracts_by_margin: u64,
+    /// 全凯利 / 半凯利 / 1/4凯利
+    pub positions: [FuturesPosition; 3],
+}
+
+/// 股票模式的附加仓位选项（仓位算法对比、组合热度、杠杆融资、期货合约）
+#[derive(Debug, Clone, Default)]
+pub struct StockSizingOptions {
+    pub sizers: Vec<PositionSizer>,
+    pub heat: Option<HeatSettings>,
+    pub leverage: Option<LeverageSettings>,
+    pub futures: Option<FuturesContract>,
+}
+
+/// 股票杠杆凯利结果
+#[derive(Debug, Clone, Copy)]
+pub struct LeveragedStockResult {
+    pub settings: LeverageSettings,
+    /// 不受杠杆上限约束、扣除融资成本后的最优名义仓位（占本金）
+    pub unconstrained_fraction: f64,
+    /// 受杠杆上限约束后的最优名义仓位（占本金）
+    pub position_fraction: f64,
+    /// 借入资金占本金比例
+    pub borrowed_fraction: f64,
+    /// 持有期融资成本占本金比例
+    pub financing_cost: f64,
+    /// 期望对数增长率
+    pub expected_log_growth: f64,
+    /// 强平价；做多不借钱时为 None
+    pub liquidation_price: Option<f64>,
+    /// 止损是否先于强平触发
+    pub stop_before_liquidation: bool,
+}
+
+/// 股票模式的附加仓位分析（仓位算法对比、组合热度、杠杆融资、期货合约）
+#[derive(Debug, Clone, Default)]
+pub struct StockSizingContext {
+    pub volatility: Option<VolatilityLevels>,
+    pub sizers: Vec<SizerResult>,
+    pub heat: Option<PortfolioHeat>,
+    pub leverage: Option<LeveragedStockResult>,
+    pub futures: Option<FuturesSizing>,
+}
+
+/// 波动率单位来源
+#[derive(Debug, Clone, Copy)]
+pub enum VolatilitySource {
+    /// 平均真实波幅（价格单位）
+    Atr(f64),
+    /// 日波动率（0-1）与持有天数，单位 = 当前价 × σ × √天数
+    Sigma { daily_vol: f64, days: f64 },
+}
+
+/// 按波动率倍数推导的止损/止盈价位
+#[derive(Debug, Clone)]
+pub struct VolatilityLevels {
+    pub source: VolatilitySource,
+    /// 一个波动率单位对应的价格距离
+    pub unit: f64,
+    /// 止损距离倍数
+    pub stop_mult: f64,
+    /// 止盈距离倍数
+    pub target_mult: f64,
+    pub stop_loss: f64,
+    pub target_price: f64,
+    /// 每日漂移（0-1，仅漂移布朗运动模型）
+    pub daily_drift: Option<f64>,
+    /// 先触及止盈的估计概率（未估计时为 None）
+    pub hit_probability: Option<f64>,
+}
+
+/// 分批止盈目标
+#[derive(Debug, Clone)]
+pub struct ScaleOutTarget {
+    /// 目标价
+    pub price: f64,
+    /// 到达后减仓比例（占初始仓位，0-1）
+    pub portion: f64,
+    /// 到达该目标的概率（0-1）
+    pub reach_prob: f64,
+}
+
+/// 分批止盈的单个结果：到达前 k 个目标后，剩余仓位在止损处出场
+#[derive(Debug, Clone)]
+pub struct ScaleOutOutcome {
+    /// 已到达的目标数量（0 表示直接止损）
+    pub targets_hit: usize,
+    /// 该结果发生的概率
+    pub probability: f64,
+    /// 按初始仓位计算的每股净盈亏
+    pub pnl_per_share: f64,
+    /// 以初始止损净风险为单位的收益倍数 (R)
+    pub r_multiple: f64,
+}
+
+/// 分批止盈计算结果
+#[derive(Debug, Clone)]
+pub struct ScaleOutResult {
+    /// 以最终目标构建的交易信息（用于止损风险与仓位换算）
+    pub info: StockInfo,
+    /// 各结果分布
+    pub outcomes: Vec<ScaleOutOutcome>,
+    /// 基于结果分布的凯利结果（期望收益按 R 计）
+    pub kelly: KellyResult,
+    /// 每股期望净盈亏
+    pub expected_pnl_per_share: f64,
+}
+
+/// 移动止损方式
+#[derive(Debug, Clone, Copy)]
+pub enum TrailingStop {
+    /// 按最高价（做空为最低价）回撤比例（0-1）
+    Percent(f64),
+    /// 按 ATR 倍数跟随
+    Atr { atr: f64, mult: f64 },
+}
+
+impl TrailingStop {
+    /// 在给定极值价位下的止损距离
+    pub fn distance(self, extreme_price: f64) -> f64 {
+        match self {
+            Self::Percent(pct) => extreme_price * pct,
+            Self::Atr { atr, mult } => atr * mult,
+        }
+    }
+}
+
+/// 价格路径模拟参数（几何布朗运动，按交易日步进）
+#[derive(Debug, Clone, Copy)]
+pub struct PathSimulation {
+    /// 年化漂移（0-1）
+    pub annual_drift: f64,
+    /// 年化波动率（0-1）
+    pub annual_vol: f64,
+    /// 最长持有交易日数（时间止损）
+    pub max_days: usize,
+    /// 模拟路径数量
+    pub paths: usize,
+    /// 随机种子
+    pub seed: u64,
+}
+
+/// 移动止损模拟结果
+#[derive(Debug, Clone)]
+pub struct TrailingStopResult {
+    /// 以初始止损构建的交易信息（止盈价按 1R 对称填充，仅用于仓位换算）
+    pub info: StockInfo,
+    /// 各路径的 R 倍数（升序）
+    pub r_multiples: Vec<f64>,
+    /// 基于路径分布的凯利结果（期望收益按 R 计）
+    pub kelly: KellyResult,
+    pub expected_pnl_per_share: f64,
+    /// 触发移动止损出场的比例
+    pub stopped_out_prob: f64,
+    /// 触发时间止损出场的比例
+    pub time_stop_prob: f64,
+    /// 盈利路径比例
+    pub win_prob: f64,
+    pub avg_holding_days: f64,
+}
+
+/// 期权类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+impl OptionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Call => "call",
+            Self::Put => "put",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Call => "看涨",
+            Self::Put => "看跌",
+        }
+    }
+}
+
+/// 期权定价的市场参数（Black–Scholes，欧式）
+#[derive(Debug, Clone, Copy)]
+pub struct OptionMarket {
+    /// 标的现价
+    pub spot: f64,
+    /// 隐含波动率（年化，0-1）
+    pub implied_vol: f64,
+    /// 无风险利率（年化连续复利，0-1）
+    pub rate: f64,
+    /// 距到期自然日数
+    pub days: f64,
+}
+
+impl OptionMarket {
+    /// 距到期年数（按 365 天折算）
+    pub fn years(&self) -> f64 {
+        self.days / 365.0
+    }
+}
+
+/// 期权策略：买入单腿，或买入 `long_strike`、卖出 `short_strike` 的垂直价差
+#[derive(Debug, Clone, Copy)]
+pub struct OptionStrategy {
+    pub kind: OptionKind,
+    pub long_strike: f64,
+    pub short_strike: Option<f64>,
+}
+
+/// 到期价格观点
+#[derive(Debug, Clone)]
+pub enum PriceView {
+    /// 主观对数正态分布：年化漂移与年化波动率（0-1）
+    Lognormal { drift: f64, vol: f64 },
+    /// 目标价格情景：(到期价格, 概率)
+    Scenarios(Vec<(f64, f64)>),
+}
+
+/// 单个期权合约的 Black–Scholes 报价（每股）
+#[derive(Debug, Clone, Copy)]
+pub struct OptionQuote {
+    pub strike: f64,
+    pub price: f64,
+    pub delta: f64,
+}
+
+/// 到期收益分布中的单个结果
+#[derive(Debug, Clone, Copy)]
+pub struct OptionOutcome {
+    /// 到期标的价格
+    pub spot: f64,
+    pub probability: f64,
+    /// 每股净盈亏（已扣除含资金成本的权利金）
+    pub pnl: f64,
+}
+
+/// 期权仓位计算结果
+#[derive(Debug, Clone)]
+pub struct OptionResult {
+    pub long_leg: OptionQuote,
+    pub short_leg: Option<OptionQuote>,
+    /// 每股净权利金（正为支付，负为收取）
+    pub net_premium: f64,
+    /// 每股最大亏损（凯利的单位风险）
+    pub max_loss: f64,
+    /// 每股最大盈利；单腿看涨无上限时为 None
+    pub max_profit: Option<f64>,
+    /// 到期盈亏平衡价（含资金成本）；全部价格区间均亏损时为 None
+    pub breakeven: Option<f64>,
+    /// 到期收益分布（情景观点为各情景，对数正态观点为离散网格）
+    pub outcomes: Vec<OptionOutcome>,
+    /// 盈利概率
+    pub win_prob: f64,
+    /// 每股期望净盈亏
+    pub expected_pnl: f64,
+    /// 基于收益分布的凯利结果（期望收益按最大亏损计）
+    pub kelly: KellyResult,
 }
 
 /// 组合腿来源类型
@@ -97,6 +1287,7 @@ pub enum PortfolioLegSource {
     Stock,
     Arbitrage2,
     ArbitrageN,
+    Option,
 }
 
 impl PortfolioLegSource {
@@ -107,6 +1298,7 @@ impl PortfolioLegSource {
             Self::Stock => "stock",
             Self::Arbitrage2 => "arbitrage2",
             Self::ArbitrageN => "arbitrageN",
+            Self::Option => "option",
         }
     }
 }
@@ -135,6 +1327,38 @@ pub struct PortfolioScenario {
     pub returns: Vec<f64>,
 }
 
+/// 连续收益凯利（Merton）的仓位约束
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MertonConstraints {
+    /// 禁止做空
+    pub long_only: bool,
+    /// 总敞口上限 Σ|f_i|（占本金）
+    pub max_leverage: Option<f64>,
+}
+
+/// 连续收益凯利（Merton）计算结果，收益与波动率均为年化
+#[derive(Debug, Clone)]
+pub struct MertonResult {
+    /// 无约束 Merton 仓位 Σ⁻¹(μ - r)
+    pub unconstrained: Vec<f64>,
+    /// 约束后的建议仓位（负数为做空）
+    pub allocations: Vec<f64>,
+    /// 净仓位 Σf_i，剩余部分按无风险利率计息（超过 100% 时按无风险利率借入）
+    pub total_allocation: f64,
+    /// 总敞口 Σ|f_i|
+    pub gross_exposure: f64,
+    /// 组合期望收益 r + fᵀ(μ - r)
+    pub expected_return: f64,
+    /// 组合波动率 √(fᵀΣf)
+    pub volatility: f64,
+    /// 期望对数增长率 r + fᵀ(μ - r) - fᵀΣf / 2
+    pub expected_log_growth: f64,
+    /// 约束是否生效
+    pub constrained: bool,
+    pub converged: bool,
+    pub iterations: usize,
+}
+
 /// 组合凯利计算结果
 #[derive(Debug, Clone)]
 pub struct PortfolioKellyResult {
@@ -148,6 +1372,12 @@ pub struct PortfolioKellyResult {
     pub expected_arithmetic_return: f64,
     /// 可达状态中的最差场景资金倍数
     pub worst_case_multiplier: f64,
+    /// 借入资金占本金比例（总仓位超过 100% 的部分）
+    pub borrowed: f64,
+    /// 持有期融资成本占本金比例
+    pub financing_cost: f64,
+    /// 触发追加保证金 / 强平的情景概率（仅杠杆模式）
+    pub margin_call_prob: Option<f64>,
     /// 优化是否收敛
     pub converged: bool,
     /// 优化迭代次数
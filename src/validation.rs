@@ -1,5 +1,7 @@
 //! 输入校验与解析
 
+use crate::types::{OptionKind, TradeDirection};
+
 /// 解析浮点数
 pub fn parse_f64(input: &str, field_name: &str) -> Result<f64, String> {
     input
@@ -47,9 +49,105 @@ pub fn parse_positive(input: &str, field_name: &str) -> Result<f64, String> {
     }
 }
 
+/// 解析非负数
+pub fn parse_non_negative(input: &str, field_name: &str) -> Result<f64, String> {
+    let value = parse_f64(input, field_name)?;
+    if value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(format!("{field_name}不能为负数"))
+    }
+}
+
+/// 解析正整数
+pub fn parse_positive_integer(input: &str, field_name: &str) -> Result<u64, String> {
+    match input.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("{field_name}必须为正整数")),
+    }
+}
+
+/// 解析非负整数（允许 0）
+pub fn parse_non_negative_integer(input: &str, field_name: &str) -> Result<u64, String> {
+    input
+        .parse::<u64>()
+        .map_err(|_| format!("{field_name}必须为非负整数"))
+}
+
+/// 解析交易方向
+pub fn parse_direction(input: &str) -> Result<TradeDirection, String> {
+    match input.trim().to_lowercase().as_str() {
+        "long" | "l" | "多" | "做多" => Ok(TradeDirection::Long),
+        "short" | "s" | "空" | "做空" => Ok(TradeDirection::Short),
+        _ => Err("交易方向必须为 long 或 short".to_string()),
+    }
+}
+
+/// 解析期权类型
+pub fn parse_option_kind(input: &str) -> Result<OptionKind, String> {
+    match input.trim().to_lowercase().as_str() {
+        "call" | "c" | "看涨" => Ok(OptionKind::Call),
+        "put" | "p" | "看跌" => Ok(OptionKind::Put),
+        _ => Err("期权类型必须为 call 或 put".to_string()),
+    }
+}
+
+/// 解析逗号分隔的逐标的正数列表
+pub fn parse_per_leg_values(input: &str, field_name: &str) -> Result<Vec<f64>, String> {
+    input
+        .split(',')
+        .map(|part| parse_positive(part.trim(), field_name))
+        .collect()
+}
+
+/// 解析逗号分隔的逐标的百分比列表（0-100）
+pub fn parse_per_leg_percents(input: &str, field_name: &str) -> Result<Vec<f64>, String> {
+    input
+        .split(',')
+        .map(|part| parse_percent(part.trim(), field_name))
+        .collect()
+}
+
+/// 将单个值展开到所有标的，或检查逐标的列表长度
+pub fn expand_per_leg(values: &mut Vec<f64>, legs: usize, option: &str) -> Result<(), String> {
+    if values.len() == 1 {
+        *values = vec![values[0]; legs];
+        Ok(())
+    } else if values.len() != legs {
+        Err(format!(
+            "{option} 需要 1 个或 {legs} 个数值，实际得到 {}",
+            values.len()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// 校验止盈/止损价与交易方向一致
+pub fn validate_stock_prices(
+    direction: TradeDirection,
+    entry_price: f64,
+    target_price: f64,
+    stop_loss: f64,
+) -> Result<(), String> {
+    match direction {
+        TradeDirection::Long if target_price <= entry_price || stop_loss >= entry_price => {
+            Err("参数错误: 止盈价必须大于当前价，止损价必须小于当前价".to_string())
+        }
+        TradeDirection::Short if target_price >= entry_price || stop_loss <= entry_price => {
+            Err("参数错误: 做空时止盈价必须小于当前价，止损价必须大于当前价".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{parse_market_price, parse_odds, parse_percent, parse_positive};
+    use super::{
+        parse_market_price, parse_non_negative, parse_non_negative_integer, parse_odds,
+        parse_percent, parse_positive, parse_positive_integer, validate_stock_prices,
+    };
+    use crate::types::TradeDirection;
 
     #[test]
     fn parse_market_price_rejects_zero() {
@@ -92,4 +190,27 @@ mod tests {
         assert!(parse_positive("-10", "本金").is_err());
         assert_eq!(parse_positive("10", "本金").unwrap(), 10.0);
     }
+
+    #[test]
+    fn parse_non_negative_accepts_zero() {
+        assert_eq!(parse_non_negative("0", "佣金").unwrap(), 0.0);
+        assert!(parse_non_negative("-0.1", "佣金").is_err());
+    }
+
+    #[test]
+    fn parse_positive_integer_rejects_fraction_and_zero() {
+        assert_eq!(parse_positive_integer("100", "每手股数").unwrap(), 100);
+        assert!(parse_positive_integer("0", "每手股数").is_err());
+        assert!(parse_positive_integer("1.5", "每手股数").is_err());
+        assert_eq!(parse_non_negative_integer("0", "随机种子").unwrap(), 0);
+        assert!(parse_non_negative_integer("-1", "随机种子").is_err());
+    }
+
+    #[test]
+    fn stock_prices_follow_direction() {
+        assert!(validate_stock_prices(TradeDirection::Long, 100.0, 120.0, 90.0).is_ok());
+        assert!(validate_stock_prices(TradeDirection::Long, 100.0, 80.0, 110.0).is_err());
+        assert!(validate_stock_prices(TradeDirection::Short, 100.0, 80.0, 110.0).is_ok());
+        assert!(validate_stock_prices(TradeDirection::Short, 100.0, 120.0, 90.0).is_err());
+    }
 }
@@ -0,0 +1,178 @@
+//! 价值投注扫描：把模型概率与赔率/价格快照对齐，按凯利计算每个结果的期望与仓位
+
+use std::fs;
+
+use crate::kelly::{kelly_criterion, kelly_polymarket};
+use crate::scanner::parse_records;
+use crate::types::{ModelProbability, OddsQuote, ValueBet, ValueScan};
+use crate::validation::parse_percent;
+
+const PROBABILITY_FIELDS: [&str; 4] = ["event", "market", "outcome", "probability"];
+
+/// 解析模型概率文件：每条为 事件、盘口、结果、概率（%）
+pub fn parse_model_probabilities(content: &str) -> Result<Vec<ModelProbability>, String> {
+    let probabilities = parse_records(content, &PROBABILITY_FIELDS)?
+        .into_iter()
+        .map(|(place, values)| {
+            Ok(ModelProbability {
+                probability: parse_percent(&values[3], "概率")
+                    .map_err(|e| format!("{place}: {e}"))?,
+                event: values[0].clone(),
+                market: values[1].clone(),
+                outcome: values[2].clone(),
+            })
+        })
+        .collect::<Result<Vec<ModelProbability>, String>>()?;
+    if probabilities.is_empty() {
+        return Err("模型概率文件中没有记录".to_string());
+    }
+    Ok(probabilities)
+}
+
+/// 读取模型概率文件
+pub fn load_model_probabilities(path: &str) -> Result<Vec<ModelProbability>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("无法读取模型概率文件 '{path}': {e}"))?;
+    parse_model_probabilities(&content)
+}
+
+/// 去掉末尾多余的 0，用于生成组合描述
+fn trim_number(value: f64) -> String {
+    let s = format!("{:.4}", value);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// 扫描价值投注
+///
+/// 每个结果取各庄家中的最优价格，与同一（事件, 盘口, 结果）的模型概率对齐；
+/// 标准赔率用 `kelly_criterion`，Polymarket 价格用 `kelly_polymarket`。
+/// 保留优势（模型概率 - 隐含概率）大于 `min_edge` 的结果，按期望收益从高到低排序。
+pub fn scan_value_bets(
+    quotes: &[OddsQuote],
+    probabilities: &[ModelProbability],
+    polymarket: bool,
+    min_edge: f64,
+) -> ValueScan {
+    let mut best: Vec<&OddsQuote> = Vec::new();
+    for quote in quotes {
+        let existing = best.iter_mut().find(|b| {
+            b.event == quote.event && b.market == quote.market && b.outcome == quote.outcome
+        });
+        match existing {
+            Some(b) if quote.odds > b.odds => *b = quote,
+            Some(_) => {}
+            None => best.push(quote),
+        }
+    }
+
+    let mut matched = 0;
+    let mut bets = Vec::new();
+    for quote in &best {
+        let Some(model) = probabilities.iter().find(|m| {
+            m.event == quote.event && m.market == quote.market && m.outcome == quote.outcome
+        }) else {
+            continue;
+        };
+        matched += 1;
+        let implied_prob = 1.0 / quote.odds;
+        let edge = model.probability - implied_prob;
+        if edge <= min_edge {
+            continue;
+        }
+        let (kelly, descriptor) = if polymarket {
+            (
+                kelly_polymarket(implied_prob, model.probability),
+                format!(
+                    "pm:{}:{}",
+                    trim_number(implied_prob * 100.0),
+                    trim_number(model.probability * 100.0)
+                ),
+            )
+        } else {
+            (
+                kelly_criterion(quote.odds, model.probability),
+                format!(
+                    "std:{}:{}",
+                    trim_number(quote.odds),
+                    trim_number(model.probability * 100.0)
+                ),
+            )
+        };
+        bets.push(ValueBet {
+            event: quote.event.clone(),
+            market: quote.market.clone(),
+            outcome: quote.outcome.clone(),
+            bookmaker: quote.bookmaker.clone(),
+            odds: quote.odds,
+            implied_prob,
+            probability: model.probability,
+            edge,
+            kelly,
+            descriptor,
+        });
+    }
+    bets.sort_by(|a, b| b.kelly.expected_value.total_cmp(&a.kelly.expected_value));
+
+    ValueScan {
+        quote_count: quotes.len(),
+        probability_count: probabilities.len(),
+        outcome_count: best.len(),
+        matched,
+        polymarket,
+        min_edge,
+        bets,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_model_probabilities, scan_value_bets};
+    use crate::scanner::parse_odds_snapshot;
+
+    const ODDS: &str = "A vs B,1x2,A,BookX,2.60
+A vs B,1x2,A,BookY,2.75
+A vs B,1x2,Draw,BookX,3.70
+A vs B,1x2,B,BookY,3.40
+C vs D,ml,C,BookX,1.50
+";
+    const MODEL: &str = "event,market,outcome,probability
+A vs B,1x2,A,42
+A vs B,1x2,Draw,25
+A vs B,1x2,B,33
+C vs D,ml,C,60
+";
+
+    #[test]
+    fn best_price_is_joined_with_model_probability() {
+        let quotes = parse_odds_snapshot(ODDS, false).unwrap();
+        let model = parse_model_probabilities(MODEL).unwrap();
+        let scan = scan_value_bets(&quotes, &model, false, 0.0);
+        assert_eq!(scan.outcome_count, 4);
+        assert_eq!(scan.matched, 4);
+        // A: 0.42 × 2.75 - 1 = 15.5%；B: 0.33 × 3.4 - 1 = 12.2%；Draw 与 C 为负期望
+        assert_eq!(scan.bets.len(), 2);
+        assert_eq!(scan.bets[0].outcome, "A");
+        assert_eq!(scan.bets[0].bookmaker, "BookY");
+        assert!((scan.bets[0].kelly.expected_value - 0.155).abs() < 1e-12);
+        assert_eq!(scan.bets[0].descriptor, "std:2.75:42");
+        assert_eq!(scan.bets[1].descriptor, "std:3.4:33");
+
+        // 最小优势 5 个百分点：A 为 42% - 36.36%，B 为 33% - 29.41%
+        assert_eq!(scan_value_bets(&quotes, &model, false, 0.05).bets.len(), 1);
+    }
+
+    #[test]
+    fn polymarket_prices_use_polymarket_kelly() {
+        let quotes = parse_odds_snapshot("E,winner,Yes,Polymarket,55\n", true).unwrap();
+        let model = parse_model_probabilities("E,winner,Yes,60\n").unwrap();
+        let scan = scan_value_bets(&quotes, &model, true, 0.0);
+        let bet = &scan.bets[0];
+        assert!((bet.implied_prob - 0.55).abs() < 1e-12);
+        // f = (p - 价格) / (1 - 价格)
+        assert!((bet.kelly.optimal_fraction - 0.05 / 0.45).abs() < 1e-12);
+        assert_eq!(bet.descriptor, "pm:55:60");
+
+        assert!(parse_odds_snapshot("E,winner,Yes,Polymarket,100\n", true).is_err());
+        assert!(parse_model_probabilities("E,winner,Yes,120\n").is_err());
+    }
+}
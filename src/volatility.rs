@@ -0,0 +1,185 @@
+//! 波动率止损：按 ATR / σ 倍数推导止损与止盈，并估计先触及止盈的概率
+
+use crate::types::{TradeDirection, VolatilityLevels, VolatilitySource};
+
+/// 布朗运动先触及上方屏障的概率
+///
+/// 从 0 出发、每日漂移 `drift`、每日标准差 `sigma`，
+/// 屏障为 `+target_distance`（止盈）与 `-stop_distance`（止损）。
+/// 无漂移时退化为 `stop_distance / (target_distance + stop_distance)`。
+pub fn target_first_probability(
+    target_distance: f64,
+    stop_distance: f64,
+    drift: f64,
+    sigma: f64,
+) -> f64 {
+    let driftless = stop_distance / (target_distance + stop_distance);
+    if drift == 0.0 || sigma <= 0.0 {
+        return driftless;
+    }
+
+    let k = 2.0 * drift / (sigma * sigma);
+    let up = (-k * target_distance).exp();
+    let down = (k * stop_distance).exp();
+    let p = (1.0 - down) / (up - down);
+    if p.is_finite() {
+        p.clamp(0.0, 1.0)
+    } else if drift > 0.0 {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// 按波动率倍数推导止损/止盈价位
+///
+/// `estimate_probability` 为真时估计先触及止盈的概率；
+/// `daily_drift` 需要日波动率（`Sigma`）来源才能换算漂移模型。
+pub fn derive_volatility_levels(
+    direction: TradeDirection,
+    entry_price: f64,
+    source: VolatilitySource,
+    stop_mult: f64,
+    target_mult: f64,
+    daily_drift: Option<f64>,
+    estimate_probability: bool,
+) -> Result<VolatilityLevels, String> {
+    let unit = match source {
+        VolatilitySource::Atr(atr) => atr,
+        VolatilitySource::Sigma { daily_vol, days } => entry_price * daily_vol * days.sqrt(),
+    };
+    if !(unit > 0.0 && unit.is_finite()) {
+        return Err("波动率单位必须为正数".to_string());
+    }
+
+    let stop_distance = stop_mult * unit;
+    let target_distance = target_mult * unit;
+    let sign = direction.sign();
+    let stop_loss = entry_price - sign * stop_distance;
+    let target_price = entry_price + sign * target_distance;
+    if stop_loss <= 0.0 || target_price <= 0.0 {
+        return Err(format!(
+            "推导出的价位必须为正数（止损 {:.4}，止盈 {:.4}），请减小倍数",
+            stop_loss, target_price
+        ));
+    }
+
+    let hit_probability = if estimate_probability || daily_drift.is_some() {
+        let (drift, sigma) = match (daily_drift, source) {
+            (None, _) => (0.0, 0.0),
+            (Some(mu), VolatilitySource::Sigma { daily_vol, .. }) => {
+                // 做空时有利方向为下跌
+                (entry_price * mu * sign, entry_price * daily_vol)
+            }
+            (Some(_), VolatilitySource::Atr(_)) => {
+                return Err("漂移模型需要日波动率（--vol），ATR 模式仅支持无漂移估计".to_string());
+            }
+        };
+        Some(target_first_probability(
+            target_distance,
+            stop_distance,
+            drift,
+            sigma,
+        ))
+    } else {
+        None
+    };
+
+    Ok(VolatilityLevels {
+        source,
+        unit,
+        stop_mult,
+        target_mult,
+        stop_loss,
+        target_price,
+        daily_drift,
+        hit_probability,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derive_volatility_levels, target_first_probability};
+    use crate::types::{TradeDirection, VolatilitySource};
+
+    const EPS: f64 = 1e-9;
+
+    #[test]
+    fn driftless_probability_is_distance_ratio() {
+        let p = target_first_probability(3.0, 2.0, 0.0, 1.0);
+        assert!((p - 0.4).abs() < EPS);
+    }
+
+    #[test]
+    fn positive_drift_raises_target_probability() {
+        let base = target_first_probability(3.0, 2.0, 0.0, 1.0);
+        let up = target_first_probability(3.0, 2.0, 0.1, 1.0);
+        let down = target_first_probability(3.0, 2.0, -0.1, 1.0);
+        assert!(up > base);
+        assert!(down < base);
+    }
+
+    #[test]
+    fn atr_levels_follow_direction() {
+        let long = derive_volatility_levels(
+            TradeDirection::Long,
+            100.0,
+            VolatilitySource::Atr(2.5),
+            2.0,
+            3.0,
+            None,
+            true,
+        )
+        .unwrap();
+        assert!((long.stop_loss - 95.0).abs() < EPS);
+        assert!((long.target_price - 107.5).abs() < EPS);
+        assert!((long.hit_probability.unwrap() - 0.4).abs() < EPS);
+
+        let short = derive_volatility_levels(
+            TradeDirection::Short,
+            100.0,
+            VolatilitySource::Atr(2.5),
+            2.0,
+            3.0,
+            None,
+            false,
+        )
+        .unwrap();
+        assert!((short.stop_loss - 105.0).abs() < EPS);
+        assert!((short.target_price - 92.5).abs() < EPS);
+        assert!(short.hit_probability.is_none());
+    }
+
+    #[test]
+    fn sigma_unit_scales_with_sqrt_days() {
+        let levels = derive_volatility_levels(
+            TradeDirection::Long,
+            100.0,
+            VolatilitySource::Sigma {
+                daily_vol: 0.02,
+                days: 4.0,
+            },
+            1.0,
+            2.0,
+            None,
+            false,
+        )
+        .unwrap();
+        assert!((levels.unit - 4.0).abs() < EPS);
+        assert!((levels.stop_loss - 96.0).abs() < EPS);
+    }
+
+    #[test]
+    fn drift_requires_sigma_source() {
+        let result = derive_volatility_levels(
+            TradeDirection::Long,
+            100.0,
+            VolatilitySource::Atr(2.0),
+            1.0,
+            2.0,
+            Some(0.001),
+            true,
+        );
+        assert!(result.is_err());
+    }
+}